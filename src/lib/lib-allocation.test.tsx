@@ -0,0 +1,79 @@
+import { Account } from '@solana/web3.js';
+import {
+  Intent, Need, ALLOCATION_PROPORTIONAL, ALLOCATION_EQUAL_SPLIT, ALLOCATION_MAX_MIN_FAIR, ALLOCATION_FIRST_COME_FIRST_SERVED,
+  ALLOCATION_LOTTERY,
+} from './lib-types';
+import { allocate, drawOrder } from './lib-allocation';
+
+// [requested, householdSize] per intent, in registration order
+function allocations(strategy: number, pool: number | bigint, needs: Array<[number | bigint, number]>,
+    order?: Array<number>): [Array<bigint>, bigint] {
+    let intents = needs.map(([requested, householdSize]) => new Intent(new Account().publicKey, new Need(BigInt(requested), householdSize)));
+    let allocated = allocate(strategy, BigInt(pool), intents, order);
+    return [intents.map(intent => intent.allocation), allocated];
+}
+
+// what allocations returns for these allocations and total
+function amounts(allocated: Array<number>, total: number): [Array<bigint>, bigint] {
+    return [allocated.map(allocation => BigInt(allocation)), BigInt(total)];
+}
+
+// a lottery drawn with a hash of `byte` repeated
+async function drawnAllocations(byte: number, pool: number, needs: Array<[number, number]>): Promise<[Array<bigint>, bigint]> {
+    let order = await drawOrder(new Uint8Array(32).fill(byte), needs.length);
+    return allocations(ALLOCATION_LOTTERY, pool, needs, order);
+}
+
+// the same cases as the tests of allocation.rs in the resource program
+describe('allocation strategies', () => {
+
+    test('proportional', () => {
+        expect(allocations(ALLOCATION_PROPORTIONAL, 9, [[0, 0], [0, 0]])).toEqual(amounts([4, 4], 8));
+        expect(allocations(ALLOCATION_PROPORTIONAL, 10, [[2, 1], [0, 3]])).toEqual(amounts([2, 7], 9));
+        expect(allocations(ALLOCATION_PROPORTIONAL, 10, [[30, 0], [10, 0]])).toEqual(amounts([7, 2], 9));
+        // u64::MAX, far above Number.MAX_SAFE_INTEGER
+        let max = BigInt("18446744073709551615");
+        let half = BigInt("9223372036854775807");
+        expect(allocations(ALLOCATION_PROPORTIONAL, max, [[max, 0], [max, 0]])).toEqual([[half, half], max - BigInt(1)]);
+    })
+
+    test('equal split', () => {
+        expect(allocations(ALLOCATION_EQUAL_SPLIT, 9, [[0, 0], [0, 0]])).toEqual(amounts([4, 4], 8));
+        expect(allocations(ALLOCATION_EQUAL_SPLIT, 10, [[2, 1], [0, 3]])).toEqual(amounts([5, 5], 10));
+        expect(allocations(ALLOCATION_EQUAL_SPLIT, 10, [[30, 0]])).toEqual(amounts([10], 10));
+    })
+
+    test('max-min fair', () => {
+        expect(allocations(ALLOCATION_MAX_MIN_FAIR, 9, [[0, 0], [0, 0]])).toEqual(amounts([4, 4], 8));
+        expect(allocations(ALLOCATION_MAX_MIN_FAIR, 9, [[5, 0], [5, 0]])).toEqual(amounts([4, 5], 9));
+        expect(allocations(ALLOCATION_MAX_MIN_FAIR, 10, [[30, 0], [2, 0]])).toEqual(amounts([8, 2], 10));
+        expect(allocations(ALLOCATION_MAX_MIN_FAIR, 10, [[30, 0], [10, 0]])).toEqual(amounts([5, 5], 10));
+        expect(allocations(ALLOCATION_MAX_MIN_FAIR, 10, [[3, 0], [4, 0]])).toEqual(amounts([3, 4], 7));
+    })
+
+    test('first come, first served', () => {
+        expect(allocations(ALLOCATION_FIRST_COME_FIRST_SERVED, 10, [[8, 0], [8, 0]])).toEqual(amounts([8, 2], 10));
+        expect(allocations(ALLOCATION_FIRST_COME_FIRST_SERVED, 10, [[2, 1], [0, 3]])).toEqual(amounts([2, 7], 9));
+        expect(allocations(ALLOCATION_FIRST_COME_FIRST_SERVED, 10, [[30, 0], [1, 0]])).toEqual(amounts([10, 0], 10));
+    })
+
+    test('lottery', async () => {
+        // sha256 of [0; 32] and position 1 is odd, [4; 32] draws the second intent first
+        expect(await drawOrder(new Uint8Array(32), 2)).toEqual([0, 1]);
+        expect(await drawOrder(new Uint8Array(32).fill(4), 2)).toEqual([1, 0]);
+        expect(await drawOrder(new Uint8Array(32).fill(4), 0)).toEqual([]);
+        expect(await drawnAllocations(0, 10, [[8, 0], [8, 0]])).toEqual(amounts([8, 2], 10));
+        expect(await drawnAllocations(4, 10, [[8, 0], [8, 0]])).toEqual(amounts([2, 8], 10));
+        expect(await drawnAllocations(4, 10, [[30, 0], [1, 0]])).toEqual(amounts([9, 1], 10));
+        // a winner without a request gets their household's share
+        expect(await drawnAllocations(4, 10, [[2, 1], [0, 3]])).toEqual(amounts([2, 7], 9));
+    })
+
+    test('nothing to allocate', () => {
+        [ALLOCATION_PROPORTIONAL, ALLOCATION_EQUAL_SPLIT, ALLOCATION_MAX_MIN_FAIR, ALLOCATION_FIRST_COME_FIRST_SERVED,
+            ALLOCATION_LOTTERY].forEach(strategy => {
+            expect(allocations(strategy, 10, [])).toEqual(amounts([], 0));
+            expect(allocations(strategy, 0, [[5, 0], [0, 2]])).toEqual(amounts([0, 0], 0));
+        });
+    })
+});
@@ -0,0 +1,122 @@
+import { sha256 } from 'crypto-hash';
+import {
+  Intent, ResourceDatabase, ALLOCATION_PROPORTIONAL, ALLOCATION_EQUAL_SPLIT, ALLOCATION_MAX_MIN_FAIR,
+  ALLOCATION_FIRST_COME_FIRST_SERVED, ALLOCATION_LOTTERY,
+} from './lib-types';
+
+/*
+Recomputes what initiateDistribution allocates, step for step like allocation.rs in the resource
+program: integer division and products as wide as the program's u128, hence BigInt. `intents` are
+in registration order, as getDatabase returns them, and a lottery serves them in `order`, see
+drawOrder. Sets every intent's allocation and returns the total allocated, the database's finalQuantity
+*/
+export function allocate(strategy: number, total: bigint, intents: Array<Intent>,
+  order: Array<number> = intents.map((_, index) => index)): bigint {
+  const demands = demandsOf(total, intents);
+  let allocations: Array<bigint>;
+  switch (strategy) {
+    case ALLOCATION_PROPORTIONAL:
+      allocations = proportional(total, demands);
+      break;
+    case ALLOCATION_EQUAL_SPLIT:
+      allocations = intents.map(() => total / BigInt(intents.length));
+      break;
+    case ALLOCATION_MAX_MIN_FAIR:
+      allocations = maxMinFair(total, demands);
+      break;
+    case ALLOCATION_FIRST_COME_FIRST_SERVED:
+      allocations = firstComeFirstServed(total, demands);
+      break;
+    case ALLOCATION_LOTTERY:
+      allocations = lottery(total, demands, order);
+      break;
+    default:
+      allocations = intents.map(() => BigInt(0));
+  }
+  intents.forEach((intent, index) => intent.allocation = allocations[index]);
+  return allocations.reduce((sum, allocation) => sum + allocation, BigInt(0));
+}
+
+// what was requested, or without a request the household's share of the pool
+function demandsOf(pool: bigint, intents: Array<Intent>): Array<bigint> {
+  const household = (intent: Intent) => BigInt(Math.max(intent.need.householdSize, 1));
+  const households = intents.reduce((sum, intent) => sum + household(intent), BigInt(0));
+  return intents.map(intent => intent.need.requested > BigInt(0) ? intent.need.requested : pool * household(intent) / households);
+}
+
+function min(a: bigint, b: bigint): bigint {
+  return a < b ? a : b;
+}
+
+function proportional(pool: bigint, demands: Array<bigint>): Array<bigint> {
+  const total = demands.reduce((sum, demand) => sum + demand, BigInt(0));
+  return demands.map(demand => total > 0 ? min(demand, pool * demand / total) : BigInt(0));
+}
+
+// smallest demands first, equal ones in registration order
+function maxMinFair(pool: bigint, demands: Array<bigint>): Array<bigint> {
+  const order = demands.map((_, index) => index)
+    .filter(index => demands[index] > 0)
+    .sort((a, b) => demands[a] < demands[b] ? -1 : demands[a] > demands[b] ? 1 : a - b);
+  const allocations = demands.map(() => BigInt(0));
+  let remaining = pool;
+  let left = BigInt(order.length);
+  order.forEach(index => {
+    allocations[index] = min(demands[index], remaining / left);
+    remaining -= allocations[index];
+    left -= BigInt(1);
+  });
+  return allocations;
+}
+
+function firstComeFirstServed(pool: bigint, demands: Array<bigint>): Array<bigint> {
+  let remaining = pool;
+  return demands.map(demand => {
+    const allocation = min(demand, remaining);
+    remaining -= allocation;
+    return allocation;
+  });
+}
+
+function lottery(pool: bigint, demands: Array<bigint>, order: Array<number>): Array<bigint> {
+  const allocations = demands.map(() => BigInt(0));
+  let remaining = pool;
+  order.forEach(index => {
+    allocations[index] = min(demands[index], remaining);
+    remaining -= allocations[index];
+  });
+  return allocations;
+}
+
+/*
+The order a lottery drawn with `hash` serves `count` intents in, like draw in allocation.rs: a
+Fisher-Yates shuffle going down from the last position, each swapped with the first 8 bytes of
+sha256(hash, position as u64 little endian) as a little endian number modulo the position plus one
+*/
+export async function drawOrder(hash: Uint8Array, count: number): Promise<Array<number>> {
+  const order = Array.from(Array(count), (_, index) => index);
+  for (let position = count - 1; position > 0; position--) {
+    const seed = Buffer.alloc(hash.length + 8);
+    seed.set(hash);
+    seed.writeUInt32LE(position, hash.length);
+    const digest = Buffer.from(await sha256(seed, { outputFormat: 'buffer' }));
+    const value = BigInt(digest.readUInt32LE(4)) * BigInt(0x100000000) + BigInt(digest.readUInt32LE(0));
+    const pick = Number(value % BigInt(position + 1));
+    [order[position], order[pick]] = [order[pick], order[position]];
+  }
+  return order;
+}
+
+/*
+Allocates the database's pool again with its strategy, a lottery drawn with the hash it recorded,
+and checks the database allocated the same. That the hash is the cluster's is left to
+ResourceAPI.verifyDraw
+*/
+export async function verifyAllocation(database: ResourceDatabase): Promise<boolean> {
+  const intents = database.intents.map(intent => new Intent(intent.recipient, intent.need));
+  const pool = database.instances.reduce((sum, instance) => sum + instance.quantity, BigInt(0));
+  const order = await drawOrder(database.draw.hash, intents.length);
+  const allocated = allocate(database.strategy, pool, intents, order);
+  return allocated === database.finalQuantity
+    && intents.every((intent, index) => intent.allocation === database.intents[index].allocation);
+}
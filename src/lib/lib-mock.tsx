@@ -99,4 +99,8 @@ export class MockSearchEngineAPI implements ISearchEngine {
         }
         return intentsList;
     }
+
+    // nothing is distributed in the mock, so there is nothing to count
+    async settleIntent(account: Account, resourceDatabase: PublicKey): Promise<void> {
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 import { Account, PublicKey } from '@solana/web3.js';
-import { TrustTableEntry, SearchEngineAccount, Resource, Location, ResourceInstance, ResourceIndex, Challenge, ResourceDatabase } from './lib-types';
-import { toBorsh, toTyped } from './lib-serialization';
+import { TrustTableEntry, SearchEngineAccount, Reputation, Resource, Location, ResourceInstance, ResourceIndex, Challenge, ResourceDatabase, DistributionWindow, QuorumRule, QUORUM_TRUST_WEIGHTED, ALLOCATION_MAX_MIN_FAIR, ALLOCATION_LOTTERY, Draw, RoundHistory, ClaimReceipt, UnitOfMeasure, Need, Intent, EligibilityRule, ELIGIBILITY_ALLOW_LIST, ELIGIBILITY_DENY_LIST, ELIGIBILITY_TRUSTED_BY_PROVIDER, Waitlist, WaitlistPage, EMPTY_PUBLIC_KEY } from './lib-types';
+import { toBorsh, toTyped, SEARCH_ENGINE_ACCOUNT_SPACE, ROUND_HISTORY_SPACE, CLAIM_RECEIPT_SPACE, WAITLIST_PAGE_SPACE } from './lib-serialization';
 
 describe('borsh', () => {
 
@@ -20,13 +20,16 @@ describe('borsh', () => {
         let them = new Account();
         let trustTableEntry = new TrustTableEntry(them.publicKey, 100);
         let intent = new Account().publicKey;
-        let searchEngineAccount = new SearchEngineAccount("us", [trustTableEntry], [intent]);
+        let reputation = new Reputation(3, 2, 1, 4, [5000000000, 0, 7]);
+        let searchEngineAccount = new SearchEngineAccount("us", [trustTableEntry], [intent], reputation);
 
         const arr = toBorsh(searchEngineAccount);
+        expect(arr).toHaveLength(SEARCH_ENGINE_ACCOUNT_SPACE);
 
         const buffer = Buffer.from(arr);
         const typed = toTyped(SearchEngineAccount, buffer);
         expect(searchEngineAccount).toStrictEqual(typed);
+        expect(typed.reputation.score()).toEqual(2);
     })
 
     test('can desserialize Resource', () => {
@@ -43,7 +46,7 @@ describe('borsh', () => {
     test('can deserialize ResourceIndex', () => {
         let map = new Map<string, Array<PublicKey>>();
         map.set("94040", [new Account().publicKey])
-        let index = new ResourceIndex(map);
+        let index = new ResourceIndex(map, new Account().publicKey);
 
         const arr = toBorsh(index);
 
@@ -69,26 +72,176 @@ describe('borsh', () => {
     })
 
     test('can deserialize ResourceInstance', () => {
-        let resourceInstance = new ResourceInstance(new Account().publicKey, 100);
+        // beyond 32 bits, so both halves of the u64 are exercised
+        let resourceInstance = new ResourceInstance(new Account().publicKey, BigInt(2 ** 40 + 7));
 
         const arr = toBorsh(resourceInstance);
+        expect(arr).toHaveLength(40);
 
         const buffer = Buffer.from(arr);
         const typed = toTyped(ResourceInstance, buffer);
         expect(resourceInstance).toStrictEqual(typed);
+
+        // above Number.MAX_SAFE_INTEGER, where a number would round: u64::MAX and 2^53 + 1
+        [BigInt("18446744073709551615"), BigInt("9007199254740993")].forEach(quantity => {
+            let large = new ResourceInstance(new Account().publicKey, quantity);
+            const largeArr = toBorsh(large);
+            expect(Buffer.from(largeArr).slice(32).equals(Buffer.from(quantity.toString(16).padStart(16, "0"), "hex").reverse())).toBe(true);
+            expect(toTyped(ResourceInstance, Buffer.from(largeArr)).quantity).toEqual(quantity);
+        });
     })
 
     test('can desserialize ResourceDatabase', () => {
-        let intents = [new Account().publicKey];
-        let instances = [new ResourceInstance(new Account().publicKey, 10)];
+        let intents = [new Intent(new Account().publicKey, new Need(BigInt(30), 6), BigInt(10))];
+        let instances = [new ResourceInstance(new Account().publicKey, BigInt(10))];
         let challenges = [new Challenge(new Account().publicKey, new Account().publicKey, true)];
         let claims = [new Account().publicKey];
-        let database = new ResourceDatabase(true, 10, intents, instances, challenges, claims);
+        let window = new DistributionWindow(1234, 1600000000, 86400);
+        let quorum = new QuorumRule(QUORUM_TRUST_WEIGHTED, 300, new Account().publicKey);
+        let escrowMint = new Account().publicKey;
+        let vault = new Account().publicKey;
+        let unit = new UnitOfMeasure("kg", 1);
+        let eligibility = new EligibilityRule(ELIGIBILITY_DENY_LIST, [new Account().publicKey]);
+        let database = new ResourceDatabase(true, BigInt(10), intents, instances, challenges, claims, window, false, quorum, 2, escrowMint,
+            vault, [instances[0].from], [claims[0]], unit, eligibility, ALLOCATION_MAX_MIN_FAIR, new Draw(BigInt(41), new Uint8Array(32).fill(4), BigInt(40)),
+            1, new Waitlist(new Account().publicKey, new Account().publicKey, 3), new Account().publicKey,
+            new Account().publicKey);
 
         const arr = toBorsh(database);
+        expect(arr).toHaveLength(6010);
 
         const buffer = Buffer.from(arr);
         const typed = toTyped(ResourceDatabase, buffer);
         expect(database).toStrictEqual(typed);
+        expect(typed.window.deadline()).toEqual(1600086400);
+        expect(typed.quorum.threshold).toEqual(300);
+        expect(typed.round).toEqual(2);
+        expect(typed.escrowMint).toEqual(escrowMint);
+        expect(typed.isEscrowed()).toBe(true);
+        expect(typed.hasVault()).toBe(true);
+        expect(typed.refunded).toEqual([instances[0].from]);
+        expect(typed.receipts).toEqual([claims[0]]);
+        expect(typed.unit).toStrictEqual(unit);
+        expect(typed.eligibility).toStrictEqual(eligibility);
+        expect(typed.strategy).toEqual(ALLOCATION_MAX_MIN_FAIR);
+        expect(typed.draw.slot).toEqual(BigInt(41));
+        expect(typed.draw.committedSlot).toEqual(BigInt(40));
+        expect(typed.draw.isDrawn()).toBe(true);
+        expect(new Draw(BigInt(0), new Uint8Array(32), BigInt(40)).isDrawn()).toBe(false);
+        expect(typed.allocation(intents[0].recipient)).toEqual(BigInt(10));
+        expect(typed.allocation(claims[0])).toEqual(BigInt(0));
+        expect(typed.slots()).toEqual(1);
+        expect(typed.waitlist.waiting).toEqual(3);
+        expect(typed.waitlist.hasPages()).toBe(true);
+    })
+
+    test('can deserialize WaitlistPage', () => {
+        let database = new Account().publicKey;
+        let recipient = new Account().publicKey;
+        let buffer = Buffer.alloc(WAITLIST_PAGE_SPACE);
+        expect(buffer).toHaveLength(264);
+
+        // the intent waits in the second slot, the first was promoted
+        database.toBuffer().copy(buffer, 0);
+        recipient.toBuffer().copy(buffer, 64 + 50);
+        buffer.writeUInt32LE(5, 64 + 50 + 32);
+        const typed: WaitlistPage = toTyped(WaitlistPage, buffer);
+        expect(typed).toStrictEqual(new WaitlistPage(database, EMPTY_PUBLIC_KEY, [new Intent(recipient, new Need(BigInt(5), 0), BigInt(0))]));
+    })
+
+    test('can deserialize ClaimReceipt', () => {
+        let database = new Account().publicKey;
+        let recipient = new Account().publicKey;
+        let buffer = Buffer.alloc(CLAIM_RECEIPT_SPACE);
+        expect(buffer).toHaveLength(93);
+        expect(toTyped(ClaimReceipt, buffer).isIssued()).toBe(false);
+
+        database.toBuffer().copy(buffer, 0);
+        recipient.toBuffer().copy(buffer, 32);
+        buffer.writeUInt32LE(3, 64);
+        buffer.writeUInt32LE(25, 68);
+        buffer.write("kg", 76);
+        buffer.writeUInt8(1, 92);
+        const typed: ClaimReceipt = toTyped(ClaimReceipt, buffer);
+        expect(typed).toStrictEqual(new ClaimReceipt(database, recipient, 3, BigInt(25), new UnitOfMeasure("kg", 1)));
+        expect(typed.unit.format(typed.quantity)).toEqual("2.5 kg");
+        expect(typed.isIssued()).toBe(true);
+    })
+
+    test('can deserialize RoundHistory', () => {
+        const RECORD_SPACE = 4918;
+        let database = new Account().publicKey;
+        let alice = new Account().publicKey;
+        let bob = new Account().publicKey;
+        let buffer = Buffer.alloc(ROUND_HISTORY_SPACE);
+        expect(buffer).toHaveLength(19708);
+        database.toBuffer().copy(buffer, 0);
+        buffer.writeUInt32LE(5, 32);
+        // rounds 1 to 4, round 4 took round 0's slot
+        [4, 1, 2, 3].forEach((round, slot) => buffer.writeUInt32LE(round, 36 + slot * RECORD_SPACE));
+        let latest = 36;
+        buffer.writeUInt32LE(5, latest + 28);
+        // intents of 50 bytes: recipient, requested, household size and allocation
+        alice.toBuffer().copy(buffer, latest + 36);
+        buffer.writeUInt32LE(3, latest + 36 + 42);
+        bob.toBuffer().copy(buffer, latest + 86);
+        buffer.writeUInt16LE(1, latest + 86 + 40);
+        buffer.writeUInt32LE(2, latest + 86 + 42);
+        alice.toBuffer().copy(buffer, latest + 436);
+        bob.toBuffer().copy(buffer, latest + 692);
+        alice.toBuffer().copy(buffer, latest + 692 + 32);
+        buffer.writeUInt8(1, latest + 692 + 64);
+        buffer.write("loaves", latest + 4852);
+        // the lottery it was committed to slot 40 and drawn with, slot 41 and its hash
+        buffer.writeUInt8(ALLOCATION_LOTTERY, latest + 4869);
+        buffer.writeUInt32LE(40, latest + 4870);
+        buffer.writeUInt32LE(41, latest + 4878);
+        buffer.fill(4, latest + 4886, latest + 4918);
+
+        const typed: RoundHistory = toTyped(RoundHistory, buffer);
+        expect(typed.database.equals(database)).toBe(true);
+        expect(typed.rounds.map(record => record.round)).toEqual([1, 2, 3, 4]);
+        expect(typed.get(0)).toBeUndefined();
+        let record = typed.get(4)!;
+        expect(record.allocation(alice)).toEqual(BigInt(3));
+        expect(record.allocation(bob)).toEqual(BigInt(0));
+        expect(record.intents[1]).toStrictEqual(new Intent(bob, new Need(BigInt(0), 1), BigInt(2)));
+        expect(record.approvalsReceived(alice)).toEqual(1);
+        expect(record.challenges).toStrictEqual([new Challenge(bob, alice, true)]);
+        expect(record.unit).toStrictEqual(new UnitOfMeasure("loaves", 0));
+        expect(record.strategy).toEqual(ALLOCATION_LOTTERY);
+        expect(record.draw).toStrictEqual(new Draw(BigInt(41), new Uint8Array(32).fill(4), BigInt(40)));
+        expect(typed.get(1)!.draw.isDrawn()).toBe(false);
+    })
+
+    test('can format UnitOfMeasure', () => {
+        expect(new UnitOfMeasure("kg", 1).format(BigInt(25))).toEqual("2.5 kg");
+        expect(new UnitOfMeasure("kg", 3).format(BigInt(5))).toEqual("0.005 kg");
+        expect(new UnitOfMeasure("loaves", 0).format(BigInt(3))).toEqual("3 loaves");
+        expect(new UnitOfMeasure().format(BigInt(7))).toEqual("7");
+        expect(new UnitOfMeasure("g", 3).format(BigInt("18446744073709551615"))).toEqual("18446744073709551.615 g");
+    })
+
+    test('can serialize QuorumRule', () => {
+        let quorum = new QuorumRule(QUORUM_TRUST_WEIGHTED, 300, new Account().publicKey);
+        const arr = toBorsh(quorum);
+        expect(arr).toHaveLength(35);
+        expect(arr[0]).toEqual(QUORUM_TRUST_WEIGHTED);
+        expect(Buffer.from(arr).readUInt16LE(1)).toEqual(300);
+        expect(toTyped(QuorumRule, Buffer.from(arr))).toStrictEqual(quorum);
+    })
+
+    test('can serialize EligibilityRule', () => {
+        let provider = new Account().publicKey;
+        let rule = new EligibilityRule(ELIGIBILITY_TRUSTED_BY_PROVIDER, [], provider, new Account().publicKey, 3);
+        const arr = toBorsh(rule);
+        expect(arr).toHaveLength(194);
+        expect(arr[0]).toEqual(ELIGIBILITY_TRUSTED_BY_PROVIDER);
+        expect(arr[193]).toEqual(3);
+        expect(toTyped(EligibilityRule, Buffer.from(arr))).toStrictEqual(rule);
+
+        let allowed = [new Account().publicKey, new Account().publicKey];
+        let allowList = new EligibilityRule(ELIGIBILITY_ALLOW_LIST, allowed);
+        expect(toTyped(EligibilityRule, Buffer.from(toBorsh(allowList))).list).toEqual(allowed);
     })
 })
\ No newline at end of file
@@ -7,7 +7,9 @@ import { PublicKey } from "@solana/web3.js";
 import { TextEncoder, TextDecoder } from "web-encoding";
 import {
     EMPTY_PUBLIC_KEY, DEFAULT_TRUST_TABLE_ENTRY, Location, MAX_TRUST_TABLE_SIZE,
-    Resource, ResourceIndex, SearchEngineAccount, TrustTableEntry, Challenge, ResourceInstance, ResourceDatabase
+    Resource, ResourceIndex, SearchEngineAccount, TrustTableEntry, Challenge, ResourceInstance, ResourceDatabase,
+    DistributionWindow, QuorumRule, EligibilityRule, MAX_ELIGIBILITY_LIST_SIZE, Reputation, RoundRecord, RoundHistory, ClaimReceipt, UnitOfMeasure,
+    Need, Intent, Draw, Waitlist, WaitlistPage, MAX_NUM_RECIPIENTS
 } from "./lib-types";
 import { serialize, deserialize } from 'borsh';
 import { types } from "util";
@@ -33,6 +35,8 @@ AllBorshSchemas.set(BorshTrustTableEntry, {
 
 const MAX_FRIENDLY_NAME_SIZE = 32;
 const MAX_NUM_INTENTS = 3;
+// four u32 counters and the next settle slot (u64), kept as bytes like the distribution window
+const REPUTATION_SPACE = 4 * 4 + 8 * MAX_NUM_INTENTS;
 export class BorshSearchEngineAccount extends BorshConstructable { }
 AllBorshSchemas.set(BorshSearchEngineAccount, {
     kind: 'struct',
@@ -40,10 +44,11 @@ AllBorshSchemas.set(BorshSearchEngineAccount, {
         ['friendlyName', [MAX_FRIENDLY_NAME_SIZE]],
         ['trustTable', [PUBLIC_KEY_SIZE + 1]],
         ['intents', [PUBLIC_KEY_SIZE * MAX_NUM_INTENTS]],
+        ['reputation', [REPUTATION_SPACE]],
     ]
 })
 // TODO calculate this using borsh library
-export const SEARCH_ENGINE_ACCOUNT_SPACE = 161;
+export const SEARCH_ENGINE_ACCOUNT_SPACE = 201;
 
 const MAX_ZIP_SIZE = 32;
 export class BorshLocation extends BorshConstructable { }
@@ -73,19 +78,56 @@ export class BorshResourceIndex extends BorshConstructable { }
 AllBorshSchemas.set(BorshResourceIndex, {
     kind: 'struct',
     fields: [
-        ['resources', [RESOURCE_INDEX_ACCOUNT_SPACE]]
+        ['resources', [RESOURCE_INDEX_ACCOUNT_SPACE]],
+        ['authority', [PUBLIC_KEY_SIZE]],
     ]
 })
 
+// quantities are u64, kept as bytes and converted to bigint with readU64/writeU64: a number
+// would round them above Number.MAX_SAFE_INTEGER
+const QUANTITY_SPACE = 8;
+
 export class BorshResourceInstance extends BorshConstructable { }
 AllBorshSchemas.set(BorshResourceInstance, {
     kind: 'struct',
     fields: [
         ['from', [PUBLIC_KEY_SIZE]],
-        ['quantity', 'u8'],
+        ['quantity', [QUANTITY_SPACE]],
+    ]
+})
+const RESOURCE_INSTANCE_SPACE = PUBLIC_KEY_SIZE + QUANTITY_SPACE;
+
+export class BorshNeed extends BorshConstructable { }
+AllBorshSchemas.set(BorshNeed, {
+    kind: 'struct',
+    fields: [
+        ['requested', [QUANTITY_SPACE]],
+        ['householdSize', 'u16'],
+    ]
+})
+const NEED_SPACE = QUANTITY_SPACE + 2;
+
+export class BorshIntent extends BorshConstructable { }
+AllBorshSchemas.set(BorshIntent, {
+    kind: 'struct',
+    fields: [
+        ['recipient', [PUBLIC_KEY_SIZE]],
+        ['need', [NEED_SPACE]],
+        ['allocation', [QUANTITY_SPACE]],
     ]
 })
-const RESOURCE_INSTANCE_SPACE = PUBLIC_KEY_SIZE + 1;
+const INTENT_SPACE = PUBLIC_KEY_SIZE + NEED_SPACE + QUANTITY_SPACE;
+
+const MAX_UNIT_NAME_SIZE = 16;
+export class BorshUnitOfMeasure extends BorshConstructable { }
+AllBorshSchemas.set(BorshUnitOfMeasure, {
+    kind: 'struct',
+    fields: [
+        ['name', [MAX_UNIT_NAME_SIZE]],
+        ['decimals', 'u8'],
+    ]
+})
+const UNIT_OF_MEASURE_SPACE = MAX_UNIT_NAME_SIZE + 1;
 
 export class BorshChallenge extends BorshConstructable { }
 AllBorshSchemas.set(BorshChallenge, {
@@ -98,21 +140,184 @@ AllBorshSchemas.set(BorshChallenge, {
 })
 const CHALLENGE_SPACE = PUBLIC_KEY_SIZE + PUBLIC_KEY_SIZE + 1;
 
-const MAX_NUM_RECIPIENTS = 2;
-const MAX_NUM_RESOURCE_INSTANCES = MAX_NUM_RECIPIENTS;
+// start slot (u64), start timestamp (i64) and duration (i64); borsh-js has no signed integers
+// so the window is kept as bytes and converted with readI64/writeI64
+const DISTRIBUTION_WINDOW_SPACE = 8 + 8 + 8;
+
+function readI64(buffer: Buffer, offset: number): number {
+    return buffer.readInt32LE(offset + 4) * 0x100000000 + buffer.readUInt32LE(offset);
+}
+
+function writeI64(buffer: Buffer, value: number, offset: number) {
+    let high = Math.floor(value / 0x100000000);
+    buffer.writeInt32LE(high, offset + 4);
+    buffer.writeUInt32LE(value - high * 0x100000000, offset);
+}
+
+const U32_RANGE = BigInt(0x100000000);
+
+function readU64(buffer: Buffer, offset: number): bigint {
+    return BigInt(buffer.readUInt32LE(offset + 4)) * U32_RANGE + BigInt(buffer.readUInt32LE(offset));
+}
+
+function writeU64(buffer: Buffer, value: bigint, offset: number) {
+    buffer.writeUInt32LE(Number(value / U32_RANGE), offset + 4);
+    buffer.writeUInt32LE(Number(value % U32_RANGE), offset);
+}
+
+function quantityBytes(quantity: bigint): Uint8Array {
+    let buffer = Buffer.alloc(QUANTITY_SPACE);
+    writeU64(buffer, quantity, 0);
+    return Uint8Array.from(buffer);
+}
+
+export class BorshQuorumRule extends BorshConstructable { }
+AllBorshSchemas.set(BorshQuorumRule, {
+    kind: 'struct',
+    fields: [
+        ['kind', 'u8'],
+        ['threshold', 'u16'],
+        ['trustProgram', [PUBLIC_KEY_SIZE]],
+    ]
+})
+const QUORUM_RULE_SPACE = 1 + 2 + PUBLIC_KEY_SIZE;
+
+export class BorshEligibilityRule extends BorshConstructable { }
+AllBorshSchemas.set(BorshEligibilityRule, {
+    kind: 'struct',
+    fields: [
+        ['kind', 'u8'],
+        ['list', [PUBLIC_KEY_SIZE * MAX_ELIGIBILITY_LIST_SIZE]],
+        ['provider', [PUBLIC_KEY_SIZE]],
+        ['trustProgram', [PUBLIC_KEY_SIZE]],
+        ['minTrust', 'u8'],
+    ]
+})
+const ELIGIBILITY_RULE_SPACE = 1 + PUBLIC_KEY_SIZE * MAX_ELIGIBILITY_LIST_SIZE + 2 * PUBLIC_KEY_SIZE + 1;
+
+// the committed and drawn slots as u64s and the hash, kept as bytes like the window
+const DRAW_SPACE = 8 + 8 + PUBLIC_KEY_SIZE;
+
+function drawBytes(draw: Draw): Uint8Array {
+    let buffer = Buffer.alloc(DRAW_SPACE);
+    writeU64(buffer, draw.committedSlot, 0);
+    writeU64(buffer, draw.slot, 8);
+    buffer.set(draw.hash, 16);
+    return Uint8Array.from(buffer);
+}
+
+function toDraw(bytes: Uint8Array): Draw {
+    let buffer = Buffer.from(bytes);
+    return new Draw(readU64(buffer, 8), Uint8Array.from(buffer.slice(16, DRAW_SPACE)), readU64(buffer, 0));
+}
+
+// head and tail pages and the waiting count (u32), kept as bytes like the draw
+const WAITLIST_SPACE = 2 * PUBLIC_KEY_SIZE + 4;
+
+function waitlistBytes(waitlist: Waitlist): Uint8Array {
+    let buffer = Buffer.alloc(WAITLIST_SPACE);
+    waitlist.head.toBuffer().copy(buffer, 0);
+    waitlist.tail.toBuffer().copy(buffer, PUBLIC_KEY_SIZE);
+    buffer.writeUInt32LE(waitlist.waiting, 2 * PUBLIC_KEY_SIZE);
+    return Uint8Array.from(buffer);
+}
+
+function toWaitlist(bytes: Uint8Array): Waitlist {
+    let buffer = Buffer.from(bytes);
+    return new Waitlist(new PublicKey(buffer.slice(0, PUBLIC_KEY_SIZE)), new PublicKey(buffer.slice(PUBLIC_KEY_SIZE, 2 * PUBLIC_KEY_SIZE)),
+        buffer.readUInt32LE(2 * PUBLIC_KEY_SIZE));
+}
+
+// contributors to one round's pool
+const MAX_NUM_RESOURCE_INSTANCES = 16;
 const MAX_NUM_CHALLENGES = MAX_NUM_RECIPIENTS * MAX_NUM_RECIPIENTS;
 export class BorshResourceDatabase extends BorshConstructable { }
 AllBorshSchemas.set(BorshResourceDatabase, {
     kind: 'struct',
     fields: [
         ['isDistributed', 'u8'],
-        ['finalQuantity', 'u8'],
-        ['intents', [PUBLIC_KEY_SIZE * MAX_NUM_RECIPIENTS]],
+        ['finalQuantity', [QUANTITY_SPACE]],
+        ['intents', [INTENT_SPACE * MAX_NUM_RECIPIENTS]],
         ['instances', [RESOURCE_INSTANCE_SPACE * MAX_NUM_RESOURCE_INSTANCES]],
         ['challenges', [CHALLENGE_SPACE * MAX_NUM_CHALLENGES]],
         ['claims', [PUBLIC_KEY_SIZE * MAX_NUM_RECIPIENTS]],
+        ['window', [DISTRIBUTION_WINDOW_SPACE]],
+        ['isFinalized', 'u8'],
+        ['quorum', [QUORUM_RULE_SPACE]],
+        ['round', 'u32'],
+        ['escrowMint', [PUBLIC_KEY_SIZE]],
+        ['vault', [PUBLIC_KEY_SIZE]],
+        ['refunded', [MAX_NUM_RESOURCE_INSTANCES]],
+        ['receipts', [MAX_NUM_RECIPIENTS]],
+        ['unit', [UNIT_OF_MEASURE_SPACE]],
+        ['eligibility', [ELIGIBILITY_RULE_SPACE]],
+        ['strategy', 'u8'],
+        ['draw', [DRAW_SPACE]],
+        ['capacity', 'u8'],
+        ['waitlist', [WAITLIST_SPACE]],
+        ['history', [PUBLIC_KEY_SIZE]],
+        ['authority', [PUBLIC_KEY_SIZE]],
+    ]
+})
+
+// pages are created with this much space and chained by addWaitlistPage
+const WAITLIST_PAGE_CAPACITY = 4;
+export class BorshWaitlistPage extends BorshConstructable { }
+AllBorshSchemas.set(BorshWaitlistPage, {
+    kind: 'struct',
+    fields: [
+        ['database', [PUBLIC_KEY_SIZE]],
+        ['next', [PUBLIC_KEY_SIZE]],
+        ['intents', [INTENT_SPACE * WAITLIST_PAGE_CAPACITY]],
+    ]
+})
+export const WAITLIST_PAGE_SPACE = 2 * PUBLIC_KEY_SIZE + INTENT_SPACE * WAITLIST_PAGE_CAPACITY;
+
+export const LAMPORT_VAULT_SPACE = PUBLIC_KEY_SIZE;
+
+export class BorshClaimReceipt extends BorshConstructable { }
+AllBorshSchemas.set(BorshClaimReceipt, {
+    kind: 'struct',
+    fields: [
+        ['database', [PUBLIC_KEY_SIZE]],
+        ['recipient', [PUBLIC_KEY_SIZE]],
+        ['round', 'u32'],
+        ['quantity', [QUANTITY_SPACE]],
+        ['unit', [UNIT_OF_MEASURE_SPACE]],
+    ]
+})
+export const CLAIM_RECEIPT_SPACE = 2 * PUBLIC_KEY_SIZE + 4 + QUANTITY_SPACE + UNIT_OF_MEASURE_SPACE;
+
+export class BorshRoundRecord extends BorshConstructable { }
+AllBorshSchemas.set(BorshRoundRecord, {
+    kind: 'struct',
+    fields: [
+        ['round', 'u32'],
+        ['window', [DISTRIBUTION_WINDOW_SPACE]],
+        ['finalQuantity', [QUANTITY_SPACE]],
+        ['intents', [INTENT_SPACE * MAX_NUM_RECIPIENTS]],
+        ['claims', [PUBLIC_KEY_SIZE * MAX_NUM_RECIPIENTS]],
+        ['challenges', [CHALLENGE_SPACE * MAX_NUM_CHALLENGES]],
+        ['unit', [UNIT_OF_MEASURE_SPACE]],
+        ['strategy', 'u8'],
+        ['draw', [DRAW_SPACE]],
     ]
 })
+const ROUND_RECORD_SPACE = 4 + DISTRIBUTION_WINDOW_SPACE + QUANTITY_SPACE + (INTENT_SPACE + PUBLIC_KEY_SIZE) * MAX_NUM_RECIPIENTS
+    + CHALLENGE_SPACE * MAX_NUM_CHALLENGES + UNIT_OF_MEASURE_SPACE + 1 + DRAW_SPACE;
+
+// round n is kept at n % MAX_NUM_ROUNDS, so only the last MAX_NUM_ROUNDS rounds are readable
+const MAX_NUM_ROUNDS = 4;
+export class BorshRoundHistory extends BorshConstructable { }
+AllBorshSchemas.set(BorshRoundHistory, {
+    kind: 'struct',
+    fields: [
+        ['database', [PUBLIC_KEY_SIZE]],
+        ['nextRound', 'u32'],
+        ['rounds', [ROUND_RECORD_SPACE * MAX_NUM_ROUNDS]],
+    ]
+})
+export const ROUND_HISTORY_SPACE = PUBLIC_KEY_SIZE + 4 + ROUND_RECORD_SPACE * MAX_NUM_ROUNDS;
 
 function paddedString(str: string, len: number): Uint8Array {
     let name = new Uint8Array(len);
@@ -165,11 +370,21 @@ function toBorsh(libObject: any): Uint8Array {
             intents.set(Uint8Array.from(libObject.intents[intentIndex].toBuffer()), intentIndex * PUBLIC_KEY_SIZE);
             intentIndex += 1;
         }
+        // the program keeps the stored reputation, this only fills the layout
+        let reputation = Buffer.alloc(REPUTATION_SPACE);
+        reputation.writeUInt32LE(libObject.reputation.approvalsGiven, 0);
+        reputation.writeUInt32LE(libObject.reputation.approvalsReceived, 4);
+        reputation.writeUInt32LE(libObject.reputation.claims, 8);
+        reputation.writeUInt32LE(libObject.reputation.disputes, 12);
+        for (let slotIndex = 0; slotIndex < MAX_NUM_INTENTS && slotIndex < libObject.reputation.nextSettleSlots.length; slotIndex++) {
+            writeI64(reputation, libObject.reputation.nextSettleSlots[slotIndex], 16 + slotIndex * 8);
+        }
         return serialize(AllBorshSchemas,
             new BorshSearchEngineAccount({
                 friendlyName: name,
                 trustTable: trustTableEntries,
                 intents: intents,
+                reputation: reputation,
             })
         );
     } else if (libObject instanceof Resource) {
@@ -199,6 +414,7 @@ function toBorsh(libObject: any): Uint8Array {
         return serialize(AllBorshSchemas,
             new BorshResourceIndex({
                 resources: resources,
+                authority: Uint8Array.from(libObject.authority.toBuffer()),
             })
         );
     } else if (libObject instanceof Challenge) {
@@ -207,41 +423,98 @@ function toBorsh(libObject: any): Uint8Array {
             to: Uint8Array.from(libObject.toAddress.toBuffer()),
             value: libObject.accepted,
         }));
+    } else if (libObject instanceof QuorumRule) {
+        return serialize(AllBorshSchemas, new BorshQuorumRule({
+            kind: libObject.kind,
+            threshold: libObject.threshold,
+            trustProgram: Uint8Array.from(libObject.trustProgram.toBuffer()),
+        }));
+    } else if (libObject instanceof EligibilityRule) {
+        let list = new Uint8Array(PUBLIC_KEY_SIZE * MAX_ELIGIBILITY_LIST_SIZE);
+        libObject.list.forEach((key, index) => {
+            list.set(Uint8Array.from(key.toBuffer()), index * PUBLIC_KEY_SIZE)
+        });
+        return serialize(AllBorshSchemas, new BorshEligibilityRule({
+            kind: libObject.kind,
+            list: list,
+            provider: Uint8Array.from(libObject.provider.toBuffer()),
+            trustProgram: Uint8Array.from(libObject.trustProgram.toBuffer()),
+            minTrust: libObject.minTrust,
+        }));
     } else if (libObject instanceof ResourceInstance) {
         return serialize(AllBorshSchemas, new BorshResourceInstance({
             from: Uint8Array.from(libObject.from.toBuffer()),
-            quantity: libObject.quantity,
+            quantity: quantityBytes(libObject.quantity),
+        }));
+    } else if (libObject instanceof Need) {
+        return serialize(AllBorshSchemas, new BorshNeed({
+            requested: quantityBytes(libObject.requested),
+            householdSize: libObject.householdSize,
+        }));
+    } else if (libObject instanceof Intent) {
+        return serialize(AllBorshSchemas, new BorshIntent({
+            recipient: Uint8Array.from(libObject.recipient.toBuffer()),
+            need: toBorsh(libObject.need),
+            allocation: quantityBytes(libObject.allocation),
+        }));
+    } else if (libObject instanceof UnitOfMeasure) {
+        return serialize(AllBorshSchemas, new BorshUnitOfMeasure({
+            name: paddedString(libObject.name, MAX_UNIT_NAME_SIZE),
+            decimals: libObject.decimals,
         }));
     } else if (libObject instanceof ResourceDatabase) {
-        let intents = new Uint8Array(64);
+        let intents = new Uint8Array(INTENT_SPACE * MAX_NUM_RECIPIENTS);
         libObject.intents.forEach((intent, index) => {
-            intents.set(Uint8Array.from(intent.toBuffer()), index * PUBLIC_KEY_SIZE)
+            intents.set(toBorsh(intent), index * INTENT_SPACE)
         });
 
-        let instances = new Uint8Array(66);
+        let instances = new Uint8Array(RESOURCE_INSTANCE_SPACE * MAX_NUM_RESOURCE_INSTANCES);
         libObject.instances.forEach((instance, index) => {
             let serialized = toBorsh(instance);
             instances.set(serialized, index * RESOURCE_INSTANCE_SPACE);
         })
 
-        let challeneges = new Uint8Array(260);
+        let challeneges = new Uint8Array(CHALLENGE_SPACE * MAX_NUM_CHALLENGES);
         libObject.challenges.forEach((challenge, index) => {
             let serialized = toBorsh(challenge);
             challeneges.set(serialized, index * CHALLENGE_SPACE);
         });
 
-        let claims = new Uint8Array(64);
+        let claims = new Uint8Array(PUBLIC_KEY_SIZE * MAX_NUM_RECIPIENTS);
         libObject.claims.forEach((claim, index) => {
             claims.set(Uint8Array.from(claim.toBuffer()), index * PUBLIC_KEY_SIZE)
         });
 
+        let window = Buffer.alloc(DISTRIBUTION_WINDOW_SPACE);
+        writeI64(window, libObject.window.startSlot, 0);
+        writeI64(window, libObject.window.startTimestamp, 8);
+        writeI64(window, libObject.window.duration, 16);
+
         return serialize(AllBorshSchemas, new BorshResourceDatabase({
             isDistributed: libObject.isDistributed,
-            finalQuantity: libObject.finalQuantity,
+            finalQuantity: quantityBytes(libObject.finalQuantity),
             intents: intents,
             instances: instances,
             challenges: challeneges,
             claims: claims,
+            window: Uint8Array.from(window),
+            isFinalized: libObject.isFinalized,
+            quorum: toBorsh(libObject.quorum),
+            round: libObject.round,
+            escrowMint: Uint8Array.from(libObject.escrowMint.toBuffer()),
+            vault: Uint8Array.from(libObject.vault.toBuffer()),
+            refunded: Uint8Array.from(libObject.instances.map(instance => libObject.refunded.some(key => key.equals(instance.from)) ? 1 : 0)
+                .concat(new Array(MAX_NUM_RESOURCE_INSTANCES - libObject.instances.length).fill(0))),
+            receipts: Uint8Array.from(libObject.claims.map(claim => libObject.receipts.some(key => key.equals(claim)) ? 1 : 0)
+                .concat(new Array(MAX_NUM_RECIPIENTS - libObject.claims.length).fill(0))),
+            unit: toBorsh(libObject.unit),
+            eligibility: toBorsh(libObject.eligibility),
+            strategy: libObject.strategy,
+            draw: drawBytes(libObject.draw),
+            capacity: libObject.capacity,
+            waitlist: waitlistBytes(libObject.waitlist),
+            history: Uint8Array.from(libObject.history.toBuffer()),
+            authority: Uint8Array.from(libObject.authority.toBuffer()),
         }));
     } else if (libObject === undefined) {
         throw new Error("undefined passed to toBorsh. This is probably from an assumption in a specific if-else block of toBorsh (arrays are certain size, certain fields set)");
@@ -250,6 +523,34 @@ function toBorsh(libObject: any): Uint8Array {
     }
 }
 
+// fixed size key arrays leave unused slots zeroed
+function toPublicKeys(keys: Uint8Array, count: number): Array<PublicKey> {
+    let publicKeys = [];
+    let keyIndex = 0;
+    while (keyIndex < count) {
+        let key = new PublicKey(keys.slice(keyIndex * PUBLIC_KEY_SIZE, keyIndex * PUBLIC_KEY_SIZE + PUBLIC_KEY_SIZE));
+        if (key.toBase58() !== EMPTY_PUBLIC_KEY.toBase58()) {
+            publicKeys.push(key);
+        }
+        keyIndex += 1;
+    }
+    return publicKeys;
+}
+
+// like toPublicKeys, for the intents of a database or an archived round
+function toIntents(intents: Uint8Array, count: number): Array<Intent> {
+    let typedIntents = [];
+    let intentIndex = 0;
+    while (intentIndex < count) {
+        let typed = toTyped(Intent, Buffer.from(intents).slice(intentIndex * INTENT_SPACE, intentIndex * INTENT_SPACE + INTENT_SPACE));
+        if (typed.recipient.toBase58() !== EMPTY_PUBLIC_KEY.toBase58()) {
+            typedIntents.push(typed);
+        }
+        intentIndex += 1;
+    }
+    return typedIntents;
+}
+
 function toTyped(t: any, borshBuffer: Buffer): any {
     if (t === TrustTableEntry) {
         let deserialized = deserialize(AllBorshSchemas, BorshTrustTableEntry, borshBuffer)
@@ -273,7 +574,19 @@ function toTyped(t: any, borshBuffer: Buffer): any {
             }
             intentIndex += 1;
         }
-        return new SearchEngineAccount(friendlyName, trustTable, intents);
+        let reputationBuffer = Buffer.from(deserialized.reputation);
+        let nextSettleSlots: Array<number> = [];
+        for (let slotIndex = 0; slotIndex < MAX_NUM_INTENTS; slotIndex++) {
+            nextSettleSlots.push(readI64(reputationBuffer, 16 + slotIndex * 8));
+        }
+        let reputation = new Reputation(
+            reputationBuffer.readUInt32LE(0),
+            reputationBuffer.readUInt32LE(4),
+            reputationBuffer.readUInt32LE(8),
+            reputationBuffer.readUInt32LE(12),
+            nextSettleSlots,
+        );
+        return new SearchEngineAccount(friendlyName, trustTable, intents, reputation);
     } else if (t === Resource) {
         let deserialized = deserialize(AllBorshSchemas, BorshResource, borshBuffer)
         let name = decodeAndUnescape(deserialized.name);
@@ -304,24 +617,34 @@ function toTyped(t: any, borshBuffer: Buffer): any {
             bucketIndex += 1;
         }
 
-        return new ResourceIndex(map);
+        let authority = new PublicKey(borshBuffer.slice(RESOURCE_INDEX_ACCOUNT_SPACE, RESOURCE_INDEX_ACCOUNT_SPACE + PUBLIC_KEY_SIZE));
+        return new ResourceIndex(map, authority);
     } else if (t === Challenge) {
         let deserialized = deserialize(AllBorshSchemas, BorshChallenge, borshBuffer)
         return new Challenge(new PublicKey(deserialized.from), new PublicKey(deserialized.to), deserialized.value ? true : false);
+    } else if (t === QuorumRule) {
+        let deserialized = deserialize(AllBorshSchemas, BorshQuorumRule, borshBuffer);
+        return new QuorumRule(deserialized.kind, deserialized.threshold, new PublicKey(deserialized.trustProgram));
+    } else if (t === EligibilityRule) {
+        let deserialized = deserialize(AllBorshSchemas, BorshEligibilityRule, borshBuffer);
+        return new EligibilityRule(deserialized.kind, toPublicKeys(deserialized.list, MAX_ELIGIBILITY_LIST_SIZE),
+            new PublicKey(deserialized.provider), new PublicKey(deserialized.trustProgram), deserialized.minTrust);
     } else if (t === ResourceInstance) {
         let deserialized = deserialize(AllBorshSchemas, BorshResourceInstance, borshBuffer);
-        return new ResourceInstance(new PublicKey(deserialized.from), deserialized.quantity);
+        return new ResourceInstance(new PublicKey(deserialized.from), readU64(Buffer.from(deserialized.quantity), 0));
+    } else if (t === Need) {
+        let deserialized = deserialize(AllBorshSchemas, BorshNeed, borshBuffer);
+        return new Need(readU64(Buffer.from(deserialized.requested), 0), deserialized.householdSize);
+    } else if (t === Intent) {
+        let deserialized = deserialize(AllBorshSchemas, BorshIntent, borshBuffer);
+        return new Intent(new PublicKey(deserialized.recipient), toTyped(Need, Buffer.from(deserialized.need)),
+            readU64(Buffer.from(deserialized.allocation), 0));
+    } else if (t === UnitOfMeasure) {
+        let deserialized = deserialize(AllBorshSchemas, BorshUnitOfMeasure, borshBuffer);
+        return new UnitOfMeasure(decodeAndUnescape(deserialized.name), deserialized.decimals);
     } else if (t === ResourceDatabase) {
         let deserialized = deserialize(AllBorshSchemas, BorshResourceDatabase, borshBuffer);
-        let intents = [];
-        let intentIndex = 0;
-        while (intentIndex < MAX_NUM_RECIPIENTS) {
-            let key = new PublicKey(deserialized.intents.slice(intentIndex * PUBLIC_KEY_SIZE, intentIndex * PUBLIC_KEY_SIZE + PUBLIC_KEY_SIZE));
-            if (key.toBase58() !== EMPTY_PUBLIC_KEY.toBase58()) {
-                intents.push(key);
-            }
-            intentIndex += 1;
-        }
+        let intents = toIntents(deserialized.intents, MAX_NUM_RECIPIENTS);
         let instances = [];
         let instanceIndex = 0;
         while (instanceIndex < MAX_NUM_RESOURCE_INSTANCES) {
@@ -349,7 +672,54 @@ function toTyped(t: any, borshBuffer: Buffer): any {
             }
             claimIndex += 1;
         }
-        return new ResourceDatabase(deserialized.isDistributed ? true : false, deserialized.finalQuantity, intents, instances, challenges, claims);
+        let windowBuffer = Buffer.from(deserialized.window);
+        let window = new DistributionWindow(readI64(windowBuffer, 0), readI64(windowBuffer, 8), readI64(windowBuffer, 16));
+        return new ResourceDatabase(deserialized.isDistributed ? true : false, readU64(Buffer.from(deserialized.finalQuantity), 0), intents, instances, challenges, claims,
+            window, deserialized.isFinalized ? true : false, toTyped(QuorumRule, Buffer.from(deserialized.quorum)), deserialized.round,
+            new PublicKey(deserialized.escrowMint), new PublicKey(deserialized.vault),
+            instances.filter((instance, index) => deserialized.refunded[index]).map(instance => instance.from),
+            claims.filter((claim, index) => deserialized.receipts[index]), toTyped(UnitOfMeasure, Buffer.from(deserialized.unit)),
+            toTyped(EligibilityRule, Buffer.from(deserialized.eligibility)), deserialized.strategy, toDraw(deserialized.draw),
+            deserialized.capacity, toWaitlist(deserialized.waitlist), new PublicKey(deserialized.history),
+            new PublicKey(deserialized.authority));
+    } else if (t === WaitlistPage) {
+        let deserialized = deserialize(AllBorshSchemas, BorshWaitlistPage, borshBuffer);
+        return new WaitlistPage(new PublicKey(deserialized.database), new PublicKey(deserialized.next),
+            toIntents(deserialized.intents, WAITLIST_PAGE_CAPACITY));
+    } else if (t === RoundRecord) {
+        let deserialized = deserialize(AllBorshSchemas, BorshRoundRecord, borshBuffer);
+        let challenges = [];
+        let challengeIndex = 0;
+        while (challengeIndex < MAX_NUM_CHALLENGES) {
+            let typed = toTyped(Challenge, Buffer.from(deserialized.challenges).slice(challengeIndex * CHALLENGE_SPACE, challengeIndex * CHALLENGE_SPACE + CHALLENGE_SPACE));
+            if (typed.fromAddress.toBase58() !== EMPTY_PUBLIC_KEY.toBase58()) {
+                challenges.push(typed);
+            }
+            challengeIndex += 1;
+        }
+        let windowBuffer = Buffer.from(deserialized.window);
+        let window = new DistributionWindow(readI64(windowBuffer, 0), readI64(windowBuffer, 8), readI64(windowBuffer, 16));
+        return new RoundRecord(deserialized.round, window, readU64(Buffer.from(deserialized.finalQuantity), 0), toIntents(deserialized.intents, MAX_NUM_RECIPIENTS),
+            toPublicKeys(deserialized.claims, MAX_NUM_RECIPIENTS), challenges, toTyped(UnitOfMeasure, Buffer.from(deserialized.unit)),
+            deserialized.strategy, toDraw(deserialized.draw));
+    } else if (t === RoundHistory) {
+        let deserialized = deserialize(AllBorshSchemas, BorshRoundHistory, borshBuffer);
+        let rounds = [];
+        // oldest first, skipping slots that hold a round from before a ResetDatabase
+        let round = Math.max(0, deserialized.nextRound - MAX_NUM_ROUNDS);
+        while (round < deserialized.nextRound) {
+            let offset = (round % MAX_NUM_ROUNDS) * ROUND_RECORD_SPACE;
+            let record = toTyped(RoundRecord, Buffer.from(deserialized.rounds).slice(offset, offset + ROUND_RECORD_SPACE));
+            if (record.round === round) {
+                rounds.push(record);
+            }
+            round += 1;
+        }
+        return new RoundHistory(new PublicKey(deserialized.database), deserialized.nextRound, rounds);
+    } else if (t === ClaimReceipt) {
+        let deserialized = deserialize(AllBorshSchemas, BorshClaimReceipt, borshBuffer);
+        return new ClaimReceipt(new PublicKey(deserialized.database), new PublicKey(deserialized.recipient), deserialized.round,
+            readU64(Buffer.from(deserialized.quantity), 0), toTyped(UnitOfMeasure, Buffer.from(deserialized.unit)));
     } else {
         throw new Error("type not supported. add a custom Borsh object in lib-serialization. also make sure server side supports this type");
     }
@@ -20,8 +20,8 @@ Search Engine
 fields
 * resources Map<Location, Resource>
 operations
-* register_resource(accounts:[owner, program], data:None)
-  auth: accounts[0].is_signer == true and accounts[1].owner ==  accounts[0]
+* register_resource(accounts:[database, authority], data:Resource)
+  auth: searchengine_id == accounts[0].owner and database.authority == accounts[1] and accounts[1].is_signer == true
 * list_resources(accounts:[], data:Location)
   auth: none
 * update_trust_table(accounts:[owner], data:TrustTable)
@@ -36,10 +36,10 @@ Resources
   auth: none
 * register_intent(accounts:[search_engine, on_behalf_of], data:None)
   auth: searchengine_id == accounts[0] and accounts[0].is_signer == true
-* record_instance(accounts:[data_account], data:ResourceInstance)
-  auth: program_id == accounts[0].owner and accounts[0].is_signer == true
-* initiate_distribution(accounts:[program_owner])
-  auth: contract.owner == accounts[0] and accounts[0].is_signer == true
+* record_instance(accounts:[data_account, provider], data:ResourceInstance)
+  auth: program_id == accounts[0].owner and instance.from == accounts[1] and accounts[1].is_signer == true
+* initiate_distribution(accounts:[database, authority, clock], data:duration)
+  auth: database.authority == accounts[1] and accounts[1].is_signer == true
 * approve_challenge(accounts:[owner, challenger], data:None)
   auth: searchengine_id == accounts[0].owner and searchengine_id == accounts[1].owner and accounts[0].is_signer == true
 * deny_challenge(accounts:[owner, challenger], data:None)
@@ -52,6 +52,8 @@ export const SE_INSTRUCTION_DEFAULT = 0;
 export const SE_INSTRUCTION_UPDATE_ACCOUNT = 1;
 export const SE_INSTRUCTION_REGISTER_RESOURCE = 2;
 export const SE_INSTRUCTION_REGISTER_INTENT = 3;
+export const SE_INSTRUCTION_INITIALIZE_DATABASE = 4;
+export const SE_INSTRUCTION_SETTLE_INTENT = 5;
 
 export const RESOURCE_INSTRUCTION_DEFAULT = 0;
 export const RESOURCE_INSTRUCTION_REGISTER_INTENT = 1;
@@ -60,8 +62,49 @@ export const RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION = 3;
 export const RESOURCE_INSTRUCTION_RECORD_CHALLENGE = 4;
 export const RESOURCE_INSTRUCTION_CLAIM_CHALLENGE = 5;
 export const RESOURCE_INSTRUCTION_RESET_DATABASE = 6;
+export const RESOURCE_INSTRUCTION_FINALIZE_DISTRIBUTION = 7;
+export const RESOURCE_INSTRUCTION_SET_QUORUM = 8;
+export const RESOURCE_INSTRUCTION_START_NEW_ROUND = 9;
+export const RESOURCE_INSTRUCTION_SET_ESCROW_MINT = 10;
+export const RESOURCE_INSTRUCTION_SET_LAMPORT_VAULT = 11;
+export const RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION = 12;
+export const RESOURCE_INSTRUCTION_ISSUE_RECEIPT = 13;
+export const RESOURCE_INSTRUCTION_SET_UNIT = 14;
+export const RESOURCE_INSTRUCTION_SET_ELIGIBILITY = 15;
+export const RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY = 16;
+export const RESOURCE_INSTRUCTION_SET_CAPACITY = 17;
+export const RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE = 18;
+export const RESOURCE_INSTRUCTION_WITHDRAW_INTENT = 19;
+export const RESOURCE_INSTRUCTION_DRAW_LOTTERY = 20;
+
+export const QUORUM_ALL = 0;
+export const QUORUM_MAJORITY = 1;
+export const QUORUM_AT_LEAST = 2;
+export const QUORUM_TRUST_WEIGHTED = 3;
+export const QUORUM_REPUTATION_WEIGHTED = 4;
+
+export const ELIGIBILITY_OPEN = 0;
+export const ELIGIBILITY_ALLOW_LIST = 1;
+export const ELIGIBILITY_DENY_LIST = 2;
+export const ELIGIBILITY_TRUSTED_BY_PROVIDER = 3;
+export const MAX_ELIGIBILITY_LIST_SIZE = 4;
+
+// how initiateDistribution splits the pool, computed the same way by allocate in lib-allocation
+export const ALLOCATION_PROPORTIONAL = 0;
+export const ALLOCATION_EQUAL_SPLIT = 1;
+export const ALLOCATION_MAX_MIN_FAIR = 2;
+export const ALLOCATION_FIRST_COME_FIRST_SERVED = 3;
+export const ALLOCATION_LOTTERY = 4;
+
+// recipient slots of a database, a capacity can take fewer of them per round
+export const MAX_NUM_RECIPIENTS = 8;
 
 export let EMPTY_PUBLIC_KEY = new PublicKey(new Uint8Array(32));
+export let TOKEN_PROGRAM_ID = new PublicKey('TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA');
+// the recent slot hashes a lottery is drawn with, newest first
+export let SYSVAR_SLOT_HASHES_PUBKEY = new PublicKey('SysvarS1otHashes111111111111111111111111111');
+// seed of the escrow authority, together with the database key
+export const ESCROW_SEED = 'escrow';
 
 export class TrustTableEntry {
   id: PublicKey;
@@ -76,28 +119,100 @@ export let DEFAULT_TRUST_TABLE_ENTRY = new TrustTableEntry(new PublicKey("FFFFFF
 
 export let MAX_TRUST_TABLE_SIZE = 1;
 
+// what an account did in settled distributions. only the search engine writes it, when an intent
+// is settled, and each distribution is counted once (nextSettleSlots, one per intent)
+export class Reputation {
+  approvalsGiven: number;
+  approvalsReceived: number;
+  claims: number;
+  disputes: number;
+  nextSettleSlots: Array<number>;
+
+  constructor(approvalsGiven: number, approvalsReceived: number, claims: number, disputes: number, nextSettleSlots: Array<number>) {
+    this.approvalsGiven = approvalsGiven;
+    this.approvalsReceived = approvalsReceived;
+    this.claims = claims;
+    this.disputes = disputes;
+    this.nextSettleSlots = nextSettleSlots;
+  }
+
+  score(): number {
+    return Math.max(0, this.approvalsGiven + this.approvalsReceived + this.claims - this.disputes);
+  }
+}
+
 export class SearchEngineAccount {
   friendlyName: string;
   trustTable: Array<TrustTableEntry>;
   intents: Array<PublicKey>;
+  reputation: Reputation;
 
-  constructor(friendlyName: string, trustTable: Array<TrustTableEntry>, intents: Array<PublicKey>) {
+  constructor(friendlyName: string, trustTable: Array<TrustTableEntry>, intents: Array<PublicKey>,
+    reputation: Reputation = new Reputation(0, 0, 0, 0, [0, 0, 0])) {
     this.friendlyName = friendlyName;
     this.trustTable = trustTable;
     this.intents = intents;
+    this.reputation = reputation;
   }
 }
 
+// quantities are u64 on chain, counted in the smallest fraction of the database's unit, and kept
+// as bigint so none rounds above Number.MAX_SAFE_INTEGER
 export class ResourceInstance {
   from: PublicKey;
-  quantity: number;
+  quantity: bigint;
 
-  constructor(from: PublicKey, quantity: number) {
+  constructor(from: PublicKey, quantity: bigint) {
     this.from = from;
     this.quantity = quantity;
   }
 }
 
+// what a recipient asks for with their intent: `requested` in the database's unit, or with none
+// requested an even share of the pool for each of the `householdSize` people they ask for
+export class Need {
+  requested: bigint;
+  householdSize: number;
+
+  constructor(requested: bigint = BigInt(0), householdSize: number = 0) {
+    this.requested = requested;
+    this.householdSize = householdSize;
+  }
+}
+
+// a registered recipient, their allocation is set when the distribution is initiated
+export class Intent {
+  recipient: PublicKey;
+  need: Need;
+  allocation: bigint;
+
+  constructor(recipient: PublicKey, need: Need = new Need(), allocation: bigint = BigInt(0)) {
+    this.recipient = recipient;
+    this.need = need;
+    this.allocation = allocation;
+  }
+}
+
+// what quantities count: a quantity q is q / 10^decimals of `name`, so 25 with one decimal of
+// "kg" is 2.5 kg
+export class UnitOfMeasure {
+  name: string;
+  decimals: number;
+
+  constructor(name: string = "", decimals: number = 0) {
+    this.name = name;
+    this.decimals = decimals;
+  }
+
+  format(quantity: bigint): string {
+    let digits = quantity.toString().padStart(this.decimals + 1, "0");
+    let whole = digits.slice(0, digits.length - this.decimals);
+    let fraction = digits.slice(digits.length - this.decimals);
+    let amount = fraction.length ? whole + "." + fraction : whole;
+    return this.name ? amount + " " + this.name : amount;
+  }
+}
+
 export class Challenge {
   fromAddress: PublicKey;
   toAddress: PublicKey;
@@ -132,30 +247,295 @@ export class Resource {
   }
 }
 
+// when challenges were opened (cluster clock) and how many seconds they stay open
+export class DistributionWindow {
+  startSlot: number;
+  startTimestamp: number;
+  duration: number;
+
+  constructor(startSlot: number, startTimestamp: number, duration: number) {
+    this.startSlot = startSlot;
+    this.startTimestamp = startTimestamp;
+    this.duration = duration;
+  }
+
+  deadline(): number {
+    return this.startTimestamp + this.duration;
+  }
+}
+
+// how many of a recipient's peers have to approve them before they can claim. threshold is a
+// number of approvals (QUORUM_AT_LEAST), summed trust (QUORUM_TRUST_WEIGHTED) or summed reputation
+// score (QUORUM_REPUTATION_WEIGHTED), and both are read from accounts owned by trustProgram
+export class QuorumRule {
+  kind: number;
+  threshold: number;
+  trustProgram: PublicKey;
+
+  constructor(kind: number, threshold: number, trustProgram: PublicKey) {
+    this.kind = kind;
+    this.threshold = threshold;
+    this.trustProgram = trustProgram;
+  }
+}
+
+// who may register an intent: anyone, the keys of `list` only, anyone but them,
+// or whoever `provider` trusts at `minTrust` or more in its `trustProgram` trust table
+export class EligibilityRule {
+  kind: number;
+  list: Array<PublicKey>;
+  provider: PublicKey;
+  trustProgram: PublicKey;
+  minTrust: number;
+
+  constructor(kind: number = ELIGIBILITY_OPEN, list: Array<PublicKey> = [], provider: PublicKey = EMPTY_PUBLIC_KEY,
+    trustProgram: PublicKey = EMPTY_PUBLIC_KEY, minTrust: number = 0) {
+    this.kind = kind;
+    this.list = list;
+    this.provider = provider;
+    this.trustProgram = trustProgram;
+    this.minTrust = minTrust;
+  }
+
+  isTrustedByProvider(): boolean {
+    return this.kind === ELIGIBILITY_TRUSTED_BY_PROVIDER;
+  }
+}
+
+// the slot initiateDistribution committed a lottery to, and the SlotHashes entry drawLottery drew it
+// with: the first slot at or after the committed one. Zero without a lottery, slot and hash zero
+// until drawn
+export class Draw {
+  slot: bigint;
+  hash: Uint8Array;
+  committedSlot: bigint;
+
+  constructor(slot: bigint = BigInt(0), hash: Uint8Array = new Uint8Array(32), committedSlot: bigint = BigInt(0)) {
+    this.slot = slot;
+    this.hash = hash;
+    this.committedSlot = committedSlot;
+  }
+
+  isDrawn(): boolean {
+    return this.slot > BigInt(0);
+  }
+}
+
+// where a database's waitlist starts and ends, EMPTY_PUBLIC_KEY while it has no pages
+export class Waitlist {
+  head: PublicKey;
+  tail: PublicKey;
+  // intents waiting over every page
+  waiting: number;
+
+  constructor(head: PublicKey = EMPTY_PUBLIC_KEY, tail: PublicKey = EMPTY_PUBLIC_KEY, waiting: number = 0) {
+    this.head = head;
+    this.tail = tail;
+    this.waiting = waiting;
+  }
+
+  hasPages(): boolean {
+    return !this.tail.equals(EMPTY_PUBLIC_KEY);
+  }
+}
+
+// one page of a waitlist, owned by the resource program and chained to the next by addWaitlistPage
+export class WaitlistPage {
+  database: PublicKey;
+  // EMPTY_PUBLIC_KEY for the tail
+  next: PublicKey;
+  // waiting in the order they registered
+  intents: Array<Intent>;
+
+  constructor(database: PublicKey, next: PublicKey, intents: Array<Intent>) {
+    this.database = database;
+    this.next = next;
+    this.intents = intents;
+  }
+}
+
 export class ResourceDatabase {
   isDistributed: boolean;
-  finalQuantity: number;
-  intents: Array<PublicKey>;
+  // what the recipients were allocated in total, at most what was deposited
+  finalQuantity: bigint;
+  intents: Array<Intent>;
+  // one per contributor, holding everything they put into this round's pool
   instances: Array<ResourceInstance>;
   challenges: Array<Challenge>;
   claims: Array<PublicKey>;
-
-  constructor(isDistributed: boolean, finalQuantity: number, intents: Array<PublicKey>,
-    instances: Array<ResourceInstance>, challenges: Array<Challenge>, claims: Array<PublicKey>) {
+  window: DistributionWindow;
+  isFinalized: boolean;
+  quorum: QuorumRule;
+  round: number;
+  // SPL token mint backing the resource, EMPTY_PUBLIC_KEY when only a quantity is recorded
+  escrowMint: PublicKey;
+  // program owned account holding the lamports of SOL denominated resources, EMPTY_PUBLIC_KEY otherwise
+  vault: PublicKey;
+  // contributors already refunded their share of what was not claimed
+  refunded: Array<PublicKey>;
+  // claimants whose claim already has its receipt
+  receipts: Array<PublicKey>;
+  // what the quantities count, carried over to the next round
+  unit: UnitOfMeasure;
+  // who may register an intent, carried over to the next round
+  eligibility: EligibilityRule;
+  // one of the ALLOCATION_* strategies, picked before anyone registers or records
+  strategy: number;
+  // what a lottery was drawn with, see drawOrder in lib-allocation
+  draw: Draw;
+  // how many intents take part in a round, 0 leaves every slot open
+  capacity: number;
+  // intents registered once every slot up to the capacity was taken
+  waitlist: Waitlist;
+  // where every round is archived, EMPTY_PUBLIC_KEY until the first startNewRound binds it
+  history: PublicKey;
+  // signs every set* call and resets, the payer of the resetDatabase that created the database
+  authority: PublicKey;
+
+  constructor(isDistributed: boolean, finalQuantity: bigint, intents: Array<Intent>,
+    instances: Array<ResourceInstance>, challenges: Array<Challenge>, claims: Array<PublicKey>,
+    window: DistributionWindow, isFinalized: boolean, quorum: QuorumRule, round: number, escrowMint: PublicKey,
+    vault: PublicKey, refunded: Array<PublicKey>, receipts: Array<PublicKey>, unit: UnitOfMeasure,
+    eligibility: EligibilityRule, strategy: number = ALLOCATION_PROPORTIONAL, draw: Draw = new Draw(),
+    capacity: number = 0, waitlist: Waitlist = new Waitlist(), history: PublicKey = EMPTY_PUBLIC_KEY,
+    authority: PublicKey = EMPTY_PUBLIC_KEY) {
     this.isDistributed = isDistributed;
     this.finalQuantity = finalQuantity;
     this.intents = intents;
     this.instances = instances;
     this.challenges = challenges;
     this.claims = claims;
+    this.window = window;
+    this.isFinalized = isFinalized;
+    this.quorum = quorum;
+    this.round = round;
+    this.escrowMint = escrowMint;
+    this.vault = vault;
+    this.refunded = refunded;
+    this.receipts = receipts;
+    this.unit = unit;
+    this.eligibility = eligibility;
+    this.strategy = strategy;
+    this.draw = draw;
+    this.capacity = capacity;
+    this.waitlist = waitlist;
+    this.history = history;
+    this.authority = authority;
+  }
+
+  // the slots open to recipients this round
+  slots(): number {
+    return this.capacity || MAX_NUM_RECIPIENTS;
+  }
+
+  isEscrowed(): boolean {
+    return !this.escrowMint.equals(EMPTY_PUBLIC_KEY);
+  }
+
+  hasVault(): boolean {
+    return !this.vault.equals(EMPTY_PUBLIC_KEY);
+  }
+
+  allocation(recipient: PublicKey): bigint {
+    let intent = this.intents.find(intent => intent.recipient.equals(recipient));
+    return intent ? intent.allocation : BigInt(0);
+  }
+}
+
+// token accounts an escrowed RecordResourceInstance, ClaimChallenge or RefundContribution moves tokens between:
+// `escrow` is owned by the escrow authority, `tokenAccount` by the provider, the claimant or the contributor
+export class EscrowAccounts {
+  escrow: PublicKey;
+  tokenAccount: PublicKey;
+
+  constructor(escrow: PublicKey, tokenAccount: PublicKey) {
+    this.escrow = escrow;
+    this.tokenAccount = tokenAccount;
+  }
+}
+
+// proof of one claim, written by IssueReceipt into an account owned by the resource program
+export class ClaimReceipt {
+  database: PublicKey;
+  recipient: PublicKey;
+  round: number;
+  quantity: bigint;
+  unit: UnitOfMeasure;
+
+  constructor(database: PublicKey, recipient: PublicKey, round: number, quantity: bigint, unit: UnitOfMeasure) {
+    this.database = database;
+    this.recipient = recipient;
+    this.round = round;
+    this.quantity = quantity;
+    this.unit = unit;
+  }
+
+  isIssued(): boolean {
+    return !this.database.equals(EMPTY_PUBLIC_KEY);
+  }
+}
+
+// a finished round as StartNewRound archived it
+export class RoundRecord {
+  round: number;
+  window: DistributionWindow;
+  finalQuantity: bigint;
+  intents: Array<Intent>;
+  claims: Array<PublicKey>;
+  challenges: Array<Challenge>;
+  unit: UnitOfMeasure;
+  strategy: number;
+  draw: Draw;
+
+  constructor(round: number, window: DistributionWindow, finalQuantity: bigint, intents: Array<Intent>,
+    claims: Array<PublicKey>, challenges: Array<Challenge>, unit: UnitOfMeasure, strategy: number = ALLOCATION_PROPORTIONAL,
+    draw: Draw = new Draw()) {
+    this.round = round;
+    this.window = window;
+    this.finalQuantity = finalQuantity;
+    this.intents = intents;
+    this.claims = claims;
+    this.challenges = challenges;
+    this.unit = unit;
+    this.strategy = strategy;
+    this.draw = draw;
+  }
+
+  allocation(recipient: PublicKey): bigint {
+    let intent = this.intents.find(intent => intent.recipient.equals(recipient));
+    return intent && this.claims.some(claim => claim.equals(recipient)) ? intent.allocation : BigInt(0);
+  }
+
+  approvalsReceived(recipient: PublicKey): number {
+    return this.challenges.filter(challenge => challenge.toAddress.equals(recipient) && challenge.accepted).length;
+  }
+}
+
+// the last rounds of one database, oldest first. rounds that were overwritten are left out
+export class RoundHistory {
+  database: PublicKey;
+  nextRound: number;
+  rounds: Array<RoundRecord>;
+
+  constructor(database: PublicKey, nextRound: number, rounds: Array<RoundRecord>) {
+    this.database = database;
+    this.nextRound = nextRound;
+    this.rounds = rounds;
+  }
+
+  get(round: number): RoundRecord | undefined {
+    return this.rounds.find(record => record.round === round);
   }
 }
 
 export class ResourceIndex {
   resources: Map<string, Array<PublicKey>>
+  // the only one registering resources
+  authority: PublicKey
 
-  constructor(resources: Map<string, Array<PublicKey>>) {
+  constructor(resources: Map<string, Array<PublicKey>>, authority: PublicKey = EMPTY_PUBLIC_KEY) {
     this.resources = resources;
+    this.authority = authority;
   }
 }
 
@@ -168,14 +548,33 @@ export interface IResourceAPI {
   /*
   Upload of a robot/human that X amount of resource exists, shold prove to other accounts that the resource is available
   */
-  recordResourceInstance(instance: ResourceInstance): Promise<void>;
+  recordResourceInstance(instance: ResourceInstance, provider: Account): Promise<void>;
+
+  /*
+  record a resource instance of a vault database, `provider` transfers `instance.quantity` lamports into `vault`
+  */
+  recordVaultResourceInstance(instance: ResourceInstance, provider: Account, vault: PublicKey): Promise<void>;
+
+  /*
+  record a resource instance of an escrowed database, `provider` signs over `instance.quantity` tokens into the escrow
+  */
+  recordEscrowedResourceInstance(instance: ResourceInstance, provider: Account, escrow: EscrowAccounts): Promise<void>;
 
   /*
   Timelocks the resources and (ideally) notifies individuals that they can challenge for their claims
   Can only be done by resource maintainer
   Should verify that trust exists between all accounts with intent, remove non-trustworthy accounts, calcuate distribution
+  Challenges are accepted for `duration` seconds
+  A lottery is only committed to a slot a few slots later, and allocated by drawLottery
+  */
+  initiateDistribution(duration: number): Promise<void>;
+
+  /*
+  draw a committed lottery with the hash of the first slot at or after the committed one, once it passed
+  Anyone can draw, nothing is claimed or finalized before. Commits to a later slot if nobody drew while
+  the slot was in the SlotHashes sysvar
   */
-  initiateDistribution(): Promise<void>;
+  drawLottery(): Promise<void>;
 
   /*
   list challenges that either:
@@ -186,13 +585,135 @@ export interface IResourceAPI {
 
   /*
   approve a challenge to increase challenge trust level and (hopefully/eventually) enable the resource to be claimed
+  `approver` is the challenge's `from` and signs; both ends have to be recipients of the round
+  */
+  approveChallenge(challenege: Challenge, approver: Account): Promise<void>;
+
+  /*
+  claim the rewards from a challenge once enough peers approved `from` for the quorum rule
+  weighted rules read the trust table or reputation of each approver's search engine account in `approvers`
+  escrowed databases pay the allocation from `escrow.escrow` into `escrow.tokenAccount`, owned by `from`
+  */
+  claimChallenge(from: PublicKey, approvers: Array<PublicKey>, escrow?: EscrowAccounts): Promise<void>;
+
+  /*
+  claim from a vault database: the allocation is paid in lamports from `vault` straight to `from`
+  Claims close when the distribution is finalized
+  */
+  claimFromVault(from: PublicKey, approvers: Array<PublicKey>, vault: PublicKey): Promise<void>;
+
+  /*
+  set how many peers have to approve a recipient before they can claim
+  Can only be done before distribution
+  */
+  setQuorum(quorum: QuorumRule): Promise<void>;
+
+  /*
+  set what recorded quantities count
+  Can only be done while nothing is recorded, and at most 19 decimals
+  */
+  setUnit(unit: UnitOfMeasure): Promise<void>;
+
+  /*
+  set how initiateDistribution splits the pool, one of the ALLOCATION_* strategies
+  Can only be done before anyone registers an intent or records an instance
+  */
+  setAllocationStrategy(strategy: number): Promise<void>;
+
+  /*
+  check the distributed allocations against the database's strategy, and that a lottery was drawn
+  from its committed slot on. The program read the hash from the SlotHashes sysvar itself, so the
+  database's record stands once the slot left the sysvar; while it is still there the hash is checked
+  against it too
+  */
+  verifyDraw(): Promise<boolean>;
+
+  /*
+  set who may register an intent, checked by registerIntent
+  Can only be done before distribution
+  */
+  setEligibility(eligibility: EligibilityRule): Promise<void>;
+
+  /*
+  set how many intents take part in a round, at most MAX_NUM_RECIPIENTS and 0 for all of them
+  Can only be done before distribution and not below the intents holding a slot, raising it promotes waiting intents
+  */
+  setCapacity(capacity: number): Promise<void>;
+
+  /*
+  chain a new page after the waitlist's tail, in a new account whose address is returned
+  Intents registered once every slot is taken wait on the tail page, anyone can call this
+  */
+  addWaitlistPage(): Promise<PublicKey>;
+
+  /*
+  `recipient` gives up their slot, which the longest waiting intent takes
+  Can only be done before distribution
+  */
+  withdrawIntent(recipient: Account): Promise<void>;
+
+  /*
+  the intents waiting for a slot, the longest waiting first
+  */
+  getWaitlist(): Promise<Array<Intent>>;
+
+  /*
+  close the distribution once its window has passed, anyone can call this
+  */
+  finalizeDistribution(): Promise<void>;
+
+  /*
+  archive the finalized round into the database's history and clear the database for the next round, anyone can call this
+  the first round binds `history` to the database, later rounds can only go to the same history
+  recipients have to settle their intents before, while the round is still finalized
+  waiting intents take the slots of recipients who did not claim
+  */
+  startNewRound(history?: PublicKey): Promise<void>;
+
+  /*
+  the archived rounds of `history`, the database's own by default, with what each recipient received and who approved whom
+  */
+  getRoundHistory(history?: PublicKey): Promise<RoundHistory>;
+
+  /*
+  back the resource with tokens of `mint`, EMPTY_PUBLIC_KEY turns escrow off
+  Can only be done while no resource instance is recorded
+  */
+  setEscrowMint(mint: PublicKey): Promise<void>;
+
+  /*
+  the address escrow token accounts of this database have to be owned by
+  */
+  getEscrowAuthority(): Promise<PublicKey>;
+
+  /*
+  back the resource with lamports held in `vault`, created with createLamportVault; null turns it off
+  Can only be done while no resource instance is recorded
+  */
+  setLamportVault(vault: PublicKey | null): Promise<void>;
+
+  /*
+  send `contributor` their share of what was deposited and not claimed, in proportion to what they deposited
+  Only once the distribution is finalized, anyone can call this
+  */
+  refundContribution(contributor: PublicKey, vault: PublicKey): Promise<void>;
+
+  /*
+  the same for a token backed resource: the share is paid from `escrow.escrow` into `escrow.tokenAccount`, whose owner contributed
+  Only once the distribution is finalized, anyone can call this
+  */
+  refundEscrowedContribution(escrow: EscrowAccounts): Promise<void>;
+
+  /*
+  issue `recipient` the receipt of their claim this round, in a new account whose address is returned
+  Once per claim, anyone can call this
   */
-  approveChallenge(challenege: Challenge): Promise<void>;
+  issueReceipt(recipient: PublicKey): Promise<PublicKey>;
 
   /*
-  claim the rewards from a challenge
+  the receipt at `receipt`, rejected unless the resource program owns it, so it can be trusted as proof of the claim
   */
-  claimChallenge(from: PublicKey): Promise<void>;
+  getClaimReceipt(receipt: PublicKey): Promise<ClaimReceipt>;
 }
 
 
@@ -217,7 +738,7 @@ export interface ISearchEngine {
   // ************************************************************************
 
   /*
-  Create resource to share with others. Should have a way for ResourceInstance to be recorded or verified (like posting photo to chain for someone to validate later)
+  Create resource to share with others, signed by the index's authority. Should have a way for ResourceInstance to be recorded or verified (like posting photo to chain for someone to validate later)
   Will also need to describe how much memory (based on the number of people * wallet id size * claim pointer size)
   */
   registerResource(resource: Resource): Promise<void>;
@@ -243,4 +764,9 @@ export interface ISearchEngine {
   registerIntent(account: Account, resource: PublicKey): Promise<void>;
 
   listIntents(account: Account): Promise<Array<PublicKey>>;
+
+  /*
+  Count the account's part in the resource's archived rounds and finalized distribution into its reputation
+  */
+  settleIntent(account: Account, resourceDatabase: PublicKey): Promise<void>;
 }
\ No newline at end of file
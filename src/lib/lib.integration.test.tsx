@@ -1,5 +1,8 @@
 import { Connection, PublicKey, Account } from '@solana/web3.js';
-import { Challenge, Location, MAX_TRUST_TABLE_SIZE, Resource, ResourceInstance, TrustTableEntry } from './lib-types';
+import {
+  Challenge, Location, MAX_TRUST_TABLE_SIZE, Resource, ResourceInstance, TrustTableEntry, QuorumRule, QUORUM_MAJORITY, EMPTY_PUBLIC_KEY,
+  UnitOfMeasure, EligibilityRule, ELIGIBILITY_ALLOW_LIST, Need
+} from './lib-types';
 import { ResourceAPI, SearchEngineAPI } from './lib';
 import {
   establishConnection, loadSearchEngineAddressFromEnvironment, loadAccountFromEnvironment,
@@ -122,17 +125,41 @@ describe('serach engine', () => {
     await resourceAPI.resetDatabase();
   });
 
+  test('set quorum', async () => {
+    await resourceAPI.setQuorum(new QuorumRule(QUORUM_MAJORITY, 0, EMPTY_PUBLIC_KEY));
+
+    let database = await resourceAPI.getDatabase();
+    expect(database.quorum.kind).toEqual(QUORUM_MAJORITY);
+  });
+
+  test('set unit', async () => {
+    await resourceAPI.setUnit(new UnitOfMeasure("loaves", 0));
+
+    let database = await resourceAPI.getDatabase();
+    expect(database.unit).toEqual(new UnitOfMeasure("loaves", 0));
+  });
+
+  test('set eligibility', async () => {
+    let allowed = [searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey];
+    await resourceAPI.setEligibility(new EligibilityRule(ELIGIBILITY_ALLOW_LIST, allowed));
+
+    let database = await resourceAPI.getDatabase();
+    expect(database.eligibility.kind).toEqual(ELIGIBILITY_ALLOW_LIST);
+    expect(database.eligibility.list).toEqual(allowed);
+  });
+
   test('can register intent', async () => {
     await resourceAPI.registerIntent(searchEnginePayerAccount);
 
     let database = await resourceAPI.getDatabase();
     expect(database.intents).toHaveLength(1);
-    expect(database.intents[0]).toEqual(searchEnginePayerAccount.publicKey);
+    expect(database.intents[0].recipient).toEqual(searchEnginePayerAccount.publicKey);
+    expect(database.intents[0].need).toStrictEqual(new Need());
   });
 
   test('record resource instance', async () => {
-    let resourceInstance = new ResourceInstance(searchEnginePayerAccount.publicKey, 10);
-    await resourceAPI.recordResourceInstance(resourceInstance);
+    let resourceInstance = new ResourceInstance(searchEnginePayerAccount.publicKey, BigInt(10));
+    await resourceAPI.recordResourceInstance(resourceInstance, searchEnginePayerAccount);
 
     let database = await resourceAPI.getDatabase();
     expect(database.instances).toHaveLength(1);
@@ -146,10 +173,12 @@ describe('serach engine', () => {
     let databaseBefore = await resourceAPI.getDatabase();
     expect(databaseBefore.isDistributed).toEqual(false);
 
-    await resourceAPI.initiateDistribution();
+    await resourceAPI.initiateDistribution(3600);
 
     let databaseAfter = await resourceAPI.getDatabase();
     expect(databaseAfter.isDistributed).toEqual(true);
+    expect(databaseAfter.window.duration).toEqual(3600);
+    expect(databaseAfter.isFinalized).toEqual(false);
   })
 
   test('list challenges', async () => {
@@ -161,18 +190,71 @@ describe('serach engine', () => {
   })
 
   test('approve challenge', async () => {
-    await resourceAPI.approveChallenge(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, true));
+    await resourceAPI.approveChallenge(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, true), searchEnginePayerAccount);
     let challenges = await resourceAPI.listChallenges();
     expect(challenges).toContainEqual(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, true));
   });
 
   test('deny challenge', async () => {
-    await resourceAPI.denyChallenge(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, false));
+    await resourceAPI.denyChallenge(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, false), searchEnginePayerAccount);
     let challenges = await resourceAPI.listChallenges();
     expect(challenges).toContainEqual(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, false));
   });
 
   test('claim challenge', async () => {
+    // the only peer just denied the second account, which is not a majority
+    await expect(resourceAPI.claimChallenge(secondSearchEnginePayerAccount.publicKey, [])).rejects.toThrow();
+
+    await resourceAPI.approveChallenge(new Challenge(searchEnginePayerAccount.publicKey, secondSearchEnginePayerAccount.publicKey, true), searchEnginePayerAccount);
+    await resourceAPI.claimChallenge(secondSearchEnginePayerAccount.publicKey, [searchEnginePayerAccount.publicKey]);
+    let database = await resourceAPI.getDatabase();
+    expect(database.claims).toContainEqual(secondSearchEnginePayerAccount.publicKey);
+  });
+
+  test('issue receipt', async () => {
+    let receipt = await resourceAPI.issueReceipt(secondSearchEnginePayerAccount.publicKey);
+    let claimReceipt = await resourceAPI.getClaimReceipt(receipt);
+    expect(claimReceipt.recipient).toEqual(secondSearchEnginePayerAccount.publicKey);
+    expect(claimReceipt.round).toEqual(0);
+
+    // one receipt per claim
+    await expect(resourceAPI.issueReceipt(secondSearchEnginePayerAccount.publicKey)).rejects.toThrow();
+    let database = await resourceAPI.getDatabase();
+    expect(database.receipts).toContainEqual(secondSearchEnginePayerAccount.publicKey);
+  });
+
+  test('finalize distribution', async () => {
+    // the hour long window from 'initiate distribution' is still open
+    await expect(resourceAPI.finalizeDistribution()).rejects.toThrow();
+    let database = await resourceAPI.getDatabase();
+    expect(database.isFinalized).toEqual(false);
+  });
 
+  test('settle intent', async () => {
+    // nothing is counted until the distribution is finalized
+    await expect(system.settleIntent(searchEnginePayerAccount, resourceProgramDatabaseAccount.publicKey)).rejects.toThrow();
+    let storedAccount = await system.getAccountDetails(searchEnginePayerAccount.publicKey);
+    expect(storedAccount.reputation.score()).toEqual(0);
+  });
+
+  test('start new round', async () => {
+    // the round can't be archived while its distribution is still open
+    let history = new Account();
+    await resourceAPI.createRoundHistory(history);
+    await expect(resourceAPI.startNewRound(history.publicKey)).rejects.toThrow();
+    let roundHistory = await resourceAPI.getRoundHistory(history.publicKey);
+    expect(roundHistory.nextRound).toEqual(0);
+    expect(roundHistory.rounds).toHaveLength(0);
+    let database = await resourceAPI.getDatabase();
+    expect(database.round).toEqual(0);
+  });
+
+  test('set lamport vault', async () => {
+    // a vault can't back instances that were recorded without one
+    let vault = new Account();
+    await resourceAPI.createLamportVault(vault);
+    await expect(resourceAPI.setLamportVault(vault.publicKey)).rejects.toThrow();
+    let database = await resourceAPI.getDatabase();
+    expect(database.hasVault()).toEqual(false);
   });
-})
\ No newline at end of file
+})
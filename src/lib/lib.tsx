@@ -2,6 +2,7 @@ import {
   Account,
   Connection,
   PublicKey,
+  SYSVAR_CLOCK_PUBKEY,
   SystemProgram,
   TransactionInstruction,
   Transaction,
@@ -13,8 +14,19 @@ import {
   Challenge, SearchEngineAccount, Location, SE_INSTRUCTION_UPDATE_ACCOUNT, SE_INSTRUCTION_REGISTER_RESOURCE,
   ResourceIndex, SE_INSTRUCTION_REGISTER_INTENT, RESOURCE_INSTRUCTION_REGISTER_INTENT, ResourceDatabase,
   RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE, RESOURCE_INSTRUCTION_RESET_DATABASE, RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION, RESOURCE_INSTRUCTION_RECORD_CHALLENGE,
+  RESOURCE_INSTRUCTION_FINALIZE_DISTRIBUTION, RESOURCE_INSTRUCTION_CLAIM_CHALLENGE, RESOURCE_INSTRUCTION_SET_QUORUM,
+  QuorumRule, EMPTY_PUBLIC_KEY, SE_INSTRUCTION_SETTLE_INTENT, RESOURCE_INSTRUCTION_START_NEW_ROUND, RoundHistory,
+  RESOURCE_INSTRUCTION_SET_ESCROW_MINT, EscrowAccounts, TOKEN_PROGRAM_ID, ESCROW_SEED,
+  RESOURCE_INSTRUCTION_SET_LAMPORT_VAULT, RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION,
+  RESOURCE_INSTRUCTION_ISSUE_RECEIPT, ClaimReceipt, RESOURCE_INSTRUCTION_SET_UNIT, UnitOfMeasure,
+  RESOURCE_INSTRUCTION_SET_ELIGIBILITY, EligibilityRule, Need, RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY,
+  SYSVAR_SLOT_HASHES_PUBKEY, ALLOCATION_LOTTERY, RESOURCE_INSTRUCTION_SET_CAPACITY, RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE,
+  RESOURCE_INSTRUCTION_WITHDRAW_INTENT, RESOURCE_INSTRUCTION_DRAW_LOTTERY, WaitlistPage, Intent,
 } from './lib-types';
-import { toBorsh, toTyped, SEARCH_ENGINE_ACCOUNT_SPACE } from './lib-serialization';
+import {
+  toBorsh, toTyped, SEARCH_ENGINE_ACCOUNT_SPACE, ROUND_HISTORY_SPACE, LAMPORT_VAULT_SPACE, CLAIM_RECEIPT_SPACE, WAITLIST_PAGE_SPACE,
+} from './lib-serialization';
+import { verifyAllocation } from './lib-allocation';
 
 
 export class ResourceAPI implements IResourceAPI {
@@ -59,22 +71,94 @@ export class ResourceAPI implements IResourceAPI {
     return toTyped(ResourceDatabase, accountInfo.data);
   }
 
-  async registerIntent(account: Account): Promise<void> {
+  // the waitlist's pages from the head to the tail, the accounts promotions take intents off and
+  // registrations are checked against
+  async _waitlistPages(): Promise<Array<PublicKey>> {
+    let waitlist = (await this.getDatabase()).waitlist;
+    let pages: Array<PublicKey> = [];
+    if (!waitlist.hasPages()) {
+      return pages;
+    }
+    let page = waitlist.head;
+    while (true) {
+      pages.push(page);
+      if (page.equals(waitlist.tail)) {
+        return pages;
+      }
+      let next = (await this._getWaitlistPage(page)).next;
+      if (next.equals(EMPTY_PUBLIC_KEY) || pages.some(key => key.equals(next))) {
+        throw new Error("WAITLIST NOT CHAINED TO ITS TAIL");
+      }
+      page = next;
+    }
+  }
+
+  async _getWaitlistPage(page: PublicKey): Promise<WaitlistPage> {
+    let accountInfo = await this.connection.getAccountInfo(page);
+    if (accountInfo == null) {
+      throw new Error("NO ACCOUNT INFO FOUND");
+    }
+    return toTyped(WaitlistPage, accountInfo.data);
+  }
+
+  // `provider` is the search engine account of the provider, needed when only accounts it trusts are eligible
+  // without a need the recipient asks for an even share of the pool
+  // once every slot is taken the waitlist's pages are checked for the intent, it waits on the tail page
+  // the recipient signs, so only they register or update their need
+  async registerIntent(account: Account, provider?: PublicKey, need: Need = new Need()): Promise<void> {
     let instruction = new Uint8Array([RESOURCE_INSTRUCTION_REGISTER_INTENT]);
+    let instruction_data = toBorsh(need);
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: account.publicKey, isSigner: true, isWritable: false },
+    ];
+    if (provider) {
+      keys.push({ pubkey: provider, isSigner: false, isWritable: false });
+    }
+    for (let page of await this._waitlistPages()) {
+      keys.push({ pubkey: page, isSigner: false, isWritable: true });
+    }
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async recordResourceInstance(instance: ResourceInstance, provider: Account): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE]);
+    let instruction_data = toBorsh(instance);
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
     const transaction = new Transaction().add(
       new TransactionInstruction({
         keys: [
           { pubkey: this.databaseId, isSigner: false, isWritable: true },
-          { pubkey: account.publicKey, isSigner: false, isWritable: false },
+          { pubkey: provider.publicKey, isSigner: true, isWritable: false },
         ],
         programId: this.resource.address,
-        data: Buffer.from(instruction),
+        data: Buffer.from(combined),
       }),
     );
     await sendAndConfirmTransaction(
       this.connection,
       transaction,
-      [this.payerAccount],
+      [this.payerAccount, provider],
       {
         commitment: 'singleGossip',
         preflightCommitment: 'singleGossip',
@@ -82,7 +166,7 @@ export class ResourceAPI implements IResourceAPI {
     );
   }
 
-  async recordResourceInstance(instance: ResourceInstance): Promise<void> {
+  async recordEscrowedResourceInstance(instance: ResourceInstance, provider: Account, escrow: EscrowAccounts): Promise<void> {
     let instruction = new Uint8Array([RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE]);
     let instruction_data = toBorsh(instance);
     let combined = new Uint8Array(1 + instruction_data.length);
@@ -92,6 +176,10 @@ export class ResourceAPI implements IResourceAPI {
       new TransactionInstruction({
         keys: [
           { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: escrow.tokenAccount, isSigner: false, isWritable: true },
+          { pubkey: escrow.escrow, isSigner: false, isWritable: true },
+          { pubkey: provider.publicKey, isSigner: true, isWritable: false },
+          { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
         ],
         programId: this.resource.address,
         data: Buffer.from(combined),
@@ -100,7 +188,7 @@ export class ResourceAPI implements IResourceAPI {
     await sendAndConfirmTransaction(
       this.connection,
       transaction,
-      [this.payerAccount],
+      [this.payerAccount, provider],
       {
         commitment: 'singleGossip',
         preflightCommitment: 'singleGossip',
@@ -108,15 +196,50 @@ export class ResourceAPI implements IResourceAPI {
     );
   }
 
-  async initiateDistribution(): Promise<void> {
-    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION]);
+  async recordVaultResourceInstance(instance: ResourceInstance, provider: Account, vault: PublicKey): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE]);
+    let instruction_data = toBorsh(instance);
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
     const transaction = new Transaction().add(
       new TransactionInstruction({
         keys: [
           { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: vault, isSigner: false, isWritable: true },
+          { pubkey: provider.publicKey, isSigner: true, isWritable: true },
+          { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
         programId: this.resource.address,
-        data: Buffer.from(instruction),
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, provider],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async initiateDistribution(duration: number): Promise<void> {
+    // tag followed by the duration in seconds as a little endian i64
+    let combined = Buffer.alloc(1 + 8);
+    combined.writeUInt8(RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION, 0);
+    combined.writeUInt32LE(duration % 0x100000000, 1);
+    combined.writeUInt32LE(Math.floor(duration / 0x100000000), 5);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+          { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: combined,
       }),
     );
     await sendAndConfirmTransaction(
@@ -129,12 +252,35 @@ export class ResourceAPI implements IResourceAPI {
       },
     );
   }
+  async drawLottery(): Promise<void> {
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+          { pubkey: SYSVAR_SLOT_HASHES_PUBKEY, isSigner: false, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_DRAW_LOTTERY]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
 
   async listChallenges(): Promise<Array<Challenge>> {
     return await (await this.getDatabase()).challenges;
   }
 
-  async _record_challenge(challenge: Challenge): Promise<void> {
+  async _record_challenge(challenge: Challenge, approver: Account): Promise<void> {
     let instruction = new Uint8Array([RESOURCE_INSTRUCTION_RECORD_CHALLENGE]);
     let instruction_data = toBorsh(challenge);
     let combined = new Uint8Array(1 + instruction_data.length);
@@ -144,6 +290,429 @@ export class ResourceAPI implements IResourceAPI {
       new TransactionInstruction({
         keys: [
           { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+          { pubkey: approver.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, approver],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async approveChallenge(challenge: Challenge, approver: Account): Promise<void> {
+    return this._record_challenge(challenge, approver);
+  }
+  async denyChallenge(challenge: Challenge, approver: Account): Promise<void> {
+    return this._record_challenge(challenge, approver);
+  }
+
+  async claimChallenge(from: PublicKey, approvers: Array<PublicKey>, escrow?: EscrowAccounts): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_CLAIM_CHALLENGE]);
+    let instruction_data = toBorsh(new Challenge(from, EMPTY_PUBLIC_KEY, true));
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
+    let keys = [{ pubkey: this.databaseId, isSigner: false, isWritable: true }];
+    if (escrow !== undefined) {
+      keys.push({ pubkey: escrow.escrow, isSigner: false, isWritable: true });
+      keys.push({ pubkey: escrow.tokenAccount, isSigner: false, isWritable: true });
+      keys.push({ pubkey: await this.getEscrowAuthority(), isSigner: false, isWritable: false });
+      keys.push({ pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false });
+    }
+    approvers.forEach((approver) => {
+      keys.push({ pubkey: approver, isSigner: false, isWritable: false });
+    });
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async claimFromVault(from: PublicKey, approvers: Array<PublicKey>, vault: PublicKey): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_CLAIM_CHALLENGE]);
+    let instruction_data = toBorsh(new Challenge(from, EMPTY_PUBLIC_KEY, true));
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: vault, isSigner: false, isWritable: true },
+      { pubkey: from, isSigner: false, isWritable: true },
+    ];
+    approvers.forEach((approver) => {
+      keys.push({ pubkey: approver, isSigner: false, isWritable: false });
+    });
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async setQuorum(quorum: QuorumRule): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_SET_QUORUM]);
+    let instruction_data = toBorsh(quorum);
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async setUnit(unit: UnitOfMeasure): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_SET_UNIT]);
+    let instruction_data = toBorsh(unit);
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async setAllocationStrategy(strategy: number): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY, strategy]);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from(instruction),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async verifyDraw(): Promise<boolean> {
+    let database = await this.getDatabase();
+    if (!database.isDistributed) {
+      return false;
+    }
+    let isLottery = database.strategy === ALLOCATION_LOTTERY;
+    if (isLottery && (!database.draw.isDrawn() || database.draw.slot < database.draw.committedSlot)) {
+      return false;
+    }
+    if (!(await verifyAllocation(database))) {
+      return false;
+    }
+    if (!isLottery) {
+      return true;
+    }
+    let accountInfo = await this.connection.getAccountInfo(SYSVAR_SLOT_HASHES_PUBKEY);
+    if (accountInfo == null) {
+      throw new Error("NO ACCOUNT INFO FOUND");
+    }
+    // a little endian u64 count followed by (slot as u64, hash) entries
+    let data = Buffer.from(accountInfo.data);
+    let count = data.readUInt32LE(0);
+    for (let index = 0; index < count; index++) {
+      let offset = 8 + index * (8 + 32);
+      let slot = BigInt(data.readUInt32LE(offset + 4)) * BigInt(0x100000000) + BigInt(data.readUInt32LE(offset));
+      if (slot === database.draw.slot) {
+        return data.slice(offset + 8, offset + 8 + 32).equals(Buffer.from(database.draw.hash));
+      }
+    }
+    // the program took the hash from the sysvar when drawing, its record stands once the slot left it
+    return true;
+  }
+
+  async setEligibility(eligibility: EligibilityRule): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_SET_ELIGIBILITY]);
+    let instruction_data = toBorsh(eligibility);
+    let combined = new Uint8Array(1 + instruction_data.length);
+    combined.set(instruction);
+    combined.set(instruction_data, 1);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from(combined),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async setCapacity(capacity: number): Promise<void> {
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+    ];
+    (await this._waitlistPages()).forEach(page => keys.push({ pubkey: page, isSigner: false, isWritable: true }));
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_SET_CAPACITY, capacity]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  // the page account is created in the same transaction and chained after the tail
+  async addWaitlistPage(): Promise<PublicKey> {
+    let page = new Account();
+    let waitlist = (await this.getDatabase()).waitlist;
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: page.publicKey, isSigner: false, isWritable: true },
+    ];
+    if (waitlist.hasPages()) {
+      keys.push({ pubkey: waitlist.tail, isSigner: false, isWritable: true });
+    }
+    const lamports = await this.connection.getMinimumBalanceForRentExemption(WAITLIST_PAGE_SPACE);
+    const transaction = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: this.payerAccount.publicKey,
+        newAccountPubkey: page.publicKey,
+        lamports: lamports,
+        space: WAITLIST_PAGE_SPACE,
+        programId: this.resource.address,
+      }),
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, page],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+    return page.publicKey;
+  }
+
+  async withdrawIntent(recipient: Account): Promise<void> {
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: recipient.publicKey, isSigner: true, isWritable: false },
+    ];
+    (await this._waitlistPages()).forEach(page => keys.push({ pubkey: page, isSigner: false, isWritable: true }));
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_WITHDRAW_INTENT]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, recipient],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async getWaitlist(): Promise<Array<Intent>> {
+    let waiting: Array<Intent> = [];
+    for (let page of await this._waitlistPages()) {
+      waiting.push(...(await this._getWaitlistPage(page)).intents);
+    }
+    return waiting;
+  }
+
+  async finalizeDistribution(): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: SYSVAR_CLOCK_PUBKEY, isSigner: false, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from(instruction),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  // an empty history owned by the resource program; the first startNewRound binds it to this database
+  async createRoundHistory(history: Account): Promise<void> {
+    const lamports = await this.connection.getMinimumBalanceForRentExemption(ROUND_HISTORY_SPACE);
+    const transaction = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: this.payerAccount.publicKey,
+        newAccountPubkey: history.publicKey,
+        lamports: lamports,
+        space: ROUND_HISTORY_SPACE,
+        programId: this.resource.address,
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, history],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  // the history the database archives into, `history` only until the first round binds one
+  async _roundHistory(history?: PublicKey): Promise<PublicKey> {
+    let bound = (await this.getDatabase()).history;
+    if (!bound.equals(EMPTY_PUBLIC_KEY)) {
+      return bound;
+    }
+    if (history === undefined) {
+      throw new Error("no round history bound yet, pass one created with createRoundHistory");
+    }
+    return history;
+  }
+
+  async startNewRound(history?: PublicKey): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_START_NEW_ROUND]);
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: await this._roundHistory(history), isSigner: false, isWritable: true },
+    ];
+    (await this._waitlistPages()).forEach(page => keys.push({ pubkey: page, isSigner: false, isWritable: true }));
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from(instruction),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async getRoundHistory(history?: PublicKey): Promise<RoundHistory> {
+    let accountInfo = await this.connection.getAccountInfo(await this._roundHistory(history));
+    if (accountInfo == null) {
+      throw new Error("NO ACCOUNT INFO FOUND");
+    }
+    return toTyped(RoundHistory, accountInfo.data);
+  }
+
+  async setEscrowMint(mint: PublicKey): Promise<void> {
+    let instruction = new Uint8Array([RESOURCE_INSTRUCTION_SET_ESCROW_MINT]);
+    let combined = new Uint8Array(1 + 32);
+    combined.set(instruction);
+    combined.set(mint.toBuffer(), 1);
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
         ],
         programId: this.resource.address,
         data: Buffer.from(combined),
@@ -160,25 +729,152 @@ export class ResourceAPI implements IResourceAPI {
     );
   }
 
-  async approveChallenge(challenge: Challenge): Promise<void> {
-    return this._record_challenge(challenge);
+  async getEscrowAuthority(): Promise<PublicKey> {
+    let [authority] = await PublicKey.findProgramAddress([Buffer.from(ESCROW_SEED), this.databaseId.toBuffer()], this.resource.address);
+    return authority;
   }
-  async denyChallenge(challenge: Challenge): Promise<void> {
-    return this._record_challenge(challenge);
+
+  // an unbound vault owned by the resource program; setLamportVault binds it to this database
+  async createLamportVault(vault: Account): Promise<void> {
+    const lamports = await this.connection.getMinimumBalanceForRentExemption(LAMPORT_VAULT_SPACE);
+    const transaction = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: this.payerAccount.publicKey,
+        newAccountPubkey: vault.publicKey,
+        lamports: lamports,
+        space: LAMPORT_VAULT_SPACE,
+        programId: this.resource.address,
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, vault],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async setLamportVault(vault: PublicKey | null): Promise<void> {
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: true },
+      { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+    ];
+    if (vault !== null) {
+      keys.push({ pubkey: vault, isSigner: false, isWritable: true });
+    }
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: keys,
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_SET_LAMPORT_VAULT]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async refundContribution(contributor: PublicKey, vault: PublicKey): Promise<void> {
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: vault, isSigner: false, isWritable: true },
+          { pubkey: contributor, isSigner: false, isWritable: true },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
+
+  async refundEscrowedContribution(escrow: EscrowAccounts): Promise<void> {
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: escrow.escrow, isSigner: false, isWritable: true },
+          { pubkey: escrow.tokenAccount, isSigner: false, isWritable: true },
+          { pubkey: await this.getEscrowAuthority(), isSigner: false, isWritable: false },
+          { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
   }
 
-  async claimChallenge() {
-    // let mut challenges: []Challenge = some_borsh_call::read_array(&data)
-    // trust_score = 0
-    // for challenge in challenges:
-    //   if req.from == challenge.from && challenge.approved = true
-    //     trust_from_owner_to_challenger = contract.trust_table[req.from]
-    //     trust_from_challengee_to_owner = contract.trust_table[challenge.to]
-    //     trust_score += (trust_from_owner_to_challenger * trust_from_challengee_to_owner)
+  // the receipt account is created in the same transaction, so a receipt either exists issued or not at all
+  async issueReceipt(recipient: PublicKey): Promise<PublicKey> {
+    let receipt = new Account();
+    const lamports = await this.connection.getMinimumBalanceForRentExemption(CLAIM_RECEIPT_SPACE);
+    const transaction = new Transaction().add(
+      SystemProgram.createAccount({
+        fromPubkey: this.payerAccount.publicKey,
+        newAccountPubkey: receipt.publicKey,
+        lamports: lamports,
+        space: CLAIM_RECEIPT_SPACE,
+        programId: this.resource.address,
+      }),
+      new TransactionInstruction({
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: receipt.publicKey, isSigner: false, isWritable: true },
+          { pubkey: recipient, isSigner: false, isWritable: false },
+        ],
+        programId: this.resource.address,
+        data: Buffer.from([RESOURCE_INSTRUCTION_ISSUE_RECEIPT]),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount, receipt],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+    return receipt.publicKey;
+  }
 
-    // if trust_score > contract.min_trust_score
-    //   contract.claim_table[req.from] = true
-    //   contract.inventory_available -= 
+  async getClaimReceipt(receipt: PublicKey): Promise<ClaimReceipt> {
+    let accountInfo = await this.connection.getAccountInfo(receipt);
+    if (accountInfo == null) {
+      throw new Error("NO ACCOUNT INFO FOUND");
+    }
+    // anyone can write these bytes into an account of their own
+    if (!accountInfo.owner.equals(this.resource.address)) {
+      throw new Error("RECEIPT NOT OWNED BY THE RESOURCE PROGRAM");
+    }
+    return toTyped(ClaimReceipt, accountInfo.data);
   }
 
   async resetDatabase() {
@@ -187,6 +883,7 @@ export class ResourceAPI implements IResourceAPI {
       new TransactionInstruction({
         keys: [
           { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
         ],
         programId: this.resource.address,
         data: Buffer.from(instruction),
@@ -337,7 +1034,11 @@ export class SearchEngineAPI implements ISearchEngine {
     combined.set(instruction_data, 1);
     const transaction = new Transaction().add(
       new TransactionInstruction({
-        keys: [{ pubkey: this.databaseId, isSigner: false, isWritable: true }],
+        // the payer initialized the index, only it vouches for resources
+        keys: [
+          { pubkey: this.databaseId, isSigner: false, isWritable: true },
+          { pubkey: this.payerAccount.publicKey, isSigner: true, isWritable: false },
+        ],
         programId: this.programId,
         data: Buffer.from(combined),
       }),
@@ -410,4 +1111,37 @@ export class SearchEngineAPI implements ISearchEngine {
     let details = await this.getAccountDetails(account.publicKey);
     return details.intents;
   }
+
+  async settleIntent(account: Account, resourceDatabase: PublicKey): Promise<void> {
+    let instruction = new Uint8Array([SE_INSTRUCTION_SETTLE_INTENT]);
+    let keys = [
+      { pubkey: this.databaseId, isSigner: false, isWritable: false },
+      { pubkey: account.publicKey, isSigner: false, isWritable: true },
+      { pubkey: resourceDatabase, isSigner: false, isWritable: false },
+    ];
+    // the rounds the database archived settle too, from the history it is bound to
+    let databaseInfo = await this.connection.getAccountInfo(resourceDatabase);
+    if (databaseInfo != null) {
+      let history = toTyped(ResourceDatabase, databaseInfo.data).history;
+      if (!history.equals(EMPTY_PUBLIC_KEY)) {
+        keys.push({ pubkey: history, isSigner: false, isWritable: false });
+      }
+    }
+    const transaction = new Transaction().add(
+      new TransactionInstruction({
+        keys,
+        programId: this.programId,
+        data: Buffer.from(instruction),
+      }),
+    );
+    await sendAndConfirmTransaction(
+      this.connection,
+      transaction,
+      [this.payerAccount],
+      {
+        commitment: 'singleGossip',
+        preflightCommitment: 'singleGossip',
+      },
+    );
+  }
 }
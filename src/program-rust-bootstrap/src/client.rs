@@ -0,0 +1,66 @@
+use solana_sdk::{
+    account::Account,
+    commitment_config::{CommitmentConfig, CommitmentLevel},
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use solana_client::{
+    client_error::Result as ClientResult,
+    rpc_client::RpcClient,
+    rpc_config::RpcSendTransactionConfig,
+};
+use crate::error::BootstrapError;
+
+const COMMITMENT_CONFIG: CommitmentConfig = CommitmentConfig {
+    commitment: CommitmentLevel::Confirmed,
+};
+
+// a fresh blockhash is fetched for every attempt
+const MAX_SEND_ATTEMPTS: usize = 3;
+
+// Signs with the payer plus any extra signers (e.g. accounts being created) and waits for confirmation.
+// Preflight simulation runs first so program errors come back decoded instead of as a timeout.
+pub fn send_instructions(rpc_client: &RpcClient, payer: &Keypair, extra_signers: &[&Keypair], instructions: &[Instruction]) -> Result<Signature, BootstrapError> {
+    let mut signers: Vec<&dyn Signer> = vec!(payer);
+    for signer in extra_signers {
+        signers.push(*signer);
+    }
+
+    let mut attempt = 1;
+    loop {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&payer.pubkey()));
+        let (blockhash, _) = rpc_client.get_recent_blockhash()?;
+        transaction.sign(&signers, blockhash);
+
+        let result = rpc_client.send_and_confirm_transaction_with_spinner_and_config(
+            &transaction,
+            COMMITMENT_CONFIG,
+            RpcSendTransactionConfig {
+                skip_preflight: false,
+                preflight_commitment: Some(COMMITMENT_CONFIG.commitment),
+                ..RpcSendTransactionConfig::default()
+            },
+        );
+        match result {
+            Ok(signature) => return Ok(signature),
+            Err(err) => {
+                let err = BootstrapError::from_client_error(err, instructions);
+                if !err.is_expired_blockhash() || attempt == MAX_SEND_ATTEMPTS {
+                    return Err(err);
+                }
+                attempt += 1;
+            }
+        }
+    }
+}
+
+// None when the account has not been created yet
+pub fn get_account(rpc_client: &RpcClient, pubkey: &Pubkey) -> ClientResult<Option<Account>> {
+    Ok(rpc_client.get_account_with_commitment(pubkey, COMMITMENT_CONFIG)?.value)
+}
+
+pub fn get_account_data(rpc_client: &RpcClient, pubkey: &Pubkey) -> ClientResult<Option<Vec<u8>>> {
+    Ok(get_account(rpc_client, pubkey)?.map(|account| account.data))
+}
@@ -0,0 +1,201 @@
+// Named cluster profiles, stored as TOML. Any setting a profile leaves out falls back to the
+// localnet defaults, which point at the files the npm scripts generate under ./src/lib.
+//
+// Program ids and database addresses may be given as base58 pubkeys or as the files holding
+// them (pubkey files for programs, keypair files for databases).
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fs, path::Path};
+use crate::provision::ProvisionResult;
+
+pub const DEFAULT_CONFIG_FILE: &str = "./src/lib/bootstrap_config.out.toml";
+pub const DEFAULT_PROFILE: &str = "localnet";
+
+pub const PROFILE_KEYS: &[&str] = &[
+    "url",
+    "payer",
+    "searchengine_program",
+    "searchengine_database",
+    "resource_program",
+    "resource_database",
+    "resource_history",
+    "resource_vault",
+];
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Profile {
+    pub url: Option<String>,
+    pub payer: Option<String>,
+    pub searchengine_program: Option<String>,
+    pub searchengine_database: Option<String>,
+    pub resource_program: Option<String>,
+    pub resource_database: Option<String>,
+    pub resource_history: Option<String>,
+    pub resource_vault: Option<String>,
+}
+
+// every setting resolved, after falling back to the localnet defaults
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub url: String,
+    pub payer: String,
+    pub searchengine_program: String,
+    pub searchengine_database: String,
+    pub resource_program: String,
+    pub resource_database: String,
+    pub resource_history: String,
+    pub resource_vault: String,
+}
+
+impl Profile {
+    pub fn localnet() -> Self {
+        Profile {
+            url: Some("http://localhost:8899".to_string()),
+            payer: Some("./src/lib/keygen.out.json".to_string()),
+            searchengine_program: Some("./src/lib/searchengine_deploy_raw.out.json".to_string()),
+            searchengine_database: Some("./src/lib/searchengine_database_keygen.out.json".to_string()),
+            resource_program: Some("./src/lib/resource_deploy_raw.out.json".to_string()),
+            resource_database: Some("./src/lib/resource_database_keygen.out.json".to_string()),
+            resource_history: Some("./src/lib/resource_history_keygen.out.json".to_string()),
+            resource_vault: Some("./src/lib/resource_vault_keygen.out.json".to_string()),
+        }
+    }
+
+    // same key files as localnet, like the bootstrap-dev npm scripts
+    pub fn devnet() -> Self {
+        Profile {
+            url: Some("https://devnet.solana.com".to_string()),
+            ..Profile::localnet()
+        }
+    }
+
+    fn field(&mut self, key: &str) -> ProvisionResult<&mut Option<String>> {
+        match key {
+            "url" => Ok(&mut self.url),
+            "payer" => Ok(&mut self.payer),
+            "searchengine_program" => Ok(&mut self.searchengine_program),
+            "searchengine_database" => Ok(&mut self.searchengine_database),
+            "resource_program" => Ok(&mut self.resource_program),
+            "resource_database" => Ok(&mut self.resource_database),
+            "resource_history" => Ok(&mut self.resource_history),
+            "resource_vault" => Ok(&mut self.resource_vault),
+            _ => Err(format!("unknown profile key {}, expected one of {}", key, PROFILE_KEYS.join(", ")).into()),
+        }
+    }
+
+    pub fn set(&mut self, key: &str, value: Option<&str>) -> ProvisionResult<()> {
+        *self.field(key)? = value.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn settings(&self) -> Settings {
+        let defaults = Profile::localnet();
+        let pick = |value: &Option<String>, default: &Option<String>| value.clone().or_else(|| default.clone()).unwrap();
+        Settings {
+            url: pick(&self.url, &defaults.url),
+            payer: pick(&self.payer, &defaults.payer),
+            searchengine_program: pick(&self.searchengine_program, &defaults.searchengine_program),
+            searchengine_database: pick(&self.searchengine_database, &defaults.searchengine_database),
+            resource_program: pick(&self.resource_program, &defaults.resource_program),
+            resource_database: pick(&self.resource_database, &defaults.resource_database),
+            resource_history: pick(&self.resource_history, &defaults.resource_history),
+            resource_vault: pick(&self.resource_vault, &defaults.resource_vault),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Config {
+    // the profile used when --profile is not given
+    #[serde(default = "default_profile")]
+    pub profile: String,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+fn default_profile() -> String {
+    DEFAULT_PROFILE.to_string()
+}
+
+fn builtin_profile(name: &str) -> Option<Profile> {
+    match name {
+        "localnet" => Some(Profile::localnet()),
+        "devnet" => Some(Profile::devnet()),
+        _ => None,
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            profile: default_profile(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    // a missing file is the same as an empty one
+    pub fn load(path: &str) -> ProvisionResult<Self> {
+        if !Path::new(path).exists() {
+            return Ok(Config::default());
+        }
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> ProvisionResult<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    pub fn save(&self, path: &str) -> ProvisionResult<()> {
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)?;
+        Ok(())
+    }
+
+    // localnet and devnet are always available, even when the file does not list them
+    pub fn profile(&self, name: &str) -> ProvisionResult<Profile> {
+        self.profiles.get(name).cloned()
+            .or_else(|| builtin_profile(name))
+            .ok_or_else(|| format!("no profile named {}", name).into())
+    }
+
+    pub fn profile_mut(&mut self, name: &str) -> &mut Profile {
+        self.profiles.entry(name.to_string()).or_insert_with(|| builtin_profile(name).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_custom_profile_falls_back_to_localnet() {
+        let config = Config::parse(r#"
+            profile = "custom"
+
+            [profiles.custom]
+            url = "http://10.0.0.2:8899"
+            resource_program = "4uQeVj5tqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM"
+        "#).unwrap();
+        let settings = config.profile(&config.profile).unwrap().settings();
+        assert_eq!(settings.url, "http://10.0.0.2:8899");
+        assert_eq!(settings.resource_program, "4uQeVj5tqViQh7yWWGStvkEG1Zmhx6uasJtWCJziofM");
+        assert_eq!(settings.payer, Profile::localnet().payer.unwrap());
+    }
+
+    #[test]
+    fn test_set_and_round_trip() {
+        let mut config = Config::default();
+        config.profile_mut("devnet").set("payer", Some("devnet_payer.json")).unwrap();
+        config.profile_mut("custom").set("url", Some("http://10.0.0.2:8899")).unwrap();
+        assert!(config.profile_mut("custom").set("color", Some("blue")).is_err());
+
+        let parsed = Config::parse(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(parsed, config);
+        assert_eq!(parsed.profile("devnet").unwrap().settings().payer, "devnet_payer.json");
+        assert_eq!(parsed.profile("localnet").unwrap(), Profile::localnet());
+        assert!(parsed.profile("mainnet").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    pubkey::Pubkey,
+    transaction::TransactionError,
+};
+use solana_client::{
+    client_error::{ClientError, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
+};
+use std::{error::Error, fmt};
+use crate::types::{SE_ERRORS, RESOURCE_ERRORS};
+
+/// Why a transaction did not land, split so callers can pick an exit code and decode program errors.
+#[derive(Debug)]
+pub enum BootstrapError {
+    /// The RPC node could not be reached or refused the request
+    Rpc(ClientError),
+
+    /// The transaction was rejected in preflight or failed on chain
+    Transaction {
+        error: TransactionError,
+        logs: Vec<String>,
+    },
+
+    /// An instruction returned ProgramError::Custom, i.e. a SearchEngineError or ResourceError
+    Program {
+        program_id: Pubkey,
+        instruction_index: u8,
+        code: u32,
+        logs: Vec<String>,
+    },
+}
+
+impl BootstrapError {
+    pub fn from_client_error(err: ClientError, instructions: &[Instruction]) -> Self {
+        let (transaction_error, logs) = match err.kind() {
+            ClientErrorKind::TransactionError(error) => (Some(error.clone()), Vec::new()),
+            ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(result),
+                ..
+            }) => (result.err.clone(), result.logs.clone().unwrap_or_default()),
+            _ => (None, Vec::new()),
+        };
+        match transaction_error {
+            Some(TransactionError::InstructionError(index, InstructionError::Custom(code)))
+                if (index as usize) < instructions.len() => BootstrapError::Program {
+                    program_id: instructions[index as usize].program_id,
+                    instruction_index: index,
+                    code,
+                    logs,
+                },
+            Some(error) => BootstrapError::Transaction { error, logs },
+            None => BootstrapError::Rpc(err),
+        }
+    }
+
+    // the blockhash expired before the transaction was confirmed, so it can be re-signed and resent
+    pub fn is_expired_blockhash(&self) -> bool {
+        match self {
+            BootstrapError::Transaction { error: TransactionError::BlockhashNotFound, .. } => true,
+            BootstrapError::Rpc(err) => match err.kind() {
+                ClientErrorKind::RpcError(RpcError::ForUser(message)) => message.starts_with("unable to confirm transaction"),
+                _ => false,
+            },
+            _ => false,
+        }
+    }
+
+    // SearchEngineError/ResourceError name for a program error from one of our programs
+    pub fn program_error_name(&self, searchengine_id: &Pubkey, resource_id: &Pubkey) -> Option<&'static str> {
+        match self {
+            BootstrapError::Program { program_id, code, .. } => {
+                let names = if program_id == searchengine_id {
+                    SE_ERRORS
+                } else if program_id == resource_id {
+                    RESOURCE_ERRORS
+                } else {
+                    return None;
+                };
+                names.get(*code as usize).copied()
+            }
+            _ => None,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            BootstrapError::Rpc(_) => EXIT_RPC,
+            BootstrapError::Transaction { .. } => EXIT_TRANSACTION,
+            BootstrapError::Program { .. } => EXIT_PROGRAM,
+        }
+    }
+
+    pub fn logs(&self) -> &[String] {
+        match self {
+            BootstrapError::Rpc(_) => &[],
+            BootstrapError::Transaction { logs, .. } => logs,
+            BootstrapError::Program { logs, .. } => logs,
+        }
+    }
+}
+
+impl fmt::Display for BootstrapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BootstrapError::Rpc(err) => write!(f, "rpc error: {}", err),
+            BootstrapError::Transaction { error, .. } => write!(f, "transaction failed: {}", error),
+            BootstrapError::Program { program_id, instruction_index, code, .. } => {
+                write!(f, "instruction {} failed in program {} with custom error {}", instruction_index, program_id, code)
+            }
+        }
+    }
+}
+
+impl Error for BootstrapError {}
+
+impl From<ClientError> for BootstrapError {
+    fn from(err: ClientError) -> Self {
+        BootstrapError::Rpc(err)
+    }
+}
+
+// Exit codes. Clap already exits with 2 on usage errors.
+pub const EXIT_FAILURE: i32 = 1;
+pub const EXIT_RPC: i32 = 3;
+pub const EXIT_TRANSACTION: i32 = 4;
+pub const EXIT_PROGRAM: i32 = 5;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_custom_error_names_program() {
+        let program_id = Pubkey::new_unique();
+        let instructions = [
+            Instruction { program_id: Pubkey::new_unique(), accounts: vec![], data: vec![] },
+            Instruction { program_id, accounts: vec![], data: vec![] },
+        ];
+        let err = ClientError::from(TransactionError::InstructionError(1, InstructionError::Custom(2)));
+        match BootstrapError::from_client_error(err, &instructions) {
+            BootstrapError::Program { program_id: failed, instruction_index, code, .. } => {
+                assert_eq!(failed, program_id);
+                assert_eq!(instruction_index, 1);
+                assert_eq!(code, 2);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let err = BootstrapError::from_client_error(
+            ClientError::from(TransactionError::InstructionError(1, InstructionError::Custom(2))),
+            &instructions);
+        assert_eq!(err.program_error_name(&program_id, &Pubkey::new_unique()), Some("BucketFull"));
+        assert_eq!(err.program_error_name(&Pubkey::new_unique(), &program_id), Some("ResourceInDistribution"));
+    }
+
+    #[test]
+    fn test_expired_blockhash() {
+        let err = BootstrapError::from_client_error(ClientError::from(TransactionError::BlockhashNotFound), &[]);
+        assert!(err.is_expired_blockhash());
+        assert_eq!(err.exit_code(), EXIT_TRANSACTION);
+    }
+}
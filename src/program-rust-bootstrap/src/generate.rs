@@ -0,0 +1,254 @@
+// Synthetic data for load and scenario testing. A plan (users, trust graph, zips and intents)
+// is derived from the seed alone, including the users' keypairs, so the same options always
+// describe the same accounts and re-running a generation only fills in what is missing.
+//
+// Everything is sent through the real instructions. Program errors (a full ResourceIndex, a
+// resource out of recipient slots, ...) are tallied instead of aborting the run, since hitting
+// those limits is usually the point.
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer, keypair_from_seed},
+};
+use solana_client::rpc_client::RpcClient;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::BTreeMap;
+use crate::{
+    client::send_instructions,
+    error::BootstrapError,
+    instruction::{searchengine, resource},
+    provision::{ProvisionResult, ensure_resource, ensure_user},
+    types::{
+        MAX_TRUST_TABLE_SIZE,
+        Location,
+        Resource,
+        TrustTableEntry,
+        Need,
+        padded_name,
+        padded_zip,
+    },
+};
+
+const NAMES: &[&str] = &[
+    "alice", "bob", "carol", "dave", "erin", "frank", "grace", "heidi",
+    "ivan", "judy", "mallory", "niaj", "olivia", "peggy", "rupert", "sybil",
+    "trent", "victor", "walter", "yolanda",
+];
+
+// every 5 digit zip, a plan can't take more distinct ones than that
+pub const MAX_ZIPS: usize = 100000;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerateOptions {
+    pub users: usize,
+    pub zips: usize,
+    // probability of each directed trust edge
+    pub trust_density: f64,
+    // fraction of users registering an intent with the resource
+    pub intent_ratio: f64,
+    pub trust_threshold: u8,
+    pub seed: u64,
+}
+
+pub struct PlannedUser {
+    pub name: String,
+    pub keypair: Keypair,
+    // (user index, trust value)
+    pub trust: Vec<(usize, u8)>,
+}
+
+pub struct Plan {
+    pub users: Vec<PlannedUser>,
+    pub zips: Vec<String>,
+    // user indexes
+    pub intents: Vec<usize>,
+    pub trust_threshold: u8,
+}
+
+impl Plan {
+    pub fn new(options: &GenerateOptions) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(options.seed);
+
+        let mut users: Vec<PlannedUser> = (0..options.users).map(|index| {
+            let mut seed = [0u8; 32];
+            rng.fill(&mut seed);
+            PlannedUser {
+                name: user_name(index),
+                keypair: keypair_from_seed(&seed).unwrap(),
+                trust: Vec::new(),
+            }
+        }).collect();
+        for (from, user) in users.iter_mut().enumerate() {
+            for to in 0..options.users {
+                if to != from && rng.gen_bool(options.trust_density) {
+                    user.trust.push((to, rng.gen_range(1, 21)));
+                }
+            }
+        }
+
+        let mut zips = Vec::new();
+        while zips.len() < options.zips {
+            let zip = format!("{:05}", rng.gen_range(0, MAX_ZIPS));
+            if !zips.contains(&zip) {
+                zips.push(zip);
+            }
+        }
+
+        let intents = (0..options.users).filter(|_| rng.gen_bool(options.intent_ratio)).collect();
+
+        Plan {
+            users,
+            zips,
+            intents,
+            trust_threshold: options.trust_threshold,
+        }
+    }
+
+    // a trust table only holds MAX_TRUST_TABLE_SIZE entries, the rest of the edges are dropped
+    pub fn trust_table(&self, user: &PlannedUser) -> [TrustTableEntry; MAX_TRUST_TABLE_SIZE] {
+        let mut trust_table = [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE];
+        for (entry, (to, value)) in trust_table.iter_mut().zip(user.trust.iter()) {
+            *entry = TrustTableEntry {
+                to: self.users[*to].keypair.pubkey().to_bytes(),
+                value: *value,
+            };
+        }
+        trust_table
+    }
+}
+
+// alice, bob, ..., then alice1, bob1, ...
+fn user_name(index: usize) -> String {
+    match index / NAMES.len() {
+        0 => NAMES[index % NAMES.len()].to_string(),
+        round => format!("{}{}", NAMES[index % NAMES.len()], round),
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PhaseReport {
+    pub succeeded: usize,
+    // failures by program error name, or the transaction error
+    pub failed: BTreeMap<String, usize>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GenerateReport {
+    pub users: PhaseReport,
+    pub dropped_trust_edges: usize,
+    pub resources: PhaseReport,
+    pub intents: PhaseReport,
+}
+
+pub struct Programs {
+    pub searchengine_id: Pubkey,
+    pub searchengine_database: Pubkey,
+    pub resource_id: Pubkey,
+    pub resource_database: Pubkey,
+}
+
+impl Programs {
+    // RPC and local failures abort the run, anything the cluster rejected is counted
+    fn tally<T>(&self, phase: &mut PhaseReport, result: ProvisionResult<T>) -> ProvisionResult<()> {
+        match result {
+            Ok(_) => phase.succeeded += 1,
+            Err(err) => match err.downcast_ref::<BootstrapError>() {
+                Some(BootstrapError::Rpc(_)) | None => return Err(err),
+                Some(bootstrap_error) => {
+                    let name = bootstrap_error
+                        .program_error_name(&self.searchengine_id, &self.resource_id)
+                        .map(str::to_string)
+                        .unwrap_or_else(|| bootstrap_error.to_string());
+                    *phase.failed.entry(name).or_insert(0) += 1;
+                }
+            },
+        }
+        Ok(())
+    }
+}
+
+// Users first, then one resource per zip (all backed by the resource program), then intents
+pub fn generate(rpc_client: &RpcClient, payer: &Keypair, programs: &Programs, plan: &Plan) -> ProvisionResult<GenerateReport> {
+    let mut report = GenerateReport::default();
+
+    for user in plan.users.iter() {
+        report.dropped_trust_edges += user.trust.len().saturating_sub(MAX_TRUST_TABLE_SIZE);
+        let result = ensure_user(rpc_client, payer, &programs.searchengine_id, &user.keypair, &user.name, plan.trust_table(user));
+        programs.tally(&mut report.users, result)?;
+    }
+
+    for zip in plan.zips.iter() {
+        let resource = Resource {
+            address: programs.resource_id.to_bytes(),
+            name: padded_name(&format!("resource-{}", zip)),
+            location: Location {
+                zip: padded_zip(zip),
+            },
+            trust_threshold: plan.trust_threshold,
+        };
+        let result = ensure_resource(rpc_client, payer, &programs.searchengine_id, &programs.searchengine_database, &resource);
+        programs.tally(&mut report.resources, result)?;
+    }
+
+    for index in plan.intents.iter() {
+        let recipient = &plan.users[*index].keypair;
+        let account = recipient.pubkey();
+        let result: ProvisionResult<_> = send_instructions(rpc_client, payer, &[recipient], &[
+            searchengine::register_intent(&programs.searchengine_id, &programs.searchengine_database, &account, &programs.resource_id),
+            resource::register_intent(&programs.resource_id, &programs.resource_database, &account, &Need::default(), &[]),
+        ]).map_err(|err| err.into());
+        programs.tally(&mut report.intents, result)?;
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn options(seed: u64) -> GenerateOptions {
+        GenerateOptions {
+            users: 30,
+            zips: 5,
+            trust_density: 0.2,
+            intent_ratio: 0.5,
+            trust_threshold: 10,
+            seed,
+        }
+    }
+
+    #[test]
+    fn test_plan_is_reproducible() {
+        let first = Plan::new(&options(7));
+        let second = Plan::new(&options(7));
+        let other = Plan::new(&options(8));
+
+        let pubkeys = |plan: &Plan| plan.users.iter().map(|user| user.keypair.pubkey()).collect::<Vec<_>>();
+        assert_eq!(pubkeys(&first), pubkeys(&second));
+        assert_ne!(pubkeys(&first), pubkeys(&other));
+        assert_eq!(first.zips, second.zips);
+        assert_eq!(first.intents, second.intents);
+        assert_eq!(
+            first.users.iter().map(|user| user.trust.clone()).collect::<Vec<_>>(),
+            second.users.iter().map(|user| user.trust.clone()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_plan_shape() {
+        let plan = Plan::new(&options(1));
+        assert_eq!(plan.users.len(), 30);
+        assert_eq!(plan.users[0].name, "alice");
+        assert_eq!(plan.users[21].name, "bob1");
+        assert_eq!(plan.zips.len(), 5);
+        for (from, user) in plan.users.iter().enumerate() {
+            assert!(user.trust.iter().all(|(to, value)| *to != from && (1..=20).contains(value)));
+        }
+
+        let dense = Plan::new(&GenerateOptions { trust_density: 1.0, intent_ratio: 1.0, ..options(1) });
+        assert!(dense.users.iter().all(|user| user.trust.len() == 29));
+        assert_eq!(dense.intents.len(), 30);
+        let table = dense.trust_table(&dense.users[0]);
+        assert_eq!(table[0].to, dense.users[dense.users[0].trust[0].0].keypair.pubkey().to_bytes());
+    }
+}
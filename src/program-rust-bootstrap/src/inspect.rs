@@ -0,0 +1,599 @@
+// Decodes raw account data into readable JSON: padded strings are trimmed, keys are base58
+// and empty (all zero) slots are left out.
+use solana_sdk::pubkey::Pubkey;
+use borsh::BorshDeserialize;
+use serde_json::{json, Value};
+use crate::types::{
+    PUBLIC_KEY_SIZE,
+    SEARCH_ENGINE_ACCOUNT_SIZE,
+    RESOURCE_INDEX_SIZE,
+    RESOURCE_DATABASE_SIZE,
+    ROUND_HISTORY_SIZE,
+    LAMPORT_VAULT_SIZE,
+    CLAIM_RECEIPT_SIZE,
+    WAITLIST_PAGE_SIZE,
+    QUORUM_ALL,
+    QUORUM_MAJORITY,
+    QUORUM_AT_LEAST,
+    QUORUM_TRUST_WEIGHTED,
+    QUORUM_REPUTATION_WEIGHTED,
+    ELIGIBILITY_OPEN,
+    ELIGIBILITY_ALLOW_LIST,
+    ELIGIBILITY_DENY_LIST,
+    ELIGIBILITY_TRUSTED_BY_PROVIDER,
+    ALLOCATION_PROPORTIONAL,
+    ALLOCATION_EQUAL_SPLIT,
+    ALLOCATION_MAX_MIN_FAIR,
+    ALLOCATION_FIRST_COME_FIRST_SERVED,
+    ALLOCATION_LOTTERY,
+    SearchEngineAccount,
+    ResourceIndex,
+    ResourceDatabase,
+    RoundHistory,
+    RoundRecord,
+    LamportVault,
+    ClaimReceipt,
+    UnitOfMeasure,
+    EligibilityRule,
+    Intent,
+    Draw,
+    Waitlist,
+    WaitlistPage,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AccountType {
+    SearchEngineAccount,
+    ResourceIndex,
+    ResourceDatabase,
+    RoundHistory,
+    LamportVault,
+    ClaimReceipt,
+    WaitlistPage,
+}
+
+impl AccountType {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "searchengine-account" => Some(AccountType::SearchEngineAccount),
+            "resource-index" => Some(AccountType::ResourceIndex),
+            "resource-database" => Some(AccountType::ResourceDatabase),
+            "round-history" => Some(AccountType::RoundHistory),
+            "lamport-vault" => Some(AccountType::LamportVault),
+            "claim-receipt" => Some(AccountType::ClaimReceipt),
+            "waitlist-page" => Some(AccountType::WaitlistPage),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccountType::SearchEngineAccount => "searchengine-account",
+            AccountType::ResourceIndex => "resource-index",
+            AccountType::ResourceDatabase => "resource-database",
+            AccountType::RoundHistory => "round-history",
+            AccountType::LamportVault => "lamport-vault",
+            AccountType::ClaimReceipt => "claim-receipt",
+            AccountType::WaitlistPage => "waitlist-page",
+        }
+    }
+
+    // every layout has a distinct size, so the data length is enough to tell them apart
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data.len() {
+            SEARCH_ENGINE_ACCOUNT_SIZE => Some(AccountType::SearchEngineAccount),
+            RESOURCE_INDEX_SIZE => Some(AccountType::ResourceIndex),
+            RESOURCE_DATABASE_SIZE => Some(AccountType::ResourceDatabase),
+            ROUND_HISTORY_SIZE => Some(AccountType::RoundHistory),
+            LAMPORT_VAULT_SIZE => Some(AccountType::LamportVault),
+            CLAIM_RECEIPT_SIZE => Some(AccountType::ClaimReceipt),
+            WAITLIST_PAGE_SIZE => Some(AccountType::WaitlistPage),
+            _ => None,
+        }
+    }
+}
+
+const EMPTY_ADDRESS: [u8; PUBLIC_KEY_SIZE] = [0u8; PUBLIC_KEY_SIZE];
+
+fn pubkey_string(bytes: &[u8; PUBLIC_KEY_SIZE]) -> String {
+    Pubkey::new_from_array(*bytes).to_string()
+}
+
+fn trimmed_string(bytes: &[u8]) -> String {
+    let end = bytes.iter().rposition(|byte| *byte != 0).map_or(0, |index| index + 1);
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+fn optional_pubkey(bytes: &[u8; PUBLIC_KEY_SIZE]) -> Option<String> {
+    if *bytes != EMPTY_ADDRESS { Some(pubkey_string(bytes)) } else { None }
+}
+
+fn pubkey_list(keys: &[[u8; PUBLIC_KEY_SIZE]]) -> Vec<String> {
+    keys.iter()
+        .filter(|key| **key != EMPTY_ADDRESS)
+        .map(pubkey_string)
+        .collect()
+}
+
+// registered recipients with what they asked for and were allocated
+fn intents_json(intents: &[Intent]) -> Vec<Value> {
+    intents.iter()
+        .filter(|intent| intent.recipient != EMPTY_ADDRESS)
+        .map(|intent| json!({
+            "recipient": pubkey_string(&intent.recipient),
+            "requested": intent.need.requested,
+            "household_size": intent.need.household_size,
+            "allocation": intent.allocation,
+        }))
+        .collect()
+}
+
+fn unit_json(unit: &UnitOfMeasure) -> Value {
+    json!({
+        "name": unit.name(),
+        "decimals": unit.decimals,
+    })
+}
+
+fn strategy_name(strategy: u8) -> &'static str {
+    match strategy {
+        ALLOCATION_PROPORTIONAL => "proportional",
+        ALLOCATION_EQUAL_SPLIT => "equal-split",
+        ALLOCATION_MAX_MIN_FAIR => "max-min-fair",
+        ALLOCATION_FIRST_COME_FIRST_SERVED => "first-come-first-served",
+        ALLOCATION_LOTTERY => "lottery",
+        _ => "unknown",
+    }
+}
+
+// the slot a lottery is committed to and the slot hash it was drawn with, null without a lottery
+fn draw_json(draw: &Draw) -> Value {
+    if *draw == Draw::default() {
+        return Value::Null
+    }
+    if !draw.is_drawn() {
+        return json!({
+            "committed_slot": draw.committed_slot,
+        })
+    }
+    json!({
+        "committed_slot": draw.committed_slot,
+        "slot": draw.slot,
+        "hash": pubkey_string(&draw.hash),
+    })
+}
+
+// the pages are inspected one by one, the database only knows the ends of the chain
+fn waitlist_json(waitlist: &Waitlist) -> Value {
+    json!({
+        "head": optional_pubkey(&waitlist.head),
+        "tail": optional_pubkey(&waitlist.tail),
+        "waiting": waitlist.waiting,
+    })
+}
+
+fn eligibility_json(eligibility: &EligibilityRule) -> Value {
+    let rule = match eligibility.kind {
+        ELIGIBILITY_OPEN => "open",
+        ELIGIBILITY_ALLOW_LIST => "allow-list",
+        ELIGIBILITY_DENY_LIST => "deny-list",
+        ELIGIBILITY_TRUSTED_BY_PROVIDER => "trusted-by-provider",
+        _ => "unknown",
+    };
+    if eligibility.kind == ELIGIBILITY_TRUSTED_BY_PROVIDER {
+        json!({
+            "rule": rule,
+            "provider": pubkey_string(&eligibility.provider),
+            "trust_program": pubkey_string(&eligibility.trust_program),
+            "min_trust": eligibility.min_trust,
+        })
+    } else {
+        json!({
+            "rule": rule,
+            "list": pubkey_list(&eligibility.list),
+        })
+    }
+}
+
+// what every recipient of an archived round received, and who approved whom
+pub fn round_json(record: &RoundRecord) -> Value {
+    let allocations: Vec<Value> = record.intents.iter()
+        .filter(|intent| intent.recipient != EMPTY_ADDRESS)
+        .map(|intent| json!({
+            "recipient": pubkey_string(&intent.recipient),
+            "requested": intent.need.requested,
+            "quantity": record.allocation(&intent.recipient),
+            "approvals_received": record.approvals_received(&intent.recipient),
+        }))
+        .collect();
+    let approvals: Vec<Value> = record.challenges.iter()
+        .filter(|challenge| challenge.from != EMPTY_ADDRESS && challenge.value)
+        .map(|challenge| json!({
+            "from": pubkey_string(&challenge.from),
+            "to": pubkey_string(&challenge.to),
+        }))
+        .collect();
+    json!({
+        "round": record.round,
+        "final_quantity": record.final_quantity,
+        "window": {
+            "start_slot": record.window.start_slot,
+            "start_timestamp": record.window.start_timestamp,
+            "duration": record.window.duration,
+            "deadline": record.window.deadline(),
+        },
+        "claims": pubkey_list(&record.claims),
+        "allocations": allocations,
+        "approvals": approvals,
+        "unit": unit_json(&record.unit),
+        "strategy": strategy_name(record.strategy),
+        "draw": draw_json(&record.draw),
+    })
+}
+
+pub fn decode(account_type: AccountType, data: &[u8]) -> std::io::Result<Value> {
+    Ok(match account_type {
+        AccountType::SearchEngineAccount => {
+            let account = SearchEngineAccount::try_from_slice(data)?;
+            let trust_table: Vec<Value> = account.trust_table.iter()
+                .filter(|entry| entry.to != EMPTY_ADDRESS)
+                .map(|entry| json!({
+                    "to": pubkey_string(&entry.to),
+                    "value": entry.value,
+                }))
+                .collect();
+            json!({
+                "friendly_name": trimmed_string(&account.friendly_name),
+                "trust_table": trust_table,
+                "intents": pubkey_list(&account.intents),
+                "reputation": {
+                    "approvals_given": account.reputation.approvals_given,
+                    "approvals_received": account.reputation.approvals_received,
+                    "claims": account.reputation.claims,
+                    "disputes": account.reputation.disputes,
+                    "next_settle_slots": account.reputation.next_settle_slots,
+                    "score": account.reputation.score(),
+                },
+            })
+        }
+        AccountType::ResourceIndex => {
+            let index = ResourceIndex::try_from_slice(data)?;
+            let buckets: Vec<Value> = index.buckets.iter()
+                .filter(|bucket| bucket.location.zip.iter().any(|byte| *byte != 0))
+                .map(|bucket| json!({
+                    "zip": trimmed_string(&bucket.location.zip),
+                    "addresses": pubkey_list(&bucket.addresses),
+                }))
+                .collect();
+            json!({
+                "buckets": buckets,
+                "authority": pubkey_string(&index.authority),
+            })
+        }
+        AccountType::ResourceDatabase => {
+            let database = ResourceDatabase::try_from_slice(data)?;
+            let instances: Vec<Value> = database.instances.iter()
+                .filter(|instance| instance.from != EMPTY_ADDRESS)
+                .map(|instance| json!({
+                    "from": pubkey_string(&instance.from),
+                    "quantity": instance.quantity,
+                    // still owed to the contributor, once claims closed
+                    "refund": if database.is_finalized { database.refund(&instance.from) } else { None },
+                }))
+                .collect();
+            let challenges: Vec<Value> = database.challenges.iter()
+                .filter(|challenge| challenge.from != EMPTY_ADDRESS)
+                .map(|challenge| json!({
+                    "from": pubkey_string(&challenge.from),
+                    "to": pubkey_string(&challenge.to),
+                    "value": challenge.value,
+                }))
+                .collect();
+            json!({
+                "is_distributed": database.is_distributed,
+                "final_quantity": database.final_quantity,
+                "intents": intents_json(&database.intents),
+                "instances": instances,
+                "challenges": challenges,
+                "claims": pubkey_list(&database.claims),
+                "window": {
+                    "start_slot": database.window.start_slot,
+                    "start_timestamp": database.window.start_timestamp,
+                    "duration": database.window.duration,
+                    "deadline": database.window.deadline(),
+                },
+                "is_finalized": database.is_finalized,
+                "quorum": {
+                    "rule": match database.quorum.kind {
+                        QUORUM_ALL => "all",
+                        QUORUM_MAJORITY => "majority",
+                        QUORUM_AT_LEAST => "at-least",
+                        QUORUM_TRUST_WEIGHTED => "trust-weighted",
+                        QUORUM_REPUTATION_WEIGHTED => "reputation-weighted",
+                        _ => "unknown",
+                    },
+                    "threshold": database.quorum.threshold,
+                    "trust_program": pubkey_string(&database.quorum.trust_program),
+                },
+                "round": database.round,
+                "escrow_mint": if database.is_escrowed() { Some(pubkey_string(&database.escrow_mint)) } else { None },
+                "vault": if database.has_vault() { Some(pubkey_string(&database.vault)) } else { None },
+                "refunded": database.instances.iter().zip(database.refunded.iter())
+                    .filter(|(instance, refunded)| instance.from != EMPTY_ADDRESS && **refunded)
+                    .map(|(instance, _)| pubkey_string(&instance.from))
+                    .collect::<Vec<String>>(),
+                "receipts": database.claims.iter().zip(database.receipts.iter())
+                    .filter(|(claim, issued)| **claim != EMPTY_ADDRESS && **issued)
+                    .map(|(claim, _)| pubkey_string(claim))
+                    .collect::<Vec<String>>(),
+                "unit": unit_json(&database.unit),
+                "eligibility": eligibility_json(&database.eligibility),
+                "strategy": strategy_name(database.strategy),
+                "draw": draw_json(&database.draw),
+                "capacity": database.capacity(),
+                "waitlist": waitlist_json(&database.waitlist),
+                "history": optional_pubkey(&database.history),
+                "authority": optional_pubkey(&database.authority),
+            })
+        }
+        AccountType::RoundHistory => {
+            let history = RoundHistory::try_from_slice(data)?;
+            let rounds: Vec<Value> = history.rounds().into_iter().map(round_json).collect();
+            json!({
+                "database": pubkey_string(&history.database),
+                "next_round": history.next_round,
+                "rounds": rounds,
+            })
+        }
+        // the vault's balance is the account's lamports, the data only binds it
+        AccountType::LamportVault => {
+            let vault = LamportVault::try_from_slice(data)?;
+            json!({
+                "database": optional_pubkey(&vault.database),
+            })
+        }
+        // waiting intents in the order they are promoted
+        AccountType::WaitlistPage => {
+            let page = WaitlistPage::try_from_slice(data)?;
+            json!({
+                "database": optional_pubkey(&page.database),
+                "next": optional_pubkey(&page.next),
+                "intents": intents_json(&page.intents),
+            })
+        }
+        // only trust a receipt whose account the resource program owns, inspect prints the owner
+        AccountType::ClaimReceipt => {
+            let receipt = ClaimReceipt::try_from_slice(data)?;
+            if receipt.database == EMPTY_ADDRESS {
+                json!({ "issued": false })
+            } else {
+                json!({
+                    "issued": true,
+                    "database": pubkey_string(&receipt.database),
+                    "recipient": pubkey_string(&receipt.recipient),
+                    "round": receipt.round,
+                    "quantity": receipt.quantity,
+                    "unit": unit_json(&receipt.unit),
+                    "amount": receipt.unit.format(receipt.quantity),
+                })
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use borsh::BorshSerialize;
+    use crate::types::{
+        MAX_INDEX_BUCKETS,
+        MAX_NUM_RESOURCE_IN_BUCKET,
+        MAX_NUM_RECIPIENTS,
+        MAX_NUM_CHALLENGES,
+        MAX_NUM_ROUNDS,
+        ResourceBucket,
+        Location,
+        Challenge,
+        DistributionWindow,
+        ResourceInstance,
+        Need,
+        padded_zip,
+    };
+
+    #[test]
+    fn test_decode_resource_index() {
+        let address = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let mut index = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: authority.to_bytes(),
+        };
+        index.buckets[0] = ResourceBucket {
+            location: Location {
+                zip: padded_zip("12345"),
+            },
+            addresses: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RESOURCE_IN_BUCKET],
+        };
+        index.buckets[0].addresses[0] = address.to_bytes();
+        let data = index.try_to_vec().unwrap();
+
+        assert_eq!(AccountType::detect(&data), Some(AccountType::ResourceIndex));
+        let decoded = decode(AccountType::ResourceIndex, &data).unwrap();
+        assert_eq!(decoded, json!({
+            "buckets": [{
+                "zip": "12345",
+                "addresses": [address.to_string()],
+            }],
+            "authority": authority.to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_decode_round_history() {
+        let database = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut history = RoundHistory {
+            database: database.to_bytes(),
+            next_round: MAX_NUM_ROUNDS as u32 + 1,
+            rounds: [RoundRecord::default(); MAX_NUM_ROUNDS],
+        };
+        for round in 0..=MAX_NUM_ROUNDS as u32 {
+            history.rounds[round as usize % MAX_NUM_ROUNDS].round = round;
+        }
+        let mut latest = RoundRecord {
+            round: MAX_NUM_ROUNDS as u32,
+            window: DistributionWindow::default(),
+            final_quantity: 5,
+            intents: [Intent::default(); MAX_NUM_RECIPIENTS],
+            claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            challenges: [Challenge::default(); MAX_NUM_CHALLENGES],
+            unit: UnitOfMeasure::default(),
+            strategy: ALLOCATION_LOTTERY,
+            draw: Draw { committed_slot: 40, slot: 41, hash: [4u8; PUBLIC_KEY_SIZE] },
+        };
+        latest.intents[0] = Intent { recipient: alice.to_bytes(), need: Need { requested: 3, household_size: 0 }, allocation: 3 };
+        latest.intents[1] = Intent { recipient: bob.to_bytes(), need: Need::default(), allocation: 2 };
+        latest.claims[0] = alice.to_bytes();
+        latest.challenges[0] = Challenge { from: bob.to_bytes(), to: alice.to_bytes(), value: true };
+        history.rounds[0] = latest;
+        let data = history.try_to_vec().unwrap();
+
+        assert_eq!(AccountType::detect(&data), Some(AccountType::RoundHistory));
+        let decoded = decode(AccountType::RoundHistory, &data).unwrap();
+        assert_eq!(decoded["database"], database.to_string());
+        // round 0 was overwritten by round MAX_NUM_ROUNDS
+        let rounds = decoded["rounds"].as_array().unwrap();
+        assert_eq!(rounds.len(), MAX_NUM_ROUNDS);
+        assert_eq!(rounds[0]["round"], 1);
+        assert_eq!(rounds[MAX_NUM_ROUNDS - 1]["allocations"], json!([
+            { "recipient": alice.to_string(), "requested": 3, "quantity": 3, "approvals_received": 1 },
+            { "recipient": bob.to_string(), "requested": 0, "quantity": 0, "approvals_received": 0 },
+        ]));
+        assert_eq!(rounds[MAX_NUM_ROUNDS - 1]["approvals"], json!([
+            { "from": bob.to_string(), "to": alice.to_string() },
+        ]));
+        assert_eq!(rounds[MAX_NUM_ROUNDS - 1]["strategy"], "lottery");
+        assert_eq!(rounds[MAX_NUM_ROUNDS - 1]["draw"], json!({ "committed_slot": 40, "slot": 41, "hash": Pubkey::new_from_array([4u8; PUBLIC_KEY_SIZE]).to_string() }));
+        assert_eq!(rounds[0]["draw"], Value::Null);
+    }
+
+    #[test]
+    fn test_decode_pooled_database() {
+        let carol = Pubkey::new_unique();
+        let dave = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mut database = ResourceDatabase::try_from_slice(&[0u8; RESOURCE_DATABASE_SIZE]).unwrap();
+        database.instances[0] = ResourceInstance { from: carol.to_bytes(), quantity: 6 };
+        database.instances[1] = ResourceInstance { from: dave.to_bytes(), quantity: 2 };
+        database.intents[0] = Intent { recipient: alice.to_bytes(), need: Need { requested: 4, household_size: 3 }, allocation: 4 };
+        database.final_quantity = 4;
+        database.claims[0] = alice.to_bytes();
+        let data = database.try_to_vec().unwrap();
+        assert_eq!(AccountType::detect(&data), Some(AccountType::ResourceDatabase));
+
+        // refunds are only owed once claims closed
+        let decoded = decode(AccountType::ResourceDatabase, &data).unwrap();
+        assert_eq!(decoded["instances"][0]["refund"], Value::Null);
+        assert_eq!(decoded["intents"], json!([
+            { "recipient": alice.to_string(), "requested": 4, "household_size": 3, "allocation": 4 },
+        ]));
+        assert_eq!(decoded["eligibility"], json!({ "rule": "open", "list": [] }));
+        assert_eq!(decoded["strategy"], "proportional");
+        assert_eq!(decoded["draw"], Value::Null);
+        assert_eq!(decoded["capacity"], MAX_NUM_RECIPIENTS);
+        assert_eq!(decoded["waitlist"], json!({ "head": null, "tail": null, "waiting": 0 }));
+        assert_eq!(decoded["history"], Value::Null);
+        assert_eq!(decoded["authority"], Value::Null);
+
+        // a lottery waiting for its draw only shows the slot it is committed to
+        let mut committed = database.clone();
+        committed.draw = Draw { committed_slot: 9, ..Draw::default() };
+        let decoded_committed = decode(AccountType::ResourceDatabase, &committed.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded_committed["draw"], json!({ "committed_slot": 9 }));
+
+        // the 4 left go back three quarters to carol, a quarter to dave
+        database.is_finalized = true;
+        database.refunded[1] = true;
+        let decoded = decode(AccountType::ResourceDatabase, &database.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded["instances"], json!([
+            { "from": carol.to_string(), "quantity": 6, "refund": 3 },
+            { "from": dave.to_string(), "quantity": 2, "refund": 0 },
+        ]));
+        assert_eq!(decoded["refunded"], json!([dave.to_string()]));
+
+        database.eligibility.kind = ELIGIBILITY_TRUSTED_BY_PROVIDER;
+        database.eligibility.provider = carol.to_bytes();
+        database.eligibility.trust_program = dave.to_bytes();
+        database.eligibility.min_trust = 5;
+        let decoded = decode(AccountType::ResourceDatabase, &database.try_to_vec().unwrap()).unwrap();
+        assert_eq!(decoded["eligibility"], json!({
+            "rule": "trusted-by-provider",
+            "provider": carol.to_string(),
+            "trust_program": dave.to_string(),
+            "min_trust": 5,
+        }));
+    }
+
+    #[test]
+    fn test_decode_lamport_vault() {
+        let data = LamportVault::default().try_to_vec().unwrap();
+        assert_eq!(AccountType::detect(&data), Some(AccountType::LamportVault));
+        assert_eq!(decode(AccountType::LamportVault, &data).unwrap(), json!({ "database": null }));
+
+        let database = Pubkey::new_unique();
+        let data = LamportVault { database: database.to_bytes() }.try_to_vec().unwrap();
+        assert_eq!(decode(AccountType::LamportVault, &data).unwrap()["database"], database.to_string());
+    }
+
+    #[test]
+    fn test_decode_claim_receipt() {
+        let data = ClaimReceipt::default().try_to_vec().unwrap();
+        assert_eq!(AccountType::detect(&data), Some(AccountType::ClaimReceipt));
+        assert_eq!(decode(AccountType::ClaimReceipt, &data).unwrap(), json!({ "issued": false }));
+
+        let database = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let data = ClaimReceipt {
+            database: database.to_bytes(),
+            recipient: recipient.to_bytes(),
+            round: 2,
+            quantity: 25,
+            unit: UnitOfMeasure::new("kg", 1),
+        }.try_to_vec().unwrap();
+        assert_eq!(decode(AccountType::ClaimReceipt, &data).unwrap(), json!({
+            "issued": true,
+            "database": database.to_string(),
+            "recipient": recipient.to_string(),
+            "round": 2,
+            "quantity": 25,
+            "unit": { "name": "kg", "decimals": 1 },
+            "amount": "2.5 kg",
+        }));
+    }
+
+    #[test]
+    fn test_decode_waitlist_page() {
+        let database = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let mut page = WaitlistPage {
+            database: database.to_bytes(),
+            ..WaitlistPage::default()
+        };
+        page.intents[1] = Intent { recipient: alice.to_bytes(), need: Need { requested: 2, household_size: 1 }, allocation: 0 };
+        let data = page.try_to_vec().unwrap();
+
+        assert_eq!(AccountType::detect(&data), Some(AccountType::WaitlistPage));
+        assert_eq!(decode(AccountType::WaitlistPage, &data).unwrap(), json!({
+            "database": database.to_string(),
+            "next": null,
+            "intents": [{ "recipient": alice.to_string(), "requested": 2, "household_size": 1, "allocation": 0 }],
+        }));
+    }
+
+    #[test]
+    fn test_decode_wrong_type() {
+        let data = vec![0u8; RESOURCE_INDEX_SIZE];
+        assert!(decode(AccountType::ResourceDatabase, &data).is_err());
+    }
+}
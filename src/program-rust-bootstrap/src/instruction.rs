@@ -0,0 +1,841 @@
+// Builders for every instruction the programs accept. Account order matches what
+// _process_instruction indexes into (and what ../lib/lib.tsx sends).
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    system_program,
+    sysvar,
+};
+use borsh::BorshSerialize;
+
+fn instruction_data<T: BorshSerialize>(tag: u8, payload: &T) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.append(&mut payload.try_to_vec().unwrap());
+    data
+}
+
+pub mod searchengine {
+    use super::*;
+    use crate::types::{
+        SE_INSTRUCTION_UPDATE_ACCOUNT,
+        SE_INSTRUCTION_REGISTER_RESOURCE,
+        SE_INSTRUCTION_REGISTER_INTENT,
+        SE_INSTRUCTION_INITIALIZE_DATABASE,
+        SE_INSTRUCTION_SETTLE_INTENT,
+        SearchEngineAccount,
+        Resource,
+    };
+
+    pub fn update_account(program_id: &Pubkey, account: &Pubkey, data: &SearchEngineAccount) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![AccountMeta::new(*account, false)],
+            data: instruction_data(SE_INSTRUCTION_UPDATE_ACCOUNT, data),
+        }
+    }
+
+    // the index's authority, signing, vouches for the resource
+    pub fn register_resource(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, resource: &Resource) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: instruction_data(SE_INSTRUCTION_REGISTER_RESOURCE, resource),
+        }
+    }
+
+    pub fn register_intent(program_id: &Pubkey, database: &Pubkey, account: &Pubkey, resource: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(*database, false),
+                AccountMeta::new(*account, false),
+                AccountMeta::new_readonly(*resource, false),
+            ],
+            data: vec![SE_INSTRUCTION_REGISTER_INTENT],
+        }
+    }
+
+    // the authority signs and is the only one registering resources from then on
+    pub fn initialize_database(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: vec![SE_INSTRUCTION_INITIALIZE_DATABASE],
+        }
+    }
+
+    // `round_history` is the history the resource database is bound to, its archived rounds
+    // settle along with a finalized one
+    pub fn settle_intent(program_id: &Pubkey, database: &Pubkey, account: &Pubkey, resource_database: &Pubkey, round_history: Option<&Pubkey>) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new_readonly(*database, false),
+            AccountMeta::new(*account, false),
+            AccountMeta::new_readonly(*resource_database, false),
+        ];
+        if let Some(round_history) = round_history {
+            accounts.push(AccountMeta::new_readonly(*round_history, false));
+        }
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: vec![SE_INSTRUCTION_SETTLE_INTENT],
+        }
+    }
+}
+
+pub mod resource {
+    use super::*;
+    use crate::types::{
+        RESOURCE_INSTRUCTION_REGISTER_INTENT,
+        RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE,
+        RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION,
+        RESOURCE_INSTRUCTION_RECORD_CHALLENGE,
+        RESOURCE_INSTRUCTION_CLAIM_CHALLENGE,
+        RESOURCE_INSTRUCTION_RESET_DATABASE,
+        RESOURCE_INSTRUCTION_START_NEW_ROUND,
+        RESOURCE_INSTRUCTION_FINALIZE_DISTRIBUTION,
+        RESOURCE_INSTRUCTION_SET_QUORUM,
+        RESOURCE_INSTRUCTION_SET_ESCROW_MINT,
+        RESOURCE_INSTRUCTION_SET_LAMPORT_VAULT,
+        RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION,
+        RESOURCE_INSTRUCTION_ISSUE_RECEIPT,
+        RESOURCE_INSTRUCTION_SET_UNIT,
+        RESOURCE_INSTRUCTION_SET_ELIGIBILITY,
+        RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY,
+        RESOURCE_INSTRUCTION_SET_CAPACITY,
+        RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE,
+        RESOURCE_INSTRUCTION_WITHDRAW_INTENT,
+        RESOURCE_INSTRUCTION_DRAW_LOTTERY,
+        ESCROW_SEED,
+        SPL_TOKEN_PROGRAM_ID,
+        ResourceInstance,
+        Challenge,
+        QuorumRule,
+        UnitOfMeasure,
+        EligibilityRule,
+        Need,
+    };
+    use std::str::FromStr;
+
+    pub fn token_program_id() -> Pubkey {
+        Pubkey::from_str(SPL_TOKEN_PROGRAM_ID).unwrap()
+    }
+
+    // the address escrow token accounts of `database` have to be owned by
+    pub fn escrow_authority(program_id: &Pubkey, database: &Pubkey) -> (Pubkey, u8) {
+        Pubkey::find_program_address(&[ESCROW_SEED, database.as_ref()], program_id)
+    }
+
+    // the recipient signs; registering again updates the need, until the distribution starts.
+    // Once every slot up to the capacity is taken the `waitlist` pages are checked for the
+    // intent, which is queued on the tail page
+    pub fn register_intent(program_id: &Pubkey, database: &Pubkey, recipient: &Pubkey, need: &Need, waitlist: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new_readonly(*recipient, true),
+        ];
+        accounts.extend(waitlist.iter().map(|page| AccountMeta::new(*page, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction_data(RESOURCE_INSTRUCTION_REGISTER_INTENT, need),
+        }
+    }
+
+    // for trusted by provider eligibility: the provider's search engine account is read for
+    // their trust in the recipient
+    pub fn register_trusted_intent(program_id: &Pubkey, database: &Pubkey, recipient: &Pubkey, need: &Need, provider: &Pubkey, waitlist: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new_readonly(*recipient, true),
+            AccountMeta::new_readonly(*provider, false),
+        ];
+        accounts.extend(waitlist.iter().map(|page| AccountMeta::new(*page, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction_data(RESOURCE_INSTRUCTION_REGISTER_INTENT, need),
+        }
+    }
+
+    // for databases recording quantities only: the provider signs for the instance
+    pub fn record_resource_instance(program_id: &Pubkey, database: &Pubkey, instance: &ResourceInstance) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(Pubkey::new_from_array(instance.from), true),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE, instance),
+        }
+    }
+
+    // for escrowed databases: the provider signs over `quantity` tokens from `source` to `escrow`
+    pub fn record_escrowed_resource_instance(program_id: &Pubkey, database: &Pubkey, instance: &ResourceInstance, source: &Pubkey, escrow: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new(*escrow, false),
+                AccountMeta::new_readonly(Pubkey::new_from_array(instance.from), true),
+                AccountMeta::new_readonly(token_program_id(), false),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE, instance),
+        }
+    }
+
+    // challenges are accepted for `duration` seconds after the cluster time at execution, a
+    // lottery is only committed to a slot a few slots later. Only the authority opens a round
+    pub fn initiate_distribution(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, duration: i64) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION, &duration),
+        }
+    }
+
+    // draws a committed lottery with the hash of its slot once that slot passed, anyone can send it
+    pub fn draw_lottery(program_id: &Pubkey, database: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(sysvar::slot_hashes::id(), false),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_DRAW_LOTTERY],
+        }
+    }
+
+    // the challenge's `from` signs their own answer
+    pub fn record_challenge(program_id: &Pubkey, database: &Pubkey, challenge: &Challenge) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+                AccountMeta::new_readonly(Pubkey::new(&challenge.from), true),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_RECORD_CHALLENGE, challenge),
+        }
+    }
+
+    pub fn finalize_distribution(program_id: &Pubkey, database: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_FINALIZE_DISTRIBUTION],
+        }
+    }
+
+    // `approvers` are the search engine accounts of the claimant's peers, only read for
+    // trust weighted quorum rules
+    pub fn claim_challenge(program_id: &Pubkey, database: &Pubkey, challenge: &Challenge, approvers: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![AccountMeta::new(*database, false)];
+        accounts.extend(approvers.iter().map(|approver| AccountMeta::new_readonly(*approver, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction_data(RESOURCE_INSTRUCTION_CLAIM_CHALLENGE, challenge),
+        }
+    }
+
+    // for vault databases: the provider transfers `quantity` lamports into the vault
+    pub fn record_vault_resource_instance(program_id: &Pubkey, database: &Pubkey, instance: &ResourceInstance, vault: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(Pubkey::new_from_array(instance.from), true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE, instance),
+        }
+    }
+
+    // for escrowed databases: the allocation is paid from `escrow` into `destination`, a token
+    // account owned by the claimant
+    pub fn claim_escrowed_challenge(program_id: &Pubkey, database: &Pubkey, challenge: &Challenge, escrow: &Pubkey, destination: &Pubkey, approvers: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new(*escrow, false),
+            AccountMeta::new(*destination, false),
+            AccountMeta::new_readonly(escrow_authority(program_id, database).0, false),
+            AccountMeta::new_readonly(token_program_id(), false),
+        ];
+        accounts.extend(approvers.iter().map(|approver| AccountMeta::new_readonly(*approver, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction_data(RESOURCE_INSTRUCTION_CLAIM_CHALLENGE, challenge),
+        }
+    }
+
+    // for vault databases: the allocation is paid from the vault straight to the claimant
+    pub fn claim_vault_challenge(program_id: &Pubkey, database: &Pubkey, challenge: &Challenge, vault: &Pubkey, approvers: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(Pubkey::new_from_array(challenge.from), false),
+        ];
+        accounts.extend(approvers.iter().map(|approver| AccountMeta::new_readonly(*approver, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: instruction_data(RESOURCE_INSTRUCTION_CLAIM_CHALLENGE, challenge),
+        }
+    }
+
+    pub fn set_quorum(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, quorum: &QuorumRule) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_SET_QUORUM, quorum),
+        }
+    }
+
+    // the signer becomes the database's authority, which signs the set_* instructions. Once
+    // there is one, only it can reset the database again
+    pub fn reset_database(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_RESET_DATABASE],
+        }
+    }
+
+    // archives the finalized round into history and clears the database for the next one
+    // `waitlist` are the waitlist's pages from the head, the intents promoted into the slots
+    // left unclaimed are taken off them
+    pub fn start_new_round(program_id: &Pubkey, database: &Pubkey, history: &Pubkey, waitlist: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new(*history, false),
+        ];
+        accounts.extend(waitlist.iter().map(|page| AccountMeta::new(*page, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: vec![RESOURCE_INSTRUCTION_START_NEW_ROUND],
+        }
+    }
+
+    // without a vault the resource goes back to recording quantities, only allowed while
+    // nothing is recorded
+    pub fn set_lamport_vault(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, vault: Option<&Pubkey>) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new_readonly(*authority, true),
+        ];
+        if let Some(vault) = vault {
+            accounts.push(AccountMeta::new(*vault, false));
+        }
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: vec![RESOURCE_INSTRUCTION_SET_LAMPORT_VAULT],
+        }
+    }
+
+    // sends the contributor their share of what was not claimed, once the distribution is finalized
+    pub fn refund_contribution(program_id: &Pubkey, database: &Pubkey, vault: &Pubkey, contributor: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new(*vault, false),
+                AccountMeta::new(*contributor, false),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION],
+        }
+    }
+
+    // for escrowed databases: the refund leaves escrow for a token account the contributor owns
+    pub fn refund_escrowed_contribution(program_id: &Pubkey, database: &Pubkey, escrow: &Pubkey, destination: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new(*escrow, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(escrow_authority(program_id, database).0, false),
+                AccountMeta::new_readonly(token_program_id(), false),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION],
+        }
+    }
+
+    // writes the recipient's claim into an empty receipt account owned by the resource program
+    pub fn issue_receipt(program_id: &Pubkey, database: &Pubkey, receipt: &Pubkey, recipient: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new(*receipt, false),
+                AccountMeta::new_readonly(*recipient, false),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_ISSUE_RECEIPT],
+        }
+    }
+
+    // what recorded quantities count, only allowed while nothing is recorded
+    pub fn set_unit(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, unit: &UnitOfMeasure) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_SET_UNIT, unit),
+        }
+    }
+
+    // who may register an intent from now on, not while the resource is distributed
+    pub fn set_eligibility(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, eligibility: &EligibilityRule) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_SET_ELIGIBILITY, eligibility),
+        }
+    }
+
+    // one of the ALLOCATION_* strategies, only before anyone registers or records
+    pub fn set_allocation_strategy(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, strategy: u8) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: vec![RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY, strategy],
+        }
+    }
+
+    // at most MAX_NUM_RECIPIENTS, zero opens every slot. Raising it promotes waiting intents
+    // from the `waitlist` pages
+    pub fn set_capacity(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, capacity: u8, waitlist: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new_readonly(*authority, true),
+        ];
+        accounts.extend(waitlist.iter().map(|page| AccountMeta::new(*page, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: vec![RESOURCE_INSTRUCTION_SET_CAPACITY, capacity],
+        }
+    }
+
+    // chains a page owned by the program after the waitlist's tail, if it has one
+    pub fn add_waitlist_page(program_id: &Pubkey, database: &Pubkey, page: &Pubkey, tail: Option<&Pubkey>) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new(*page, false),
+        ];
+        if let Some(tail) = tail {
+            accounts.push(AccountMeta::new(*tail, false));
+        }
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: vec![RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE],
+        }
+    }
+
+    // the recipient signs to give up their slot, the longest waiting intent on the `waitlist`
+    // pages takes it
+    pub fn withdraw_intent(program_id: &Pubkey, database: &Pubkey, recipient: &Pubkey, waitlist: &[Pubkey]) -> Instruction {
+        let mut accounts = vec![
+            AccountMeta::new(*database, false),
+            AccountMeta::new_readonly(*recipient, true),
+        ];
+        accounts.extend(waitlist.iter().map(|page| AccountMeta::new(*page, false)));
+        Instruction {
+            program_id: *program_id,
+            accounts,
+            data: vec![RESOURCE_INSTRUCTION_WITHDRAW_INTENT],
+        }
+    }
+
+    // a zero mint turns escrow off again, only allowed while nothing is recorded
+    pub fn set_escrow_mint(program_id: &Pubkey, database: &Pubkey, authority: &Pubkey, mint: &Pubkey) -> Instruction {
+        Instruction {
+            program_id: *program_id,
+            accounts: vec![
+                AccountMeta::new(*database, false),
+                AccountMeta::new_readonly(*authority, true),
+            ],
+            data: instruction_data(RESOURCE_INSTRUCTION_SET_ESCROW_MINT, &mint.to_bytes()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::{
+        RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION,
+        RESOURCE_INSTRUCTION_RECORD_CHALLENGE,
+        RESOURCE_INSTRUCTION_SET_QUORUM,
+        RESOURCE_INSTRUCTION_START_NEW_ROUND,
+        RESOURCE_INSTRUCTION_SET_ESCROW_MINT,
+        RESOURCE_INSTRUCTION_ISSUE_RECEIPT,
+        RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION,
+        RESOURCE_INSTRUCTION_SET_UNIT,
+        RESOURCE_INSTRUCTION_SET_ELIGIBILITY,
+        RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY,
+        RESOURCE_INSTRUCTION_REGISTER_INTENT,
+        RESOURCE_INSTRUCTION_SET_CAPACITY,
+        RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE,
+        RESOURCE_INSTRUCTION_WITHDRAW_INTENT,
+        RESOURCE_INSTRUCTION_DRAW_LOTTERY,
+        ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        ALLOCATION_MAX_MIN_FAIR,
+        QUORUM_TRUST_WEIGHTED,
+        SE_INSTRUCTION_REGISTER_RESOURCE,
+        SE_INSTRUCTION_SETTLE_INTENT,
+        Challenge,
+        QuorumRule,
+        ResourceInstance,
+        UnitOfMeasure,
+        EligibilityRule,
+        Need,
+        MAX_UNIT_NAME_SIZE,
+        Location,
+        Resource,
+        padded_name,
+        padded_zip,
+    };
+
+    #[test]
+    fn test_register_resource_payload() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let authority = Pubkey::new_unique();
+        let resource = Resource {
+            address: Pubkey::new_unique().to_bytes(),
+            name: padded_name("jeff"),
+            location: Location {
+                zip: padded_zip("12345"),
+            },
+            trust_threshold: 10,
+        };
+
+        let instruction = searchengine::register_resource(&program_id, &database, &authority, &resource);
+        assert_eq!(instruction.data[0], SE_INSTRUCTION_REGISTER_RESOURCE);
+        assert_eq!(instruction.data[1..], resource.try_to_vec().unwrap()[..]);
+        assert_eq!(instruction.accounts[0].pubkey, database);
+        assert!(instruction.accounts[0].is_writable);
+        assert_eq!(instruction.accounts[1].pubkey, authority);
+        assert!(instruction.accounts[1].is_signer);
+    }
+
+    #[test]
+    fn test_settle_intent_writes_only_the_account() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let resource_database = Pubkey::new_unique();
+        let round_history = Pubkey::new_unique();
+
+        let instruction = searchengine::settle_intent(&program_id, &database, &account, &resource_database, None);
+        assert_eq!(instruction.data, vec![SE_INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(instruction.accounts.len(), 3);
+        assert_eq!(instruction.accounts[1].pubkey, account);
+        assert!(instruction.accounts[1].is_writable);
+        assert!(!instruction.accounts[0].is_writable);
+        assert!(!instruction.accounts[2].is_writable);
+
+        let instruction = searchengine::settle_intent(&program_id, &database, &account, &resource_database, Some(&round_history));
+        assert_eq!(instruction.accounts[3].pubkey, round_history);
+        assert!(!instruction.accounts[3].is_writable);
+    }
+
+    #[test]
+    fn test_record_challenge_payload() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let challenge = Challenge {
+            from: Pubkey::new_unique().to_bytes(),
+            to: Pubkey::new_unique().to_bytes(),
+            value: true,
+        };
+
+        let instruction = resource::record_challenge(&program_id, &database, &challenge);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_RECORD_CHALLENGE);
+        assert_eq!(instruction.data.len(), 1 + 32 + 32 + 1);
+        assert_eq!(instruction.data[65], 1);
+        assert_eq!(instruction.accounts[1].pubkey, sysvar::clock::id());
+        assert!(!instruction.accounts[1].is_writable);
+        assert_eq!(instruction.accounts[2].pubkey.to_bytes(), challenge.from);
+        assert!(instruction.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_initiate_distribution_payload() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+
+        let authority = Pubkey::new_unique();
+        let instruction = resource::initiate_distribution(&program_id, &database, &authority, 3600);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION);
+        assert_eq!(instruction.data[1..], 3600i64.to_le_bytes());
+        assert_eq!(instruction.accounts[1].pubkey, authority);
+        assert!(instruction.accounts[1].is_signer);
+        assert_eq!(instruction.accounts[2].pubkey, sysvar::clock::id());
+        assert_eq!(instruction.accounts.len(), 3);
+
+        let instruction = resource::draw_lottery(&program_id, &database);
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(instruction.accounts[1].pubkey, sysvar::clock::id());
+        assert_eq!(instruction.accounts[2].pubkey, sysvar::slot_hashes::id());
+    }
+
+    #[test]
+    fn test_set_quorum_payload() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let quorum = QuorumRule {
+            kind: QUORUM_TRUST_WEIGHTED,
+            threshold: 300,
+            trust_program: Pubkey::new_unique().to_bytes(),
+        };
+
+        let authority = Pubkey::new_unique();
+        let instruction = resource::set_quorum(&program_id, &database, &authority, &quorum);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_SET_QUORUM);
+        assert_eq!(instruction.accounts[1].pubkey, authority);
+        assert!(instruction.accounts[1].is_signer && !instruction.accounts[1].is_writable);
+        assert_eq!(instruction.data[1], QUORUM_TRUST_WEIGHTED);
+        assert_eq!(instruction.data[2..4], 300u16.to_le_bytes());
+        assert_eq!(instruction.data[4..], quorum.trust_program);
+    }
+
+    #[test]
+    fn test_claim_challenge_approvers_are_readonly() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let approvers = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let instruction = resource::claim_challenge(&program_id, &database, &Challenge::default(), &approvers);
+        assert_eq!(instruction.accounts.len(), 3);
+        assert!(instruction.accounts[0].is_writable);
+        assert_eq!(instruction.accounts[2].pubkey, approvers[1]);
+        assert!(instruction.accounts[1..].iter().all(|meta| !meta.is_writable));
+    }
+
+    #[test]
+    fn test_start_new_round_writes_history() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let history = Pubkey::new_unique();
+
+        let page = Pubkey::new_unique();
+
+        let instruction = resource::start_new_round(&program_id, &database, &history, &[page]);
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(instruction.accounts[1].pubkey, history);
+        assert_eq!(instruction.accounts[2].pubkey, page);
+        assert!(instruction.accounts.iter().all(|meta| meta.is_writable));
+    }
+
+    #[test]
+    fn test_set_escrow_mint_payload() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+
+        let instruction = resource::set_escrow_mint(&program_id, &database, &Pubkey::new_unique(), &mint);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_SET_ESCROW_MINT);
+        assert_eq!(instruction.data[1..], mint.to_bytes());
+    }
+
+    #[test]
+    fn test_escrowed_instructions_pass_token_accounts() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let provider = Pubkey::new_unique();
+        let escrow = Pubkey::new_unique();
+        let token_account = Pubkey::new_unique();
+        let instance = ResourceInstance {
+            from: provider.to_bytes(),
+            quantity: 4,
+        };
+
+        let record = resource::record_escrowed_resource_instance(&program_id, &database, &instance, &token_account, &escrow);
+        assert_eq!(record.accounts[2].pubkey, escrow);
+        assert_eq!(record.accounts[3].pubkey, provider);
+        assert!(record.accounts[3].is_signer);
+        assert_eq!(record.accounts[4].pubkey, resource::token_program_id());
+
+        let approver = Pubkey::new_unique();
+        let claim = resource::claim_escrowed_challenge(&program_id, &database, &Challenge::default(), &escrow, &token_account, &[approver]);
+        assert_eq!(claim.accounts.len(), 6);
+        assert_eq!(claim.accounts[3].pubkey, resource::escrow_authority(&program_id, &database).0);
+        assert!(!claim.accounts[3].is_signer);
+        assert_eq!(claim.accounts[5].pubkey, approver);
+
+        let refund = resource::refund_escrowed_contribution(&program_id, &database, &escrow, &token_account);
+        assert_eq!(refund.accounts[1].pubkey, escrow);
+        assert!(refund.accounts[2].is_writable);
+        assert_eq!(refund.accounts[3].pubkey, resource::escrow_authority(&program_id, &database).0);
+        assert_eq!(refund.data, vec![RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION]);
+    }
+
+    #[test]
+    fn test_vault_instructions_pass_the_vault() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let vault = Pubkey::new_unique();
+        let provider = Pubkey::new_unique();
+        let instance = ResourceInstance {
+            from: provider.to_bytes(),
+            quantity: 4,
+        };
+
+        let record = resource::record_vault_resource_instance(&program_id, &database, &instance, &vault);
+        assert_eq!(record.accounts[1].pubkey, vault);
+        assert!(record.accounts[2].is_signer && record.accounts[2].is_writable);
+        assert_eq!(record.accounts[3].pubkey, system_program::id());
+
+        let claim = resource::claim_vault_challenge(&program_id, &database, &Challenge { from: provider.to_bytes(), ..Challenge::default() }, &vault, &[]);
+        assert_eq!(claim.accounts[2].pubkey, provider);
+        assert!(claim.accounts[2].is_writable);
+
+        let authority = Pubkey::new_unique();
+        assert_eq!(resource::set_lamport_vault(&program_id, &database, &authority, None).accounts.len(), 2);
+        assert_eq!(resource::set_lamport_vault(&program_id, &database, &authority, Some(&vault)).accounts[2].pubkey, vault);
+    }
+
+    #[test]
+    fn test_issue_receipt_reads_the_recipient() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let receipt = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+
+        let instruction = resource::issue_receipt(&program_id, &database, &receipt, &recipient);
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_ISSUE_RECEIPT]);
+        assert_eq!(instruction.accounts[1].pubkey, receipt);
+        assert!(instruction.accounts[1].is_writable);
+        assert_eq!(instruction.accounts[2].pubkey, recipient);
+        assert!(!instruction.accounts[2].is_writable && !instruction.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_set_allocation_strategy() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+
+        let authority = Pubkey::new_unique();
+
+        let instruction = resource::set_allocation_strategy(&program_id, &database, &authority, ALLOCATION_MAX_MIN_FAIR);
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_MAX_MIN_FAIR]);
+        assert_eq!(instruction.accounts.len(), 2);
+        assert!(instruction.accounts[0].is_writable);
+        assert!(instruction.accounts[1].is_signer);
+    }
+
+    #[test]
+    fn test_set_unit() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let unit = UnitOfMeasure::new("kg", 1);
+
+        let instruction = resource::set_unit(&program_id, &database, &Pubkey::new_unique(), &unit);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_SET_UNIT);
+        assert_eq!(&instruction.data[1..3], b"kg");
+        assert_eq!(instruction.data.len(), 1 + MAX_UNIT_NAME_SIZE + 1);
+        assert_eq!(instruction.data[1 + MAX_UNIT_NAME_SIZE], 1);
+    }
+
+    #[test]
+    fn test_trusted_by_provider_eligibility() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let provider = Pubkey::new_unique();
+        let eligibility = EligibilityRule {
+            kind: ELIGIBILITY_TRUSTED_BY_PROVIDER,
+            provider: provider.to_bytes(),
+            trust_program: Pubkey::new_unique().to_bytes(),
+            min_trust: 5,
+            ..EligibilityRule::default()
+        };
+
+        let instruction = resource::set_eligibility(&program_id, &database, &Pubkey::new_unique(), &eligibility);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_SET_ELIGIBILITY);
+        assert_eq!(instruction.data[1..], eligibility.try_to_vec().unwrap()[..]);
+
+        // the provider's account follows the recipient, read only
+        let need = Need {
+            requested: 30,
+            household_size: 6,
+        };
+        let instruction = resource::register_trusted_intent(&program_id, &database, &recipient, &need, &provider, &[]);
+        assert_eq!(instruction.data[0], RESOURCE_INSTRUCTION_REGISTER_INTENT);
+        assert_eq!(instruction.data[1..], need.try_to_vec().unwrap()[..]);
+        assert_eq!(instruction.accounts[1].pubkey, recipient);
+        assert_eq!(instruction.accounts[2].pubkey, provider);
+        assert!(!instruction.accounts[2].is_writable && !instruction.accounts[2].is_signer);
+    }
+
+    #[test]
+    fn test_waitlist_instructions() {
+        let program_id = Pubkey::new_unique();
+        let database = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let pages = [Pubkey::new_unique(), Pubkey::new_unique()];
+
+        let authority = Pubkey::new_unique();
+
+        let instruction = resource::set_capacity(&program_id, &database, &authority, 1, &pages);
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_SET_CAPACITY, 1]);
+        assert_eq!(instruction.accounts.len(), 4);
+        assert!(instruction.accounts[1].is_signer);
+        assert!(instruction.accounts[2..].iter().all(|meta| meta.is_writable));
+
+        // the new page comes before the tail it is chained to
+        let instruction = resource::add_waitlist_page(&program_id, &database, &pages[1], Some(&pages[0]));
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE]);
+        assert_eq!(instruction.accounts[1].pubkey, pages[1]);
+        assert_eq!(instruction.accounts[2].pubkey, pages[0]);
+        assert_eq!(resource::add_waitlist_page(&program_id, &database, &pages[0], None).accounts.len(), 2);
+
+        // every page is passed to queue an intent, checked for it first
+        let instruction = resource::register_intent(&program_id, &database, &recipient, &Need::default(), &pages);
+        assert_eq!(instruction.accounts.len(), 4);
+        assert!(instruction.accounts[1].is_signer);
+        assert_eq!(instruction.accounts[3].pubkey, pages[1]);
+        assert!(instruction.accounts[2..].iter().all(|meta| meta.is_writable));
+
+        let instruction = resource::withdraw_intent(&program_id, &database, &recipient, &pages);
+        assert_eq!(instruction.data, vec![RESOURCE_INSTRUCTION_WITHDRAW_INTENT]);
+        assert!(instruction.accounts[1].is_signer && !instruction.accounts[1].is_writable);
+        assert_eq!(instruction.accounts[3].pubkey, pages[1]);
+    }
+}
@@ -0,0 +1,9 @@
+pub mod types;
+pub mod instruction;
+pub mod client;
+pub mod error;
+pub mod inspect;
+pub mod provision;
+pub mod manifest;
+pub mod config;
+pub mod generate;
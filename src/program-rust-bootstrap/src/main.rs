@@ -1,143 +1,1443 @@
 use solana_sdk::{
     system_instruction::create_account,
-    account::Account,
-    transaction::Transaction,
-    commitment_config::{CommitmentConfig,CommitmentLevel},
+    instruction::Instruction,
+    pubkey::Pubkey,
     signature::{Keypair, Signer, read_keypair_file, write_keypair_file},
     pubkey::{read_pubkey_file},
 };
-use solana_client::{
-    rpc_client::RpcClient,
-    rpc_config::RpcSendTransactionConfig,
+use solana_client::{client_error::ClientError, rpc_client::RpcClient};
+use std::{convert::TryInto, error::Error, str::FromStr};
+use clap::{App, AppSettings, Arg, ArgMatches};
+use borsh::BorshDeserialize;
+use serde_json::{json, Value};
+use bootstrap::{
+    client::{send_instructions, get_account, get_account_data},
+    config::{Config, Profile, Settings, DEFAULT_CONFIG_FILE, PROFILE_KEYS},
+    error::{BootstrapError, EXIT_FAILURE, EXIT_RPC},
+    generate::{self, GenerateOptions, PhaseReport, Plan, Programs, MAX_ZIPS},
+    instruction::{searchengine, resource},
+    inspect::{self, AccountType},
+    manifest::{self, Manifest},
+    provision::read_or_create_keypair,
+    types::{
+        RESOURCE_INDEX_SIZE,
+        RESOURCE_DATABASE_SIZE,
+        ROUND_HISTORY_SIZE,
+        LAMPORT_VAULT_SIZE,
+        CLAIM_RECEIPT_SIZE,
+        WAITLIST_PAGE_SIZE,
+        SEARCH_ENGINE_ACCOUNT_SIZE,
+        MAX_TRUST_TABLE_SIZE,
+        MAX_NUM_INTENTS,
+        PUBLIC_KEY_SIZE,
+        SearchEngineAccount,
+        TrustTableEntry,
+        Reputation,
+        Resource,
+        Location,
+        ResourceInstance,
+        Challenge,
+        QuorumRule,
+        RoundHistory,
+        ResourceDatabase,
+        WaitlistPage,
+        UnitOfMeasure,
+        Need,
+        QUORUM_ALL,
+        QUORUM_MAJORITY,
+        QUORUM_AT_LEAST,
+        QUORUM_TRUST_WEIGHTED,
+        QUORUM_REPUTATION_WEIGHTED,
+        ELIGIBILITY_OPEN,
+        ELIGIBILITY_ALLOW_LIST,
+        ELIGIBILITY_DENY_LIST,
+        ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        ALLOCATION_PROPORTIONAL,
+        ALLOCATION_EQUAL_SPLIT,
+        ALLOCATION_MAX_MIN_FAIR,
+        ALLOCATION_FIRST_COME_FIRST_SERVED,
+        ALLOCATION_LOTTERY,
+        MAX_ELIGIBILITY_LIST_SIZE,
+        EligibilityRule,
+        padded_name,
+        padded_zip,
+    },
 };
-use std::convert::TryInto;
-use clap::{App, Arg};
 
+type CommandResult = Result<Value, Box<dyn Error>>;
 
-fn bootstrap_database(url: String, payer_keypair_file: String, contract_pubkey_file: String, database_keypair_out_file: String, space: usize) {
-    // payer (read from file)
-    let payer = read_keypair_file(&payer_keypair_file).unwrap();
-    let payer_pubkey = payer.pubkey();
-    println!("{:?}", payer_pubkey.to_string());
-    // let _unuser = write_pubkey_file("/tmp/pubkey.out.json", payer_pubkey);
+#[derive(Clone, Copy, PartialEq)]
+enum Output {
+    Text,
+    Json,
+}
+
+// Everything shared between subcommands. Keys are read lazily because create-database
+// writes the database keypair files the other subcommands read.
+struct Context<'a> {
+    config_file: &'a str,
+    config: Config,
+    profile: String,
+    settings: Settings,
+}
+
+impl<'a> Context<'a> {
+    // Command line options override the selected profile. Only `config set` may select a
+    // profile that does not exist yet, since that is how profiles are created.
+    fn new(matches: &'a ArgMatches, config: Config) -> Result<Self, Box<dyn Error>> {
+        let profile = matches.value_of("profile").map(str::to_string).unwrap_or_else(|| config.profile.clone());
+        let creates_profile = matches.subcommand_matches("config").and_then(ArgMatches::subcommand_name) == Some("set");
+        let mut settings = match config.profile(&profile) {
+            Ok(selected) => selected.settings(),
+            Err(_) if creates_profile => Profile::default().settings(),
+            Err(err) => return Err(err),
+        };
+        let mut overrides = [
+            ("url", &mut settings.url),
+            ("payer-keypair-file", &mut settings.payer),
+            ("searchengine-pubkey-file", &mut settings.searchengine_program),
+            ("searchengine-database-keypair-file", &mut settings.searchengine_database),
+            ("resource-pubkey-file", &mut settings.resource_program),
+            ("resource-database-keypair-file", &mut settings.resource_database),
+            ("resource-history-keypair-file", &mut settings.resource_history),
+            ("resource-vault-keypair-file", &mut settings.resource_vault),
+        ];
+        for (name, setting) in overrides.iter_mut() {
+            if let Some(value) = matches.value_of(name) {
+                **setting = value.to_string();
+            }
+        }
+        Ok(Context {
+            config_file: matches.value_of("config").unwrap(),
+            config,
+            profile,
+            settings,
+        })
+    }
+
+    fn rpc_client(&self) -> RpcClient {
+        RpcClient::new(self.settings.url.clone())
+    }
+
+    fn payer(&self) -> Result<Keypair, Box<dyn Error>> {
+        read_keypair(&self.settings.payer)
+    }
+
+    fn searchengine_id(&self) -> Result<Pubkey, Box<dyn Error>> {
+        program_id(&self.settings.searchengine_program)
+    }
+
+    fn searchengine_database(&self) -> Result<Pubkey, Box<dyn Error>> {
+        database_address(&self.settings.searchengine_database)
+    }
+
+    fn resource_id(&self) -> Result<Pubkey, Box<dyn Error>> {
+        program_id(&self.settings.resource_program)
+    }
+
+    fn resource_database(&self) -> Result<Pubkey, Box<dyn Error>> {
+        database_address(&self.settings.resource_database)
+    }
+
+    fn resource_history(&self) -> Result<Pubkey, Box<dyn Error>> {
+        database_address(&self.settings.resource_history)
+    }
+
+    fn resource_vault(&self) -> Result<Pubkey, Box<dyn Error>> {
+        database_address(&self.settings.resource_vault)
+    }
+
+    fn send(&self, extra_signers: &[&Keypair], instructions: &[Instruction]) -> CommandResult {
+        let signature = send_instructions(&self.rpc_client(), &self.payer()?, extra_signers, instructions)?;
+        Ok(json!({ "signature": signature.to_string() }))
+    }
+
+    // an unreadable program id just means its errors are not named
+    fn program_error_name(&self, err: &BootstrapError) -> Option<&'static str> {
+        err.program_error_name(
+            &self.searchengine_id().unwrap_or_default(),
+            &self.resource_id().unwrap_or_default())
+    }
+}
+
+fn read_keypair(file: &str) -> Result<Keypair, Box<dyn Error>> {
+    read_keypair_file(file).map_err(|err| format!("failed to read keypair {}: {}", file, err).into())
+}
 
-    // search engine (read from file)
-    let contract_pubkey = read_pubkey_file(&contract_pubkey_file).unwrap();
-    println!("{:?}", contract_pubkey.to_string());
+fn read_pubkey(file: &str) -> Result<Pubkey, Box<dyn Error>> {
+    read_pubkey_file(file).map_err(|err| format!("failed to read pubkey {}: {}", file, err).into())
+}
+
+// base58 or a pubkey file
+fn program_id(value: &str) -> Result<Pubkey, Box<dyn Error>> {
+    Pubkey::from_str(value).or_else(|_| read_pubkey(value))
+}
+
+// base58 or a keypair file
+fn database_address(value: &str) -> Result<Pubkey, Box<dyn Error>> {
+    Pubkey::from_str(value).or_else(|_| Ok(read_keypair(value)?.pubkey()))
+}
+
+fn pubkey_of(matches: &ArgMatches, name: &str) -> Result<Option<Pubkey>, Box<dyn Error>> {
+    match matches.value_of(name) {
+        Some(value) => match Pubkey::from_str(value) {
+            Ok(pubkey) => Ok(Some(pubkey)),
+            Err(_) => Err(format!("--{} is not a base58 pubkey: {}", name, value).into()),
+        },
+        None => Ok(None),
+    }
+}
+
+// Creates the database account and runs the program's initialization instruction in the same
+// transaction, so a database either exists in a valid state or not at all
+fn bootstrap_database(context: &Context, contract_pubkey: Pubkey, database_keypair_out_file: &str, space: usize, initialize: Option<fn(&Pubkey, &Pubkey, &Pubkey) -> Instruction>) -> CommandResult {
+    if Pubkey::from_str(database_keypair_out_file).is_ok() {
+        return Err(format!("the database is configured as the address {}; creating it needs a keypair file", database_keypair_out_file).into());
+    }
+    let payer = context.payer()?;
 
     // database
     let database = Keypair::new();
     let database_pubkey = database.pubkey();
-    println!("{:?}", database_pubkey.to_string());
-    let _unused = write_keypair_file(&database, database_keypair_out_file);
+    write_keypair_file(&database, database_keypair_out_file)
+        .map_err(|err| format!("failed to write database keypair to {}: {}", database_keypair_out_file, err))?;
 
-    // TODO move this to a param
-    let lamports: u64 = 64000;
-    let database_account = Account::new(lamports, space, &contract_pubkey);
+    let lamports = context.rpc_client().get_minimum_balance_for_rent_exemption(space)?;
     let create_account_instruction = create_account(
-        &payer_pubkey, 
+        &payer.pubkey(),
         &database_pubkey,
-        database_account.lamports,
-        database_account.data.len().try_into().unwrap(),
+        lamports,
+        space.try_into().unwrap(),
         &contract_pubkey
     );
+    let mut instructions = vec![create_account_instruction];
+    if let Some(initialize) = initialize {
+        instructions.push(initialize(&contract_pubkey, &database_pubkey, &payer.pubkey()));
+    }
+    let signature = send_instructions(&context.rpc_client(), &payer, &[&database], &instructions)?;
+    Ok(json!({
+        "payer": payer.pubkey().to_string(),
+        "program": contract_pubkey.to_string(),
+        "database": database_pubkey.to_string(),
+        "signature": signature.to_string(),
+    }))
+}
+
+fn create_database(context: &Context, matches: &ArgMatches) -> CommandResult {
+    match matches.value_of("database-type").unwrap() {
+        "searchengine" => bootstrap_database(
+            context,
+            context.searchengine_id()?,
+            &context.settings.searchengine_database,
+            RESOURCE_INDEX_SIZE,
+            Some(searchengine::initialize_database)),
+        "resource" => bootstrap_database(
+            context,
+            context.resource_id()?,
+            &context.settings.resource_database,
+            RESOURCE_DATABASE_SIZE,
+            Some(resource::reset_database)),
+        // zeroed data is an empty history; the first StartNewRound binds it to the database
+        "history" => bootstrap_database(
+            context,
+            context.resource_id()?,
+            &context.settings.resource_history,
+            ROUND_HISTORY_SIZE,
+            None),
+        // zeroed data is an unbound vault; set-lamport-vault binds it to the database
+        "vault" => bootstrap_database(
+            context,
+            context.resource_id()?,
+            &context.settings.resource_vault,
+            LAMPORT_VAULT_SIZE,
+            None),
+        other => Err(format!("unknown database type {}", other).into()),
+    }
+}
+
+// Creates the account on first use (generating its keypair) and keeps any intents already
+// cached on it, since UpdateAccount overwrites the whole account
+fn update_account(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let searchengine_id = context.searchengine_id()?;
+    let account_keypair_file = matches.value_of("account-keypair-file").unwrap();
+    let account = read_or_create_keypair(account_keypair_file)
+        .map_err(|err| format!("failed to read or write account keypair {}: {}", account_keypair_file, err))?;
+
+    let rpc_client = context.rpc_client();
     let mut instructions = Vec::new();
-    instructions.push(create_account_instruction);
-    let mut transaction = Transaction::new_with_payer(&instructions, Some(&payer_pubkey));
-
-    let rpc_client = RpcClient::new(url);
-    let (blockhash, _) = rpc_client.get_recent_blockhash().unwrap();
-    let signers: Vec<&dyn Signer> = vec!(&payer, &database);
-    transaction.sign(&signers, blockhash);
-    println!("{:?}", transaction);
-
-    let commitment_config = CommitmentConfig{
-        commitment: CommitmentLevel::Confirmed,
-    };
-
-    let _result = rpc_client
-    .send_and_confirm_transaction_with_spinner_and_config(
-        &transaction,
-        commitment_config,
-        RpcSendTransactionConfig {
-            skip_preflight: true,
-            preflight_commitment: Some(commitment_config.commitment),
-            ..RpcSendTransactionConfig::default()
+    let mut search_engine_account = match get_account_data(&rpc_client, &account.pubkey())? {
+        Some(data) => SearchEngineAccount::try_from_slice(&data)
+            .map_err(|_| format!("{} is not a SearchEngineAccount", account.pubkey()))?,
+        None => {
+            let lamports = rpc_client.get_minimum_balance_for_rent_exemption(SEARCH_ENGINE_ACCOUNT_SIZE)?;
+            instructions.push(create_account(
+                &context.payer()?.pubkey(),
+                &account.pubkey(),
+                lamports,
+                SEARCH_ENGINE_ACCOUNT_SIZE.try_into().unwrap(),
+                &searchengine_id,
+            ));
+            SearchEngineAccount {
+                friendly_name: padded_name(""),
+                trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+                intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+                reputation: Reputation::default(),
+            }
+        }
+    };
+
+    if let Some(name) = matches.value_of("name") {
+        search_engine_account.friendly_name = padded_name(name);
+    }
+    if let Some(entries) = matches.values_of("trust") {
+        let entries: Vec<&str> = entries.collect();
+        if entries.len() > MAX_TRUST_TABLE_SIZE {
+            return Err(format!("at most {} --trust entries fit in the trust table", MAX_TRUST_TABLE_SIZE).into());
+        }
+        let mut trust_table = [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE];
+        for (entry, value) in trust_table.iter_mut().zip(entries) {
+            *entry = parse_trust_entry(value)?;
+        }
+        search_engine_account.trust_table = trust_table;
+    }
+
+    instructions.push(searchengine::update_account(&searchengine_id, &account.pubkey(), &search_engine_account));
+    let mut output = context.send(&[&account], &instructions)?;
+    output["account"] = json!(account.pubkey().to_string());
+    Ok(output)
+}
+
+// PUBKEY:VALUE
+fn parse_trust_entry(value: &str) -> Result<TrustTableEntry, Box<dyn Error>> {
+    let mut parts = value.splitn(2, ':');
+    let to = parts.next().and_then(|to| Pubkey::from_str(to).ok());
+    let trust = parts.next().and_then(|trust| trust.parse::<u8>().ok());
+    match (to, trust) {
+        (Some(to), Some(trust)) => Ok(TrustTableEntry {
+            to: to.to_bytes(),
+            value: trust,
+        }),
+        _ => Err(format!("--trust expects PUBKEY:VALUE, got {}", value).into()),
+    }
+}
+
+fn register_resource(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let address = match pubkey_of(matches, "address")? {
+        Some(address) => address,
+        None => context.resource_id()?,
+    };
+    let resource = Resource {
+        address: address.to_bytes(),
+        name: padded_name(matches.value_of("name").unwrap()),
+        location: Location {
+            zip: padded_zip(matches.value_of("zip").unwrap()),
+        },
+        trust_threshold: matches.value_of_t_or_exit("trust-threshold"),
+    };
+    let instruction = searchengine::register_resource(&context.searchengine_id()?, &context.searchengine_database()?, &context.payer()?.pubkey(), &resource);
+    context.send(&[], &[instruction])
+}
+
+fn resource_database_of(context: &Context, pubkey: &Pubkey) -> Result<ResourceDatabase, Box<dyn Error>> {
+    let data = get_account_data(&context.rpc_client(), pubkey)?
+        .ok_or_else(|| format!("resource database {} does not exist", pubkey))?;
+    ResourceDatabase::try_from_slice(&data).map_err(|_| format!("{} is not a ResourceDatabase", pubkey).into())
+}
+
+// The database's waitlist pages from the head to the tail, what promotions take intents off
+// and registrations are checked against
+fn waitlist_pages(context: &Context, database: &ResourceDatabase) -> Result<Vec<Pubkey>, Box<dyn Error>> {
+    let mut pages = Vec::new();
+    if !database.waitlist.has_pages() {
+        return Ok(pages)
+    }
+    let mut page = Pubkey::new_from_array(database.waitlist.head);
+    loop {
+        pages.push(page);
+        if page.to_bytes() == database.waitlist.tail {
+            return Ok(pages)
+        }
+        let data = get_account_data(&context.rpc_client(), &page)?
+            .ok_or_else(|| format!("waitlist page {} does not exist", page))?;
+        let next = WaitlistPage::try_from_slice(&data)
+            .map_err(|_| format!("{} is not a WaitlistPage", page))?
+            .next;
+        if next == [0u8; PUBLIC_KEY_SIZE] || pages.contains(&Pubkey::new_from_array(next)) {
+            return Err(format!("the waitlist is not chained from {} to its tail", page).into())
+        }
+        page = Pubkey::new_from_array(next);
+    }
+}
+
+// Records the intent with both programs: the search engine caches it on the account and
+// the resource adds the account to its recipients, if its eligibility rule admits them
+fn register_intent(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let recipient = read_keypair(matches.value_of("recipient-keypair-file").unwrap())?;
+    let account = recipient.pubkey();
+    let resource_id = match pubkey_of(matches, "resource")? {
+        Some(resource_id) => resource_id,
+        None => context.resource_id()?,
+    };
+    let resource_database = match pubkey_of(matches, "resource-database")? {
+        Some(resource_database) => resource_database,
+        None => context.resource_database()?,
+    };
+    let need = Need {
+        requested: matches.value_of_t_or_exit("requested"),
+        household_size: matches.value_of_t_or_exit("household-size"),
+    };
+    // the pages are checked for the intent and the tail queues it if every slot is taken
+    let pages = waitlist_pages(context, &resource_database_of(context, &resource_database)?)?;
+    context.send(&[&recipient], &[
+        searchengine::register_intent(&context.searchengine_id()?, &context.searchengine_database()?, &account, &resource_id),
+        match pubkey_of(matches, "provider")? {
+            Some(provider) => resource::register_trusted_intent(&resource_id, &resource_database, &account, &need, &provider, &pages),
+            None => resource::register_intent(&resource_id, &resource_database, &account, &need, &pages),
         },
+    ])
+}
+
+// Counts the account's part in the archived rounds and a finalized distribution into its
+// reputation
+fn settle_intent(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let account = pubkey_of(matches, "account")?.unwrap();
+    let resource_database = match pubkey_of(matches, "resource-database")? {
+        Some(resource_database) => resource_database,
+        None => context.resource_database()?,
+    };
+    let database = resource_database_of(context, &resource_database)?;
+    let round_history = match database.history {
+        history if history == [0u8; PUBLIC_KEY_SIZE] => None,
+        history => Some(Pubkey::new_from_array(history)),
+    };
+    context.send(&[], &[searchengine::settle_intent(&context.searchengine_id()?, &context.searchengine_database()?, &account, &resource_database, round_history.as_ref())])
+}
+
+fn record_instance(context: &Context, matches: &ArgMatches) -> CommandResult {
+    // the provider signs for what they record, so the payer is the provider
+    let instance = ResourceInstance {
+        from: context.payer()?.pubkey().to_bytes(),
+        quantity: matches.value_of_t_or_exit("quantity"),
+    };
+    let instruction = match pubkey_of(matches, "escrow")? {
+        Some(escrow) => {
+            let source = pubkey_of(matches, "token-account")?.ok_or("--escrow needs --token-account")?;
+            resource::record_escrowed_resource_instance(&context.resource_id()?, &context.resource_database()?, &instance, &source, &escrow)
+        }
+        None if matches.is_present("vault") => {
+            resource::record_vault_resource_instance(&context.resource_id()?, &context.resource_database()?, &instance, &context.resource_vault()?)
+        }
+        None => resource::record_resource_instance(&context.resource_id()?, &context.resource_database()?, &instance),
+    };
+    context.send(&[], &[instruction])
+}
+
+fn initiate_distribution(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let duration = matches.value_of_t_or_exit("duration");
+    context.send(&[], &[resource::initiate_distribution(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey(), duration)])
+}
+
+fn draw_lottery(context: &Context, _matches: &ArgMatches) -> CommandResult {
+    context.send(&[], &[resource::draw_lottery(&context.resource_id()?, &context.resource_database()?)])
+}
+
+fn record_challenge(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let from = read_keypair(matches.value_of("from-keypair-file").unwrap())?;
+    let challenge = Challenge {
+        from: from.pubkey().to_bytes(),
+        to: pubkey_of(matches, "to")?.unwrap().to_bytes(),
+        value: !matches.is_present("deny"),
+    };
+    context.send(&[&from], &[resource::record_challenge(&context.resource_id()?, &context.resource_database()?, &challenge)])
+}
+
+fn claim(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let challenge = Challenge {
+        from: pubkey_of(matches, "from")?.unwrap().to_bytes(),
+        to: pubkey_of(matches, "to")?.map(|to| to.to_bytes()).unwrap_or([0u8; PUBLIC_KEY_SIZE]),
+        value: true,
+    };
+    let mut approvers = Vec::new();
+    if let Some(values) = matches.values_of("approver") {
+        for value in values {
+            approvers.push(Pubkey::from_str(value).map_err(|_| format!("--approver is not a base58 pubkey: {}", value))?);
+        }
+    }
+    let instruction = match pubkey_of(matches, "escrow")? {
+        Some(escrow) => {
+            let destination = pubkey_of(matches, "token-account")?.ok_or("--escrow needs --token-account")?;
+            resource::claim_escrowed_challenge(&context.resource_id()?, &context.resource_database()?, &challenge, &escrow, &destination, &approvers)
+        }
+        None if matches.is_present("vault") => {
+            resource::claim_vault_challenge(&context.resource_id()?, &context.resource_database()?, &challenge, &context.resource_vault()?, &approvers)
+        }
+        None => resource::claim_challenge(&context.resource_id()?, &context.resource_database()?, &challenge, &approvers),
+    };
+    context.send(&[], &[instruction])
+}
+
+// weighted rules read trust or reputation from accounts owned by the configured search engine
+fn set_quorum(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let kind = match matches.value_of("rule").unwrap() {
+        "all" => QUORUM_ALL,
+        "majority" => QUORUM_MAJORITY,
+        "at-least" => QUORUM_AT_LEAST,
+        "trust-weighted" => QUORUM_TRUST_WEIGHTED,
+        _ => QUORUM_REPUTATION_WEIGHTED,
+    };
+    let is_weighted = kind == QUORUM_TRUST_WEIGHTED || kind == QUORUM_REPUTATION_WEIGHTED;
+    let quorum = QuorumRule {
+        kind,
+        threshold: matches.value_of_t_or_exit("threshold"),
+        trust_program: if is_weighted { context.searchengine_id()?.to_bytes() } else { [0u8; PUBLIC_KEY_SIZE] },
+    };
+    context.send(&[], &[resource::set_quorum(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey(), &quorum)])
+}
+
+// trusted-by-provider reads the provider's trust table from an account owned by the configured
+// search engine
+fn set_eligibility(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let kind = match matches.value_of("rule").unwrap() {
+        "open" => ELIGIBILITY_OPEN,
+        "allow-list" => ELIGIBILITY_ALLOW_LIST,
+        "deny-list" => ELIGIBILITY_DENY_LIST,
+        _ => ELIGIBILITY_TRUSTED_BY_PROVIDER,
+    };
+    let mut eligibility = EligibilityRule {
+        kind,
+        ..EligibilityRule::default()
+    };
+    let accounts: Vec<&str> = matches.values_of("account").map(|values| values.collect()).unwrap_or_default();
+    if accounts.len() > MAX_ELIGIBILITY_LIST_SIZE {
+        return Err(format!("at most {} --account can be listed", MAX_ELIGIBILITY_LIST_SIZE).into())
+    }
+    for (slot, value) in eligibility.list.iter_mut().zip(accounts) {
+        *slot = Pubkey::from_str(value).map_err(|_| format!("--account is not a base58 pubkey: {}", value))?.to_bytes();
+    }
+    if kind == ELIGIBILITY_TRUSTED_BY_PROVIDER {
+        eligibility.provider = pubkey_of(matches, "provider")?.ok_or("trusted-by-provider needs --provider")?.to_bytes();
+        eligibility.trust_program = context.searchengine_id()?.to_bytes();
+        eligibility.min_trust = matches.value_of_t_or_exit("min-trust");
+    }
+    context.send(&[], &[resource::set_eligibility(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey(), &eligibility)])
+}
+
+fn set_allocation_strategy(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let strategy = match matches.value_of("strategy").unwrap() {
+        "proportional" => ALLOCATION_PROPORTIONAL,
+        "equal-split" => ALLOCATION_EQUAL_SPLIT,
+        "max-min-fair" => ALLOCATION_MAX_MIN_FAIR,
+        "first-come-first-served" => ALLOCATION_FIRST_COME_FIRST_SERVED,
+        _ => ALLOCATION_LOTTERY,
+    };
+    context.send(&[], &[resource::set_allocation_strategy(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey(), strategy)])
+}
+
+fn set_capacity(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let resource_database = context.resource_database()?;
+    let pages = waitlist_pages(context, &resource_database_of(context, &resource_database)?)?;
+    let capacity = matches.value_of_t_or_exit("capacity");
+    context.send(&[], &[resource::set_capacity(&context.resource_id()?, &resource_database, &context.payer()?.pubkey(), capacity, &pages)])
+}
+
+// Creates a page account owned by the resource program and chains it after the waitlist's tail
+fn add_waitlist_page(context: &Context, _matches: &ArgMatches) -> CommandResult {
+    let resource_id = context.resource_id()?;
+    let resource_database = context.resource_database()?;
+    let waitlist = resource_database_of(context, &resource_database)?.waitlist;
+    let tail = Pubkey::new_from_array(waitlist.tail);
+    let page = Keypair::new();
+    let lamports = context.rpc_client().get_minimum_balance_for_rent_exemption(WAITLIST_PAGE_SIZE)?;
+    let create_page = create_account(
+        &context.payer()?.pubkey(),
+        &page.pubkey(),
+        lamports,
+        WAITLIST_PAGE_SIZE as u64,
+        &resource_id,
+    );
+    let add = resource::add_waitlist_page(&resource_id, &resource_database, &page.pubkey(), if waitlist.has_pages() { Some(&tail) } else { None });
+    let mut result = context.send(&[&page], &[create_page, add])?;
+    result["page"] = json!(page.pubkey().to_string());
+    Ok(result)
+}
+
+// The recipient gives up their slot to the longest waiting intent
+fn withdraw_intent(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let recipient = read_keypair(matches.value_of("recipient-keypair-file").unwrap())?;
+    let resource_database = context.resource_database()?;
+    let pages = waitlist_pages(context, &resource_database_of(context, &resource_database)?)?;
+    context.send(&[&recipient], &[resource::withdraw_intent(&context.resource_id()?, &resource_database, &recipient.pubkey(), &pages)])
+}
+
+fn finalize_distribution(context: &Context, _matches: &ArgMatches) -> CommandResult {
+    context.send(&[], &[resource::finalize_distribution(&context.resource_id()?, &context.resource_database()?)])
+}
+
+// settle-intent has to run before this, while the round is still finalized on the database
+// Escrow token accounts are created with the spl-token CLI, owned by the escrow authority
+// printed here
+fn set_escrow_mint(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let resource_id = context.resource_id()?;
+    let resource_database = context.resource_database()?;
+    let mint = pubkey_of(matches, "mint")?.unwrap_or_default();
+    let mut result = context.send(&[], &[resource::set_escrow_mint(&resource_id, &resource_database, &context.payer()?.pubkey(), &mint)])?;
+    result["escrow_authority"] = json!(resource::escrow_authority(&resource_id, &resource_database).0.to_string());
+    Ok(result)
+}
+
+fn set_unit(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let unit = UnitOfMeasure::new(matches.value_of("name").unwrap(), matches.value_of_t_or_exit("decimals"));
+    context.send(&[], &[resource::set_unit(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey(), &unit)])
+}
+
+fn set_lamport_vault(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let vault = if matches.is_present("off") { None } else { Some(context.resource_vault()?) };
+    context.send(&[], &[resource::set_lamport_vault(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey(), vault.as_ref())])
+}
+
+// Sends a contributor their share of what is left of the pool once the distribution is
+// finalized, from the vault or, for escrowed resources, into their token account
+fn refund(context: &Context, matches: &ArgMatches) -> CommandResult {
+    if let Some(escrow) = pubkey_of(matches, "escrow")? {
+        let destination = pubkey_of(matches, "token-account")?.ok_or("--escrow needs --token-account")?;
+        return context.send(&[], &[resource::refund_escrowed_contribution(&context.resource_id()?, &context.resource_database()?, &escrow, &destination)])
+    }
+    let contributor = match pubkey_of(matches, "contributor")? {
+        Some(contributor) => contributor,
+        None => context.payer()?.pubkey(),
+    };
+    context.send(&[], &[resource::refund_contribution(&context.resource_id()?, &context.resource_database()?, &context.resource_vault()?, &contributor)])
+}
+
+// Each receipt is a new account, created here and owned by the resource program; its address
+// is printed for the recipient to hand to whoever checks it with inspect
+fn issue_receipt(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let resource_id = context.resource_id()?;
+    let recipient = match pubkey_of(matches, "recipient")? {
+        Some(recipient) => recipient,
+        None => context.payer()?.pubkey(),
+    };
+    let receipt = Keypair::new();
+    let lamports = context.rpc_client().get_minimum_balance_for_rent_exemption(CLAIM_RECEIPT_SIZE)?;
+    let create_receipt = create_account(
+        &context.payer()?.pubkey(),
+        &receipt.pubkey(),
+        lamports,
+        CLAIM_RECEIPT_SIZE as u64,
+        &resource_id,
     );
+    let issue = resource::issue_receipt(&resource_id, &context.resource_database()?, &receipt.pubkey(), &recipient);
+    let mut result = context.send(&[&receipt], &[create_receipt, issue])?;
+    result["receipt"] = json!(receipt.pubkey().to_string());
+    Ok(result)
+}
+
+// The history the database archives into, the configured one until the first round binds it
+fn round_history_of(context: &Context, database: &ResourceDatabase) -> Result<Pubkey, Box<dyn Error>> {
+    if database.history != [0u8; PUBLIC_KEY_SIZE] {
+        return Ok(Pubkey::new_from_array(database.history))
+    }
+    context.resource_history()
+}
+
+fn start_new_round(context: &Context, _matches: &ArgMatches) -> CommandResult {
+    let resource_database = context.resource_database()?;
+    let database = resource_database_of(context, &resource_database)?;
+    let pages = waitlist_pages(context, &database)?;
+    context.send(&[], &[resource::start_new_round(&context.resource_id()?, &resource_database, &round_history_of(context, &database)?, &pages)])
+}
+
+// Archived rounds of the database's history, oldest first, or only the one given with --round
+fn rounds(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let history_pubkey = round_history_of(context, &resource_database_of(context, &context.resource_database()?)?)?;
+    let data = get_account_data(&context.rpc_client(), &history_pubkey)?
+        .ok_or_else(|| format!("round history {} does not exist", history_pubkey))?;
+    let history = RoundHistory::try_from_slice(&data)
+        .map_err(|_| format!("{} is not a RoundHistory", history_pubkey))?;
+    match matches.value_of("round") {
+        Some(round) => {
+            let round: u32 = round.parse().map_err(|_| format!("--round expects a number, got {}", round))?;
+            history.get(round)
+                .map(inspect::round_json)
+                .ok_or_else(|| format!("round {} is not in the history (next round {})", round, history.next_round).into())
+        }
+        None => Ok(Value::Array(history.rounds().into_iter().map(inspect::round_json).collect())),
+    }
+}
+
+fn reset(context: &Context, _matches: &ArgMatches) -> CommandResult {
+    context.send(&[], &[resource::reset_database(&context.resource_id()?, &context.resource_database()?, &context.payer()?.pubkey())])
+}
+
+// The type is detected from the data unless --type is given
+fn inspect_account(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let pubkey = pubkey_of(matches, "pubkey")?.unwrap();
+    let account = get_account(&context.rpc_client(), &pubkey)?
+        .ok_or_else(|| format!("account {} does not exist", pubkey))?;
+
+    let account_type = match matches.value_of("type") {
+        Some(name) => AccountType::from_name(name),
+        None => AccountType::detect(&account.data),
+    }.ok_or_else(|| format!("unrecognized account data ({} bytes), pass --type", account.data.len()))?;
+    let data = inspect::decode(account_type, &account.data)
+        .map_err(|err| format!("failed to decode {}: {}", account_type.name(), err))?;
+
+    Ok(json!({
+        "pubkey": pubkey.to_string(),
+        "owner": account.owner.to_string(),
+        "lamports": account.lamports,
+        "type": account_type.name(),
+        "data": data,
+    }))
+}
+
+fn apply(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let manifest_file = matches.value_of("manifest").unwrap();
+    let manifest = Manifest::load(manifest_file)
+        .map_err(|err| format!("failed to load manifest {}: {}", manifest_file, err))?;
+    let summary = manifest::apply(&context.rpc_client(), &context.payer()?, &manifest)?;
+    Ok(Value::Array(summary.iter().map(|entry| json!({
+        "kind": entry.kind,
+        "name": entry.name,
+        "address": entry.address.to_string(),
+        "status": entry.status.to_string(),
+    })).collect()))
+}
+
+fn phase_json(phase: &PhaseReport) -> Value {
+    json!({
+        "succeeded": phase.succeeded,
+        "failed": phase.failed,
+    })
+}
+
+fn generate_data(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let options = GenerateOptions {
+        users: matches.value_of_t_or_exit("users"),
+        zips: matches.value_of_t_or_exit("zips"),
+        trust_density: matches.value_of_t_or_exit("trust-density"),
+        intent_ratio: matches.value_of_t_or_exit("intent-ratio"),
+        trust_threshold: matches.value_of_t_or_exit("trust-threshold"),
+        seed: matches.value_of_t_or_exit("seed"),
+    };
+    if !(0.0..=1.0).contains(&options.trust_density) || !(0.0..=1.0).contains(&options.intent_ratio) {
+        return Err("--trust-density and --intent-ratio must be between 0 and 1".into());
+    }
+    if options.zips > MAX_ZIPS {
+        return Err(format!("--zips can be at most {}, the number of 5 digit zips", MAX_ZIPS).into());
+    }
+    let programs = Programs {
+        searchengine_id: context.searchengine_id()?,
+        searchengine_database: context.searchengine_database()?,
+        resource_id: context.resource_id()?,
+        resource_database: context.resource_database()?,
+    };
+    let plan = Plan::new(&options);
+    let report = generate::generate(&context.rpc_client(), &context.payer()?, &programs, &plan)?;
+    Ok(json!({
+        "seed": options.seed,
+        "users": phase_json(&report.users),
+        "dropped_trust_edges": report.dropped_trust_edges,
+        "resources": phase_json(&report.resources),
+        "intents": phase_json(&report.intents),
+    }))
+}
+
+// The selected profile after command line overrides
+fn config_show(context: &Context, _matches: &ArgMatches) -> CommandResult {
+    let mut profiles: Vec<&str> = vec!("localnet", "devnet");
+    for name in context.config.profiles.keys() {
+        if !profiles.contains(&name.as_str()) {
+            profiles.push(name);
+        }
+    }
+    Ok(json!({
+        "config_file": context.config_file,
+        "profile": context.profile,
+        "profiles": profiles,
+        "url": context.settings.url,
+        "payer": context.settings.payer,
+        "searchengine_program": context.settings.searchengine_program,
+        "searchengine_database": context.settings.searchengine_database,
+        "resource_program": context.settings.resource_program,
+        "resource_database": context.settings.resource_database,
+        "resource_history": context.settings.resource_history,
+        "resource_vault": context.settings.resource_vault,
+    }))
+}
+
+// Sets (or without a value, clears) one key of the selected profile, creating the profile if needed
+fn config_set(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let key = matches.value_of("key").unwrap();
+    let value = matches.value_of("value");
+    let mut config = context.config.clone();
+    config.profile_mut(&context.profile).set(key, value)?;
+    config.save(context.config_file)?;
+    Ok(json!({
+        "profile": context.profile,
+        "key": key,
+        "value": value,
+    }))
+}
+
+fn config_use(context: &Context, matches: &ArgMatches) -> CommandResult {
+    let name = matches.value_of("name").unwrap();
+    let mut config = context.config.clone();
+    config.profile(name)?;
+    config.profile = name.to_string();
+    config.save(context.config_file)?;
+    Ok(json!({ "profile": name }))
+}
+
+// Text output is one `key: value` line per field, or one row per entry for lists
+fn print_text(value: &Value) {
+    match value {
+        Value::Object(fields) => {
+            for (key, field) in fields {
+                match field {
+                    Value::String(string) => println!("{}: {}", key, string),
+                    Value::Object(_) | Value::Array(_) => println!("{}: {}", key, serde_json::to_string_pretty(field).unwrap()),
+                    _ => println!("{}: {}", key, field),
+                }
+            }
+        }
+        Value::Array(rows) => {
+            for row in rows {
+                let columns: Vec<String> = row.as_object().into_iter().flat_map(|fields| fields.values()).map(|column| match column {
+                    Value::String(string) => format!("{:<10}", string),
+                    _ => format!("{:<10}", column),
+                }).collect();
+                println!("{}", columns.join(" ").trim_end());
+            }
+        }
+        _ => println!("{}", value),
+    }
+}
+
+// context is None when the config file itself could not be loaded
+fn report_error(context: Option<&Context>, output: Output, err: &(dyn Error + 'static)) -> i32 {
+    let bootstrap_error = err.downcast_ref::<BootstrapError>();
+    let program_error = match (bootstrap_error, context) {
+        (Some(bootstrap_error), Some(context)) => context.program_error_name(bootstrap_error),
+        _ => None,
+    };
+    let logs = bootstrap_error.map(|err| err.logs()).unwrap_or(&[]);
+
+    match output {
+        Output::Json => {
+            let kind = match bootstrap_error {
+                Some(BootstrapError::Rpc(_)) => "rpc",
+                Some(BootstrapError::Transaction { .. }) => "transaction",
+                Some(BootstrapError::Program { .. }) => "program",
+                None if err.is::<ClientError>() => "rpc",
+                None => "other",
+            };
+            println!("{}", serde_json::to_string_pretty(&json!({
+                "error": {
+                    "kind": kind,
+                    "message": err.to_string(),
+                    "program_error": program_error,
+                    "logs": logs,
+                }
+            })).unwrap());
+        }
+        Output::Text => {
+            eprintln!("{}", err);
+            if let Some(name) = program_error {
+                eprintln!("program error: {}", name);
+            }
+            for log in logs {
+                eprintln!("  {}", log);
+            }
+        }
+    }
+    match bootstrap_error {
+        Some(bootstrap_error) => bootstrap_error.exit_code(),
+        // account reads go straight to the RPC client
+        None if err.is::<ClientError>() => EXIT_RPC,
+        None => EXIT_FAILURE,
+    }
+}
+
+fn pubkey_arg<'a>(name: &'a str, about: &'a str) -> Arg<'a> {
+    Arg::new(name)
+        .about(about)
+        .long(name)
+        .takes_value(true)
+        .value_name("PUBKEY")
 }
 
 fn main() {
     let matches = App::new("bootstrap")
     .version("0.0.1")
     .author("Jeff")
-    // databases
+    .setting(AppSettings::SubcommandRequiredElseHelp)
+    .arg(
+        Arg::new("output")
+            .about("print results as text or a single JSON document")
+            .long("output")
+            .global(true)
+            .possible_value("text")
+            .possible_value("json")
+            .default_value("text")
+    )
+    .arg(
+        Arg::new("config")
+            .about("the config file holding the profiles")
+            .long("config")
+            .global(true)
+            .default_value(DEFAULT_CONFIG_FILE)
+    )
     .arg(
-        Arg::new("database-type")
-            .about("which database to provision")
-            .possible_value("searchengine")
-            .possible_value("resource")
+        Arg::new("profile")
+            .about("the profile to use instead of the config file's current one")
+            .long("profile")
+            .global(true)
+            .takes_value(true)
     )
+    // the options below override the profile
     .arg(
         Arg::new("url")
             .about("url to connect to")
-            .default_value("http://localhost:8899")
+            .long("url")
+            .global(true)
+            .takes_value(true)
     )
     // payer
     .arg(
         Arg::new("payer-keypair-file")
             .about("the file to read/save the private key to")
-            .default_value("./src/lib/keygen.out.json")
+            .long("payer-keypair-file")
+            .global(true)
+            .takes_value(true)
     )
     // search engine
     .arg(
         Arg::new("searchengine-pubkey-file")
-            .about("the file to read the public key from")
-            .default_value("./src/lib/searchengine_deploy_raw.out.json")
+            .about("the program id, or the file to read it from")
+            .long("searchengine-pubkey-file")
+            .global(true)
+            .takes_value(true)
     )
     .arg(
         Arg::new("searchengine-database-keypair-file")
-            .about("the file to read/save the private key to")
-            .default_value("./src/lib/searchengine_database_keygen.out.json")
+            .about("the database address, or the file to read/save its private key to")
+            .long("searchengine-database-keypair-file")
+            .global(true)
+            .takes_value(true)
     )
     // resource
     .arg(
         Arg::new("resource-pubkey-file")
-            .about("the file to read the public key from")
-            .default_value("./src/lib/resource_deploy_raw.out.json")
+            .about("the program id, or the file to read it from")
+            .long("resource-pubkey-file")
+            .global(true)
+            .takes_value(true)
     )
     .arg(
         Arg::new("resource-database-keypair-file")
-            .about("the file to read/save the private key to")
-            .default_value("./src/lib/resource_database_keygen.out.json")
+            .about("the database address, or the file to read/save its private key to")
+            .long("resource-database-keypair-file")
+            .global(true)
+            .takes_value(true)
+    )
+    .arg(
+        Arg::new("resource-history-keypair-file")
+            .about("the round history the first start-new-round binds, or the file to read/save its private key to")
+            .long("resource-history-keypair-file")
+            .global(true)
+            .takes_value(true)
+    )
+    .arg(
+        Arg::new("resource-vault-keypair-file")
+            .about("the lamport vault address, or the file to read/save its private key to")
+            .long("resource-vault-keypair-file")
+            .global(true)
+            .takes_value(true)
+    )
+    // databases
+    .subcommand(
+        App::new("create-database")
+            .about("create and initialize a database account")
+            .arg(
+                Arg::new("database-type")
+                    .about("which database to provision")
+                    .required(true)
+                    .possible_value("searchengine")
+                    .possible_value("resource")
+                    .possible_value("history")
+                    .possible_value("vault")
+            )
+    )
+    // search engine instructions
+    .subcommand(
+        App::new("update-account")
+            .about("create or update a search engine account")
+            .arg(
+                Arg::new("account-keypair-file")
+                    .about("the file to read/save the account's private key to")
+                    .long("account-keypair-file")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(
+                Arg::new("name")
+                    .about("friendly name, truncated to 32 bytes")
+                    .long("name")
+                    .takes_value(true)
+            )
+            .arg(
+                Arg::new("trust")
+                    .about("trust table entry as PUBKEY:VALUE")
+                    .long("trust")
+                    .takes_value(true)
+                    .multiple_occurrences(true)
+            )
+    )
+    .subcommand(
+        App::new("register-resource")
+            .about("add a resource to the search engine index")
+            .arg(pubkey_arg("address", "the resource program (defaults to --resource-pubkey-file)"))
+            .arg(
+                Arg::new("name")
+                    .about("resource name, truncated to 32 bytes")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(
+                Arg::new("zip")
+                    .about("zip code the resource is indexed under")
+                    .long("zip")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(
+                Arg::new("trust-threshold")
+                    .about("minimum trust required by the resource")
+                    .long("trust-threshold")
+                    .takes_value(true)
+                    .default_value("10")
+            )
+    )
+    .subcommand(
+        App::new("register-intent")
+            .about("register an account's intent with the search engine and the resource")
+            .arg(
+                Arg::new("recipient-keypair-file")
+                    .about("keypair of the search engine account, who signs the registration")
+                    .long("recipient-keypair-file")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(pubkey_arg("resource", "the resource program (defaults to --resource-pubkey-file)"))
+            .arg(pubkey_arg("resource-database", "the resource database (defaults to --resource-database-keypair-file)"))
+            .arg(pubkey_arg("provider", "the provider's search engine account, for trusted-by-provider eligibility"))
+            .arg(
+                Arg::new("requested")
+                    .about("quantity the account needs, 0 asks for the household's even share of the pool")
+                    .long("requested")
+                    .takes_value(true)
+                    .default_value("0")
+            )
+            .arg(
+                Arg::new("household-size")
+                    .about("people the quantity is for, 0 counts one")
+                    .long("household-size")
+                    .takes_value(true)
+                    .default_value("0")
+            )
+    )
+    .subcommand(
+        App::new("settle-intent")
+            .about("count an account's part in a finalized distribution into its reputation")
+            .arg(pubkey_arg("account", "the search engine account").required(true))
+            .arg(pubkey_arg("resource-database", "the resource database (defaults to --resource-database-keypair-file)"))
+    )
+    // resource instructions
+    .subcommand(
+        App::new("record-instance")
+            .about("record a quantity of the resource")
+            .arg(
+                Arg::new("quantity")
+                    .about("how much was provided, counted in the smallest fraction of the unit")
+                    .long("quantity")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(pubkey_arg("escrow", "escrow token account, for escrowed resources"))
+            .arg(pubkey_arg("token-account", "the payer's token account the quantity is taken from"))
+            .arg(
+                Arg::new("vault")
+                    .about("deposit the quantity in lamports into the configured vault")
+                    .long("vault")
+                    .conflicts_with("escrow")
+            )
+    )
+    .subcommand(
+        App::new("initiate-distribution")
+            .about("calculate the distribution and open challenges")
+            .arg(
+                Arg::new("duration")
+                    .about("how many seconds challenges stay open")
+                    .long("duration")
+                    .takes_value(true)
+                    .default_value("86400")
+            )
+    )
+    .subcommand(
+        App::new("draw-lottery")
+            .about("draw a lottery distribution once the slot it is committed to passed")
+    )
+    .subcommand(
+        App::new("record-challenge")
+            .about("approve (or deny) another recipient's challenge")
+            .arg(
+                Arg::new("from-keypair-file")
+                    .about("keypair of the recipient answering the challenge, who signs the answer")
+                    .long("from-keypair-file")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(pubkey_arg("to", "the recipient being challenged").required(true))
+            .arg(
+                Arg::new("deny")
+                    .about("deny instead of approve")
+                    .long("deny")
+            )
+    )
+    .subcommand(
+        App::new("claim")
+            .about("claim an allocation")
+            .arg(pubkey_arg("from", "the recipient claiming").required(true))
+            .arg(pubkey_arg("to", "the challenge counterpart, if any"))
+            .arg(
+                pubkey_arg("approver", "search engine account of a peer, read by weighted quorum rules")
+                    .multiple_occurrences(true)
+            )
+            .arg(pubkey_arg("escrow", "escrow token account, for escrowed resources"))
+            .arg(pubkey_arg("token-account", "the recipient's token account the allocation is paid into"))
+            .arg(
+                Arg::new("vault")
+                    .about("pay the allocation in lamports out of the configured vault")
+                    .long("vault")
+                    .conflicts_with("escrow")
+            )
+    )
+    .subcommand(
+        App::new("set-quorum")
+            .about("set how many peers have to approve a recipient before they can claim")
+            .arg(
+                Arg::new("rule")
+                    .about("the quorum rule")
+                    .long("rule")
+                    .takes_value(true)
+                    .possible_values(&["all", "majority", "at-least", "trust-weighted", "reputation-weighted"])
+                    .required(true)
+            )
+            .arg(
+                Arg::new("threshold")
+                    .about("approvals for at-least, summed trust or reputation for the weighted rules")
+                    .long("threshold")
+                    .takes_value(true)
+                    .default_value("0")
+            )
+    )
+    .subcommand(
+        App::new("set-eligibility")
+            .about("set who may register an intent for the resource")
+            .arg(
+                Arg::new("rule")
+                    .about("the eligibility rule")
+                    .long("rule")
+                    .takes_value(true)
+                    .possible_values(&["open", "allow-list", "deny-list", "trusted-by-provider"])
+                    .required(true)
+            )
+            .arg(
+                pubkey_arg("account", "search engine account on the allow or deny list")
+                    .multiple_occurrences(true)
+            )
+            .arg(pubkey_arg("provider", "the provider's search engine account, for trusted-by-provider"))
+            .arg(
+                Arg::new("min-trust")
+                    .about("trust the provider has to give a recipient, for trusted-by-provider")
+                    .long("min-trust")
+                    .takes_value(true)
+                    .default_value("1")
+            )
+    )
+    .subcommand(
+        App::new("set-allocation-strategy")
+            .about("set how the pool is split between recipients, before anyone registers or records")
+            .arg(
+                Arg::new("strategy")
+                    .about("the allocation strategy")
+                    .long("strategy")
+                    .takes_value(true)
+                    .possible_values(&["proportional", "equal-split", "max-min-fair", "first-come-first-served", "lottery"])
+                    .required(true)
+            )
+    )
+    .subcommand(
+        App::new("set-capacity")
+            .about("set how many intents take part in a round, the rest wait on the waitlist")
+            .arg(
+                Arg::new("capacity")
+                    .about("at most the database's slots, 0 opens all of them")
+                    .long("capacity")
+                    .takes_value(true)
+                    .required(true)
+            )
+    )
+    .subcommand(
+        App::new("add-waitlist-page")
+            .about("create a waitlist page and chain it after the waitlist's tail")
+    )
+    .subcommand(
+        App::new("withdraw-intent")
+            .about("give up a recipient's slot to the longest waiting intent")
+            .arg(
+                Arg::new("recipient-keypair-file")
+                    .about("keypair of the recipient, who signs the withdrawal")
+                    .long("recipient-keypair-file")
+                    .takes_value(true)
+                    .required(true)
+            )
+    )
+    .subcommand(
+        App::new("finalize-distribution")
+            .about("close challenges once the distribution window has passed")
+    )
+    .subcommand(
+        App::new("set-escrow-mint")
+            .about("back the resource with tokens of a mint, held in escrow until claimed")
+            .arg(pubkey_arg("mint", "the SPL token mint (leave out to turn escrow off)"))
+    )
+    .subcommand(
+        App::new("set-unit")
+            .about("set what recorded quantities count, before anything is recorded")
+            .arg(
+                Arg::new("name")
+                    .about("the unit, such as kg or loaves (at most 16 bytes)")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+            )
+            .arg(
+                Arg::new("decimals")
+                    .about("decimals of a quantity, 1 records 25 as 2.5")
+                    .long("decimals")
+                    .takes_value(true)
+                    .default_value("0")
+            )
+    )
+    .subcommand(
+        App::new("set-lamport-vault")
+            .about("back the resource with lamports held in the configured vault until claimed")
+            .arg(
+                Arg::new("off")
+                    .about("go back to recording quantities only")
+                    .long("off")
+            )
+    )
+    .subcommand(
+        App::new("refund")
+            .about("send a contributor their share of what was not claimed, once finalized")
+            .arg(pubkey_arg("contributor", "who provided the resource (defaults to the payer)"))
+            .arg(pubkey_arg("escrow", "escrow token account, for escrowed resources").conflicts_with("contributor"))
+            .arg(pubkey_arg("token-account", "the contributor's token account the refund is paid into"))
+    )
+    .subcommand(
+        App::new("issue-receipt")
+            .about("issue a claimed recipient the receipt of their claim")
+            .arg(pubkey_arg("recipient", "who claimed (defaults to the payer)"))
+    )
+    .subcommand(
+        App::new("start-new-round")
+            .about("archive the finalized round into the round history and clear the database")
+    )
+    .subcommand(
+        App::new("rounds")
+            .about("list the archived rounds with what each recipient received")
+            .arg(
+                Arg::new("round")
+                    .about("only this round")
+                    .long("round")
+                    .takes_value(true)
+            )
+    )
+    .subcommand(
+        App::new("reset")
+            .about("reset the resource database")
+    )
+    // environments
+    .subcommand(
+        App::new("apply")
+            .about("create whatever a manifest describes that does not exist yet")
+            .arg(
+                Arg::new("manifest")
+                    .about("the TOML manifest to apply")
+                    .required(true)
+            )
+    )
+    .subcommand(
+        App::new("generate")
+            .about("fill the cluster with seeded synthetic users, trust, resources and intents")
+            .arg(
+                Arg::new("users")
+                    .about("how many users to create")
+                    .long("users")
+                    .takes_value(true)
+                    .default_value("20")
+            )
+            .arg(
+                Arg::new("zips")
+                    .about("how many zip codes to register a resource in")
+                    .long("zips")
+                    .takes_value(true)
+                    .default_value("3")
+            )
+            .arg(
+                Arg::new("trust-density")
+                    .about("probability that a user trusts any other user")
+                    .long("trust-density")
+                    .takes_value(true)
+                    .default_value("0.1")
+            )
+            .arg(
+                Arg::new("intent-ratio")
+                    .about("fraction of users registering an intent")
+                    .long("intent-ratio")
+                    .takes_value(true)
+                    .default_value("0.5")
+            )
+            .arg(
+                Arg::new("trust-threshold")
+                    .about("minimum trust required by the generated resources")
+                    .long("trust-threshold")
+                    .takes_value(true)
+                    .default_value("10")
+            )
+            .arg(
+                Arg::new("seed")
+                    .about("the same seed generates the same accounts")
+                    .long("seed")
+                    .takes_value(true)
+                    .default_value("0")
+            )
+    )
+    // profiles
+    .subcommand(
+        App::new("config")
+            .about("show or edit the profiles in the config file")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(
+                App::new("show")
+                    .about("print the settings of the selected profile")
+            )
+            .subcommand(
+                App::new("set")
+                    .about("set a key of the selected profile, or clear it when no value is given")
+                    .arg(
+                        Arg::new("key")
+                            .about("the setting to change")
+                            .required(true)
+                            .possible_values(PROFILE_KEYS)
+                    )
+                    .arg(
+                        Arg::new("value")
+                            .about("the new value")
+                    )
+            )
+            .subcommand(
+                App::new("use")
+                    .about("make a profile the default")
+                    .arg(
+                        Arg::new("name")
+                            .about("the profile name")
+                            .required(true)
+                    )
+            )
+    )
+    // state
+    .subcommand(
+        App::new("inspect")
+            .about("fetch an account and print its decoded state as JSON")
+            .arg(
+                Arg::new("pubkey")
+                    .about("the account to inspect")
+                    .required(true)
+            )
+            .arg(
+                Arg::new("type")
+                    .about("decode as this type instead of detecting it")
+                    .long("type")
+                    .takes_value(true)
+                    .possible_value("searchengine-account")
+                    .possible_value("resource-index")
+                    .possible_value("resource-database")
+                    .possible_value("round-history")
+                    .possible_value("lamport-vault")
+                    .possible_value("claim-receipt")
+                    .possible_value("waitlist-page")
+            )
     )
     .get_matches();
 
-    let url = matches.value_of("url").unwrap();
-    let db_type = matches.value_of("database-type").unwrap();
+    let output = match matches.value_of("output").unwrap() {
+        "json" => Output::Json,
+        _ => Output::Text,
+    };
 
-    // payer
-    let payer_keypair_file = matches.value_of("payer-keypair-file").unwrap();
-    // search engine
-    let searchengine_pubkey_file = matches.value_of("searchengine-pubkey-file").unwrap();
-    let searchengine_database_keypair_file = matches.value_of("searchengine-database-keypair-file").unwrap();
-    // resource
-    let resource_pubkey_file = matches.value_of("resource-pubkey-file").unwrap();
-    let resource_database_keypair_file = matches.value_of("resource-database-keypair-file").unwrap();
+    let context = match Config::load(matches.value_of("config").unwrap()).and_then(|config| Context::new(&matches, config)) {
+        Ok(context) => context,
+        Err(err) => std::process::exit(report_error(None, output, err.as_ref())),
+    };
 
-    match db_type {
-        "searchengine" => bootstrap_database(
-            url.to_string(),
-            payer_keypair_file.to_string(),
-            searchengine_pubkey_file.to_string(),
-            searchengine_database_keypair_file.to_string(),
-            384),
-        "resource" => bootstrap_database(
-            url.to_string(),
-            payer_keypair_file.to_string(),
-            resource_pubkey_file.to_string(),
-            resource_database_keypair_file.to_string(),
-            456),
-        _ => println!("Some other subcommand was used"),
-    }
-}
\ No newline at end of file
+    let result = match matches.subcommand() {
+        Some(("create-database", sub_matches)) => create_database(&context, sub_matches),
+        Some(("update-account", sub_matches)) => update_account(&context, sub_matches),
+        Some(("register-resource", sub_matches)) => register_resource(&context, sub_matches),
+        Some(("register-intent", sub_matches)) => register_intent(&context, sub_matches),
+        Some(("settle-intent", sub_matches)) => settle_intent(&context, sub_matches),
+        Some(("record-instance", sub_matches)) => record_instance(&context, sub_matches),
+        Some(("initiate-distribution", sub_matches)) => initiate_distribution(&context, sub_matches),
+        Some(("draw-lottery", sub_matches)) => draw_lottery(&context, sub_matches),
+        Some(("record-challenge", sub_matches)) => record_challenge(&context, sub_matches),
+        Some(("claim", sub_matches)) => claim(&context, sub_matches),
+        Some(("set-quorum", sub_matches)) => set_quorum(&context, sub_matches),
+        Some(("set-eligibility", sub_matches)) => set_eligibility(&context, sub_matches),
+        Some(("set-allocation-strategy", sub_matches)) => set_allocation_strategy(&context, sub_matches),
+        Some(("set-capacity", sub_matches)) => set_capacity(&context, sub_matches),
+        Some(("add-waitlist-page", sub_matches)) => add_waitlist_page(&context, sub_matches),
+        Some(("withdraw-intent", sub_matches)) => withdraw_intent(&context, sub_matches),
+        Some(("finalize-distribution", sub_matches)) => finalize_distribution(&context, sub_matches),
+        Some(("set-escrow-mint", sub_matches)) => set_escrow_mint(&context, sub_matches),
+        Some(("set-unit", sub_matches)) => set_unit(&context, sub_matches),
+        Some(("set-lamport-vault", sub_matches)) => set_lamport_vault(&context, sub_matches),
+        Some(("refund", sub_matches)) => refund(&context, sub_matches),
+        Some(("issue-receipt", sub_matches)) => issue_receipt(&context, sub_matches),
+        Some(("start-new-round", sub_matches)) => start_new_round(&context, sub_matches),
+        Some(("rounds", sub_matches)) => rounds(&context, sub_matches),
+        Some(("reset", sub_matches)) => reset(&context, sub_matches),
+        Some(("inspect", sub_matches)) => inspect_account(&context, sub_matches),
+        Some(("apply", sub_matches)) => apply(&context, sub_matches),
+        Some(("generate", sub_matches)) => generate_data(&context, sub_matches),
+        Some(("config", config_matches)) => match config_matches.subcommand() {
+            Some(("show", sub_matches)) => config_show(&context, sub_matches),
+            Some(("set", sub_matches)) => config_set(&context, sub_matches),
+            Some(("use", sub_matches)) => config_use(&context, sub_matches),
+            _ => Err("Some other config subcommand was used".into()),
+        },
+        _ => Err("Some other subcommand was used".into()),
+    };
+
+    match result {
+        Ok(value) => match output {
+            Output::Json => println!("{}", serde_json::to_string_pretty(&value).unwrap()),
+            Output::Text => print_text(&value),
+        },
+        Err(err) => std::process::exit(report_error(Some(&context), output, err.as_ref())),
+    }
+}
@@ -0,0 +1,246 @@
+// Declarative description of an environment. `bootstrap apply` creates whatever is missing
+// and leaves everything else alone, so the same manifest can be applied repeatedly.
+//
+// Relative paths are resolved against the directory holding the manifest.
+use serde::Deserialize;
+use solana_sdk::{
+    pubkey::{Pubkey, read_pubkey_file},
+    signature::{Keypair, Signer},
+};
+use solana_client::rpc_client::RpcClient;
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use crate::{
+    instruction::{searchengine, resource},
+    provision::{
+        ProvisionResult,
+        Status,
+        check_program,
+        ensure_database,
+        ensure_resource,
+        ensure_user,
+        read_or_create_keypair,
+    },
+    types::{
+        MAX_TRUST_TABLE_SIZE,
+        RESOURCE_INDEX_SIZE,
+        RESOURCE_DATABASE_SIZE,
+        Location,
+        Resource,
+        TrustTableEntry,
+        padded_name,
+        padded_zip,
+    },
+};
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Manifest {
+    pub programs: Programs,
+    pub databases: Databases,
+    #[serde(default)]
+    pub users: Vec<User>,
+    #[serde(default)]
+    pub resources: Vec<ResourceEntry>,
+    #[serde(default)]
+    pub trust: Vec<TrustEdge>,
+    #[serde(skip)]
+    pub base_dir: PathBuf,
+}
+
+// base58 pubkeys or files written by `solana program deploy`
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Programs {
+    pub searchengine: String,
+    pub resource: String,
+}
+
+// keypair files, generated on first apply
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct Databases {
+    pub searchengine: String,
+    pub resource: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct User {
+    pub name: String,
+    pub keypair_file: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct ResourceEntry {
+    pub name: String,
+    pub zip: String,
+    #[serde(default = "default_trust_threshold")]
+    pub trust_threshold: u8,
+    // defaults to programs.resource
+    pub address: Option<String>,
+}
+
+fn default_trust_threshold() -> u8 {
+    10
+}
+
+// trust `from` places in `to`, both user names
+#[derive(Deserialize, Debug, PartialEq)]
+pub struct TrustEdge {
+    pub from: String,
+    pub to: String,
+    pub value: u8,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct SummaryEntry {
+    pub kind: &'static str,
+    pub name: String,
+    pub address: Pubkey,
+    pub status: Status,
+}
+
+impl Manifest {
+    pub fn load(path: &str) -> ProvisionResult<Self> {
+        let mut manifest = Self::parse(&fs::read_to_string(path)?)?;
+        manifest.base_dir = Path::new(path).parent().map(Path::to_path_buf).unwrap_or_default();
+        Ok(manifest)
+    }
+
+    pub fn parse(contents: &str) -> ProvisionResult<Self> {
+        let manifest: Manifest = toml::from_str(contents)?;
+        manifest.validate()?;
+        Ok(manifest)
+    }
+
+    fn validate(&self) -> ProvisionResult<()> {
+        for edge in self.trust.iter() {
+            for name in [&edge.from, &edge.to].iter() {
+                if !self.users.iter().any(|user| &user.name == *name) {
+                    return Err(format!("trust edge references unknown user {}", name).into());
+                }
+            }
+        }
+        for user in self.users.iter() {
+            let count = self.trust.iter().filter(|edge| edge.from == user.name).count();
+            if count > MAX_TRUST_TABLE_SIZE {
+                return Err(format!("{} has {} trust edges but a trust table holds {}", user.name, count, MAX_TRUST_TABLE_SIZE).into());
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, file: &str) -> String {
+        self.base_dir.join(file).to_string_lossy().to_string()
+    }
+
+    fn pubkey(&self, value: &str) -> ProvisionResult<Pubkey> {
+        match Pubkey::from_str(value) {
+            Ok(pubkey) => Ok(pubkey),
+            Err(_) => read_pubkey_file(&self.path(value)),
+        }
+    }
+}
+
+pub fn apply(rpc_client: &RpcClient, payer: &Keypair, manifest: &Manifest) -> ProvisionResult<Vec<SummaryEntry>> {
+    let mut summary = Vec::new();
+
+    // programs
+    let searchengine_id = manifest.pubkey(&manifest.programs.searchengine)?;
+    let resource_id = manifest.pubkey(&manifest.programs.resource)?;
+    check_program(rpc_client, &searchengine_id)?;
+    check_program(rpc_client, &resource_id)?;
+
+    // databases
+    let (searchengine_database, status) = ensure_database(
+        rpc_client,
+        payer,
+        &searchengine_id,
+        &manifest.path(&manifest.databases.searchengine),
+        RESOURCE_INDEX_SIZE,
+        searchengine::initialize_database)?;
+    summary.push(SummaryEntry { kind: "database", name: "searchengine".to_string(), address: searchengine_database, status });
+    let (resource_database, status) = ensure_database(
+        rpc_client,
+        payer,
+        &resource_id,
+        &manifest.path(&manifest.databases.resource),
+        RESOURCE_DATABASE_SIZE,
+        resource::reset_database)?;
+    summary.push(SummaryEntry { kind: "database", name: "resource".to_string(), address: resource_database, status });
+
+    // users, with their trust edges folded into their trust tables
+    let mut keypairs = HashMap::new();
+    for user in manifest.users.iter() {
+        keypairs.insert(user.name.as_str(), read_or_create_keypair(&manifest.path(&user.keypair_file))?);
+    }
+    for user in manifest.users.iter() {
+        let mut trust_table = [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE];
+        for (entry, edge) in trust_table.iter_mut().zip(manifest.trust.iter().filter(|edge| edge.from == user.name)) {
+            *entry = TrustTableEntry {
+                to: keypairs[edge.to.as_str()].pubkey().to_bytes(),
+                value: edge.value,
+            };
+        }
+        let account = &keypairs[user.name.as_str()];
+        let status = ensure_user(rpc_client, payer, &searchengine_id, account, &user.name, trust_table)?;
+        summary.push(SummaryEntry { kind: "user", name: user.name.clone(), address: account.pubkey(), status });
+    }
+
+    // resources
+    for entry in manifest.resources.iter() {
+        let address = match &entry.address {
+            Some(address) => manifest.pubkey(address)?,
+            None => resource_id,
+        };
+        let resource = Resource {
+            address: address.to_bytes(),
+            name: padded_name(&entry.name),
+            location: Location {
+                zip: padded_zip(&entry.zip),
+            },
+            trust_threshold: entry.trust_threshold,
+        };
+        let status = ensure_resource(rpc_client, payer, &searchengine_id, &searchengine_database, &resource)?;
+        summary.push(SummaryEntry { kind: "resource", name: entry.name.clone(), address, status });
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_example_manifest() {
+        let manifest = Manifest::parse(include_str!("../manifest.example.toml")).unwrap();
+        assert_eq!(manifest.users.len(), 2);
+        assert_eq!(manifest.resources[0].trust_threshold, 10);
+        assert_eq!(manifest.trust[0], TrustEdge {
+            from: "alice".to_string(),
+            to: "bob".to_string(),
+            value: 10,
+        });
+    }
+
+    #[test]
+    fn test_parse_unknown_trust_user() {
+        let contents = r#"
+            [programs]
+            searchengine = "searchengine.json"
+            resource = "resource.json"
+
+            [databases]
+            searchengine = "searchengine_database.json"
+            resource = "resource_database.json"
+
+            [[trust]]
+            from = "alice"
+            to = "mallory"
+            value = 1
+        "#;
+        assert!(Manifest::parse(contents).is_err());
+    }
+}
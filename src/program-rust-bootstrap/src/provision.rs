@@ -0,0 +1,130 @@
+// Idempotent building blocks: each ensure_* call creates or updates the account only when
+// the chain differs from what was asked for, and reports what it did.
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer, read_keypair_file, write_keypair_file},
+    system_instruction::create_account,
+};
+use solana_client::rpc_client::RpcClient;
+use borsh::BorshDeserialize;
+use std::{convert::TryInto, error::Error, fmt, path::Path};
+use crate::{
+    client::{send_instructions, get_account, get_account_data},
+    instruction::searchengine,
+    types::{
+        MAX_TRUST_TABLE_SIZE,
+        MAX_NUM_INTENTS,
+        PUBLIC_KEY_SIZE,
+        SEARCH_ENGINE_ACCOUNT_SIZE,
+        SearchEngineAccount,
+        TrustTableEntry,
+        Reputation,
+        Resource,
+        ResourceIndex,
+        padded_name,
+    },
+};
+
+pub type ProvisionResult<T> = Result<T, Box<dyn Error>>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Status {
+    Created,
+    Updated,
+    Unchanged,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Status::Created => write!(f, "created"),
+            Status::Updated => write!(f, "updated"),
+            Status::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+pub fn read_or_create_keypair(file: &str) -> ProvisionResult<Keypair> {
+    if Path::new(file).exists() {
+        return read_keypair_file(file);
+    }
+    let keypair = Keypair::new();
+    write_keypair_file(&keypair, file)?;
+    Ok(keypair)
+}
+
+// fails unless the program is deployed, since programs are deployed with the solana CLI
+pub fn check_program(rpc_client: &RpcClient, program_id: &Pubkey) -> ProvisionResult<()> {
+    match get_account(rpc_client, program_id)? {
+        Some(account) if account.executable => Ok(()),
+        Some(_) => Err(format!("{} is not an executable program", program_id).into()),
+        None => Err(format!("program {} is not deployed", program_id).into()),
+    }
+}
+
+pub fn ensure_database(rpc_client: &RpcClient, payer: &Keypair, program_id: &Pubkey, keypair_file: &str, space: usize, initialize: fn(&Pubkey, &Pubkey, &Pubkey) -> Instruction) -> ProvisionResult<(Pubkey, Status)> {
+    let database = read_or_create_keypair(keypair_file)?;
+    if let Some(account) = get_account(rpc_client, &database.pubkey())? {
+        if account.owner != *program_id || account.data.len() != space {
+            return Err(format!("database {} exists but is not a {} byte account owned by {}", database.pubkey(), space, program_id).into());
+        }
+        return Ok((database.pubkey(), Status::Unchanged));
+    }
+
+    let lamports = rpc_client.get_minimum_balance_for_rent_exemption(space)?;
+    send_instructions(rpc_client, payer, &[&database], &[
+        create_account(&payer.pubkey(), &database.pubkey(), lamports, space.try_into().unwrap(), program_id),
+        initialize(program_id, &database.pubkey(), &payer.pubkey()),
+    ])?;
+    Ok((database.pubkey(), Status::Created))
+}
+
+// Intents cached on an existing account are kept, since UpdateAccount overwrites the whole account
+pub fn ensure_user(rpc_client: &RpcClient, payer: &Keypair, searchengine_id: &Pubkey, account: &Keypair, name: &str, trust_table: [TrustTableEntry; MAX_TRUST_TABLE_SIZE]) -> ProvisionResult<Status> {
+    let mut instructions = Vec::new();
+    let (mut search_engine_account, status) = match get_account_data(rpc_client, &account.pubkey())? {
+        Some(data) => (SearchEngineAccount::try_from_slice(&data)?, Status::Updated),
+        None => {
+            let lamports = rpc_client.get_minimum_balance_for_rent_exemption(SEARCH_ENGINE_ACCOUNT_SIZE)?;
+            instructions.push(create_account(
+                &payer.pubkey(),
+                &account.pubkey(),
+                lamports,
+                SEARCH_ENGINE_ACCOUNT_SIZE.try_into().unwrap(),
+                searchengine_id,
+            ));
+            let empty = SearchEngineAccount {
+                friendly_name: padded_name(""),
+                trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+                intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+                reputation: Reputation::default(),
+            };
+            (empty, Status::Created)
+        }
+    };
+
+    let friendly_name = padded_name(name);
+    if status == Status::Updated && search_engine_account.friendly_name == friendly_name && search_engine_account.trust_table == trust_table {
+        return Ok(Status::Unchanged);
+    }
+    search_engine_account.friendly_name = friendly_name;
+    search_engine_account.trust_table = trust_table;
+    instructions.push(searchengine::update_account(searchengine_id, &account.pubkey(), &search_engine_account));
+    send_instructions(rpc_client, payer, &[account], &instructions)?;
+    Ok(status)
+}
+
+pub fn ensure_resource(rpc_client: &RpcClient, payer: &Keypair, searchengine_id: &Pubkey, database: &Pubkey, resource: &Resource) -> ProvisionResult<Status> {
+    let data = get_account_data(rpc_client, database)?
+        .ok_or_else(|| format!("search engine database {} does not exist", database))?;
+    let index = ResourceIndex::try_from_slice(&data)?;
+    let registered = index.buckets.iter().any(|bucket| {
+        bucket.location.zip == resource.location.zip && bucket.addresses.contains(&resource.address)
+    });
+    if registered {
+        return Ok(Status::Unchanged);
+    }
+    send_instructions(rpc_client, payer, &[], &[searchengine::register_resource(searchengine_id, database, &payer.pubkey(), resource)])?;
+    Ok(Status::Created)
+}
@@ -0,0 +1,594 @@
+// Mirrors of the on-chain layouts in ../program-rust-searchengine/src/types.rs and
+// ../program-rust-resource/src/types.rs. The programs pin an older solana-program than
+// solana-sdk pulls in here, so these are kept in sync by hand (like ../lib/lib-types.tsx).
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub const PUBLIC_KEY_SIZE: usize = 32;
+
+// searchengine
+
+pub const SE_INSTRUCTION_DEFAULT: u8 = 0;
+pub const SE_INSTRUCTION_UPDATE_ACCOUNT: u8 = 1;
+pub const SE_INSTRUCTION_REGISTER_RESOURCE: u8 = 2;
+pub const SE_INSTRUCTION_REGISTER_INTENT: u8 = 3;
+pub const SE_INSTRUCTION_INITIALIZE_DATABASE: u8 = 4;
+pub const SE_INSTRUCTION_SETTLE_INTENT: u8 = 5;
+
+// SearchEngineError variants, indexed by their ProgramError::Custom code
+pub const SE_ERRORS: &[&str] = &[
+    "InvalidInstruction",
+    "DatabaseFull",
+    "BucketFull",
+    "ResourceNotRegistered",
+    "IntentNotRegistered",
+    "DistributionNotFinalized",
+    "AlreadySettled",
+    "WrongAuthority",
+    "WrongRoundHistory",
+];
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct TrustTableEntry {
+    pub to: [u8; PUBLIC_KEY_SIZE],
+    pub value: u8,
+}
+
+pub const MAX_TRUST_TABLE_SIZE: usize = 1;
+pub const MAX_FRIENDLY_NAME_SIZE: usize = 32;
+pub const MAX_NUM_INTENTS: usize = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Reputation {
+    pub approvals_given: u32,
+    pub approvals_received: u32,
+    pub claims: u32,
+    pub disputes: u32,
+    pub next_settle_slots: [u64; MAX_NUM_INTENTS],
+}
+
+impl Reputation {
+    pub fn score(&self) -> u32 {
+        self.approvals_given
+            .saturating_add(self.approvals_received)
+            .saturating_add(self.claims)
+            .saturating_sub(self.disputes)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct SearchEngineAccount {
+    pub friendly_name: [u8; MAX_FRIENDLY_NAME_SIZE],
+    pub trust_table: [TrustTableEntry; MAX_TRUST_TABLE_SIZE],
+    pub intents: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+    pub reputation: Reputation,
+}
+pub const SEARCH_ENGINE_ACCOUNT_SIZE: usize = 201;
+
+pub const MAX_ZIP_SIZE: usize = 32;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Copy, Default)]
+pub struct Location {
+    pub zip: [u8; MAX_ZIP_SIZE],
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct Resource {
+    pub address: [u8; PUBLIC_KEY_SIZE],
+    pub name: [u8; MAX_FRIENDLY_NAME_SIZE],
+    pub location: Location,
+    pub trust_threshold: u8,
+}
+
+pub const MAX_NUM_RESOURCE_IN_BUCKET: usize = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct ResourceBucket {
+    pub location: Location,
+    pub addresses: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RESOURCE_IN_BUCKET],
+}
+
+pub const MAX_INDEX_BUCKETS: usize = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ResourceIndex {
+    pub buckets: [ResourceBucket; MAX_INDEX_BUCKETS],
+    pub authority: [u8; PUBLIC_KEY_SIZE],
+}
+pub const RESOURCE_INDEX_SIZE: usize = 416;
+
+// resource
+
+pub const RESOURCE_INSTRUCTION_DEFAULT: u8 = 0;
+pub const RESOURCE_INSTRUCTION_REGISTER_INTENT: u8 = 1;
+pub const RESOURCE_INSTRUCTION_RECORD_RESOURCE_INSTANCE: u8 = 2;
+pub const RESOURCE_INSTRUCTION_INITIATE_DISTRIBUTION: u8 = 3;
+pub const RESOURCE_INSTRUCTION_RECORD_CHALLENGE: u8 = 4;
+pub const RESOURCE_INSTRUCTION_CLAIM_CHALLENGE: u8 = 5;
+pub const RESOURCE_INSTRUCTION_RESET_DATABASE: u8 = 6;
+pub const RESOURCE_INSTRUCTION_FINALIZE_DISTRIBUTION: u8 = 7;
+pub const RESOURCE_INSTRUCTION_SET_QUORUM: u8 = 8;
+pub const RESOURCE_INSTRUCTION_START_NEW_ROUND: u8 = 9;
+pub const RESOURCE_INSTRUCTION_SET_ESCROW_MINT: u8 = 10;
+pub const RESOURCE_INSTRUCTION_SET_LAMPORT_VAULT: u8 = 11;
+pub const RESOURCE_INSTRUCTION_REFUND_CONTRIBUTION: u8 = 12;
+pub const RESOURCE_INSTRUCTION_ISSUE_RECEIPT: u8 = 13;
+pub const RESOURCE_INSTRUCTION_SET_UNIT: u8 = 14;
+pub const RESOURCE_INSTRUCTION_SET_ELIGIBILITY: u8 = 15;
+pub const RESOURCE_INSTRUCTION_SET_ALLOCATION_STRATEGY: u8 = 16;
+pub const RESOURCE_INSTRUCTION_SET_CAPACITY: u8 = 17;
+pub const RESOURCE_INSTRUCTION_ADD_WAITLIST_PAGE: u8 = 18;
+pub const RESOURCE_INSTRUCTION_WITHDRAW_INTENT: u8 = 19;
+pub const RESOURCE_INSTRUCTION_DRAW_LOTTERY: u8 = 20;
+
+// seed of the escrow authority, together with the database key
+pub const ESCROW_SEED: &[u8] = b"escrow";
+// the SPL token program escrowed resources transfer through
+pub const SPL_TOKEN_PROGRAM_ID: &str = "TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA";
+
+// ResourceError variants, indexed by their ProgramError::Custom code
+pub const RESOURCE_ERRORS: &[&str] = &[
+    "InvalidInstruction",
+    "NoResourceInstanceSpace",
+    "ResourceInDistribution",
+    "ResourceNotInDistribution",
+    "DistributionClosed",
+    "DistributionOpen",
+    "NotARecipient",
+    "AlreadyClaimed",
+    "QuorumNotMet",
+    "InvalidQuorumRule",
+    "WrongRoundHistory",
+    "WrongEscrow",
+    "EscrowInUse",
+    "NothingToRefund",
+    "ReceiptAlreadyIssued",
+    "NotClaimed",
+    "QuantityOverflow",
+    "InvalidUnit",
+    "UnitInUse",
+    "RefundsOutstanding",
+    "NotEligible",
+    "InvalidEligibilityRule",
+    "InvalidStrategy",
+    "StrategyInUse",
+    "WaitlistFull",
+    "WrongWaitlist",
+    "InvalidCapacity",
+    "WrongAuthority",
+    "DrawPending",
+    "NothingToDraw",
+    "AlreadyWaiting",
+];
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Challenge {
+    pub from: [u8; PUBLIC_KEY_SIZE],
+    pub to: [u8; PUBLIC_KEY_SIZE],
+    pub value: bool,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct ResourceInstance {
+    pub from: [u8; PUBLIC_KEY_SIZE],
+    pub quantity: u64,
+}
+
+pub const MAX_UNIT_NAME_SIZE: usize = 16;
+pub const MAX_UNIT_DECIMALS: u8 = 19;
+
+// A quantity q counts q / 10^decimals of the named unit
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct UnitOfMeasure {
+    pub name: [u8; MAX_UNIT_NAME_SIZE],
+    pub decimals: u8,
+}
+
+impl UnitOfMeasure {
+    pub fn new(name: &str, decimals: u8) -> Self {
+        let mut padded = [0u8; MAX_UNIT_NAME_SIZE];
+        for (place, data) in padded.iter_mut().zip(name.as_bytes().iter()) {
+            *place = *data
+        }
+        UnitOfMeasure {
+            name: padded,
+            decimals,
+        }
+    }
+
+    pub fn name(&self) -> String {
+        String::from_utf8_lossy(&self.name).trim_end_matches('\0').to_string()
+    }
+
+    // 25 with one decimal of kg reads 2.5 kg
+    pub fn format(&self, quantity: u64) -> String {
+        let digits = format!("{:0>width$}", quantity, width = usize::from(self.decimals) + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - usize::from(self.decimals));
+        let amount = if fraction.is_empty() {
+            whole.to_string()
+        } else {
+            format!("{}.{}", whole, fraction)
+        };
+        let name = self.name();
+        if name.is_empty() {
+            amount
+        } else {
+            format!("{} {}", amount, name)
+        }
+    }
+}
+
+// When challenges were opened and how long (in seconds) they stay open
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct DistributionWindow {
+    pub start_slot: u64,
+    pub start_timestamp: i64,
+    pub duration: i64,
+}
+
+impl DistributionWindow {
+    pub fn deadline(&self) -> i64 {
+        self.start_timestamp.saturating_add(self.duration)
+    }
+}
+
+pub const QUORUM_ALL: u8 = 0;
+pub const QUORUM_MAJORITY: u8 = 1;
+pub const QUORUM_AT_LEAST: u8 = 2;
+pub const QUORUM_TRUST_WEIGHTED: u8 = 3;
+pub const QUORUM_REPUTATION_WEIGHTED: u8 = 4;
+
+// How many of a recipient's peers have to approve them before they can claim
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct QuorumRule {
+    pub kind: u8,
+    pub threshold: u16,
+    pub trust_program: [u8; PUBLIC_KEY_SIZE],
+}
+
+pub const ELIGIBILITY_OPEN: u8 = 0;
+pub const ELIGIBILITY_ALLOW_LIST: u8 = 1;
+pub const ELIGIBILITY_DENY_LIST: u8 = 2;
+pub const ELIGIBILITY_TRUSTED_BY_PROVIDER: u8 = 3;
+
+pub const MAX_ELIGIBILITY_LIST_SIZE: usize = 4;
+
+// Who may register an intent: anyone, those on the list, those not on it, or those the
+// provider's search engine account trusts with at least min_trust
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct EligibilityRule {
+    pub kind: u8,
+    pub list: [[u8; PUBLIC_KEY_SIZE]; MAX_ELIGIBILITY_LIST_SIZE],
+    pub provider: [u8; PUBLIC_KEY_SIZE],
+    pub trust_program: [u8; PUBLIC_KEY_SIZE],
+    pub min_trust: u8,
+}
+
+// What a recipient asks for with their intent; no quantity asks for their household's even
+// share of the pool, a household being one person unless a size is given
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Need {
+    pub requested: u64,
+    pub household_size: u16,
+}
+
+// A registered recipient and what InitiateDistribution allocated them
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Intent {
+    pub recipient: [u8; PUBLIC_KEY_SIZE],
+    pub need: Need,
+    pub allocation: u64,
+}
+
+pub const ALLOCATION_PROPORTIONAL: u8 = 0;
+pub const ALLOCATION_EQUAL_SPLIT: u8 = 1;
+pub const ALLOCATION_MAX_MIN_FAIR: u8 = 2;
+pub const ALLOCATION_FIRST_COME_FIRST_SERVED: u8 = 3;
+pub const ALLOCATION_LOTTERY: u8 = 4;
+
+// the slot a lottery is committed to, and the SlotHashes entry it was drawn with
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Draw {
+    pub committed_slot: u64,
+    // zero until drawn
+    pub slot: u64,
+    pub hash: [u8; PUBLIC_KEY_SIZE],
+}
+
+impl Draw {
+    pub fn is_drawn(&self) -> bool {
+        self.slot != 0
+    }
+}
+
+// recipients taking part in one round, the capacity can lower it and the rest wait
+pub const MAX_NUM_RECIPIENTS: usize = 8;
+// contributors to one round's pool, each holds one slot
+pub const MAX_NUM_RESOURCE_INSTANCES: usize = 16;
+pub const MAX_NUM_CHALLENGES: usize = MAX_NUM_RECIPIENTS * MAX_NUM_RECIPIENTS;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct ResourceDatabase {
+    pub is_distributed: bool,
+    // allocated to the recipients in total
+    pub final_quantity: u64,
+    pub intents: [Intent; MAX_NUM_RECIPIENTS],
+    pub instances: [ResourceInstance; MAX_NUM_RESOURCE_INSTANCES],
+    pub challenges: [Challenge; MAX_NUM_CHALLENGES],
+    pub claims: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+    pub window: DistributionWindow,
+    pub is_finalized: bool,
+    pub quorum: QuorumRule,
+    pub round: u32,
+    pub escrow_mint: [u8; PUBLIC_KEY_SIZE],
+    pub vault: [u8; PUBLIC_KEY_SIZE],
+    pub refunded: [bool; MAX_NUM_RESOURCE_INSTANCES],
+    pub receipts: [bool; MAX_NUM_RECIPIENTS],
+    pub unit: UnitOfMeasure,
+    pub eligibility: EligibilityRule,
+    // how InitiateDistribution splits the pool, one of the ALLOCATION_* strategies
+    pub strategy: u8,
+    pub draw: Draw,
+    // zero leaves every slot open
+    pub capacity: u8,
+    pub waitlist: Waitlist,
+    // bound by the first StartNewRound, zero before that
+    pub history: [u8; PUBLIC_KEY_SIZE],
+    // signs the set-* commands and resets
+    pub authority: [u8; PUBLIC_KEY_SIZE],
+}
+pub const RESOURCE_DATABASE_SIZE: usize = 6010;
+
+impl ResourceDatabase {
+    pub fn is_escrowed(&self) -> bool {
+        self.escrow_mint != [0u8; PUBLIC_KEY_SIZE]
+    }
+
+    pub fn has_vault(&self) -> bool {
+        self.vault != [0u8; PUBLIC_KEY_SIZE]
+    }
+
+    pub fn capacity(&self) -> usize {
+        match self.capacity {
+            0 => MAX_NUM_RECIPIENTS,
+            capacity => usize::from(capacity),
+        }
+    }
+
+    pub fn allocation(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> u64 {
+        if *recipient == [0u8; PUBLIC_KEY_SIZE] {
+            return 0
+        }
+        self.intents.iter().find(|intent| intent.recipient == *recipient).map(|intent| intent.allocation).unwrap_or(0)
+    }
+
+    // what the program refunds a contributor once finalized, their share of the pool left
+    // unallocated or unclaimed, plus the rounding leftovers for the last one refunded; None if
+    // the quantities overflow
+    pub fn refund(&self, contributor: &[u8; PUBLIC_KEY_SIZE]) -> Option<u64> {
+        let deposited = self.instances.iter().try_fold(0u64, |sum, instance| sum.checked_add(instance.quantity))?;
+        let paid_out = self.claims.iter()
+            .filter(|claim| **claim != [0u8; PUBLIC_KEY_SIZE])
+            .try_fold(0u64, |sum, claim| sum.checked_add(self.allocation(claim)))?;
+        let remainder = deposited.saturating_sub(paid_out);
+        let index = match self.instances.iter().position(|instance| instance.from == *contributor) {
+            Some(index) if *contributor != [0u8; PUBLIC_KEY_SIZE] && deposited > 0 && self.instances[index].quantity > 0 && !self.refunded[index] => index,
+            _ => return Some(0),
+        };
+        let share = |quantity: u64| (u128::from(remainder) * u128::from(quantity) / u128::from(deposited)) as u64;
+        let mut others = self.instances.iter().zip(self.refunded.iter()).enumerate()
+            .filter(|(other, (instance, _))| *other != index && instance.quantity > 0);
+        if others.clone().all(|(_, (_, refunded))| *refunded) {
+            let refunded = others.try_fold(0u64, |sum, (_, (instance, _))| sum.checked_add(share(instance.quantity)))?;
+            return Some(remainder.saturating_sub(refunded))
+        }
+        Some(share(self.instances[index].quantity))
+    }
+}
+
+// the first and last page of the waitlist, zero while it has none
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Waitlist {
+    pub head: [u8; PUBLIC_KEY_SIZE],
+    pub tail: [u8; PUBLIC_KEY_SIZE],
+    pub waiting: u32,
+}
+
+impl Waitlist {
+    pub fn has_pages(&self) -> bool {
+        self.tail != [0u8; PUBLIC_KEY_SIZE]
+    }
+}
+
+pub const WAITLIST_PAGE_CAPACITY: usize = 4;
+
+// one page of the waitlist, chained to the next by AddWaitlistPage
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct WaitlistPage {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+    pub next: [u8; PUBLIC_KEY_SIZE],
+    pub intents: [Intent; WAITLIST_PAGE_CAPACITY],
+}
+pub const WAITLIST_PAGE_SIZE: usize = 264;
+
+// holds the lamports of a SOL denominated resource, bound to its database by SetLamportVault
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct LamportVault {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+}
+pub const LAMPORT_VAULT_SIZE: usize = 32;
+
+// proof of one claim, written by IssueReceipt into an account the resource program owns
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct ClaimReceipt {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+    pub recipient: [u8; PUBLIC_KEY_SIZE],
+    pub round: u32,
+    pub quantity: u64,
+    pub unit: UnitOfMeasure,
+}
+pub const CLAIM_RECEIPT_SIZE: usize = 93;
+
+// A finished round as StartNewRound archives it
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Copy)]
+pub struct RoundRecord {
+    pub round: u32,
+    pub window: DistributionWindow,
+    pub final_quantity: u64,
+    pub intents: [Intent; MAX_NUM_RECIPIENTS],
+    pub claims: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+    pub challenges: [Challenge; MAX_NUM_CHALLENGES],
+    pub unit: UnitOfMeasure,
+    pub strategy: u8,
+    pub draw: Draw,
+}
+
+impl Default for RoundRecord {
+    fn default() -> Self {
+        RoundRecord {
+            round: 0,
+            window: DistributionWindow::default(),
+            final_quantity: 0,
+            intents: [Intent::default(); MAX_NUM_RECIPIENTS],
+            claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            challenges: [Challenge::default(); MAX_NUM_CHALLENGES],
+            unit: UnitOfMeasure::default(),
+            strategy: 0,
+            draw: Draw::default(),
+        }
+    }
+}
+
+impl RoundRecord {
+    pub fn allocation(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> u64 {
+        if *recipient == [0u8; PUBLIC_KEY_SIZE] || !self.claims.contains(recipient) {
+            return 0
+        }
+        self.intents.iter().find(|intent| intent.recipient == *recipient).map(|intent| intent.allocation).unwrap_or(0)
+    }
+
+    pub fn approvals_received(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> u32 {
+        self.challenges.iter().filter(|challenge| challenge.to == *recipient && challenge.value).count() as u32
+    }
+}
+
+pub const MAX_NUM_ROUNDS: usize = 4;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct RoundHistory {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+    pub next_round: u32,
+    pub rounds: [RoundRecord; MAX_NUM_ROUNDS],
+}
+pub const ROUND_HISTORY_SIZE: usize = 19708;
+
+impl RoundHistory {
+    // None once the round was overwritten, or if it was never archived
+    pub fn get(&self, round: u32) -> Option<&RoundRecord> {
+        if round >= self.next_round || round as usize + MAX_NUM_ROUNDS < self.next_round as usize {
+            return None
+        }
+        let record = &self.rounds[round as usize % MAX_NUM_ROUNDS];
+        if record.round == round {
+            Some(record)
+        } else {
+            None
+        }
+    }
+
+    // oldest first
+    pub fn rounds(&self) -> Vec<&RoundRecord> {
+        let first = self.next_round.saturating_sub(MAX_NUM_ROUNDS as u32);
+        (first..self.next_round).filter_map(|round| self.get(round)).collect()
+    }
+}
+
+// zip codes and names are stored zero padded; longer values are truncated
+pub fn padded_name(value: &str) -> [u8; MAX_FRIENDLY_NAME_SIZE] {
+    let mut name = [0u8; MAX_FRIENDLY_NAME_SIZE];
+    for (place, data) in name.iter_mut().zip(value.as_bytes().iter()) {
+        *place = *data
+    }
+    name
+}
+
+pub fn padded_zip(value: &str) -> [u8; MAX_ZIP_SIZE] {
+    let mut zip = [0u8; MAX_ZIP_SIZE];
+    for (place, data) in zip.iter_mut().zip(value.as_bytes().iter()) {
+        *place = *data
+    }
+    zip
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_mirrored_sizes() {
+        let account = SearchEngineAccount {
+            friendly_name: padded_name("jeff"),
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        assert_eq!(account.try_to_vec().unwrap().len(), SEARCH_ENGINE_ACCOUNT_SIZE);
+
+        let index = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        };
+        assert_eq!(index.try_to_vec().unwrap().len(), RESOURCE_INDEX_SIZE);
+
+        let database = ResourceDatabase {
+            is_distributed: false,
+            final_quantity: 0,
+            intents: [Intent::default(); MAX_NUM_RECIPIENTS],
+            instances: [ResourceInstance::default(); MAX_NUM_RESOURCE_INSTANCES],
+            challenges: [Challenge::default(); MAX_NUM_CHALLENGES],
+            claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            window: DistributionWindow::default(),
+            is_finalized: false,
+            quorum: QuorumRule::default(),
+            round: 0,
+            escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+            vault: [0u8; PUBLIC_KEY_SIZE],
+            refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+            receipts: [false; MAX_NUM_RECIPIENTS],
+            unit: UnitOfMeasure::default(),
+            eligibility: EligibilityRule::default(),
+            strategy: ALLOCATION_PROPORTIONAL,
+            draw: Draw::default(),
+            capacity: 0,
+            waitlist: Waitlist::default(),
+            history: [0u8; PUBLIC_KEY_SIZE],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        };
+        assert_eq!(database.try_to_vec().unwrap().len(), RESOURCE_DATABASE_SIZE);
+        assert_eq!(WaitlistPage::default().try_to_vec().unwrap().len(), WAITLIST_PAGE_SIZE);
+        assert_eq!(LamportVault::default().try_to_vec().unwrap().len(), LAMPORT_VAULT_SIZE);
+        assert_eq!(ClaimReceipt::default().try_to_vec().unwrap().len(), CLAIM_RECEIPT_SIZE);
+
+        let history = RoundHistory {
+            database: [0u8; PUBLIC_KEY_SIZE],
+            next_round: 0,
+            rounds: [RoundRecord::default(); MAX_NUM_ROUNDS],
+        };
+        assert_eq!(history.try_to_vec().unwrap().len(), ROUND_HISTORY_SIZE);
+    }
+
+    #[test]
+    fn test_format_quantity() {
+        assert_eq!(UnitOfMeasure::new("kg", 1).format(25), "2.5 kg");
+        assert_eq!(UnitOfMeasure::new("kg", 3).format(5), "0.005 kg");
+        assert_eq!(UnitOfMeasure::new("loaves", 0).format(12), "12 loaves");
+        assert_eq!(UnitOfMeasure::default().format(u64::MAX), u64::MAX.to_string());
+        assert_eq!(UnitOfMeasure::new("sol", 9).format(1_500_000_000), "1.500000000 sol");
+    }
+
+    #[test]
+    fn test_padded_zip() {
+        let zip = padded_zip("12345");
+        assert_eq!(&zip[..5], "12345".as_bytes());
+        assert_eq!(zip[5..], [0u8; MAX_ZIP_SIZE - 5]);
+    }
+}
@@ -0,0 +1,175 @@
+// Seeds corpus/process_instruction with every step of a few rounds, each input holding the
+// accounts as the steps before left them, and corpus/unpack with their instruction data. Every
+// step has to succeed, so the fuzzer starts from accounts the program accepts.
+use std::{collections::BTreeMap, fs, path::Path};
+use borsh::BorshSerialize;
+use solana_program::{
+    account_info::AccountInfo, clock::{Clock, Epoch}, pubkey::Pubkey, sysvar::Sysvar,
+};
+use solana_bpf_resource::types::{
+    INSTRUCTION_REGISTER_INTENT, INSTRUCTION_RECORD_RESOURCE_INSTANCE, INSTRUCTION_INITIATE_DISTRIBUTION,
+    INSTRUCTION_RECORD_CHALLENGE, INSTRUCTION_CLAIM_CHALLENGE, INSTRUCTION_RESET_DATABASE,
+    INSTRUCTION_FINALIZE_DISTRIBUTION, INSTRUCTION_SET_QUORUM, INSTRUCTION_START_NEW_ROUND,
+    INSTRUCTION_ISSUE_RECEIPT, INSTRUCTION_SET_ALLOCATION_STRATEGY, INSTRUCTION_SET_CAPACITY,
+    INSTRUCTION_ADD_WAITLIST_PAGE, INSTRUCTION_WITHDRAW_INTENT, INSTRUCTION_DRAW_LOTTERY,
+    ALLOCATION_LOTTERY, QUORUM_TRUST_WEIGHTED, PUBLIC_KEY_SIZE, MAX_FRIENDLY_NAME_SIZE, MAX_NUM_INTENTS,
+    RESOURCE_DATABASE_SIZE, ROUND_HISTORY_SIZE, CLAIM_RECEIPT_SIZE, WAITLIST_PAGE_SIZE,
+    Need, ResourceInstance, Challenge, QuorumRule, SearchEngineAccount, TrustTableEntry, Reputation,
+};
+use solana_bpf_resource_fuzz::{
+    key, Account, Input, SIGNER, FOREIGN_OWNER, CLOCK, SLOT_HASHES, FOREIGN_PROGRAM,
+};
+
+const AUTHORITY: u8 = 7;
+const ALICE: u8 = 1;
+const BOB: u8 = 2;
+const CAROL: u8 = 3;
+const DATABASE: u8 = 0x10;
+const HISTORY: u8 = 0x11;
+const RECEIPT: u8 = 0x12;
+const LOTTERY_DATABASE: u8 = 0x20;
+const WAITLIST_DATABASE: u8 = 0x30;
+const PAGE: u8 = 0x31;
+
+// the accounts every step reads and writes, by key seed
+struct Ledger {
+    accounts: BTreeMap<u8, Account>,
+}
+
+impl Ledger {
+    fn set(&mut self, seed: u8, data: Vec<u8>) {
+        self.accounts.insert(seed, Account { seed, data, ..Account::default() });
+    }
+
+    fn set_clock(&mut self, slot: u64, unix_timestamp: i64) {
+        let key = key(CLOCK);
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; Clock::size_of()];
+        {
+            let mut account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, Epoch::default());
+            Clock { slot, unix_timestamp, ..Clock::default() }.to_account_info(&mut account).unwrap();
+        }
+        self.set(CLOCK, data);
+    }
+
+    // writes the step to the corpus, then runs it and keeps what it wrote
+    fn step(&mut self, corpus: &Path, name: &str, accounts: &[(u8, u8)], instruction_data: Vec<u8>) {
+        let mut input = Input {
+            accounts: accounts.iter().map(|(seed, flags)| Account {
+                flags: *flags,
+                ..self.accounts.get(seed).cloned().unwrap_or(Account { seed: *seed, ..Account::default() })
+            }).collect(),
+            instruction_data,
+        };
+        let bytes = input.to_bytes();
+        // the target reads back the same accounts
+        assert_eq!(Input::from_bytes(&bytes).to_bytes(), bytes);
+        fs::write(corpus.join("process_instruction").join(name), bytes).unwrap();
+        fs::write(corpus.join("unpack").join(name), &input.instruction_data).unwrap();
+        if let Err(error) = input.process() {
+            panic!("{}: {:?}", name, error);
+        }
+        for account in input.accounts.into_iter() {
+            self.accounts.insert(account.seed, Account { flags: 0, ..account });
+        }
+    }
+}
+
+fn with_data<T: BorshSerialize>(instruction: u8, data: &T) -> Vec<u8> {
+    let mut instruction_data = vec![instruction];
+    instruction_data.extend(data.try_to_vec().unwrap());
+    instruction_data
+}
+
+// SlotHashes sysvar data as the runtime lays it out, newest entry first
+fn slot_hashes(entries: &[(u64, [u8; PUBLIC_KEY_SIZE])]) -> Vec<u8> {
+    let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+    for (slot, hash) in entries.iter() {
+        data.extend_from_slice(&slot.to_le_bytes());
+        data.extend_from_slice(hash);
+    }
+    data
+}
+
+fn main() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    fs::create_dir_all(corpus.join("process_instruction")).unwrap();
+    fs::create_dir_all(corpus.join("unpack")).unwrap();
+
+    let mut ledger = Ledger { accounts: BTreeMap::new() };
+    for database in [DATABASE, LOTTERY_DATABASE, WAITLIST_DATABASE].iter() {
+        ledger.set(*database, vec![0u8; RESOURCE_DATABASE_SIZE]);
+    }
+    ledger.set(HISTORY, vec![0u8; ROUND_HISTORY_SIZE]);
+    ledger.set(RECEIPT, vec![0u8; CLAIM_RECEIPT_SIZE]);
+    ledger.set(PAGE, vec![0u8; WAITLIST_PAGE_SIZE]);
+    // alice's search engine account trusts bob, for the trust weighted quorum
+    ledger.set(ALICE, SearchEngineAccount {
+        friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+        trust_table: [TrustTableEntry { to: key(BOB).to_bytes(), value: 1 }],
+        intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+        reputation: Reputation::default(),
+    }.try_to_vec().unwrap());
+    let ledger = &mut ledger;
+    let corpus = corpus.as_path();
+
+    // a round recording quantities only, bob claims on alice's trust
+    ledger.step(corpus, "reset_database", &[(DATABASE, 0), (AUTHORITY, SIGNER)], vec![INSTRUCTION_RESET_DATABASE]);
+    ledger.step(corpus, "set_quorum", &[(DATABASE, 0), (AUTHORITY, SIGNER)], with_data(INSTRUCTION_SET_QUORUM, &QuorumRule {
+        kind: QUORUM_TRUST_WEIGHTED,
+        threshold: 1,
+        trust_program: key(FOREIGN_PROGRAM).to_bytes(),
+    }));
+    ledger.step(corpus, "register_intent", &[(DATABASE, 0), (ALICE, SIGNER)], vec![INSTRUCTION_REGISTER_INTENT]);
+    ledger.step(corpus, "register_need", &[(DATABASE, 0), (BOB, SIGNER)], with_data(INSTRUCTION_REGISTER_INTENT, &Need {
+        requested: 5,
+        household_size: 1,
+    }));
+    ledger.step(corpus, "record_resource_instance", &[(DATABASE, 0), (CAROL, SIGNER)], with_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &ResourceInstance {
+        from: key(CAROL).to_bytes(),
+        quantity: 10,
+    }));
+    ledger.set_clock(100, 1000);
+    ledger.step(corpus, "initiate_distribution", &[(DATABASE, 0), (AUTHORITY, SIGNER), (CLOCK, 0)], with_data(INSTRUCTION_INITIATE_DISTRIBUTION, &3600i64));
+    ledger.step(corpus, "record_challenge", &[(DATABASE, 0), (CLOCK, 0), (ALICE, SIGNER)], with_data(INSTRUCTION_RECORD_CHALLENGE, &Challenge {
+        from: key(ALICE).to_bytes(),
+        to: key(BOB).to_bytes(),
+        value: true,
+    }));
+    ledger.step(corpus, "claim_challenge", &[(DATABASE, 0), (ALICE, FOREIGN_OWNER)], with_data(INSTRUCTION_CLAIM_CHALLENGE, &Challenge {
+        from: key(BOB).to_bytes(),
+        to: [0u8; PUBLIC_KEY_SIZE],
+        value: true,
+    }));
+    ledger.step(corpus, "issue_receipt", &[(DATABASE, 0), (RECEIPT, 0), (BOB, 0)], vec![INSTRUCTION_ISSUE_RECEIPT]);
+    ledger.set_clock(200, 1000 + 3600);
+    ledger.step(corpus, "finalize_distribution", &[(DATABASE, 0), (CLOCK, 0)], vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    ledger.step(corpus, "start_new_round", &[(DATABASE, 0), (HISTORY, 0)], vec![INSTRUCTION_START_NEW_ROUND]);
+
+    // a lottery started at slot 100, so committed to slot 104 and drawn with the hash of slot 105
+    ledger.step(corpus, "reset_lottery_database", &[(LOTTERY_DATABASE, 0), (AUTHORITY, SIGNER)], vec![INSTRUCTION_RESET_DATABASE]);
+    ledger.step(corpus, "set_allocation_strategy", &[(LOTTERY_DATABASE, 0), (AUTHORITY, SIGNER)], vec![INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_LOTTERY]);
+    ledger.step(corpus, "register_lottery_intent", &[(LOTTERY_DATABASE, 0), (ALICE, SIGNER)], vec![INSTRUCTION_REGISTER_INTENT]);
+    ledger.step(corpus, "register_lottery_need", &[(LOTTERY_DATABASE, 0), (BOB, SIGNER)], with_data(INSTRUCTION_REGISTER_INTENT, &Need {
+        requested: 8,
+        household_size: 0,
+    }));
+    ledger.step(corpus, "record_lottery_instance", &[(LOTTERY_DATABASE, 0), (CAROL, SIGNER)], with_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &ResourceInstance {
+        from: key(CAROL).to_bytes(),
+        quantity: 10,
+    }));
+    ledger.set_clock(100, 1000);
+    ledger.step(corpus, "commit_lottery", &[(LOTTERY_DATABASE, 0), (AUTHORITY, SIGNER), (CLOCK, 0)], with_data(INSTRUCTION_INITIATE_DISTRIBUTION, &3600i64));
+    ledger.set_clock(110, 1010);
+    ledger.set(SLOT_HASHES, slot_hashes(&[(109, [9u8; PUBLIC_KEY_SIZE]), (105, [5u8; PUBLIC_KEY_SIZE]), (103, [3u8; PUBLIC_KEY_SIZE])]));
+    ledger.step(corpus, "draw_lottery", &[(LOTTERY_DATABASE, 0), (CLOCK, 0), (SLOT_HASHES, 0)], vec![INSTRUCTION_DRAW_LOTTERY]);
+
+    // one slot, bob waits on the page until alice withdraws
+    ledger.step(corpus, "reset_waitlist_database", &[(WAITLIST_DATABASE, 0), (AUTHORITY, SIGNER)], vec![INSTRUCTION_RESET_DATABASE]);
+    ledger.step(corpus, "set_capacity", &[(WAITLIST_DATABASE, 0), (AUTHORITY, SIGNER)], vec![INSTRUCTION_SET_CAPACITY, 1]);
+    ledger.step(corpus, "add_waitlist_page", &[(WAITLIST_DATABASE, 0), (PAGE, 0)], vec![INSTRUCTION_ADD_WAITLIST_PAGE]);
+    ledger.step(corpus, "register_first_intent", &[(WAITLIST_DATABASE, 0), (ALICE, SIGNER)], vec![INSTRUCTION_REGISTER_INTENT]);
+    ledger.step(corpus, "register_waiting_intent", &[(WAITLIST_DATABASE, 0), (BOB, SIGNER), (PAGE, 0)], vec![INSTRUCTION_REGISTER_INTENT]);
+    ledger.step(corpus, "withdraw_intent", &[(WAITLIST_DATABASE, 0), (ALICE, SIGNER), (PAGE, 0)], vec![INSTRUCTION_WITHDRAW_INTENT]);
+}
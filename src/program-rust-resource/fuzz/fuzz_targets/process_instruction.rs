@@ -0,0 +1,10 @@
+#![no_main]
+// Arbitrary instruction data against an arbitrary account list, signers and owners included, see
+// Input for the layout; `cargo run --example seed_corpus` seeds it with every step of a round.
+// Anything the program cannot use has to come back as a ProgramError.
+use libfuzzer_sys::fuzz_target;
+use solana_bpf_resource_fuzz::Input;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Input::from_bytes(data).process();
+});
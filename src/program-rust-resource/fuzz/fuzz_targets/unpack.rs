@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use solana_bpf_resource::instruction::ResourceInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = ResourceInstruction::unpack(data);
+});
@@ -0,0 +1,117 @@
+// The input of the process_instruction target, shared with the seed corpus: an account count,
+// then per account a key seed, a flags byte, its lamports as a u64 and its data length as a u32
+// (both little endian) followed by the data, then the instruction data. Inputs cut short read
+// the missing bytes as zero.
+use solana_program::{
+    account_info::AccountInfo, clock::Epoch, entrypoint::ProgramResult, pubkey::Pubkey, sysvar,
+};
+use solana_bpf_resource::{process_instruction, types::PUBLIC_KEY_SIZE};
+
+pub const MAX_ACCOUNTS: usize = 8;
+
+// flags
+pub const SIGNER: u8 = 1;
+// owned by FOREIGN_PROGRAM rather than the resource program, like a trust program's accounts
+pub const FOREIGN_OWNER: u8 = 2;
+
+// key seeds standing for the sysvars instead of [seed; 32]
+pub const CLOCK: u8 = 0xfe;
+pub const SLOT_HASHES: u8 = 0xff;
+// the key of the owner of FOREIGN_OWNER accounts
+pub const FOREIGN_PROGRAM: u8 = 0xfd;
+
+pub fn key(seed: u8) -> Pubkey {
+    match seed {
+        CLOCK => sysvar::clock::id(),
+        SLOT_HASHES => sysvar::slot_hashes::id(),
+        _ => Pubkey::new(&[seed; PUBLIC_KEY_SIZE]),
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Account {
+    pub seed: u8,
+    pub flags: u8,
+    pub lamports: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Input {
+    pub accounts: Vec<Account>,
+    pub instruction_data: Vec<u8>,
+}
+
+// reads the input front to back, zero once it ran out
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let (taken, rest) = self.bytes.split_at(len.min(self.bytes.len()));
+        self.bytes = rest;
+        taken
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.take(1).first().copied().unwrap_or(0)
+    }
+
+    fn u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        let taken = self.take(bytes.len());
+        bytes[..taken.len()].copy_from_slice(taken);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        let taken = self.take(bytes.len());
+        bytes[..taken.len()].copy_from_slice(taken);
+        u64::from_le_bytes(bytes)
+    }
+}
+
+impl Input {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut reader = Reader { bytes };
+        let count = usize::from(reader.u8()) % (MAX_ACCOUNTS + 1);
+        let accounts = (0..count).map(|_| {
+            let seed = reader.u8();
+            let flags = reader.u8();
+            let lamports = reader.u64();
+            let len = reader.u32() as usize;
+            Account { seed, flags, lamports, data: reader.take(len).to_vec() }
+        }).collect();
+        Input { accounts, instruction_data: reader.bytes.to_vec() }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.accounts.len() as u8];
+        for account in self.accounts.iter() {
+            bytes.push(account.seed);
+            bytes.push(account.flags);
+            bytes.extend_from_slice(&account.lamports.to_le_bytes());
+            bytes.extend_from_slice(&(account.data.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&account.data);
+        }
+        bytes.extend_from_slice(&self.instruction_data);
+        bytes
+    }
+
+    // runs the resource program, whose id is the zero key, on the input and keeps what it wrote
+    // to the accounts
+    pub fn process(&mut self) -> ProgramResult {
+        let program_id = Pubkey::default();
+        let foreign_program = key(FOREIGN_PROGRAM);
+        let keys: Vec<Pubkey> = self.accounts.iter().map(|account| key(account.seed)).collect();
+        let infos: Vec<AccountInfo> = self.accounts.iter_mut().zip(keys.iter())
+            .map(|(account, key)| {
+                let owner = if account.flags & FOREIGN_OWNER != 0 { &foreign_program } else { &program_id };
+                AccountInfo::new(key, account.flags & SIGNER != 0, true, &mut account.lamports, &mut account.data, owner, false, Epoch::default())
+            })
+            .collect();
+        process_instruction(&program_id, &infos, &self.instruction_data)
+    }
+}
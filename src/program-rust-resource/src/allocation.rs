@@ -0,0 +1,257 @@
+// Allocation strategies InitiateDistribution splits a round's pool with, picked by the
+// database's strategy. Every strategy allocates at most the pool and leaves unused slots at zero.
+//
+// A recipient's demand is what they requested, or without a request their household's share of
+// the pool among all registered households. Slots fill in the order intents were registered,
+// which is the order first come, first served serves them in.
+//
+// A lottery serves them in an order drawn from the seed instead, the hash of the SlotHashes entry
+// DrawLottery recorded in the database, so whoever reads the database can draw it again.
+use solana_program::hash::hashv;
+use crate::types::{
+    ALLOCATION_PROPORTIONAL,
+    ALLOCATION_EQUAL_SPLIT,
+    ALLOCATION_MAX_MIN_FAIR,
+    ALLOCATION_FIRST_COME_FIRST_SERVED,
+    ALLOCATION_LOTTERY,
+    MAX_NUM_RECIPIENTS,
+    PUBLIC_KEY_SIZE,
+    Intent,
+};
+
+pub fn is_valid(strategy: u8) -> bool {
+    strategy <= ALLOCATION_LOTTERY
+}
+
+// sets every intent's allocation and returns the total allocated, only a lottery uses the seed
+pub fn allocate(strategy: u8, pool: u64, seed: &[u8; PUBLIC_KEY_SIZE], intents: &mut [Intent; MAX_NUM_RECIPIENTS]) -> u64 {
+    let demands = demands(pool, intents);
+    let allocations = match strategy {
+        ALLOCATION_PROPORTIONAL => proportional(pool, &demands),
+        ALLOCATION_EQUAL_SPLIT => equal_split(pool, intents),
+        ALLOCATION_MAX_MIN_FAIR => max_min_fair(pool, &demands),
+        ALLOCATION_FIRST_COME_FIRST_SERVED => first_come_first_served(pool, &demands),
+        ALLOCATION_LOTTERY => lottery(pool, &demands, seed, intents),
+        _ => [0u128; MAX_NUM_RECIPIENTS],
+    };
+    let mut allocated = 0;
+    for (allocation, intent) in allocations.iter().zip(intents.iter_mut()) {
+        intent.allocation = *allocation as u64;
+        allocated += intent.allocation;
+    }
+    allocated
+}
+
+// widened so the products can't overflow, every allocation is at most the pool
+fn demands(pool: u64, intents: &[Intent; MAX_NUM_RECIPIENTS]) -> [u128; MAX_NUM_RECIPIENTS] {
+    let households: u64 = intents.iter().filter(|intent| intent.is_registered()).map(|intent| intent.need.household()).sum();
+    let mut demands = [0u128; MAX_NUM_RECIPIENTS];
+    for (demand, intent) in demands.iter_mut().zip(intents.iter()) {
+        if !intent.is_registered() {
+            continue
+        }
+        *demand = if intent.need.requested > 0 {
+            u128::from(intent.need.requested)
+        } else {
+            u128::from(pool) * u128::from(intent.need.household()) / u128::from(households)
+        };
+    }
+    demands
+}
+
+// in proportion to demand, and never more than it. What rounding leaves is not allocated.
+fn proportional(pool: u64, demands: &[u128; MAX_NUM_RECIPIENTS]) -> [u128; MAX_NUM_RECIPIENTS] {
+    let total: u128 = demands.iter().sum();
+    let mut allocations = [0u128; MAX_NUM_RECIPIENTS];
+    if total == 0 {
+        return allocations
+    }
+    for (allocation, demand) in allocations.iter_mut().zip(demands.iter()) {
+        *allocation = (*demand).min(u128::from(pool) * *demand / total);
+    }
+    allocations
+}
+
+// the same share for every intent, whatever they asked for
+fn equal_split(pool: u64, intents: &[Intent; MAX_NUM_RECIPIENTS]) -> [u128; MAX_NUM_RECIPIENTS] {
+    let registered = intents.iter().filter(|intent| intent.is_registered()).count() as u128;
+    let mut allocations = [0u128; MAX_NUM_RECIPIENTS];
+    if registered == 0 {
+        return allocations
+    }
+    for (allocation, intent) in allocations.iter_mut().zip(intents.iter()) {
+        if intent.is_registered() {
+            *allocation = u128::from(pool) / registered;
+        }
+    }
+    allocations
+}
+
+// Raises everyone's allocation together until the pool runs out, except that nobody gets more
+// than their demand; what the smaller demands leave goes to the larger ones. Equal demands are
+// served in registration order, so what rounding leaves goes to the later one.
+fn max_min_fair(pool: u64, demands: &[u128; MAX_NUM_RECIPIENTS]) -> [u128; MAX_NUM_RECIPIENTS] {
+    let mut order = [0usize; MAX_NUM_RECIPIENTS];
+    for (position, index) in order.iter_mut().enumerate() {
+        *index = position;
+    }
+    order.sort_by_key(|index| demands[*index]);
+    let mut allocations = [0u128; MAX_NUM_RECIPIENTS];
+    let mut remaining = u128::from(pool);
+    let mut left = demands.iter().filter(|demand| **demand > 0).count() as u128;
+    for index in order.iter().filter(|index| demands[**index] > 0) {
+        allocations[*index] = demands[*index].min(remaining / left);
+        remaining -= allocations[*index];
+        left -= 1;
+    }
+    allocations
+}
+
+// each demand in full, in registration order, until the pool runs out
+fn first_come_first_served(pool: u64, demands: &[u128; MAX_NUM_RECIPIENTS]) -> [u128; MAX_NUM_RECIPIENTS] {
+    let mut allocations = [0u128; MAX_NUM_RECIPIENTS];
+    let mut remaining = u128::from(pool);
+    for (allocation, demand) in allocations.iter_mut().zip(demands.iter()) {
+        *allocation = (*demand).min(remaining);
+        remaining -= *allocation;
+    }
+    allocations
+}
+
+// Slots of the registered intents in drawn order, followed by how many there are. A Fisher-Yates
+// shuffle of the slots in registration order: going down from the last position, each one is
+// swapped with a position at or below it, the first 8 bytes of sha256(seed, position as u64 little
+// endian) as a little endian number modulo the position plus one.
+pub fn draw(seed: &[u8; PUBLIC_KEY_SIZE], intents: &[Intent; MAX_NUM_RECIPIENTS]) -> ([usize; MAX_NUM_RECIPIENTS], usize) {
+    let mut order = [0usize; MAX_NUM_RECIPIENTS];
+    let mut registered = 0;
+    for (index, intent) in intents.iter().enumerate() {
+        if intent.is_registered() {
+            order[registered] = index;
+            registered += 1;
+        }
+    }
+    for position in (1..registered).rev() {
+        let hash = hashv(&[seed, &(position as u64).to_le_bytes()]);
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&hash.as_ref()[..8]);
+        let pick = u64::from_le_bytes(bytes) % (position as u64 + 1);
+        order.swap(position, pick as usize);
+    }
+    (order, registered)
+}
+
+// each demand in full, in drawn order, until the pool runs out: the first drawn win, the rest
+// get nothing rather than a fraction
+fn lottery(pool: u64, demands: &[u128; MAX_NUM_RECIPIENTS], seed: &[u8; PUBLIC_KEY_SIZE], intents: &[Intent; MAX_NUM_RECIPIENTS]) -> [u128; MAX_NUM_RECIPIENTS] {
+    let (order, registered) = draw(seed, intents);
+    let mut allocations = [0u128; MAX_NUM_RECIPIENTS];
+    let mut remaining = u128::from(pool);
+    for index in order[..registered].iter() {
+        allocations[*index] = demands[*index].min(remaining);
+        remaining -= allocations[*index];
+    }
+    allocations
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::Need;
+
+    fn intents(needs: &[(u64, u16)]) -> [Intent; MAX_NUM_RECIPIENTS] {
+        let mut intents = [Intent::default(); MAX_NUM_RECIPIENTS];
+        for (index, (requested, household_size)) in needs.iter().enumerate() {
+            intents[index] = Intent {
+                recipient: [index as u8 + 1; 32],
+                need: Need {
+                    requested: *requested,
+                    household_size: *household_size,
+                },
+                allocation: 0,
+            };
+        }
+        intents
+    }
+
+    fn allocations(strategy: u8, pool: u64, needs: &[(u64, u16)]) -> (Vec<u64>, u64) {
+        drawn_allocations(strategy, pool, &[0u8; PUBLIC_KEY_SIZE], needs)
+    }
+
+    fn drawn_allocations(strategy: u8, pool: u64, seed: &[u8; PUBLIC_KEY_SIZE], needs: &[(u64, u16)]) -> (Vec<u64>, u64) {
+        let mut intents = intents(needs);
+        let allocated = allocate(strategy, pool, seed, &mut intents);
+        assert!(intents[needs.len()..].iter().all(|intent| intent.allocation == 0));
+        (intents[..needs.len()].iter().map(|intent| intent.allocation).collect(), allocated)
+    }
+
+    // the drawn slots only
+    fn drawn(seed: &[u8; PUBLIC_KEY_SIZE], intents: &[Intent; MAX_NUM_RECIPIENTS]) -> (Vec<usize>, usize) {
+        let (order, registered) = draw(seed, intents);
+        (order[..registered].to_vec(), registered)
+    }
+
+    // the same cases are checked against the SDK in lib-allocation.test.tsx
+    #[test]
+    fn test_proportional() {
+        assert_eq!(allocations(ALLOCATION_PROPORTIONAL, 9, &[(0, 0), (0, 0)]), (vec![4, 4], 8));
+        assert_eq!(allocations(ALLOCATION_PROPORTIONAL, 10, &[(2, 1), (0, 3)]), (vec![2, 7], 9));
+        assert_eq!(allocations(ALLOCATION_PROPORTIONAL, 10, &[(30, 0), (10, 0)]), (vec![7, 2], 9));
+        assert_eq!(allocations(ALLOCATION_PROPORTIONAL, 9007199254740991, &[(9007199254740991, 0), (9007199254740991, 0)]), (vec![4503599627370495, 4503599627370495], 9007199254740990));
+        assert_eq!(allocations(ALLOCATION_PROPORTIONAL, u64::MAX, &[(u64::MAX, 0), (u64::MAX, 0)]), (vec![u64::MAX / 2, u64::MAX / 2], u64::MAX - 1));
+    }
+
+    #[test]
+    fn test_equal_split() {
+        assert_eq!(allocations(ALLOCATION_EQUAL_SPLIT, 9, &[(0, 0), (0, 0)]), (vec![4, 4], 8));
+        assert_eq!(allocations(ALLOCATION_EQUAL_SPLIT, 10, &[(2, 1), (0, 3)]), (vec![5, 5], 10));
+        assert_eq!(allocations(ALLOCATION_EQUAL_SPLIT, 10, &[(30, 0)]), (vec![10], 10));
+        assert_eq!(allocations(ALLOCATION_EQUAL_SPLIT, 20, &[(1, 0); MAX_NUM_RECIPIENTS]), (vec![2; MAX_NUM_RECIPIENTS], 16));
+    }
+
+    #[test]
+    fn test_max_min_fair() {
+        assert_eq!(allocations(ALLOCATION_MAX_MIN_FAIR, 9, &[(0, 0), (0, 0)]), (vec![4, 4], 8));
+        assert_eq!(allocations(ALLOCATION_MAX_MIN_FAIR, 9, &[(5, 0), (5, 0)]), (vec![4, 5], 9));
+        // the smaller demand is met in full and the rest goes to the larger one
+        assert_eq!(allocations(ALLOCATION_MAX_MIN_FAIR, 10, &[(30, 0), (2, 0)]), (vec![8, 2], 10));
+        assert_eq!(allocations(ALLOCATION_MAX_MIN_FAIR, 10, &[(30, 0), (10, 0)]), (vec![5, 5], 10));
+        assert_eq!(allocations(ALLOCATION_MAX_MIN_FAIR, 10, &[(3, 0), (4, 0)]), (vec![3, 4], 7));
+    }
+
+    #[test]
+    fn test_first_come_first_served() {
+        assert_eq!(allocations(ALLOCATION_FIRST_COME_FIRST_SERVED, 10, &[(8, 0), (8, 0)]), (vec![8, 2], 10));
+        assert_eq!(allocations(ALLOCATION_FIRST_COME_FIRST_SERVED, 10, &[(2, 1), (0, 3)]), (vec![2, 7], 9));
+        assert_eq!(allocations(ALLOCATION_FIRST_COME_FIRST_SERVED, 10, &[(30, 0), (1, 0)]), (vec![10, 0], 10));
+    }
+
+    #[test]
+    fn test_lottery() {
+        // sha256 of [0; 32] and position 1 is odd, [4; 32] draws the second slot first
+        let kept = [0u8; PUBLIC_KEY_SIZE];
+        let swapped = [4u8; PUBLIC_KEY_SIZE];
+        assert_eq!(drawn(&kept, &intents(&[(8, 0), (8, 0)])), (vec![0, 1], 2));
+        assert_eq!(drawn(&swapped, &intents(&[(8, 0), (8, 0)])), (vec![1, 0], 2));
+        assert_eq!(drawn_allocations(ALLOCATION_LOTTERY, 10, &kept, &[(8, 0), (8, 0)]), (vec![8, 2], 10));
+        assert_eq!(drawn_allocations(ALLOCATION_LOTTERY, 10, &swapped, &[(8, 0), (8, 0)]), (vec![2, 8], 10));
+        assert_eq!(drawn_allocations(ALLOCATION_LOTTERY, 10, &swapped, &[(30, 0), (1, 0)]), (vec![9, 1], 10));
+        // a winner without a request gets their household's share
+        assert_eq!(drawn_allocations(ALLOCATION_LOTTERY, 10, &swapped, &[(2, 1), (0, 3)]), (vec![2, 7], 9));
+
+        // a lone or missing intent is not shuffled
+        let mut intents = intents(&[(8, 0), (8, 0)]);
+        intents[0] = Intent::default();
+        assert_eq!(drawn(&swapped, &intents), (vec![1], 1));
+        assert_eq!(drawn(&swapped, &[Intent::default(); MAX_NUM_RECIPIENTS]), (vec![], 0));
+    }
+
+    #[test]
+    fn test_nothing_to_allocate() {
+        for strategy in ALLOCATION_PROPORTIONAL..=ALLOCATION_LOTTERY {
+            assert_eq!(allocations(strategy, 10, &[]), (vec![], 0));
+            assert_eq!(allocations(strategy, 0, &[(5, 0), (0, 2)]), (vec![0, 0], 0));
+        }
+        assert!(!is_valid(ALLOCATION_LOTTERY + 1));
+    }
+}
@@ -9,19 +9,137 @@ pub enum ResourceError {
     #[error("Invalid instruction")]
     InvalidInstruction,
 
-    /// Someone is trying to record a resource instance but there is no empty space in the array
+    /// A new contributor is trying to record a resource instance but every contributor slot is taken
     #[error("No ResourceInstance Space")]
     NoResourceInstanceSpace,
 
     /// Someone is trying to either:
     /// * record a resource instance
     /// * re-initiate a resource distribution
+    ///
     /// but the Resource is being distributed and can't be modified
     #[error("ResourceInstance is being distributed")]
     ResourceInDistribution,
 
     #[error("ResourceInstance not being distributed")]
     ResourceNotInDistribution,
+
+    /// A challenge was recorded, or the distribution finalized again, after the window closed
+    #[error("Distribution window closed")]
+    DistributionClosed,
+
+    /// The distribution can't be finalized before the window closes
+    #[error("Distribution window still open")]
+    DistributionOpen,
+
+    /// Only recipients that registered an intent can claim
+    #[error("Not a recipient")]
+    NotARecipient,
+
+    #[error("Already claimed")]
+    AlreadyClaimed,
+
+    /// The recipient's peers have not approved them enough for the quorum rule
+    #[error("Quorum not met")]
+    QuorumNotMet,
+
+    /// Unknown quorum kind, a zero threshold or a trust weighted rule without a trust program
+    #[error("Invalid quorum rule")]
+    InvalidQuorumRule,
+
+    /// The round history account already archives another database's rounds, or the database
+    /// already archives into another history
+    #[error("Wrong round history")]
+    WrongRoundHistory,
+
+    /// A token account is not the database's escrow, or holds another mint or owner than
+    /// expected, or a vault is not the database's
+    #[error("Wrong escrow account")]
+    WrongEscrow,
+
+    /// The escrow mint or vault can't change while recorded instances are backed by the old
+    /// one, and a resource is backed by tokens or lamports, not both
+    #[error("Escrow in use")]
+    EscrowInUse,
+
+    /// Not a contributor of the round, or already refunded
+    #[error("Nothing to refund")]
+    NothingToRefund,
+
+    /// The claim already has its receipt, or the receipt account already holds another one
+    #[error("Receipt already issued")]
+    ReceiptAlreadyIssued,
+
+    /// Receipts are only issued to recipients that claimed this round
+    #[error("Not claimed")]
+    NotClaimed,
+
+    /// The quantities recorded for the resource add up to more than a u64 holds
+    #[error("Quantity overflow")]
+    QuantityOverflow,
+
+    /// A unit counts at most MAX_UNIT_DECIMALS decimals
+    #[error("Invalid unit")]
+    InvalidUnit,
+
+    /// The unit can't change while recorded instances are counted in the old one
+    #[error("Unit in use")]
+    UnitInUse,
+
+    /// A round backed by tokens or lamports can't start over, nor the database be reset, while
+    /// deposits are neither paid out nor refunded
+    #[error("Refunds outstanding")]
+    RefundsOutstanding,
+
+    /// The resource's eligibility rule does not admit the recipient: not on the allow list, on
+    /// the deny list, or not trusted enough by the provider
+    #[error("Not eligible")]
+    NotEligible,
+
+    /// Unknown eligibility kind, or a trusted by provider rule without a provider, its trust
+    /// program or a minimum trust
+    #[error("Invalid eligibility rule")]
+    InvalidEligibilityRule,
+
+    /// Unknown allocation strategy
+    #[error("Invalid allocation strategy")]
+    InvalidStrategy,
+
+    /// The allocation strategy is picked before any recipient registers or contributor records
+    #[error("Allocation strategy in use")]
+    StrategyInUse,
+
+    /// Every slot up to the capacity is taken and the waitlist has no page, or its tail page
+    /// is full, to queue the intent on
+    #[error("Waitlist full")]
+    WaitlistFull,
+
+    /// The page is bound to another database, or already chained in the waitlist
+    #[error("Wrong waitlist page")]
+    WrongWaitlist,
+
+    /// A capacity takes at most MAX_NUM_RECIPIENTS intents, and no fewer than already hold a
+    /// slot
+    #[error("Invalid capacity")]
+    InvalidCapacity,
+
+    /// Setting up or resetting the database takes the signature of the authority it was
+    /// created or last reset with
+    #[error("Wrong authority")]
+    WrongAuthority,
+
+    /// A lottery is drawn once its committed slot passed and has a slot hash, and nothing is
+    /// claimed nor the distribution finalized before
+    #[error("Draw pending")]
+    DrawPending,
+
+    /// The round is not allocated by lottery, or its lottery was already drawn
+    #[error("Nothing to draw")]
+    NothingToDraw,
+
+    /// The recipient already waits for a slot, on one of the waitlist's pages
+    #[error("Already waiting")]
+    AlreadyWaiting,
 }
 impl From<ResourceError> for ProgramError {
     fn from(e: ResourceError) -> Self {
@@ -6,8 +6,27 @@ use crate::types::{
     INSTRUCTION_RECORD_CHALLENGE,
     INSTRUCTION_CLAIM_CHALLENGE,
     INSTRUCTION_RESET_DATABASE,
+    INSTRUCTION_FINALIZE_DISTRIBUTION,
+    INSTRUCTION_SET_QUORUM,
+    INSTRUCTION_START_NEW_ROUND,
+    INSTRUCTION_SET_ESCROW_MINT,
+    INSTRUCTION_SET_LAMPORT_VAULT,
+    INSTRUCTION_REFUND_CONTRIBUTION,
+    INSTRUCTION_ISSUE_RECEIPT,
+    INSTRUCTION_SET_UNIT,
+    INSTRUCTION_SET_ELIGIBILITY,
+    INSTRUCTION_SET_ALLOCATION_STRATEGY,
+    INSTRUCTION_SET_CAPACITY,
+    INSTRUCTION_ADD_WAITLIST_PAGE,
+    INSTRUCTION_WITHDRAW_INTENT,
+    INSTRUCTION_DRAW_LOTTERY,
+    PUBLIC_KEY_SIZE,
     ResourceInstance,
     Challenge,
+    QuorumRule,
+    UnitOfMeasure,
+    EligibilityRule,
+    Need,
 };
 use crate::error::ResourceError::InvalidInstruction;
 use borsh::{BorshDeserialize};
@@ -18,12 +37,48 @@ use std::mem::size_of;
 #[derive(Debug, PartialEq)]
 pub enum ResourceInstruction {
     Default(),
-    RegisterIntent(),
+    // the recipient is the second account and signs; without data they ask for an even share
+    // of the pool. Newcomers wait for the next round while distributing, on the waitlist whose
+    // pages all follow, and wait there once
+    RegisterIntent(Need),
+    // `from` signs: the second account for quantities only, the provider after the source and
+    // escrow for tokens, after the vault for lamports
     RecordResourceInstance(ResourceInstance),
-    InitiateDistribution(),
+    // the database, the authority signing, then the clock; seconds the challenges stay open. A
+    // lottery is only committed to a later slot, DrawLottery allocates it
+    InitiateDistribution(i64),
+    // the database, the clock, then `from`, who signs; about one of the pairs of recipients
+    // InitiateDistribution laid out
     RecordChallenge(Challenge),
     ClaimChallenge(Challenge),
-    ResetDatabase()
+    // the second account signs and becomes the authority, which the Set* instructions take as
+    // their second account too. Refused while a backed round still holds deposits; the bound
+    // history and the round number carry over
+    ResetDatabase(),
+    FinalizeDistribution(),
+    SetQuorum(QuorumRule),
+    StartNewRound(),
+    // the mint, zero to go back to recording quantities only
+    SetEscrowMint([u8; PUBLIC_KEY_SIZE]),
+    // the vault is the third account, leaving it out goes back to recording quantities only
+    SetLamportVault(),
+    // the contributor is the third account, or owns it for token backed resources
+    RefundContribution(),
+    // the recipient is the third account
+    IssueReceipt(),
+    SetUnit(UnitOfMeasure),
+    SetEligibility(EligibilityRule),
+    // one of the ALLOCATION_* strategies
+    SetAllocationStrategy(u8),
+    // intents taking part in a round, at most MAX_NUM_RECIPIENTS and zero for all of them
+    SetCapacity(u8),
+    // the page is the second account, the current tail the third
+    AddWaitlistPage(),
+    // the recipient is the second account and signs
+    WithdrawIntent(),
+    // the database, the clock, then the SlotHashes sysvar; anyone can draw once the committed
+    // slot passed
+    DrawLottery(),
 }
 
 impl ResourceInstruction {
@@ -33,8 +88,16 @@ impl ResourceInstruction {
             INSTRUCTION_DEFAULT => {
                 Self::Default()
             }
+            INSTRUCTION_REGISTER_INTENT if _rest.is_empty() => {
+                Self::RegisterIntent(Need::default())
+            }
             INSTRUCTION_REGISTER_INTENT => {
-                Self::RegisterIntent()
+                match Need::try_from_slice(_rest) {
+                    Ok(need) => Self::RegisterIntent(need),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
             }
             INSTRUCTION_RECORD_RESOURCE_INSTANCE => {
                 match ResourceInstance::try_from_slice(_rest) {
@@ -45,7 +108,12 @@ impl ResourceInstruction {
                 }
             }
             INSTRUCTION_INITIATE_DISTRIBUTION => {
-                Self::InitiateDistribution()
+                match i64::try_from_slice(_rest) {
+                    Ok(duration) => Self::InitiateDistribution(duration),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
             }
             INSTRUCTION_RECORD_CHALLENGE => {
                 match Challenge::try_from_slice(_rest) {
@@ -66,6 +134,78 @@ impl ResourceInstruction {
             INSTRUCTION_RESET_DATABASE => {
                 Self::ResetDatabase()
             }
+            INSTRUCTION_FINALIZE_DISTRIBUTION => {
+                Self::FinalizeDistribution()
+            }
+            INSTRUCTION_SET_QUORUM => {
+                match QuorumRule::try_from_slice(_rest) {
+                    Ok(quorum) => Self::SetQuorum(quorum),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
+            }
+            INSTRUCTION_START_NEW_ROUND => {
+                Self::StartNewRound()
+            }
+            INSTRUCTION_SET_ESCROW_MINT => {
+                match <[u8; PUBLIC_KEY_SIZE]>::try_from_slice(_rest) {
+                    Ok(mint) => Self::SetEscrowMint(mint),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
+            }
+            INSTRUCTION_SET_LAMPORT_VAULT => {
+                Self::SetLamportVault()
+            }
+            INSTRUCTION_REFUND_CONTRIBUTION => {
+                Self::RefundContribution()
+            }
+            INSTRUCTION_ISSUE_RECEIPT => {
+                Self::IssueReceipt()
+            }
+            INSTRUCTION_SET_UNIT => {
+                match UnitOfMeasure::try_from_slice(_rest) {
+                    Ok(unit) => Self::SetUnit(unit),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
+            }
+            INSTRUCTION_SET_ELIGIBILITY => {
+                match EligibilityRule::try_from_slice(_rest) {
+                    Ok(eligibility) => Self::SetEligibility(eligibility),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
+            }
+            INSTRUCTION_SET_ALLOCATION_STRATEGY => {
+                match u8::try_from_slice(_rest) {
+                    Ok(strategy) => Self::SetAllocationStrategy(strategy),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
+            }
+            INSTRUCTION_SET_CAPACITY => {
+                match u8::try_from_slice(_rest) {
+                    Ok(capacity) => Self::SetCapacity(capacity),
+                    Err(_err) => {
+                        return Err(ProgramError::InvalidInstructionData)
+                    }
+                }
+            }
+            INSTRUCTION_ADD_WAITLIST_PAGE => {
+                Self::AddWaitlistPage()
+            }
+            INSTRUCTION_WITHDRAW_INTENT => {
+                Self::WithdrawIntent()
+            }
+            INSTRUCTION_DRAW_LOTTERY => {
+                Self::DrawLottery()
+            }
             _ => return Err(InvalidInstruction.into()),
         })
     }
@@ -83,6 +223,7 @@ pub fn unpack<T>(input: &[u8]) -> Result<&T, ProgramError> {
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::types::MAX_UNIT_NAME_SIZE;
     use borsh::BorshSerialize;
     use solana_program::{pubkey::Pubkey};
 
@@ -101,8 +242,20 @@ mod test {
         data.push(INSTRUCTION_REGISTER_INTENT);
 
         let result = ResourceInstruction::unpack(&data).unwrap();
-        let expected = ResourceInstruction::RegisterIntent();
+        let expected = ResourceInstruction::RegisterIntent(Need::default());
         assert_eq!(expected, result);
+
+        let need = Need {
+            requested: 30,
+            household_size: 6,
+        };
+        data.append(&mut need.try_to_vec().unwrap());
+        let result = ResourceInstruction::unpack(&data).unwrap();
+        assert_eq!(ResourceInstruction::RegisterIntent(need), result);
+
+        // a need is read whole or not at all
+        let result = ResourceInstruction::unpack(&data[..data.len() - 1]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
     }
 
     #[test]
@@ -125,10 +278,111 @@ mod test {
     fn test_initiate_distribution() {
         let mut data = Vec::<u8>::new();
         data.push(INSTRUCTION_INITIATE_DISTRIBUTION);
+        data.append(&mut 3600i64.try_to_vec().unwrap());
 
         let result = ResourceInstruction::unpack(&data).unwrap();
-        let expected = ResourceInstruction::InitiateDistribution();
+        let expected = ResourceInstruction::InitiateDistribution(3600);
         assert_eq!(expected, result);
+
+        // the window length is required
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_INITIATE_DISTRIBUTION]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+    }
+
+    #[test]
+    fn test_finalize_distribution() {
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_FINALIZE_DISTRIBUTION]).unwrap();
+        assert_eq!(ResourceInstruction::FinalizeDistribution(), result);
+    }
+
+    #[test]
+    fn test_draw_lottery() {
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_DRAW_LOTTERY]).unwrap();
+        assert_eq!(ResourceInstruction::DrawLottery(), result);
+    }
+
+    #[test]
+    fn test_lamport_vault_instructions() {
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_SET_LAMPORT_VAULT]).unwrap();
+        assert_eq!(ResourceInstruction::SetLamportVault(), result);
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_REFUND_CONTRIBUTION]).unwrap();
+        assert_eq!(ResourceInstruction::RefundContribution(), result);
+    }
+
+    #[test]
+    fn test_issue_receipt() {
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_ISSUE_RECEIPT]).unwrap();
+        assert_eq!(ResourceInstruction::IssueReceipt(), result);
+    }
+
+    #[test]
+    fn test_set_unit() {
+        let mut name = [0u8; MAX_UNIT_NAME_SIZE];
+        name[..2].copy_from_slice(b"kg");
+        let unit = UnitOfMeasure {
+            name,
+            decimals: 1,
+        };
+        let mut data = vec![INSTRUCTION_SET_UNIT];
+        data.append(&mut unit.try_to_vec().unwrap());
+
+        let result = ResourceInstruction::unpack(&data).unwrap();
+        assert_eq!(ResourceInstruction::SetUnit(unit), result);
+
+        // the unit is required
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_SET_UNIT]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+    }
+
+    #[test]
+    fn test_set_eligibility() {
+        let mut list = [[0u8; PUBLIC_KEY_SIZE]; crate::types::MAX_ELIGIBILITY_LIST_SIZE];
+        list[0] = Pubkey::new_unique().to_bytes();
+        let eligibility = EligibilityRule {
+            kind: crate::types::ELIGIBILITY_DENY_LIST,
+            list,
+            ..EligibilityRule::default()
+        };
+        let mut data = vec![INSTRUCTION_SET_ELIGIBILITY];
+        data.append(&mut eligibility.try_to_vec().unwrap());
+
+        let result = ResourceInstruction::unpack(&data).unwrap();
+        assert_eq!(ResourceInstruction::SetEligibility(eligibility), result);
+
+        // the whole rule is required
+        let result = ResourceInstruction::unpack(&data[..data.len() - 1]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+    }
+
+    #[test]
+    fn test_set_allocation_strategy() {
+        let data = [INSTRUCTION_SET_ALLOCATION_STRATEGY, crate::types::ALLOCATION_MAX_MIN_FAIR];
+        let result = ResourceInstruction::unpack(&data).unwrap();
+        assert_eq!(ResourceInstruction::SetAllocationStrategy(crate::types::ALLOCATION_MAX_MIN_FAIR), result);
+
+        // the strategy is required
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_SET_ALLOCATION_STRATEGY]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+    }
+
+    #[test]
+    fn test_waitlist_instructions() {
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_SET_CAPACITY, 1]).unwrap();
+        assert_eq!(ResourceInstruction::SetCapacity(1), result);
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_ADD_WAITLIST_PAGE]).unwrap();
+        assert_eq!(ResourceInstruction::AddWaitlistPage(), result);
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_WITHDRAW_INTENT]).unwrap();
+        assert_eq!(ResourceInstruction::WithdrawIntent(), result);
+
+        // the capacity is required
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_SET_CAPACITY]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+    }
+
+    #[test]
+    fn test_start_new_round() {
+        let result = ResourceInstruction::unpack(&[INSTRUCTION_START_NEW_ROUND]).unwrap();
+        assert_eq!(ResourceInstruction::StartNewRound(), result);
     }
 
     #[test]
@@ -163,4 +417,42 @@ mod test {
         let expected = ResourceInstruction::ClaimChallenge(challenge);
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn test_set_quorum() {
+        let mut data = Vec::<u8>::new();
+        data.push(INSTRUCTION_SET_QUORUM);
+        let quorum = QuorumRule {
+            kind: crate::types::QUORUM_AT_LEAST,
+            threshold: 2,
+            trust_program: [0u8; 32],
+        };
+        data.append(&mut quorum.try_to_vec().unwrap());
+
+        let result = ResourceInstruction::unpack(&data).unwrap();
+        let expected = ResourceInstruction::SetQuorum(quorum);
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_set_escrow_mint() {
+        let mint = Pubkey::new_unique().to_bytes();
+        let mut data = vec![INSTRUCTION_SET_ESCROW_MINT];
+        data.extend_from_slice(&mint);
+
+        let result = ResourceInstruction::unpack(&data).unwrap();
+        assert_eq!(ResourceInstruction::SetEscrowMint(mint), result);
+
+        // the whole key is required
+        let result = ResourceInstruction::unpack(&data[..PUBLIC_KEY_SIZE]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+    }
+
+    proptest::proptest! {
+        // arbitrary bytes decode to an instruction or an error, never a panic
+        #[test]
+        fn test_unpack_arbitrary(data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..256)) {
+            let _ = ResourceInstruction::unpack(&data);
+        }
+    }
 }
@@ -1,15 +1,22 @@
 pub mod types;
 pub mod error;
 pub mod instruction;
+pub mod allocation;
 
 use solana_program::{
     account_info::{AccountInfo},
-    entrypoint,
+    clock::Clock,
     entrypoint::ProgramResult,
     info,
+    program::{invoke, invoke_signed},
+    program_pack::Pack,
     pubkey::Pubkey,
     program_error::ProgramError,
+    system_instruction,
+    system_program,
+    sysvar::{self, Sysvar},
 };
+use spl_token::{instruction::transfer, state::Account as TokenAccount};
 use borsh::{BorshSerialize, BorshDeserialize};
 use crate::{
     instruction::ResourceInstruction,
@@ -17,20 +24,384 @@ use crate::{
         NoResourceInstanceSpace,
         ResourceInDistribution,
         ResourceNotInDistribution,
+        DistributionClosed,
+        DistributionOpen,
+        NotARecipient,
+        AlreadyClaimed,
+        QuorumNotMet,
+        InvalidQuorumRule,
+        WrongRoundHistory,
+        WrongEscrow,
+        EscrowInUse,
+        NothingToRefund,
+        ReceiptAlreadyIssued,
+        NotClaimed,
+        QuantityOverflow,
+        InvalidUnit,
+        UnitInUse,
+        RefundsOutstanding,
+        NotEligible,
+        InvalidEligibilityRule,
+        InvalidStrategy,
+        StrategyInUse,
+        WaitlistFull,
+        WrongWaitlist,
+        InvalidCapacity,
+        WrongAuthority,
+        DrawPending,
+        NothingToDraw,
+        AlreadyWaiting,
     },
     types::{
+        ESCROW_SEED,
         PUBLIC_KEY_SIZE,
         MAX_NUM_RECIPIENTS,
         MAX_NUM_RESOURCE_INSTANCES,
         MAX_NUM_CHALLENGES,
+        DRAW_DELAY,
+        RESOURCE_DATABASE_SIZE,
+        ROUND_HISTORY_SIZE,
+        LAMPORT_VAULT_SIZE,
+        CLAIM_RECEIPT_SIZE,
+        WAITLIST_PAGE_SIZE,
         ResourceDatabase,
+        LamportVault,
+        ClaimReceipt,
+        RoundHistory,
+        RoundRecord,
         ResourceInstance,
         Challenge,
+        DistributionWindow,
+        QuorumRule,
+        UnitOfMeasure,
+        EligibilityRule,
+        Intent,
+        Draw,
+        Waitlist,
+        WaitlistPage,
+        SearchEngineAccount,
+        QUORUM_REPUTATION_WEIGHTED,
+        ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        ALLOCATION_PROPORTIONAL,
+        ALLOCATION_LOTTERY,
     },
 };
 
+// Instructions index into the account list, so a short list is an error rather than a panic
+fn account_at<'a, 'b>(accounts: &'a [AccountInfo<'b>], index: usize) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+// Setup instructions take the database's authority, signing, right after the database
+fn check_authority(accounts: &[AccountInfo], resource_database: &ResourceDatabase) -> ProgramResult {
+    let authority = account_at(accounts, 1)?;
+    if authority.key.to_bytes() != resource_database.authority {
+        return Err(ProgramError::from(WrongAuthority))
+    }
+    if !authority.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    Ok(())
+}
+
+// Signs for every escrow token account of the database; escrows are token accounts of the
+// database's mint owned by this address
+pub fn escrow_authority(program_id: &Pubkey, database: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[ESCROW_SEED, database.as_ref()], program_id)
+}
+
+fn check_token_account(account: &AccountInfo, mint: &[u8; PUBLIC_KEY_SIZE], owner: &Pubkey) -> ProgramResult {
+    if *account.owner != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    let token_account = TokenAccount::unpack(&account.try_borrow_data()?)?;
+    if token_account.mint.to_bytes() != *mint || token_account.owner != *owner {
+        return Err(ProgramError::from(WrongEscrow))
+    }
+    Ok(())
+}
+
+// Moves the instance's quantity from the provider's token account into escrow. Accounts after
+// the database: provider token account (w), escrow (w), provider (signer), token program.
+// A pool that only records quantities moves nothing, the contributor vouches for their
+// instance by signing as the second account.
+fn check_contributor(accounts: &[AccountInfo], instance: &ResourceInstance) -> ProgramResult {
+    let contributor = account_at(accounts, 1)?;
+    if contributor.key.to_bytes() != instance.from {
+        return Err(ProgramError::InvalidInstructionData)
+    }
+    if !contributor.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    Ok(())
+}
+
+fn deposit(program_id: &Pubkey, accounts: &[AccountInfo], resource_database: &ResourceDatabase, instance: &ResourceInstance) -> ProgramResult {
+    let database = account_at(accounts, 0)?;
+    let source = account_at(accounts, 1)?;
+    let escrow = account_at(accounts, 2)?;
+    let provider = account_at(accounts, 3)?;
+    let token_program = account_at(accounts, 4)?;
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    if provider.key.to_bytes() != instance.from {
+        return Err(ProgramError::InvalidInstructionData)
+    }
+    if !provider.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    let (authority, _) = escrow_authority(program_id, database.key);
+    check_token_account(escrow, &resource_database.escrow_mint, &authority)?;
+
+    invoke(
+        &transfer(token_program.key, source.key, escrow.key, provider.key, &[], instance.quantity)?,
+        &[source.clone(), escrow.clone(), provider.clone(), token_program.clone()],
+    )
+}
+
+// whose token account it is, refunds go to a contributor's token account
+fn token_account_owner(account: &AccountInfo) -> Result<[u8; PUBLIC_KEY_SIZE], ProgramError> {
+    if *account.owner != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    Ok(TokenAccount::unpack(&account.try_borrow_data()?)?.owner.to_bytes())
+}
+
+// Moves `amount` out of escrow into the recipient's token account, a claimant's allocation or a
+// contributor's refund. Accounts after the database: escrow (w), recipient token account (w),
+// escrow authority, token program.
+fn pay_out(program_id: &Pubkey, accounts: &[AccountInfo], resource_database: &ResourceDatabase, recipient: &[u8; PUBLIC_KEY_SIZE], amount: u64) -> ProgramResult {
+    let database = account_at(accounts, 0)?;
+    let escrow = account_at(accounts, 1)?;
+    let destination = account_at(accounts, 2)?;
+    let authority = account_at(accounts, 3)?;
+    let token_program = account_at(accounts, 4)?;
+    if *token_program.key != spl_token::id() {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    let (authority_key, bump) = escrow_authority(program_id, database.key);
+    if *authority.key != authority_key {
+        return Err(ProgramError::from(WrongEscrow))
+    }
+    check_token_account(escrow, &resource_database.escrow_mint, &authority_key)?;
+    check_token_account(destination, &resource_database.escrow_mint, &Pubkey::new(recipient))?;
+
+    invoke_signed(
+        &transfer(token_program.key, escrow.key, destination.key, &authority_key, &[], amount)?,
+        &[escrow.clone(), destination.clone(), authority.clone(), token_program.clone()],
+        &[&[ESCROW_SEED, database.key.as_ref(), &[bump]]],
+    )
+}
+
+fn check_vault(vault: &AccountInfo, resource_database: &ResourceDatabase) -> ProgramResult {
+    if vault.key.to_bytes() != resource_database.vault {
+        return Err(ProgramError::from(WrongEscrow))
+    }
+    Ok(())
+}
+
+// The vault is owned by this program, so it can be debited without a signature
+fn move_lamports(from: &AccountInfo, to: &AccountInfo, amount: u64) -> ProgramResult {
+    if from.lamports() < amount {
+        return Err(ProgramError::InsufficientFunds)
+    }
+    **from.try_borrow_mut_lamports()? -= amount;
+    **to.try_borrow_mut_lamports()? += amount;
+    Ok(())
+}
+
+// Moves the instance's quantity in lamports from the provider into the vault. Accounts after
+// the database: vault (w), provider (signer, w), system program.
+fn deposit_lamports(accounts: &[AccountInfo], resource_database: &ResourceDatabase, instance: &ResourceInstance) -> ProgramResult {
+    let vault = account_at(accounts, 1)?;
+    let provider = account_at(accounts, 2)?;
+    let system_program_info = account_at(accounts, 3)?;
+    if *system_program_info.key != system_program::id() {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    if provider.key.to_bytes() != instance.from {
+        return Err(ProgramError::InvalidInstructionData)
+    }
+    if !provider.is_signer {
+        return Err(ProgramError::MissingRequiredSignature)
+    }
+    check_vault(vault, resource_database)?;
+
+    invoke(
+        &system_instruction::transfer(provider.key, vault.key, instance.quantity),
+        &[provider.clone(), vault.clone(), system_program_info.clone()],
+    )
+}
+
+// Pays the claimant's allocation out of the vault. Accounts after the database: vault (w),
+// claimant (w).
+fn pay_out_lamports(accounts: &[AccountInfo], resource_database: &ResourceDatabase, claimant: &[u8; PUBLIC_KEY_SIZE], amount: u64) -> ProgramResult {
+    let vault = account_at(accounts, 1)?;
+    let destination = account_at(accounts, 2)?;
+    check_vault(vault, resource_database)?;
+    if destination.key.to_bytes() != *claimant {
+        return Err(ProgramError::InvalidInstructionData)
+    }
+    move_lamports(vault, destination, amount)
+}
+
+// Claims only draw on what this round's pool holds, whatever else the escrow or vault keeps
+fn check_pool(resource_database: &ResourceDatabase, allocation: u64) -> ProgramResult {
+    let deposited = resource_database.deposited().ok_or(QuantityOverflow)?;
+    let paid_out = resource_database.paid_out().ok_or(QuantityOverflow)?;
+    if deposited.saturating_sub(paid_out) < allocation {
+        return Err(ProgramError::InsufficientFunds)
+    }
+    Ok(())
+}
+
+// Where the first slot at or after a lottery's committed slot stands in the SlotHashes sysvar
+enum SlotHash {
+    // no slot that late has a hash yet
+    Pending,
+    Found(u64, [u8; PUBLIC_KEY_SIZE]),
+    // the oldest entry kept is later than the committed slot, the one wanted may have left
+    Expired,
+}
+
+// Read in place as the sysvar is too large to deserialize whole: a u64 count, then (slot, hash)
+// entries newest first. Slots without a block have no entry, so the hash of the first slot at or
+// after `committed_slot` is only known once an older entry, or the committed slot itself, is seen.
+fn slot_hash_from(slot_hashes: &AccountInfo, committed_slot: u64) -> Result<SlotHash, ProgramError> {
+    if *slot_hashes.key != sysvar::slot_hashes::id() {
+        return Err(ProgramError::InvalidArgument)
+    }
+    let data = slot_hashes.try_borrow_data()?;
+    let mut count = [0u8; 8];
+    count.copy_from_slice(data.get(..8).ok_or(ProgramError::InvalidAccountData)?);
+    let entry_size = 8 + PUBLIC_KEY_SIZE;
+    let mut found = SlotHash::Pending;
+    for index in 0..u64::from_le_bytes(count) as usize {
+        let entry = data.get(8 + index * entry_size..8 + (index + 1) * entry_size).ok_or(ProgramError::InvalidAccountData)?;
+        let mut slot = [0u8; 8];
+        slot.copy_from_slice(&entry[..8]);
+        let slot = u64::from_le_bytes(slot);
+        if slot < committed_slot {
+            return Ok(found)
+        }
+        let mut hash = [0u8; PUBLIC_KEY_SIZE];
+        hash.copy_from_slice(&entry[8..]);
+        if slot == committed_slot {
+            return Ok(SlotHash::Found(slot, hash))
+        }
+        found = SlotHash::Found(slot, hash);
+    }
+    match found {
+        SlotHash::Pending => Ok(SlotHash::Pending),
+        _ => Ok(SlotHash::Expired),
+    }
+}
+
+// Waitlist pages follow the accounts an instruction otherwise takes, found by key as only the
+// database knows which ones are at the head and the tail
+fn waitlist_page<'a, 'b>(program_id: &Pubkey, accounts: &'a [AccountInfo<'b>], key: &[u8; PUBLIC_KEY_SIZE]) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    let page = accounts.iter()
+        .find(|account| account.key.to_bytes() == *key)
+        .ok_or(ProgramError::NotEnoughAccountKeys)?;
+    if page.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    Ok(page)
+}
+
+// Queues the intent on the waitlist's tail page, a full tail needs AddWaitlistPage first
+fn enqueue(program_id: &Pubkey, accounts: &[AccountInfo], waitlist: &mut Waitlist, intent: Intent) -> ProgramResult {
+    if !waitlist.has_pages() {
+        return Err(ProgramError::from(WaitlistFull))
+    }
+    let page_info = waitlist_page(program_id, accounts, &waitlist.tail)?;
+    let mut page_account_data = page_info.try_borrow_mut_data()?;
+    let mut page = WaitlistPage::try_from_slice(&page_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+    page.push(intent).ok_or(WaitlistFull)?;
+    waitlist.waiting = waitlist.waiting.saturating_add(1);
+    page_account_data.copy_from_slice(&page.try_to_vec().unwrap());
+    Ok(())
+}
+
+// Whether the recipient already waits on the waitlist. Every page of the chain follows the
+// accounts the instruction otherwise takes.
+fn is_waiting(program_id: &Pubkey, accounts: &[AccountInfo], waitlist: &Waitlist, recipient: &[u8; PUBLIC_KEY_SIZE]) -> Result<bool, ProgramError> {
+    if waitlist.waiting == 0 {
+        return Ok(false)
+    }
+    let mut key = waitlist.head;
+    // the chain can't be longer than the accounts passed
+    for _ in 0..accounts.len() {
+        let page_info = waitlist_page(program_id, accounts, &key)?;
+        let page = WaitlistPage::try_from_slice(&page_info.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)?;
+        if page.intents.iter().any(|intent| intent.recipient == *recipient) {
+            return Ok(true)
+        }
+        if key == waitlist.tail {
+            return Ok(false)
+        }
+        key = page.next;
+    }
+    Err(ProgramError::NotEnoughAccountKeys)
+}
+
+// Takes the longest waiting intent off the head page. Drained pages leave the chain, so the
+// next page becomes the head, and AddWaitlistPage can chain them again.
+fn dequeue(program_id: &Pubkey, accounts: &[AccountInfo], waitlist: &mut Waitlist) -> Result<Intent, ProgramError> {
+    loop {
+        let page_info = waitlist_page(program_id, accounts, &waitlist.head)?;
+        let mut page_account_data = page_info.try_borrow_mut_data()?;
+        let mut page = WaitlistPage::try_from_slice(&page_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+        let promoted = page.pop();
+        let is_drained = page.is_empty() && waitlist.head != waitlist.tail;
+        if is_drained {
+            waitlist.head = page.next;
+            page.next = [0u8; PUBLIC_KEY_SIZE];
+        }
+        page_account_data.copy_from_slice(&page.try_to_vec().unwrap());
+        match promoted {
+            Some(intent) => {
+                waitlist.waiting = waitlist.waiting.saturating_sub(1);
+                return Ok(intent)
+            }
+            None if is_drained => continue,
+            None => return Err(ProgramError::InvalidAccountData),
+        }
+    }
+}
+
+// Fills the free slots up to the capacity with the longest waiting intents. Once they are taken,
+// the `replaceable` recipients give up their slot in order, one for each intent promoted. A
+// recipient who got a slot some other way in the meantime drops their waiting intent.
+fn promote(program_id: &Pubkey, accounts: &[AccountInfo], resource_database: &mut ResourceDatabase, replaceable: &[[u8; PUBLIC_KEY_SIZE]]) -> ProgramResult {
+    let mut replaceable = replaceable.iter();
+    while resource_database.waitlist.waiting > 0 {
+        if resource_database.open_slot().is_none() && replaceable.len() == 0 {
+            break
+        }
+        let intent = dequeue(program_id, accounts, &mut resource_database.waitlist)?;
+        if resource_database.intent(&intent.recipient).is_some() {
+            continue
+        }
+        // the capacity is never below the registered intents, freeing a slot opens one
+        if resource_database.open_slot().is_none() {
+            let replaced = replaceable.next()
+                .and_then(|recipient| resource_database.intent(recipient))
+                .ok_or(ProgramError::InvalidAccountData)?;
+            resource_database.remove_intent(replaced);
+        }
+        let index = resource_database.open_slot().ok_or(ProgramError::InvalidAccountData)?;
+        info!("promoting waiting intent");
+        resource_database.intents[index] = intent;
+    }
+    Ok(())
+}
+
+// The database and the round history are boxed as they are read, each is larger than the
+// program's stack frame.
 fn _process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
@@ -39,40 +410,114 @@ fn _process_instruction(
         ResourceInstruction::Default() => {
             info!("OK")
         }
-        ResourceInstruction::RegisterIntent() => {
+        ResourceInstruction::RegisterIntent(need) => {
             info!("recording intent");
-            let mut database_account_data = accounts[0].try_borrow_mut_data().unwrap();
-            let mut resource_database = ResourceDatabase::try_from_slice(&database_account_data).unwrap();
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database = Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
 
             let empty_address = [0u8; PUBLIC_KEY_SIZE];
-            if accounts.len() != 2 || empty_address == accounts[1].key.to_bytes() {
+            // the provider's search engine account follows the intent for trusted by provider
+            // rules, the waitlist's pages come last once every slot is taken
+            if accounts.len() < 2 {
+                return Err(ProgramError::InvalidInstructionData)
+            }
+            let recipient = account_at(accounts, 1)?;
+            let intent_key_bytes = recipient.key.to_bytes();
+            if empty_address == intent_key_bytes {
                 return Err(ProgramError::InvalidInstructionData)
             }
+            // only the recipient registers or updates their need
+            if !recipient.is_signer {
+                return Err(ProgramError::MissingRequiredSignature)
+            }
 
-            for key in resource_database.intents.iter_mut() {
-                if *key == accounts[1].key.to_bytes() {
-                    info!("intent already exists");
-                    return Ok(())
+            let eligibility = resource_database.eligibility;
+            let mut trust = 0;
+            if eligibility.kind == ELIGIBILITY_TRUSTED_BY_PROVIDER {
+                let provider = account_at(accounts, 2)?;
+                if provider.key.to_bytes() != eligibility.provider {
+                    return Err(ProgramError::InvalidAccountData)
                 }
-                if key == &empty_address {
-                    key.copy_from_slice(&accounts[1].key.to_bytes());
+                if provider.owner.to_bytes() != eligibility.trust_program {
+                    return Err(ProgramError::IncorrectProgramId)
+                }
+                let provider_account = SearchEngineAccount::try_from_slice(&provider.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)?;
+                trust = provider_account.trust_table.iter()
+                    .filter(|entry| entry.to == intent_key_bytes)
+                    .map(|entry| entry.value)
+                    .max()
+                    .unwrap_or(0);
+            }
+            if !eligibility.admits(&intent_key_bytes, trust) {
+                info!("recipient not eligible");
+                return Err(ProgramError::from(NotEligible))
+            }
+
+            // registering again updates the need, until the pool is allocated
+            if let Some(index) = resource_database.intent(&intent_key_bytes) {
+                info!("intent already exists");
+                if !resource_database.is_distributed {
+                    resource_database.intents[index].need = need;
                     database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
-                    return Ok(())
+                }
+                return Ok(())
+            }
+
+            let intent = Intent {
+                recipient: intent_key_bytes,
+                need,
+                allocation: 0,
+            };
+            // nobody takes a free slot ahead of those already waiting for one, and the round's
+            // recipients are settled once distributing, newcomers wait for the next one
+            match resource_database.open_slot() {
+                Some(index) if resource_database.waitlist.waiting == 0 && !resource_database.is_distributed => {
+                    resource_database.intents[index] = intent;
+                }
+                _ => {
+                    // a waiting intent keeps its need and its place
+                    if is_waiting(program_id, accounts, &resource_database.waitlist, &intent_key_bytes)? {
+                        return Err(ProgramError::from(AlreadyWaiting))
+                    }
+                    info!("waitlisting intent");
+                    enqueue(program_id, accounts, &mut resource_database.waitlist, intent)?;
                 }
             }
-            return Err(ProgramError::InvalidInstructionData)
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
         }
         ResourceInstruction::RecordResourceInstance(resource_instance) => {
             info!("recording resource instance");
-            let mut database_account_data = accounts[0].try_borrow_mut_data().unwrap();
-            let mut resource_database =  ResourceDatabase::try_from_slice(&database_account_data).unwrap();
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
 
             if resource_database.is_distributed {
                 return Err(ProgramError::from(ResourceInDistribution))
             }
+            // the round's total has to stay countable, it backs the average and every payout
+            resource_database.deposited()
+                .and_then(|deposited| deposited.checked_add(resource_instance.quantity))
+                .ok_or(QuantityOverflow)?;
+
+            // token backed resources only count what was deposited, a full database rolls it back
+            if resource_database.is_escrowed() {
+                deposit(program_id, accounts, &resource_database, &resource_instance)?;
+            }
+            if resource_database.has_vault() {
+                deposit_lamports(accounts, &resource_database, &resource_instance)?;
+            }
+            if !resource_database.is_escrowed() && !resource_database.has_vault() {
+                check_contributor(accounts, &resource_instance)?;
+            }
 
+            // a contributor's instances pool into their slot, the total fits so their part does too
             let empty_address = [0u8; PUBLIC_KEY_SIZE];
             for instance in resource_database.instances.iter_mut() {
+                if instance.from == resource_instance.from && instance.from != empty_address {
+                    instance.quantity += resource_instance.quantity;
+                    database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+                    return Ok(())
+                }
                 if instance.from == empty_address {
                     *instance = resource_instance;
                     database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
@@ -81,46 +526,57 @@ fn _process_instruction(
             }
             return Err(ProgramError::from(NoResourceInstanceSpace));
         }
-        ResourceInstruction::InitiateDistribution() => {
+        ResourceInstruction::InitiateDistribution(duration) => {
             info!("initiating distribution");
-            let mut database_account_data = accounts[0].try_borrow_mut_data().unwrap();
-            let mut resource_database =  ResourceDatabase::try_from_slice(&database_account_data).unwrap();
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            // the authority picks when the round opens and how long its window stays open
+            check_authority(accounts, &resource_database)?;
+            let clock = Clock::from_account_info(account_at(accounts, 2)?)?;
 
             // nothing to do, already in distribution
             if resource_database.is_distributed {
                 return Err(ProgramError::from(ResourceInDistribution))
             }
 
-            // flip state so that challenges can be accepted
+            // a zero window closes right away, every challenge is then denied on finalize
+            if duration < 0 {
+                return Err(ProgramError::InvalidInstructionData)
+            }
+
+            // flip state so that challenges can be accepted until the window closes
             resource_database.is_distributed = true;
+            resource_database.window = DistributionWindow {
+                start_slot: clock.slot,
+                start_timestamp: clock.unix_timestamp,
+                duration,
+            };
 
-            // calculate distribution
-            let mut quantity_sum = 0;
-            let mut quantity_count = 0;
-            let empty_address = [0u8; PUBLIC_KEY_SIZE];
-            for instance in resource_database.instances.iter() {
-                if instance.from == empty_address {
-                    break;
-                }
-                quantity_sum += instance.quantity;
-                quantity_count += 1;
-            }
-            let mut final_quantity = 0;
-            if quantity_sum > 0 {
-                final_quantity = quantity_sum / quantity_count;
+            // the pool is allocated by need, what is left unallocated goes back to the
+            // contributors with what is not claimed. A lottery is committed to a slot whose hash
+            // nobody knows yet and DrawLottery allocates it once that slot passed
+            if resource_database.strategy == ALLOCATION_LOTTERY {
+                resource_database.draw = Draw {
+                    committed_slot: clock.slot.saturating_add(DRAW_DELAY),
+                    ..Draw::default()
+                };
+            } else {
+                resource_database.draw = Draw::default();
+                let pool = resource_database.deposited().ok_or(QuantityOverflow)?;
+                resource_database.allocate(pool);
             }
-            resource_database.final_quantity = final_quantity;
 
-            // initialize challenges
+            // initialize challenges, between the registered intents packed at the front
+            let registered = resource_database.intents.iter().filter(|intent| intent.is_registered()).count();
             let mut challenge_index = 0;
-            for from_index in 0..resource_database.intents.len() {
-                for to_index in 0..resource_database.intents.len() {
+            for from_index in 0..registered {
+                for to_index in 0..registered {
                     if from_index == to_index {
                         continue
                     }
                     resource_database.challenges[challenge_index] = Challenge{
-                        from: resource_database.intents[from_index],
-                        to: resource_database.intents[to_index],
+                        from: resource_database.intents[from_index].recipient,
+                        to: resource_database.intents[to_index].recipient,
                         value: false,
                     };
                     challenge_index += 1;
@@ -133,110 +589,832 @@ fn _process_instruction(
         }
         ResourceInstruction::RecordChallenge(challenge) => {
             info!("approving challenge");
-            let mut database_account_data = accounts[0].try_borrow_mut_data().unwrap();
-            let mut resource_database =  ResourceDatabase::try_from_slice(&database_account_data).unwrap();
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+
+            let clock = Clock::from_account_info(account_at(accounts, 1)?)?;
 
             // nothing to do, already in distribution
             if !resource_database.is_distributed {
                 return Err(ProgramError::from(ResourceNotInDistribution))
             }
 
-            // TODO data validation
-            let empty_address = [0u8; PUBLIC_KEY_SIZE];
-            if challenge.from == empty_address || challenge.to == empty_address {
+            if resource_database.is_finalized || resource_database.window.is_closed(clock.unix_timestamp) {
+                return Err(ProgramError::from(DistributionClosed))
+            }
+
+            // the approver signs their own answer, about another recipient of the round
+            let approver = account_at(accounts, 2)?;
+            if approver.key.to_bytes() != challenge.from || challenge.from == challenge.to {
                 return Err(ProgramError::InvalidInstructionData)
             }
+            if !approver.is_signer {
+                return Err(ProgramError::MissingRequiredSignature)
+            }
+            if resource_database.intent(&challenge.from).is_none() || resource_database.intent(&challenge.to).is_none() {
+                return Err(ProgramError::from(NotARecipient))
+            }
 
-            let mut found = false;
-            for existing_challenge in resource_database.challenges.iter_mut() {
-                if existing_challenge.from == challenge.from && existing_challenge.to == challenge.to {
-                    *existing_challenge = challenge;
-                    found = true;
-                    break;
+            // InitiateDistribution laid out a slot for every ordered pair of the round's recipients
+            let existing_challenge = resource_database.challenges.iter_mut()
+                .find(|existing_challenge| existing_challenge.from == challenge.from && existing_challenge.to == challenge.to)
+                .ok_or(NotARecipient)?;
+            *existing_challenge = challenge;
+
+            // save data
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::ClaimChallenge(challenge) => {
+            info!("claiming");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if !resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceNotInDistribution))
+            }
+            if !resource_database.is_allocated() {
+                return Err(ProgramError::from(DrawPending))
+            }
+
+            // the claimant is the challenge's `from`, `to` is not used
+            let empty_address = [0u8; PUBLIC_KEY_SIZE];
+            let claimant = challenge.from;
+            if resource_database.intent(&claimant).is_none() {
+                return Err(ProgramError::from(NotARecipient))
+            }
+            if resource_database.claims.contains(&claimant) {
+                return Err(ProgramError::from(AlreadyClaimed))
+            }
+
+            // what is left of a backed pool is refunded once the distribution is finalized
+            let is_backed = resource_database.is_escrowed() || resource_database.has_vault();
+            if is_backed && resource_database.is_finalized {
+                return Err(ProgramError::from(DistributionClosed))
+            }
+
+            // escrowed claims pass the token accounts or the vault first, the approvers follow them
+            let approvers_start = if resource_database.is_escrowed() {
+                5
+            } else if resource_database.has_vault() {
+                3
+            } else {
+                1
+            };
+
+            // every other recipient was challenged to vouch for the claimant on distribution
+            let mut peers: u32 = 0;
+            let mut approvals: u32 = 0;
+            let mut weight: u32 = 0;
+            // only answers of this round's recipients count
+            for existing_challenge in resource_database.challenges.iter() {
+                if existing_challenge.to != claimant || resource_database.intent(&existing_challenge.from).is_none() {
+                    continue
                 }
-                if existing_challenge.from == empty_address {
-                    *existing_challenge = challenge;
-                    found = true;
-                    break
+                peers += 1;
+                if !existing_challenge.value {
+                    continue
+                }
+                approvals += 1;
+
+                // approvers' search engine accounts follow the database, each counts once
+                if !resource_database.quorum.is_weighted() {
+                    continue
+                }
+                let approver = accounts.iter().skip(approvers_start).find(|account| account.key.to_bytes() == existing_challenge.from);
+                if let Some(approver) = approver {
+                    if approver.owner.to_bytes() != resource_database.quorum.trust_program {
+                        return Err(ProgramError::IncorrectProgramId)
+                    }
+                    let approver_account = SearchEngineAccount::try_from_slice(&approver.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)?;
+                    if resource_database.quorum.kind == QUORUM_REPUTATION_WEIGHTED {
+                        weight = weight.saturating_add(approver_account.reputation.score());
+                        continue
+                    }
+                    for entry in approver_account.trust_table.iter() {
+                        if entry.to == claimant {
+                            weight += u32::from(entry.value);
+                        }
+                    }
                 }
             }
-            // TODO (I think we can filter out errors based on allowed addresses in the intents)
-            if !found {
-                return Err(ProgramError::InvalidInstructionData)
+            if !resource_database.quorum.is_met(peers, approvals, weight) {
+                return Err(ProgramError::from(QuorumNotMet))
+            }
+            let allocation = resource_database.allocation(&claimant);
+            if is_backed && allocation > 0 {
+                check_pool(&resource_database, allocation)?;
+            }
+            if resource_database.is_escrowed() && allocation > 0 {
+                pay_out(program_id, accounts, &resource_database, &claimant, allocation)?;
+            }
+            if resource_database.has_vault() && allocation > 0 {
+                pay_out_lamports(accounts, &resource_database, &claimant, allocation)?;
             }
 
-            // save data
+            // one slot per recipient, so there is always room
+            for claim in resource_database.claims.iter_mut() {
+                if *claim == empty_address {
+                    *claim = claimant;
+                    break
+                }
+            }
             database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
             return Ok(())
         }
-        ResourceInstruction::ClaimChallenge(_challenge) => {
-            info!("would claim challenge");
+        ResourceInstruction::SetQuorum(quorum) => {
+            info!("setting quorum");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
+
+            // the rule can't change under recipients that are already collecting approvals
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            if !quorum.is_valid() {
+                return Err(ProgramError::from(InvalidQuorumRule))
+            }
+
+            resource_database.quorum = quorum;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
         }
-        ResourceInstruction::ResetDatabase() => {
-            let mut database_account_data = accounts[0].try_borrow_mut_data().unwrap();
-            let resource_database = ResourceDatabase {
-                is_distributed: false,
-                final_quantity: 0,
-                intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
-                instances: [ResourceInstance{
-                    from: [0u8; PUBLIC_KEY_SIZE],
-                    quantity: 0,
-                }; MAX_NUM_RESOURCE_INSTANCES],
-                challenges: [Challenge{
-                    from: [0u8; PUBLIC_KEY_SIZE],
-                    to: [0u8; PUBLIC_KEY_SIZE],
-                    value: false,
-                }; MAX_NUM_CHALLENGES],
-                claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
-            };
+        ResourceInstruction::SetEscrowMint(mint) => {
+            info!("setting escrow mint");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
+
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            // deposits of the old mint would no longer match the escrow
+            let empty_address = [0u8; PUBLIC_KEY_SIZE];
+            if resource_database.instances.iter().any(|instance| instance.from != empty_address) {
+                return Err(ProgramError::from(EscrowInUse))
+            }
+
+            if mint != empty_address && resource_database.has_vault() {
+                return Err(ProgramError::from(EscrowInUse))
+            }
+
+            resource_database.escrow_mint = mint;
             database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
             return Ok(())
         }
-    }
-    Ok(())
-}
+        ResourceInstruction::SetLamportVault() => {
+            info!("setting lamport vault");
+            let database_info = account_at(accounts, 0)?;
+            let mut database_account_data = database_info.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
 
-entrypoint!(process_instruction);
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            // lamports already deposited would be stranded in the old vault
+            let empty_address = [0u8; PUBLIC_KEY_SIZE];
+            if resource_database.instances.iter().any(|instance| instance.from != empty_address) || resource_database.is_escrowed() {
+                return Err(ProgramError::from(EscrowInUse))
+            }
 
-fn process_instruction(
-    program_id: &Pubkey,
-    accounts: &[AccountInfo],
-    instruction_data: &[u8],
-) -> ProgramResult {
-    info!("🚜resource🚜");
+            let mut vault_key = empty_address;
+            if let Some(vault_info) = accounts.get(2) {
+                if vault_info.owner != program_id {
+                    return Err(ProgramError::IncorrectProgramId)
+                }
+                if vault_info.key == database_info.key {
+                    return Err(ProgramError::from(WrongEscrow))
+                }
+                let mut vault_account_data = vault_info.try_borrow_mut_data()?;
+                if vault_account_data.len() != LAMPORT_VAULT_SIZE {
+                    return Err(ProgramError::InvalidAccountData)
+                }
+                let mut vault = LamportVault::try_from_slice(&vault_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+                let database_key = database_info.key.to_bytes();
+                if vault.database == empty_address {
+                    vault.database = database_key;
+                } else if vault.database != database_key {
+                    return Err(ProgramError::from(WrongEscrow))
+                }
+                vault_account_data.copy_from_slice(&vault.try_to_vec().unwrap());
+                vault_key = vault_info.key.to_bytes();
+            }
 
-    if let Err(error) = _process_instruction(program_id, accounts, instruction_data) {
-        // error.print::<ResourceError>();
-        info!(&error.to_string());
-        return Err(error);
-    }
-    Ok(())
-}
+            resource_database.vault = vault_key;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::RefundContribution() => {
+            info!("refunding contribution");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            let vault = account_at(accounts, 1)?;
+            let contributor = account_at(accounts, 2)?;
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use solana_program::{
-        clock::Epoch,
-        program_error::ProgramError,
-    };
-    use crate::{
-        error::ResourceError,
-        types::{
-            INSTRUCTION_REGISTER_INTENT,
-            INSTRUCTION_RECORD_RESOURCE_INSTANCE,
-            INSTRUCTION_INITIATE_DISTRIBUTION,
-            INSTRUCTION_RECORD_CHALLENGE,
-            MAX_NUM_RESOURCE_INSTANCES,
-            MAX_NUM_RECIPIENTS,
-            MAX_NUM_CHALLENGES,
-            RESOURCE_DATABASE_SIZE,
-            ResourceInstance,
-            Challenge,
+            if !resource_database.is_escrowed() && !resource_database.has_vault() {
+                return Err(ProgramError::from(WrongEscrow))
+            }
+            if resource_database.has_vault() {
+                check_vault(vault, &resource_database)?;
+            }
+            // claims close on finalize, so the remainder can't change anymore
+            if !resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceNotInDistribution))
+            }
+            if !resource_database.is_finalized {
+                return Err(ProgramError::from(DistributionOpen))
+            }
+
+            // anyone can send a contributor their refund, it can only go to them
+            let contributor_key = if resource_database.is_escrowed() {
+                token_account_owner(contributor)?
+            } else {
+                contributor.key.to_bytes()
+            };
+            let refund = resource_database.refund(&contributor_key).ok_or(QuantityOverflow)?;
+            let index = match resource_database.contributor(&contributor_key) {
+                Some(index) if !resource_database.refunded[index] => index,
+                _ => return Err(ProgramError::from(NothingToRefund)),
+            };
+            if refund > 0 && resource_database.is_escrowed() {
+                pay_out(program_id, accounts, &resource_database, &contributor_key, refund)?;
+            }
+            if refund > 0 && resource_database.has_vault() {
+                move_lamports(vault, contributor, refund)?;
+            }
+            resource_database.refunded[index] = true;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
         }
-    };
+        ResourceInstruction::IssueReceipt() => {
+            info!("issuing receipt");
+            let database_info = account_at(accounts, 0)?;
+            let receipt_info = account_at(accounts, 1)?;
+            let recipient = account_at(accounts, 2)?;
+            let mut database_account_data = database_info.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
 
-    #[test]
+            if !resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceNotInDistribution))
+            }
+            // anyone can issue a recipient's receipt, it always names them
+            let recipient_key = recipient.key.to_bytes();
+            let index = resource_database.claims.iter()
+                .position(|claim| *claim != [0u8; PUBLIC_KEY_SIZE] && *claim == recipient_key)
+                .ok_or(NotClaimed)?;
+            if resource_database.receipts[index] {
+                return Err(ProgramError::from(ReceiptAlreadyIssued))
+            }
+
+            if receipt_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId)
+            }
+            let mut receipt_account_data = receipt_info.try_borrow_mut_data()?;
+            if receipt_account_data.len() != CLAIM_RECEIPT_SIZE {
+                return Err(ProgramError::InvalidAccountData)
+            }
+            let receipt = ClaimReceipt::try_from_slice(&receipt_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            if receipt.is_issued() {
+                return Err(ProgramError::from(ReceiptAlreadyIssued))
+            }
+
+            let receipt = ClaimReceipt {
+                database: database_info.key.to_bytes(),
+                recipient: recipient_key,
+                round: resource_database.round,
+                quantity: resource_database.allocation(&recipient_key),
+                unit: resource_database.unit,
+            };
+            resource_database.receipts[index] = true;
+            receipt_account_data.copy_from_slice(&receipt.try_to_vec().unwrap());
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::SetUnit(unit) => {
+            info!("setting unit");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
+
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            // recorded quantities would silently change meaning
+            let empty_address = [0u8; PUBLIC_KEY_SIZE];
+            if resource_database.instances.iter().any(|instance| instance.from != empty_address) {
+                return Err(ProgramError::from(UnitInUse))
+            }
+            if !unit.is_valid() {
+                return Err(ProgramError::from(InvalidUnit))
+            }
+
+            resource_database.unit = unit;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::SetEligibility(eligibility) => {
+            info!("setting eligibility");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
+
+            // like the quorum, the rule is settled before challenges go out
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            if !eligibility.is_valid() {
+                return Err(ProgramError::from(InvalidEligibilityRule))
+            }
+
+            resource_database.eligibility = eligibility;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::SetAllocationStrategy(strategy) => {
+            info!("setting allocation strategy");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
+
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            // picked when the resource is set up, recipients and contributors join knowing it
+            let empty_address = [0u8; PUBLIC_KEY_SIZE];
+            if resource_database.intents.iter().any(|intent| intent.is_registered())
+                || resource_database.waitlist.waiting > 0
+                || resource_database.instances.iter().any(|instance| instance.from != empty_address) {
+                return Err(ProgramError::from(StrategyInUse))
+            }
+            if !allocation::is_valid(strategy) {
+                return Err(ProgramError::from(InvalidStrategy))
+            }
+
+            resource_database.strategy = strategy;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::SetCapacity(capacity) => {
+            info!("setting capacity");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            check_authority(accounts, &resource_database)?;
+
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            // recipients holding a slot keep it
+            let registered = resource_database.intents.iter().filter(|intent| intent.is_registered()).count();
+            if usize::from(capacity) > MAX_NUM_RECIPIENTS || (capacity > 0 && usize::from(capacity) < registered) {
+                return Err(ProgramError::from(InvalidCapacity))
+            }
+
+            // a larger capacity takes in the longest waiting intents, their pages follow the database
+            resource_database.capacity = capacity;
+            promote(program_id, accounts, &mut resource_database, &[])?;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::AddWaitlistPage() => {
+            info!("adding waitlist page");
+            let database_info = account_at(accounts, 0)?;
+            let page_info = account_at(accounts, 1)?;
+            let mut database_account_data = database_info.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+
+            if page_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId)
+            }
+            if page_info.key == database_info.key {
+                return Err(ProgramError::from(WrongWaitlist))
+            }
+            let mut page_account_data = page_info.try_borrow_mut_data()?;
+            if page_account_data.len() != WAITLIST_PAGE_SIZE {
+                return Err(ProgramError::InvalidAccountData)
+            }
+            let mut page = WaitlistPage::try_from_slice(&page_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+
+            // a new page, or one of the database's drained out of the chain: pages in the chain
+            // are the tail or point to the next one
+            let empty_address = [0u8; PUBLIC_KEY_SIZE];
+            let database_key = database_info.key.to_bytes();
+            let page_key = page_info.key.to_bytes();
+            if page.database == empty_address {
+                page.database = database_key;
+            } else if page.database != database_key || page.next != empty_address
+                || page_key == resource_database.waitlist.tail || !page.is_empty() {
+                return Err(ProgramError::from(WrongWaitlist))
+            }
+
+            // anyone can grow the waitlist, the page goes after the tail
+            let waitlist = &mut resource_database.waitlist;
+            if waitlist.has_pages() {
+                let tail_info = waitlist_page(program_id, accounts, &waitlist.tail)?;
+                let mut tail_account_data = tail_info.try_borrow_mut_data()?;
+                let mut tail = WaitlistPage::try_from_slice(&tail_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+                tail.next = page_key;
+                tail_account_data.copy_from_slice(&tail.try_to_vec().unwrap());
+            } else {
+                waitlist.head = page_key;
+            }
+            waitlist.tail = page_key;
+
+            page_account_data.copy_from_slice(&page.try_to_vec().unwrap());
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::WithdrawIntent() => {
+            info!("withdrawing intent");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            let recipient = account_at(accounts, 1)?;
+
+            // allocations and challenges are settled for the round once distributing
+            if resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceInDistribution))
+            }
+            if !recipient.is_signer {
+                return Err(ProgramError::MissingRequiredSignature)
+            }
+            let index = resource_database.intent(&recipient.key.to_bytes()).ok_or(NotARecipient)?;
+
+            // the slot goes to the longest waiting intent, its pages follow the recipient
+            resource_database.remove_intent(index);
+            promote(program_id, accounts, &mut resource_database, &[])?;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::DrawLottery() => {
+            info!("drawing lottery");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            let clock = Clock::from_account_info(account_at(accounts, 1)?)?;
+
+            if !resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceNotInDistribution))
+            }
+            if resource_database.is_allocated() {
+                return Err(ProgramError::from(NothingToDraw))
+            }
+            // the committed slot's own hash is only in the sysvar of a later slot
+            let committed_slot = resource_database.draw.committed_slot;
+            if clock.slot <= committed_slot {
+                return Err(ProgramError::from(DrawPending))
+            }
+
+            match slot_hash_from(account_at(accounts, 2)?, committed_slot)? {
+                SlotHash::Pending => return Err(ProgramError::from(DrawPending)),
+                // nobody drew while the slot was kept, so the lottery is committed again
+                SlotHash::Expired => {
+                    info!("committing lottery to a later slot");
+                    resource_database.draw.committed_slot = clock.slot.saturating_add(DRAW_DELAY);
+                }
+                SlotHash::Found(slot, hash) => {
+                    resource_database.draw.slot = slot;
+                    resource_database.draw.hash = hash;
+                    let pool = resource_database.deposited().ok_or(QuantityOverflow)?;
+                    resource_database.allocate(pool);
+                }
+            }
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::FinalizeDistribution() => {
+            info!("finalizing distribution");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            let clock = Clock::from_account_info(account_at(accounts, 1)?)?;
+
+            if !resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceNotInDistribution))
+            }
+            if resource_database.is_finalized {
+                return Err(ProgramError::from(DistributionClosed))
+            }
+            if !resource_database.window.is_closed(clock.unix_timestamp) {
+                return Err(ProgramError::from(DistributionOpen))
+            }
+            // an undrawn lottery would refund the whole pool, so it is drawn first
+            if !resource_database.is_allocated() {
+                return Err(ProgramError::from(DrawPending))
+            }
+
+            // anyone can settle once time is up, challenges not approved by then stay denied
+            resource_database.is_finalized = true;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::StartNewRound() => {
+            info!("starting new round");
+            let database_info = account_at(accounts, 0)?;
+            let history_info = account_at(accounts, 1)?;
+            let mut database_account_data = database_info.try_borrow_mut_data()?;
+            let mut resource_database =  Box::<ResourceDatabase>::try_from_slice(&database_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+
+            // only a settled round is archived, so its claims and approvals are final
+            if !resource_database.is_distributed {
+                return Err(ProgramError::from(ResourceNotInDistribution))
+            }
+            if !resource_database.is_finalized {
+                return Err(ProgramError::from(DistributionOpen))
+            }
+            // what was left of a backed pool goes back to its contributors before the round closes
+            let is_backed = resource_database.is_escrowed() || resource_database.has_vault();
+            if is_backed && resource_database.has_outstanding_refunds().ok_or(QuantityOverflow)? {
+                return Err(ProgramError::from(RefundsOutstanding))
+            }
+
+            if history_info.owner != program_id {
+                return Err(ProgramError::IncorrectProgramId)
+            }
+            let mut history_account_data = history_info.try_borrow_mut_data()?;
+            if history_account_data.len() != ROUND_HISTORY_SIZE {
+                return Err(ProgramError::InvalidAccountData)
+            }
+            let mut history = Box::<RoundHistory>::try_from_slice(&history_account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            // the first round binds the database and its history to each other, for good
+            let history_key = history_info.key.to_bytes();
+            if resource_database.history == [0u8; PUBLIC_KEY_SIZE] {
+                resource_database.history = history_key;
+            } else if resource_database.history != history_key {
+                return Err(ProgramError::from(WrongRoundHistory))
+            }
+            let database_key = database_info.key.to_bytes();
+            if history.database == [0u8; PUBLIC_KEY_SIZE] {
+                history.database = database_key;
+            } else if history.database != database_key {
+                return Err(ProgramError::from(WrongRoundHistory))
+            }
+
+            history.archive(RoundRecord {
+                round: resource_database.round,
+                window: resource_database.window,
+                final_quantity: resource_database.final_quantity,
+                intents: resource_database.intents,
+                claims: resource_database.claims,
+                challenges: resource_database.challenges,
+                unit: resource_database.unit,
+                strategy: resource_database.strategy,
+                draw: resource_database.draw,
+            });
+
+            // a recipient who did not claim gives their slot to the longest waiting intent
+            let unclaimed: Vec<[u8; PUBLIC_KEY_SIZE]> = resource_database.intents.iter()
+                .filter(|intent| intent.is_registered() && !resource_database.claims.contains(&intent.recipient))
+                .map(|intent| intent.recipient)
+                .collect();
+
+            // recipients with their needs, the quorum, the unit, the eligibility rule, the capacity
+            // and the waitlist carry over, everything about the old round starts over
+            resource_database.is_distributed = false;
+            resource_database.final_quantity = 0;
+            for intent in resource_database.intents.iter_mut() {
+                intent.allocation = 0;
+            }
+            resource_database.instances = [ResourceInstance::default(); MAX_NUM_RESOURCE_INSTANCES];
+            resource_database.challenges = [Challenge::default(); MAX_NUM_CHALLENGES];
+            resource_database.claims = [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS];
+            resource_database.window = DistributionWindow::default();
+            resource_database.is_finalized = false;
+            resource_database.draw = Draw::default();
+            resource_database.refunded = [false; MAX_NUM_RESOURCE_INSTANCES];
+            resource_database.receipts = [false; MAX_NUM_RECIPIENTS];
+            resource_database.round = resource_database.round.saturating_add(1);
+            // waitlist pages follow the history
+            promote(program_id, accounts, &mut resource_database, &unclaimed)?;
+
+            // written in place, the growing buffer serializing the whole history takes would not
+            // fit the program's heap next to the database's
+            history.serialize(&mut &mut history_account_data[..]).map_err(|_| ProgramError::AccountDataTooSmall)?;
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+        ResourceInstruction::ResetDatabase() => {
+            info!("resetting database");
+            let mut database_account_data = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            if database_account_data.len() != RESOURCE_DATABASE_SIZE {
+                return Err(ProgramError::InvalidAccountData)
+            }
+            // a fresh account has no authority yet, whoever signs the first reset becomes it
+            let authority = account_at(accounts, 1)?;
+            let existing = Box::<ResourceDatabase>::try_from_slice(&database_account_data).ok();
+            if let Some(existing) = &existing {
+                if existing.authority != [0u8; PUBLIC_KEY_SIZE] && existing.authority != authority.key.to_bytes() {
+                    return Err(ProgramError::from(WrongAuthority))
+                }
+            }
+            if !authority.is_signer {
+                return Err(ProgramError::MissingRequiredSignature)
+            }
+            // the escrow or vault would keep deposits no claim or refund could reach any more
+            if let Some(existing) = &existing {
+                if existing.has_outstanding_deposits().ok_or(QuantityOverflow)? {
+                    return Err(ProgramError::from(RefundsOutstanding))
+                }
+            }
+            // the history keeps archiving this database's rounds, numbered on from the last one so
+            // the newer records stay reachable; a fresh account has none
+            let (history, round) = existing.map(|database| (database.history, database.round)).unwrap_or_default();
+            let resource_database = Box::new(ResourceDatabase {
+                is_distributed: false,
+                final_quantity: 0,
+                intents: [Intent::default(); MAX_NUM_RECIPIENTS],
+                instances: [ResourceInstance{
+                    from: [0u8; PUBLIC_KEY_SIZE],
+                    quantity: 0,
+                }; MAX_NUM_RESOURCE_INSTANCES],
+                challenges: [Challenge{
+                    from: [0u8; PUBLIC_KEY_SIZE],
+                    to: [0u8; PUBLIC_KEY_SIZE],
+                    value: false,
+                }; MAX_NUM_CHALLENGES],
+                claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+                window: DistributionWindow::default(),
+                is_finalized: false,
+                quorum: QuorumRule::default(),
+                round,
+                escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+                vault: [0u8; PUBLIC_KEY_SIZE],
+                refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+                receipts: [false; MAX_NUM_RECIPIENTS],
+                unit: UnitOfMeasure::default(),
+                eligibility: EligibilityRule::default(),
+                strategy: ALLOCATION_PROPORTIONAL,
+                draw: Draw::default(),
+                capacity: 0,
+                waitlist: Waitlist::default(),
+                history,
+                authority: authority.key.to_bytes(),
+            });
+            database_account_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+            return Ok(())
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
+
+pub fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    instruction_data: &[u8],
+) -> ProgramResult {
+    info!("🚜resource🚜");
+
+    if let Err(error) = _process_instruction(program_id, accounts, instruction_data) {
+        // error.print::<ResourceError>();
+        info!(&error.to_string());
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_program::{
+        clock::Epoch,
+        program_error::ProgramError,
+        sysvar,
+    };
+    use crate::{
+        error::ResourceError,
+        types::{
+            INSTRUCTION_REGISTER_INTENT,
+            INSTRUCTION_RECORD_RESOURCE_INSTANCE,
+            INSTRUCTION_INITIATE_DISTRIBUTION,
+            INSTRUCTION_RECORD_CHALLENGE,
+            INSTRUCTION_RESET_DATABASE,
+            INSTRUCTION_FINALIZE_DISTRIBUTION,
+            INSTRUCTION_CLAIM_CHALLENGE,
+            INSTRUCTION_SET_QUORUM,
+            INSTRUCTION_START_NEW_ROUND,
+            INSTRUCTION_SET_ESCROW_MINT,
+            INSTRUCTION_SET_LAMPORT_VAULT,
+            INSTRUCTION_REFUND_CONTRIBUTION,
+            INSTRUCTION_ISSUE_RECEIPT,
+            INSTRUCTION_SET_UNIT,
+            INSTRUCTION_SET_ELIGIBILITY,
+            INSTRUCTION_SET_ALLOCATION_STRATEGY,
+            INSTRUCTION_SET_CAPACITY,
+            INSTRUCTION_ADD_WAITLIST_PAGE,
+            INSTRUCTION_WITHDRAW_INTENT,
+            INSTRUCTION_DRAW_LOTTERY,
+            MAX_UNIT_DECIMALS,
+            MAX_FRIENDLY_NAME_SIZE,
+            MAX_NUM_ROUNDS,
+            MAX_NUM_INTENTS,
+            MAX_TRUST_TABLE_SIZE,
+            MAX_NUM_RESOURCE_INSTANCES,
+            MAX_NUM_RECIPIENTS,
+            MAX_NUM_CHALLENGES,
+            ResourceInstance,
+            Challenge,
+            TrustTableEntry,
+            QUORUM_ALL,
+            QUORUM_MAJORITY,
+            QUORUM_AT_LEAST,
+            QUORUM_TRUST_WEIGHTED,
+            ELIGIBILITY_OPEN,
+            ELIGIBILITY_ALLOW_LIST,
+            ELIGIBILITY_DENY_LIST,
+            ALLOCATION_EQUAL_SPLIT,
+            ALLOCATION_FIRST_COME_FIRST_SERVED,
+            ALLOCATION_LOTTERY,
+            Reputation,
+            Intent,
+            Need,
+        }
+    };
+
+    // a recipient who declared no need
+    fn intent(recipient: &Pubkey) -> Intent {
+        Intent {
+            recipient: recipient.to_bytes(),
+            ..Intent::default()
+        }
+    }
+
+    // the first slots hold the given intents, the rest are free
+    fn intents(registered: &[Intent]) -> [Intent; MAX_NUM_RECIPIENTS] {
+        let mut intents = [Intent::default(); MAX_NUM_RECIPIENTS];
+        intents[..registered.len()].copy_from_slice(registered);
+        intents
+    }
+
+    fn registered(resource_database: &ResourceDatabase) -> Vec<[u8; PUBLIC_KEY_SIZE]> {
+        resource_database.intents.iter().filter(|intent| intent.is_registered()).map(|intent| intent.recipient).collect()
+    }
+
+    // serialized clock sysvar at the given time
+    fn clock_data_at(unix_timestamp: i64) -> Vec<u8> {
+        let key = sysvar::clock::id();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; Clock::size_of()];
+        {
+            let mut account = AccountInfo::new(&key, false, false, &mut lamports, &mut data, &owner, false, Epoch::default());
+            let clock = Clock {
+                slot: unix_timestamp as u64,
+                unix_timestamp,
+                ..Clock::default()
+            };
+            clock.to_account_info(&mut account).unwrap();
+        }
+        data
+    }
+
+    // signs the setup instructions of every database the tests build
+    fn authority_key() -> Pubkey {
+        Pubkey::new(&[7u8; PUBLIC_KEY_SIZE])
+    }
+
+    // the authority's account
+    fn authority<'a>() -> AccountInfo<'a> {
+        signer(authority_key())
+    }
+
+    // a signing account without data, leaked to live as long as the test
+    fn signer<'a>(key: Pubkey) -> AccountInfo<'a> {
+        AccountInfo::new(
+            Box::leak(Box::new(key)),
+            true,
+            false,
+            Box::leak(Box::new(0)),
+            Box::leak(Vec::new().into_boxed_slice()),
+            Box::leak(Box::new(Pubkey::default())),
+            false,
+            Epoch::default(),
+        )
+    }
+
+    // SlotHashes sysvar data as the runtime lays it out, newest entry first
+    fn serialized_slot_hashes(entries: &[(u64, [u8; PUBLIC_KEY_SIZE])]) -> Vec<u8> {
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for (slot, hash) in entries.iter() {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(hash);
+        }
+        data
+    }
+
+    fn initiate_distribution_data(duration: i64) -> Vec<u8> {
+        let mut instruction_data = vec![INSTRUCTION_INITIATE_DISTRIBUTION];
+        instruction_data.extend(duration.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    fn challenge_data(challenge: &Challenge) -> Vec<u8> {
+        let mut instruction_data = vec![INSTRUCTION_RECORD_CHALLENGE];
+        instruction_data.extend(challenge.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    #[test]
     fn test_missing_instruction_data() {
         let program_id = Pubkey::default();
         let key = Pubkey::default();
@@ -257,7 +1435,7 @@ mod test {
 
         let instruction_data: Vec<u8> = Vec::new();
         let result = process_instruction(&program_id, &accounts, &instruction_data);
-        let _result = match result {
+        match result {
             Ok(_ok) => assert_eq!(true, false, "process_instruction should have triggered error"),
             Err(err) => assert_eq!(
                 ProgramError::from(ResourceError::InvalidInstruction),
@@ -277,7 +1455,7 @@ mod test {
         let resource_database = ResourceDatabase {
             is_distributed: false,
             final_quantity: 0,
-            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            intents: [Intent::default(); MAX_NUM_RECIPIENTS],
             instances: [ResourceInstance{
                 from: [0u8; PUBLIC_KEY_SIZE],
                 quantity: 0,
@@ -288,6 +1466,22 @@ mod test {
                 value: false,
             }; MAX_NUM_CHALLENGES],
             claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            window: DistributionWindow::default(),
+            is_finalized: false,
+            quorum: QuorumRule::default(),
+            round: 0,
+            escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+            vault: [0u8; PUBLIC_KEY_SIZE],
+            refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+            receipts: [false; MAX_NUM_RECIPIENTS],
+            unit: UnitOfMeasure::default(),
+            eligibility: EligibilityRule::default(),
+            strategy: ALLOCATION_PROPORTIONAL,
+            draw: Draw::default(),
+            capacity: 0,
+            waitlist: Waitlist::default(),
+            history: [0u8; PUBLIC_KEY_SIZE],
+            authority: authority_key().to_bytes(),
         };
         database_data.copy_from_slice(&resource_database.try_to_vec().unwrap());
         let mut database_lamports = 0;
@@ -306,7 +1500,7 @@ mod test {
         let mut recipient_lamports = 0;
         let recipient_account = AccountInfo::new(
             &key,
-            false,
+            true,
             true,
             &mut recipient_lamports,
             &mut recipient_data,
@@ -316,13 +1510,86 @@ mod test {
         );
         let accounts = vec![database_account, recipient_account];
 
-        let mut instruction_data: Vec<u8> = Vec::new();
-        instruction_data.push(INSTRUCTION_REGISTER_INTENT);
+        let instruction_data: Vec<u8> = vec![INSTRUCTION_REGISTER_INTENT];
+
+        // only the recipient can register
+        let unsigned = AccountInfo { is_signer: false, ..accounts[1].clone() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), unsigned], &instruction_data);
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
 
         let result = process_instruction(&program_id, &accounts, &instruction_data);
         assert_eq!(result.unwrap(), ());
         let resource_database = ResourceDatabase::try_from_slice(&database_data).unwrap();
-        assert_eq!(resource_database.intents[0], key.to_bytes());
+        assert_eq!(resource_database.intents[0].recipient, key.to_bytes());
+    }
+
+    fn register_intent_data(requested: u64, household_size: u16) -> Vec<u8> {
+        let mut instruction_data = vec![INSTRUCTION_REGISTER_INTENT];
+        instruction_data.extend(Need { requested, household_size }.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    #[test]
+    fn test_need_based_allocation() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut alice_lamports = 0;
+        let mut alice_data = vec![];
+        let alice_info = AccountInfo::new(&alice, true, false, &mut alice_lamports, &mut alice_data, &owner, false, Epoch::default());
+        let mut bob_lamports = 0;
+        let mut bob_data = vec![];
+        let bob_info = AccountInfo::new(&bob, true, false, &mut bob_lamports, &mut bob_data, &owner, false, Epoch::default());
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(0);
+        let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        // registering again updates the need
+        process_instruction(&program_id, &[database.clone(), alice_info.clone()], &register_intent_data(2, 1)).unwrap();
+        process_instruction(&program_id, &[database.clone(), alice_info.clone()], &register_intent_data(6, 6)).unwrap();
+        process_instruction(&program_id, &[database.clone(), bob_info.clone()], &register_intent_data(2, 0)).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone()], &[INSTRUCTION_REGISTER_INTENT, 2]);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.intents[0].need, Need { requested: 6, household_size: 6 });
+        assert_eq!(resource_database.intents[1].need.household(), 1);
+
+        // 4 for 8 requested, everyone gets half
+        record_instance(&program_id, &database, &Pubkey::new_unique(), 4).unwrap();
+        process_instruction(&program_id, &[database.clone(), authority(), clock.clone()], &initiate_distribution_data(60)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.allocation(&alice.to_bytes()), 3);
+        assert_eq!(resource_database.allocation(&bob.to_bytes()), 1);
+        assert_eq!(resource_database.final_quantity, 4);
+
+        // the allocations are settled, the need can't change anymore
+        process_instruction(&program_id, &[database.clone(), bob_info.clone()], &register_intent_data(4, 0)).unwrap();
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.intents[1].need.requested, 2);
+
+        // a larger pool allocates no more than was requested, the rest is left for the contributors
+        resource_database.allocate(20);
+        assert_eq!(resource_database.allocation(&alice.to_bytes()), 6);
+        assert_eq!(resource_database.allocation(&bob.to_bytes()), 2);
+        assert_eq!(resource_database.final_quantity, 8);
+
+        // without a quantity alice asks for her household's share, three of the four people
+        resource_database.intents[0].need = Need { requested: 0, household_size: 3 };
+        resource_database.allocate(8);
+        assert_eq!(resource_database.allocation(&alice.to_bytes()), 6);
+        assert_eq!(resource_database.allocation(&bob.to_bytes()), 2);
+
+        // nobody to allocate to
+        resource_database.intents = [Intent::default(); MAX_NUM_RECIPIENTS];
+        resource_database.allocate(8);
+        assert_eq!(resource_database.final_quantity, 0);
     }
 
     #[test]
@@ -335,7 +1602,7 @@ mod test {
         let resource_database = ResourceDatabase {
             is_distributed: false,
             final_quantity: 0,
-            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            intents: [Intent::default(); MAX_NUM_RECIPIENTS],
             instances: [ResourceInstance{
                 from: [0u8; PUBLIC_KEY_SIZE],
                 quantity: 0,
@@ -346,6 +1613,22 @@ mod test {
                 value: false,
             }; MAX_NUM_CHALLENGES],
             claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            window: DistributionWindow::default(),
+            is_finalized: false,
+            quorum: QuorumRule::default(),
+            round: 0,
+            escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+            vault: [0u8; PUBLIC_KEY_SIZE],
+            refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+            receipts: [false; MAX_NUM_RECIPIENTS],
+            unit: UnitOfMeasure::default(),
+            eligibility: EligibilityRule::default(),
+            strategy: ALLOCATION_PROPORTIONAL,
+            draw: Draw::default(),
+            capacity: 0,
+            waitlist: Waitlist::default(),
+            history: [0u8; PUBLIC_KEY_SIZE],
+            authority: authority_key().to_bytes(),
         };
         data.copy_from_slice(&resource_database.try_to_vec().unwrap());
 
@@ -360,16 +1643,26 @@ mod test {
             false,
             Epoch::default(),
         );
-        let accounts = vec![account];
+        let contributor = Pubkey::new_unique();
+        let accounts = [account, signer(contributor)];
 
         let mut instruction_data: Vec<u8> = Vec::new();
         instruction_data.push(INSTRUCTION_RECORD_RESOURCE_INSTANCE);
         let resource_instance = ResourceInstance {
-            from:  Pubkey::new_unique().to_bytes(),
+            from:  contributor.to_bytes(),
             quantity: 5,
         };
         instruction_data.append(&mut resource_instance.try_to_vec().unwrap());
 
+        // the contributor signs for what they record
+        let result = process_instruction(&program_id, &accounts[..1], &instruction_data);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let unsigned = AccountInfo { is_signer: false, ..accounts[1].clone() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), unsigned], &instruction_data);
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        let result = process_instruction(&program_id, &[accounts[0].clone(), signer(Pubkey::new_unique())], &instruction_data);
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+
         let result = process_instruction(&program_id, &accounts, &instruction_data);
         assert_eq!(result.unwrap(), ());
         let resource_database = ResourceDatabase::try_from_slice(&data).unwrap();
@@ -383,10 +1676,11 @@ mod test {
         let mut lamports = 0;
         let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
 
+        // a single recipient is allocated the whole pool
         let resource_database = ResourceDatabase {
             is_distributed: false,
             final_quantity: 0,
-            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            intents: intents(&[intent(&Pubkey::new_unique())]),
             instances: [ResourceInstance{
                 from: [0u8; PUBLIC_KEY_SIZE],
                 quantity: 0,
@@ -397,6 +1691,22 @@ mod test {
                 value: false,
             }; MAX_NUM_CHALLENGES],
             claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            window: DistributionWindow::default(),
+            is_finalized: false,
+            quorum: QuorumRule::default(),
+            round: 0,
+            escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+            vault: [0u8; PUBLIC_KEY_SIZE],
+            refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+            receipts: [false; MAX_NUM_RECIPIENTS],
+            unit: UnitOfMeasure::default(),
+            eligibility: EligibilityRule::default(),
+            strategy: ALLOCATION_PROPORTIONAL,
+            draw: Draw::default(),
+            capacity: 0,
+            waitlist: Waitlist::default(),
+            history: [0u8; PUBLIC_KEY_SIZE],
+            authority: authority_key().to_bytes(),
         };
         data.copy_from_slice(&resource_database.try_to_vec().unwrap());
 
@@ -411,27 +1721,71 @@ mod test {
             false,
             Epoch::default(),
         );
-        let accounts = vec![account];
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(100);
+        let clock_account = AccountInfo::new(
+            &clock_key,
+            false,
+            false,
+            &mut clock_lamports,
+            &mut clock_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let accounts = [account, clock_account];
 
         // add a resource ...
         let mut record_resource_instance_instruction_data: Vec<u8> = Vec::new();
         record_resource_instance_instruction_data.push(INSTRUCTION_RECORD_RESOURCE_INSTANCE);
+        let contributor = Pubkey::new_unique();
         let resource_instance = ResourceInstance {
-            from:  Pubkey::new_unique().to_bytes(),
+            from:  contributor.to_bytes(),
             quantity: 5,
         };
         record_resource_instance_instruction_data.append(&mut resource_instance.try_to_vec().unwrap());
-        let _result = process_instruction(&program_id, &accounts, &record_resource_instance_instruction_data);
-
-        // ... then initiate distribution
-        let mut instruction_data: Vec<u8> = Vec::new();
-        instruction_data.push(INSTRUCTION_INITIATE_DISTRIBUTION);
+        let _result = process_instruction(&program_id, &[accounts[0].clone(), signer(contributor)], &record_resource_instance_instruction_data);
 
-        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        // ... then initiate distribution, which only the authority can open
+        let unsigned = AccountInfo { is_signer: false, ..authority() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), unsigned, accounts[1].clone()], &initiate_distribution_data(0));
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        let result = process_instruction(&program_id, &[accounts[0].clone(), signer(Pubkey::new_unique()), accounts[1].clone()], &initiate_distribution_data(0));
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongAuthority)), result);
+        let result = process_instruction(&program_id, &[accounts[0].clone(), authority(), accounts[1].clone()], &initiate_distribution_data(3600));
         assert_eq!(result.unwrap(), ());
         let resource_database = ResourceDatabase::try_from_slice(&data).unwrap();
-        assert_eq!(resource_database.is_distributed, true);
+        assert!(resource_database.is_distributed);
         assert_eq!(resource_database.final_quantity, resource_instance.quantity);
+        assert_eq!(resource_database.window, DistributionWindow {
+            start_slot: 100,
+            start_timestamp: 100,
+            duration: 3600,
+        });
+        assert_eq!(resource_database.window.deadline(), 3700);
+    }
+
+    #[test]
+    fn test_initiate_resource_distribution_negative_duration() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let clock_key = sysvar::clock::id();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(100);
+        let accounts = [
+            AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default()),
+            AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default()),
+        ];
+        process_instruction(&program_id, &[accounts[0].clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        let result = process_instruction(&program_id, &[accounts[0].clone(), authority(), accounts[1].clone()], &initiate_distribution_data(-1));
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+        let resource_database = ResourceDatabase::try_from_slice(&data).unwrap();
+        assert!(!resource_database.is_distributed);
     }
 
     #[test]
@@ -440,22 +1794,45 @@ mod test {
         let key = Pubkey::default();
         let mut lamports = 0;
         let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let carol = Pubkey::new_unique();
+        // the pairs InitiateDistribution laid out, carol registered after it
+        let mut challenges = [Challenge::default(); MAX_NUM_CHALLENGES];
+        challenges[0] = Challenge { from: alice.to_bytes(), to: bob.to_bytes(), value: false };
+        challenges[1] = Challenge { from: bob.to_bytes(), to: alice.to_bytes(), value: false };
 
         let resource_database = ResourceDatabase {
             // set to true so we don't need a bunch of other setup
             is_distributed: true,
             final_quantity: 0,
-            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            intents: intents(&[intent(&alice), intent(&bob), intent(&carol)]),
             instances: [ResourceInstance{
                 from: [0u8; PUBLIC_KEY_SIZE],
                 quantity: 0,
             }; MAX_NUM_RESOURCE_INSTANCES],
-            challenges: [Challenge{
-                from: [0u8; PUBLIC_KEY_SIZE],
-                to: [0u8; PUBLIC_KEY_SIZE],
-                value: false,
-            }; MAX_NUM_CHALLENGES],
+            challenges,
             claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            window: DistributionWindow {
+                start_slot: 0,
+                start_timestamp: 0,
+                duration: 3600,
+            },
+            is_finalized: false,
+            quorum: QuorumRule::default(),
+            round: 0,
+            escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+            vault: [0u8; PUBLIC_KEY_SIZE],
+            refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+            receipts: [false; MAX_NUM_RECIPIENTS],
+            unit: UnitOfMeasure::default(),
+            eligibility: EligibilityRule::default(),
+            strategy: ALLOCATION_PROPORTIONAL,
+            draw: Draw::default(),
+            capacity: 0,
+            waitlist: Waitlist::default(),
+            history: [0u8; PUBLIC_KEY_SIZE],
+            authority: authority_key().to_bytes(),
         };
         data.copy_from_slice(&resource_database.try_to_vec().unwrap());
 
@@ -470,21 +1847,1854 @@ mod test {
             false,
             Epoch::default(),
         );
-        let accounts = vec![account];
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(100);
+        let clock_account = AccountInfo::new(
+            &clock_key,
+            false,
+            false,
+            &mut clock_lamports,
+            &mut clock_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let accounts = vec![account, clock_account];
 
-        let mut instruction_data: Vec<u8> = Vec::new();
-        instruction_data.push(INSTRUCTION_RECORD_CHALLENGE);
         let challenge = Challenge {
-            from: Pubkey::new_unique().to_bytes(),
-            to: Pubkey::new_unique().to_bytes(),
+            from: alice.to_bytes(),
+            to: bob.to_bytes(),
             value: true,
         };
-        instruction_data.append(&mut challenge.try_to_vec().unwrap());
-        let result = process_instruction(&program_id, &accounts, &instruction_data);
+
+        // only alice can answer for herself
+        let result = process_instruction(&program_id, &accounts, &challenge_data(&challenge));
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), signer(bob)], &challenge_data(&challenge));
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+        let unsigned = AccountInfo { is_signer: false, ..signer(alice) };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), unsigned], &challenge_data(&challenge));
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        let to_herself = Challenge { to: alice.to_bytes(), ..challenge };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), signer(alice)], &challenge_data(&to_herself));
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+
+        // about a recipient of the round, and from one
+        let stranger = Pubkey::new_unique();
+        let about_stranger = Challenge { to: stranger.to_bytes(), ..challenge };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), signer(alice)], &challenge_data(&about_stranger));
+        assert_eq!(Err(ProgramError::from(ResourceError::NotARecipient)), result);
+        let from_stranger = Challenge { from: stranger.to_bytes(), ..challenge };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), signer(stranger)], &challenge_data(&from_stranger));
+        assert_eq!(Err(ProgramError::from(ResourceError::NotARecipient)), result);
+
+        // only about the pairs laid out when the round opened
+        let about_carol = Challenge { to: carol.to_bytes(), ..challenge };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), signer(alice)], &challenge_data(&about_carol));
+        assert_eq!(Err(ProgramError::from(ResourceError::NotARecipient)), result);
+
+        let result = process_instruction(&program_id, &[accounts[0].clone(), accounts[1].clone(), signer(alice)], &challenge_data(&challenge));
         assert_eq!(result.unwrap(), ());
         let resource_database = ResourceDatabase::try_from_slice(&data).unwrap();
         assert_eq!(resource_database.challenges[0].from, challenge.from);
         assert_eq!(resource_database.challenges[0].to, challenge.to);
-        assert_eq!(resource_database.challenges[0].value, true);
+        assert!(resource_database.challenges[0].value);
+        assert_eq!(resource_database.challenges[1], challenges[1]);
+        assert_eq!(resource_database.challenges[2], Challenge::default());
+    }
+
+    #[test]
+    fn test_challenge_deadline_and_finalize() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let clock_key = sysvar::clock::id();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(100);
+        let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+        let accounts = vec![database.clone(), clock.clone()];
+        process_instruction(&program_id, &[accounts[0].clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        // nothing to finalize before the distribution starts
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_FINALIZE_DISTRIBUTION]);
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceNotInDistribution)), result);
+
+        let (alice, bob) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        resource_database.intents = intents(&[intent(&alice), intent(&bob)]);
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        process_instruction(&program_id, &[accounts[0].clone(), authority(), accounts[1].clone()], &initiate_distribution_data(60)).unwrap();
+        let challenge = Challenge {
+            from: alice.to_bytes(),
+            to: bob.to_bytes(),
+            value: true,
+        };
+        process_instruction(&program_id, &[database.clone(), clock.clone(), signer(alice)], &challenge_data(&challenge)).unwrap();
+
+        // the window is still open one second before the deadline
+        clock.data.borrow_mut().copy_from_slice(&clock_data_at(159));
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_FINALIZE_DISTRIBUTION]);
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionOpen)), result);
+
+        // at the deadline challenges are rejected and anyone can finalize
+        clock.data.borrow_mut().copy_from_slice(&clock_data_at(160));
+        let late_challenge = Challenge {
+            from: challenge.to,
+            to: challenge.from,
+            value: true,
+        };
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone(), signer(bob)], &challenge_data(&late_challenge));
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionClosed)), result);
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_FINALIZE_DISTRIBUTION]).unwrap();
+
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert!(resource_database.is_finalized);
+        assert_eq!(resource_database.challenges[0], challenge);
+        assert_eq!(resource_database.challenges[1], Challenge { value: false, ..late_challenge });
+
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_FINALIZE_DISTRIBUTION]);
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionClosed)), result);
+    }
+
+    // alice and bob are the recipients, alice approved bob but bob has not answered yet
+    fn claimable_database(alice: &Pubkey, bob: &Pubkey, quorum: QuorumRule) -> Vec<u8> {
+        let mut resource_database = ResourceDatabase {
+            is_distributed: true,
+            final_quantity: 10,
+            intents: intents(&[Intent { allocation: 5, ..intent(alice) }, Intent { allocation: 5, ..intent(bob) }]),
+            instances: [ResourceInstance::default(); MAX_NUM_RESOURCE_INSTANCES],
+            challenges: [Challenge::default(); MAX_NUM_CHALLENGES],
+            claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            window: DistributionWindow::default(),
+            is_finalized: false,
+            quorum,
+            round: 0,
+            escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+            vault: [0u8; PUBLIC_KEY_SIZE],
+            refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+            receipts: [false; MAX_NUM_RECIPIENTS],
+            unit: UnitOfMeasure::default(),
+            eligibility: EligibilityRule::default(),
+            strategy: ALLOCATION_PROPORTIONAL,
+            draw: Draw::default(),
+            capacity: 0,
+            waitlist: Waitlist::default(),
+            history: [0u8; PUBLIC_KEY_SIZE],
+            authority: authority_key().to_bytes(),
+        };
+        resource_database.challenges[0] = Challenge {
+            from: alice.to_bytes(),
+            to: bob.to_bytes(),
+            value: true,
+        };
+        resource_database.challenges[1] = Challenge {
+            from: bob.to_bytes(),
+            to: alice.to_bytes(),
+            value: false,
+        };
+        resource_database.try_to_vec().unwrap()
+    }
+
+    fn claim_data(claimant: &Pubkey) -> Vec<u8> {
+        let mut instruction_data = vec![INSTRUCTION_CLAIM_CHALLENGE];
+        instruction_data.extend(Challenge {
+            from: claimant.to_bytes(),
+            to: [0u8; PUBLIC_KEY_SIZE],
+            value: true,
+        }.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    fn set_quorum_data(kind: u8, threshold: u16, trust_program: &Pubkey) -> Vec<u8> {
+        let mut instruction_data = vec![INSTRUCTION_SET_QUORUM];
+        instruction_data.extend(QuorumRule {
+            kind,
+            threshold,
+            trust_program: trust_program.to_bytes(),
+        }.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    #[test]
+    fn test_quorum_rules() {
+        let rule = |kind, threshold| QuorumRule {
+            kind,
+            threshold,
+            trust_program: [0u8; PUBLIC_KEY_SIZE],
+        };
+        assert!(rule(QUORUM_ALL, 0).is_met(3, 3, 0));
+        assert!(!rule(QUORUM_ALL, 0).is_met(3, 2, 0));
+        assert!(rule(QUORUM_ALL, 0).is_met(0, 0, 0));
+        assert!(rule(QUORUM_MAJORITY, 0).is_met(3, 2, 0));
+        assert!(!rule(QUORUM_MAJORITY, 0).is_met(4, 2, 0));
+        assert!(rule(QUORUM_MAJORITY, 0).is_met(0, 0, 0));
+        assert!(rule(QUORUM_AT_LEAST, 2).is_met(4, 2, 0));
+        assert!(!rule(QUORUM_AT_LEAST, 2).is_met(1, 1, 0));
+        assert!(rule(QUORUM_TRUST_WEIGHTED, 10).is_met(2, 1, 10));
+        assert!(!rule(QUORUM_TRUST_WEIGHTED, 10).is_met(2, 2, 9));
+        assert!(rule(QUORUM_REPUTATION_WEIGHTED, 4).is_met(2, 1, 4));
+
+        assert!(!rule(QUORUM_AT_LEAST, 0).is_valid());
+        assert!(!rule(QUORUM_TRUST_WEIGHTED, 10).is_valid());
+        assert!(!rule(QUORUM_REPUTATION_WEIGHTED, 4).is_valid());
+        assert!(!rule(QUORUM_REPUTATION_WEIGHTED + 1, 0).is_valid());
+    }
+
+    #[test]
+    fn test_claim_challenge() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, QuorumRule::default());
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let accounts = vec![database.clone()];
+
+        // every peer has to approve by default
+        let result = process_instruction(&program_id, &accounts, &claim_data(&alice));
+        assert_eq!(Err(ProgramError::from(ResourceError::QuorumNotMet)), result);
+        process_instruction(&program_id, &accounts, &claim_data(&bob)).unwrap();
+        let result = process_instruction(&program_id, &accounts, &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::AlreadyClaimed)), result);
+        let result = process_instruction(&program_id, &accounts, &claim_data(&Pubkey::new_unique()));
+        assert_eq!(Err(ProgramError::from(ResourceError::NotARecipient)), result);
+
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.claims[..2], [bob.to_bytes(), [0u8; PUBLIC_KEY_SIZE]]);
+
+        // approvals only count from this round's recipients
+        let at_least_one = QuorumRule { kind: QUORUM_AT_LEAST, threshold: 1, trust_program: [0u8; PUBLIC_KEY_SIZE] };
+        let mut resource_database = ResourceDatabase::try_from_slice(&claimable_database(&alice, &bob, at_least_one)).unwrap();
+        resource_database.challenges[2] = Challenge { from: Pubkey::new_unique().to_bytes(), to: alice.to_bytes(), value: true };
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &accounts, &claim_data(&alice));
+        assert_eq!(Err(ProgramError::from(ResourceError::QuorumNotMet)), result);
+        resource_database.challenges[1].value = true;
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        process_instruction(&program_id, &accounts, &claim_data(&alice)).unwrap();
+    }
+
+    #[test]
+    fn test_issue_receipt() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let receipt_key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, QuorumRule::default());
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut receipt_lamports = 0;
+        let mut receipt_data = vec![0u8; CLAIM_RECEIPT_SIZE];
+        let receipt = AccountInfo::new(&receipt_key, false, true, &mut receipt_lamports, &mut receipt_data, &program_id, false, Epoch::default());
+        let mut alice_lamports = 0;
+        let mut alice_data = vec![];
+        let alice_info = AccountInfo::new(&alice, false, false, &mut alice_lamports, &mut alice_data, &owner, false, Epoch::default());
+        let mut bob_lamports = 0;
+        let mut bob_data = vec![];
+        let bob_info = AccountInfo::new(&bob, false, false, &mut bob_lamports, &mut bob_data, &owner, false, Epoch::default());
+        process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob)).unwrap();
+
+        // alice has not claimed
+        let result = process_instruction(&program_id, &[database.clone(), receipt.clone(), alice_info], &[INSTRUCTION_ISSUE_RECEIPT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::NotClaimed)), result);
+
+        // only the program can vouch for what a receipt says
+        let mut foreign_lamports = 0;
+        let mut foreign_data = vec![0u8; CLAIM_RECEIPT_SIZE];
+        let foreign = AccountInfo::new(&receipt_key, false, true, &mut foreign_lamports, &mut foreign_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), foreign, bob_info.clone()], &[INSTRUCTION_ISSUE_RECEIPT]);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        process_instruction(&program_id, &[database.clone(), receipt.clone(), bob_info.clone()], &[INSTRUCTION_ISSUE_RECEIPT]).unwrap();
+        let issued = ClaimReceipt::try_from_slice(&receipt.data.borrow()).unwrap();
+        assert_eq!(issued, ClaimReceipt {
+            database: key.to_bytes(),
+            recipient: bob.to_bytes(),
+            round: 0,
+            quantity: 5,
+            unit: UnitOfMeasure::default(),
+        });
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.receipts[..2], [true, false]);
+
+        // one receipt per claim, whichever account it would go into
+        let other_key = Pubkey::new_unique();
+        let mut other_lamports = 0;
+        let mut other_data = vec![0u8; CLAIM_RECEIPT_SIZE];
+        let other = AccountInfo::new(&other_key, false, true, &mut other_lamports, &mut other_data, &program_id, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), other, bob_info], &[INSTRUCTION_ISSUE_RECEIPT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::ReceiptAlreadyIssued)), result);
+    }
+
+    #[test]
+    fn test_trust_weighted_claim() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let trust_program = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let quorum = QuorumRule {
+            kind: QUORUM_TRUST_WEIGHTED,
+            threshold: 5,
+            trust_program: trust_program.to_bytes(),
+        };
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, quorum);
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+
+        // alice approved bob, with how much she trusts him read from her search engine account
+        let alice_account = |value| SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry {
+                to: bob.to_bytes(),
+                value,
+            }; MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        }.try_to_vec().unwrap();
+        let mut alice_lamports = 0;
+        let mut alice_data = alice_account(3);
+        let approver = AccountInfo::new(&alice, false, false, &mut alice_lamports, &mut alice_data, &trust_program, false, Epoch::default());
+
+        let result = process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::QuorumNotMet)), result);
+        let result = process_instruction(&program_id, &[database.clone(), approver.clone()], &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::QuorumNotMet)), result);
+
+        approver.data.borrow_mut().copy_from_slice(&alice_account(7));
+        let mut forged_lamports = 0;
+        let mut forged_data = alice_account(7);
+        let forged = AccountInfo::new(&alice, false, false, &mut forged_lamports, &mut forged_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), forged], &claim_data(&bob));
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        process_instruction(&program_id, &[database.clone(), approver], &claim_data(&bob)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.claims[0], bob.to_bytes());
+    }
+
+    #[test]
+    fn test_reputation_weighted_claim() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let trust_program = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let quorum = QuorumRule {
+            kind: QUORUM_REPUTATION_WEIGHTED,
+            threshold: 3,
+            trust_program: trust_program.to_bytes(),
+        };
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, quorum);
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+
+        // alice does not trust bob at all, but her approval carries her standing
+        let alice_account = |claims| SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation {
+                approvals_given: 1,
+                approvals_received: 1,
+                claims,
+                disputes: 0,
+                next_settle_slots: [0; MAX_NUM_INTENTS],
+            },
+        }.try_to_vec().unwrap();
+        let mut alice_lamports = 0;
+        let mut alice_data = alice_account(0);
+        let approver = AccountInfo::new(&alice, false, false, &mut alice_lamports, &mut alice_data, &trust_program, false, Epoch::default());
+
+        let result = process_instruction(&program_id, &[database.clone(), approver.clone()], &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::QuorumNotMet)), result);
+
+        approver.data.borrow_mut().copy_from_slice(&alice_account(1));
+        process_instruction(&program_id, &[database.clone(), approver], &claim_data(&bob)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.claims[0], bob.to_bytes());
+    }
+
+    #[test]
+    fn test_set_quorum() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let accounts = vec![database.clone(), authority()];
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        let result = process_instruction(&program_id, &accounts, &set_quorum_data(QUORUM_AT_LEAST, 0, &Pubkey::default()));
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidQuorumRule)), result);
+        process_instruction(&program_id, &accounts, &set_quorum_data(QUORUM_AT_LEAST, 2, &Pubkey::default())).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.quorum.kind, QUORUM_AT_LEAST);
+        assert_eq!(resource_database.quorum.threshold, 2);
+
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        database.data.borrow_mut().copy_from_slice(&claimable_database(&alice, &bob, QuorumRule::default()));
+        let result = process_instruction(&program_id, &accounts, &set_quorum_data(QUORUM_MAJORITY, 0, &Pubkey::default()));
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceInDistribution)), result);
+    }
+
+    #[test]
+    fn test_set_escrow_mint() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let mint = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let accounts = vec![database.clone(), authority()];
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        let mut set_escrow_mint = vec![INSTRUCTION_SET_ESCROW_MINT];
+        set_escrow_mint.extend_from_slice(mint.as_ref());
+        process_instruction(&program_id, &accounts, &set_escrow_mint).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.escrow_mint, mint.to_bytes());
+        assert!(resource_database.is_escrowed());
+
+        // recorded instances were deposited in the mint they were recorded with
+        let mut resource_database = resource_database;
+        resource_database.instances[0] = ResourceInstance {
+            from: Pubkey::new_unique().to_bytes(),
+            quantity: 3,
+        };
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &accounts, &set_escrow_mint);
+        assert_eq!(Err(ProgramError::from(ResourceError::EscrowInUse)), result);
+
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        database.data.borrow_mut().copy_from_slice(&claimable_database(&alice, &bob, QuorumRule::default()));
+        let result = process_instruction(&program_id, &accounts, &set_escrow_mint);
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceInDistribution)), result);
+    }
+
+    fn set_unit_data(name: &str, decimals: u8) -> Vec<u8> {
+        let mut unit = UnitOfMeasure {
+            decimals,
+            ..UnitOfMeasure::default()
+        };
+        unit.name[..name.len()].copy_from_slice(name.as_bytes());
+        let mut instruction_data = vec![INSTRUCTION_SET_UNIT];
+        instruction_data.extend(unit.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    fn set_eligibility_data(kind: u8, listed: &[Pubkey]) -> Vec<u8> {
+        let mut eligibility = EligibilityRule {
+            kind,
+            ..EligibilityRule::default()
+        };
+        for (slot, key) in eligibility.list.iter_mut().zip(listed.iter()) {
+            *slot = key.to_bytes();
+        }
+        let mut data = vec![INSTRUCTION_SET_ELIGIBILITY];
+        data.append(&mut eligibility.try_to_vec().unwrap());
+        data
+    }
+
+    #[test]
+    fn test_eligibility_lists() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let carol = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut recipient_lamports = [0u64; 3];
+        let mut recipient_datas = [vec![], vec![], vec![]];
+        let keys = [alice, bob, carol];
+        let recipients: Vec<AccountInfo> = keys.iter().zip(recipient_lamports.iter_mut()).zip(recipient_datas.iter_mut())
+            .map(|((key, lamports), data)| AccountInfo::new(key, true, false, lamports, data, &owner, false, Epoch::default()))
+            .collect();
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        // only the authority the database was reset with sets the rule, and has to sign
+        let result = process_instruction(&program_id, &[database.clone(), recipients[0].clone()], &set_eligibility_data(ELIGIBILITY_DENY_LIST, &[bob]));
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongAuthority)), result);
+        let unsigned = AccountInfo { is_signer: false, ..authority() };
+        let result = process_instruction(&program_id, &[database.clone(), unsigned], &set_eligibility_data(ELIGIBILITY_DENY_LIST, &[bob]));
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &set_eligibility_data(ELIGIBILITY_TRUSTED_BY_PROVIDER, &[]));
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidEligibilityRule)), result);
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &set_eligibility_data(9, &[]));
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidEligibilityRule)), result);
+
+        process_instruction(&program_id, &[database.clone(), authority()], &set_eligibility_data(ELIGIBILITY_DENY_LIST, &[alice])).unwrap();
+        assert_eq!(Err(ProgramError::from(ResourceError::NotEligible)), process_instruction(&program_id, &[database.clone(), recipients[0].clone()], &[INSTRUCTION_REGISTER_INTENT]));
+        process_instruction(&program_id, &[database.clone(), recipients[1].clone()], &[INSTRUCTION_REGISTER_INTENT]).unwrap();
+
+        process_instruction(&program_id, &[database.clone(), authority()], &set_eligibility_data(ELIGIBILITY_ALLOW_LIST, &[alice])).unwrap();
+        assert_eq!(Err(ProgramError::from(ResourceError::NotEligible)), process_instruction(&program_id, &[database.clone(), recipients[2].clone()], &[INSTRUCTION_REGISTER_INTENT]));
+        process_instruction(&program_id, &[database.clone(), recipients[0].clone()], &[INSTRUCTION_REGISTER_INTENT]).unwrap();
+
+        // bob registered under the deny list and keeps his slot
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [bob.to_bytes(), alice.to_bytes()]);
+        assert_eq!(resource_database.eligibility.kind, ELIGIBILITY_ALLOW_LIST);
+    }
+
+    #[test]
+    fn test_eligibility_trusted_by_provider() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let trust_program = Pubkey::new_unique();
+        let provider = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut alice_lamports = 0;
+        let mut alice_data = vec![];
+        let alice_info = AccountInfo::new(&alice, true, false, &mut alice_lamports, &mut alice_data, &owner, false, Epoch::default());
+        let mut bob_lamports = 0;
+        let mut bob_data = vec![];
+        let bob_info = AccountInfo::new(&bob, true, false, &mut bob_lamports, &mut bob_data, &owner, false, Epoch::default());
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        let mut eligibility = EligibilityRule {
+            kind: ELIGIBILITY_TRUSTED_BY_PROVIDER,
+            provider: provider.to_bytes(),
+            trust_program: trust_program.to_bytes(),
+            min_trust: 5,
+            ..EligibilityRule::default()
+        };
+        let mut instruction_data = vec![INSTRUCTION_SET_ELIGIBILITY];
+        instruction_data.append(&mut eligibility.try_to_vec().unwrap());
+        process_instruction(&program_id, &[database.clone(), authority()], &instruction_data).unwrap();
+
+        // the provider trusts bob, read from their search engine account
+        let provider_account = |value| SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry {
+                to: bob.to_bytes(),
+                value,
+            }; MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        }.try_to_vec().unwrap();
+        let mut provider_lamports = 0;
+        let mut provider_data = provider_account(3);
+        let provider_info = AccountInfo::new(&provider, false, false, &mut provider_lamports, &mut provider_data, &trust_program, false, Epoch::default());
+
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone()], &[INSTRUCTION_REGISTER_INTENT]);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone(), alice_info.clone()], &[INSTRUCTION_REGISTER_INTENT]);
+        assert_eq!(Err(ProgramError::InvalidAccountData), result);
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone(), provider_info.clone()], &[INSTRUCTION_REGISTER_INTENT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::NotEligible)), result);
+
+        let mut forged_lamports = 0;
+        let mut forged_data = provider_account(7);
+        let forged = AccountInfo::new(&provider, false, false, &mut forged_lamports, &mut forged_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone(), forged], &[INSTRUCTION_REGISTER_INTENT]);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        provider_info.data.borrow_mut().copy_from_slice(&provider_account(5));
+        process_instruction(&program_id, &[database.clone(), bob_info.clone(), provider_info.clone()], &[INSTRUCTION_REGISTER_INTENT]).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), alice_info.clone(), provider_info.clone()], &[INSTRUCTION_REGISTER_INTENT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::NotEligible)), result);
+
+        // back to open, the provider's account is no longer needed
+        eligibility.kind = ELIGIBILITY_OPEN;
+        let mut instruction_data = vec![INSTRUCTION_SET_ELIGIBILITY];
+        instruction_data.append(&mut eligibility.try_to_vec().unwrap());
+        process_instruction(&program_id, &[database.clone(), authority()], &instruction_data).unwrap();
+        process_instruction(&program_id, &[database.clone(), alice_info], &[INSTRUCTION_REGISTER_INTENT]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [bob.to_bytes(), alice.to_bytes()]);
+    }
+
+    // records a quantity only instance, signed by its contributor
+    fn record_instance(program_id: &Pubkey, database: &AccountInfo, from: &Pubkey, quantity: u64) -> ProgramResult {
+        process_instruction(program_id, &[database.clone(), signer(*from)], &record_instance_data(from, quantity))
+    }
+
+    fn record_instance_data(from: &Pubkey, quantity: u64) -> Vec<u8> {
+        let instance = ResourceInstance {
+            from: from.to_bytes(),
+            quantity,
+        };
+        let mut instruction_data = vec![INSTRUCTION_RECORD_RESOURCE_INSTANCE];
+        instruction_data.extend(instance.try_to_vec().unwrap());
+        instruction_data
+    }
+
+    #[test]
+    fn test_set_unit() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let accounts = vec![database.clone(), authority()];
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        let result = process_instruction(&program_id, &accounts, &set_unit_data("kg", MAX_UNIT_DECIMALS + 1));
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidUnit)), result);
+        process_instruction(&program_id, &accounts, &set_unit_data("kg", 1)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(&resource_database.unit.name[..3], b"kg\0");
+        assert_eq!(resource_database.unit.decimals, 1);
+
+        // 25 recorded as 2.5 kg can't turn into 25 loaves
+        record_instance(&program_id, &database, &Pubkey::new_unique(), 25).unwrap();
+        let result = process_instruction(&program_id, &accounts, &set_unit_data("loaves", 0));
+        assert_eq!(Err(ProgramError::from(ResourceError::UnitInUse)), result);
+
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        database.data.borrow_mut().copy_from_slice(&claimable_database(&alice, &bob, QuorumRule::default()));
+        let result = process_instruction(&program_id, &accounts, &set_unit_data("loaves", 0));
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceInDistribution)), result);
+    }
+
+    #[test]
+    fn test_set_allocation_strategy() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut alice_lamports = 0;
+        let mut alice_data = vec![];
+        let alice_info = AccountInfo::new(&alice, true, false, &mut alice_lamports, &mut alice_data, &owner, false, Epoch::default());
+        let mut bob_lamports = 0;
+        let mut bob_data = vec![];
+        let bob_info = AccountInfo::new(&bob, true, false, &mut bob_lamports, &mut bob_data, &owner, false, Epoch::default());
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(0);
+        let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_LOTTERY + 1]);
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidStrategy)), result);
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_FIRST_COME_FIRST_SERVED]).unwrap();
+
+        // once someone registered, the strategy stays
+        process_instruction(&program_id, &[database.clone(), alice_info.clone()], &register_intent_data(6, 0)).unwrap();
+        process_instruction(&program_id, &[database.clone(), bob_info.clone()], &register_intent_data(6, 0)).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_EQUAL_SPLIT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::StrategyInUse)), result);
+
+        // alice registered first and is served in full
+        record_instance(&program_id, &database, &Pubkey::new_unique(), 8).unwrap();
+        process_instruction(&program_id, &[database.clone(), authority(), clock.clone()], &initiate_distribution_data(60)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.strategy, ALLOCATION_FIRST_COME_FIRST_SERVED);
+        assert_eq!(resource_database.allocation(&alice.to_bytes()), 6);
+        assert_eq!(resource_database.allocation(&bob.to_bytes()), 2);
+        assert_eq!(resource_database.final_quantity, 8);
+
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_EQUAL_SPLIT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceInDistribution)), result);
+    }
+
+    #[test]
+    fn test_lottery() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut alice_lamports = 0;
+        let mut alice_data = vec![];
+        let alice_info = AccountInfo::new(&alice, true, false, &mut alice_lamports, &mut alice_data, &owner, false, Epoch::default());
+        let mut bob_lamports = 0;
+        let mut bob_data = vec![];
+        let bob_info = AccountInfo::new(&bob, true, false, &mut bob_lamports, &mut bob_data, &owner, false, Epoch::default());
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(0);
+        let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+        let slot_hashes_at = |entries: &[(u64, [u8; PUBLIC_KEY_SIZE])]| {
+            AccountInfo::new(Box::leak(Box::new(sysvar::slot_hashes::id())), false, false, Box::leak(Box::new(0u64)), Box::leak(serialized_slot_hashes(entries).into_boxed_slice()), Box::leak(Box::new(Pubkey::default())), false, Epoch::default())
+        };
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_LOTTERY]).unwrap();
+        process_instruction(&program_id, &[database.clone(), alice_info.clone()], &register_intent_data(6, 0)).unwrap();
+        process_instruction(&program_id, &[database.clone(), bob_info.clone()], &register_intent_data(6, 0)).unwrap();
+        record_instance(&program_id, &database, &Pubkey::new_unique(), 8).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes_at(&[(1, [4u8; PUBLIC_KEY_SIZE])])], &[INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceNotInDistribution)), result);
+
+        // the lottery is committed to a slot to come, nothing is allocated nor claimed until drawn
+        process_instruction(&program_id, &[database.clone(), authority(), clock.clone()], &initiate_distribution_data(15)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.draw, Draw { committed_slot: DRAW_DELAY, ..Draw::default() });
+        assert_eq!(resource_database.final_quantity, 0);
+        let approval = Challenge {
+            from: alice.to_bytes(),
+            to: bob.to_bytes(),
+            value: true,
+        };
+        process_instruction(&program_id, &[database.clone(), clock.clone(), alice_info.clone()], &challenge_data(&approval)).unwrap();
+        let result = process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::DrawPending)), result);
+
+        // the committed slot's hash only shows up in the sysvar of a later slot
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes_at(&[])], &[INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(Err(ProgramError::from(ResourceError::DrawPending)), result);
+        clock.data.borrow_mut().copy_from_slice(&clock_data_at(10));
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone()], &[INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone(), alice_info.clone()], &[INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(Err(ProgramError::InvalidArgument), result);
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes_at(&[(3, [4u8; PUBLIC_KEY_SIZE])])], &[INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(Err(ProgramError::from(ResourceError::DrawPending)), result);
+
+        // once the sysvar no longer reaches back to the committed slot a later one is committed
+        process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes_at(&[(9, [4u8; PUBLIC_KEY_SIZE]), (8, [0u8; PUBLIC_KEY_SIZE])])], &[INSTRUCTION_DRAW_LOTTERY]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.draw, Draw { committed_slot: 10 + DRAW_DELAY, ..Draw::default() });
+
+        // nor is the distribution finalized
+        clock.data.borrow_mut().copy_from_slice(&clock_data_at(20));
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone()], &[INSTRUCTION_FINALIZE_DISTRIBUTION]);
+        assert_eq!(Err(ProgramError::from(ResourceError::DrawPending)), result);
+
+        // a skipped committed slot draws with the next one, [4; 32] draws bob first and he is
+        // served in full. Both slots and the hash are kept
+        let slot_hashes = slot_hashes_at(&[(19, [1u8; PUBLIC_KEY_SIZE]), (15, [4u8; PUBLIC_KEY_SIZE]), (13, [0u8; PUBLIC_KEY_SIZE])]);
+        process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes.clone()], &[INSTRUCTION_DRAW_LOTTERY]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.draw, Draw { committed_slot: 14, slot: 15, hash: [4u8; PUBLIC_KEY_SIZE] });
+        assert_eq!(resource_database.allocation(&alice.to_bytes()), 2);
+        assert_eq!(resource_database.allocation(&bob.to_bytes()), 6);
+        assert_eq!(resource_database.final_quantity, 8);
+        let result = process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes], &[INSTRUCTION_DRAW_LOTTERY]);
+        assert_eq!(Err(ProgramError::from(ResourceError::NothingToDraw)), result);
+        process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob)).unwrap();
+        process_instruction(&program_id, &[database.clone(), clock.clone()], &[INSTRUCTION_FINALIZE_DISTRIBUTION]).unwrap();
+    }
+
+    #[test]
+    fn test_quantity_overflow() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(0);
+        let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+        let accounts = [database.clone(), clock];
+        process_instruction(&program_id, &[accounts[0].clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        resource_database.intents = intents(&[intent(&Pubkey::new_unique()), intent(&Pubkey::new_unique())]);
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+
+        // the total may reach u64::MAX but not go past it
+        record_instance(&program_id, &accounts[0], &Pubkey::new_unique(), u64::MAX - 1).unwrap();
+        let result = record_instance(&program_id, &accounts[0], &Pubkey::new_unique(), 2);
+        assert_eq!(Err(ProgramError::from(ResourceError::QuantityOverflow)), result);
+        record_instance(&program_id, &accounts[0], &Pubkey::new_unique(), 1).unwrap();
+
+        process_instruction(&program_id, &[accounts[0].clone(), authority(), accounts[1].clone()], &initiate_distribution_data(60)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.intents[0].allocation, u64::MAX / 2);
+        assert_eq!(resource_database.final_quantity, u64::MAX - 1);
+        assert_eq!(resource_database.deposited(), Some(u64::MAX));
+
+        // the sum is checked again when distributing, whatever put the instances there
+        let mut resource_database = resource_database;
+        resource_database.is_distributed = false;
+        resource_database.instances[1].quantity = 2;
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &[accounts[0].clone(), authority(), accounts[1].clone()], &initiate_distribution_data(60));
+        assert_eq!(Err(ProgramError::from(ResourceError::QuantityOverflow)), result);
+        assert_eq!(resource_database.deposited(), None);
+    }
+
+    #[test]
+    fn test_pooled_contributions() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let carol = Pubkey::new_unique();
+        let dave = Pubkey::new_unique();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let clock_key = sysvar::clock::id();
+        let mut clock_lamports = 0;
+        let mut clock_data = clock_data_at(0);
+        let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+        let accounts = [database.clone(), clock];
+        process_instruction(&program_id, &[accounts[0].clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        resource_database.intents = intents(&[intent(&Pubkey::new_unique()), intent(&Pubkey::new_unique())]);
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+
+        // carol's second contribution adds to her first
+        record_instance(&program_id, &accounts[0], &carol, 3).unwrap();
+        record_instance(&program_id, &accounts[0], &dave, 4).unwrap();
+        record_instance(&program_id, &accounts[0], &carol, 2).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.instances[0], ResourceInstance { from: carol.to_bytes(), quantity: 5 });
+        assert_eq!(resource_database.instances[1], ResourceInstance { from: dave.to_bytes(), quantity: 4 });
+        assert_eq!(resource_database.contributor(&dave.to_bytes()), Some(1));
+
+        // once every slot is taken only contributors already in the pool can add to it
+        for _ in 2..MAX_NUM_RESOURCE_INSTANCES {
+            record_instance(&program_id, &accounts[0], &Pubkey::new_unique(), 0).unwrap();
+        }
+        let result = record_instance(&program_id, &accounts[0], &Pubkey::new_unique(), 1);
+        assert_eq!(Err(ProgramError::from(ResourceError::NoResourceInstanceSpace)), result);
+        record_instance(&program_id, &accounts[0], &dave, 1).unwrap();
+
+        // the 10 in the pool is split between the two recipients
+        process_instruction(&program_id, &[accounts[0].clone(), authority(), accounts[1].clone()], &initiate_distribution_data(0)).unwrap();
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.final_quantity, 10);
+        assert_eq!(resource_database.intents[1].allocation, 5);
+
+        // with one of them claiming, the 5 left go back half to carol and half to dave
+        resource_database.claims[0] = resource_database.intents[0].recipient;
+        assert_eq!(resource_database.refund(&carol.to_bytes()), Some(2));
+        assert_eq!(resource_database.refund(&dave.to_bytes()), Some(2));
+        assert_eq!(resource_database.refund(&Pubkey::new_unique().to_bytes()), Some(0));
+        assert_eq!(resource_database.has_outstanding_refunds(), Some(true));
+        // dave, refunded last, also takes the 1 the shares rounded away
+        resource_database.refunded[0] = true;
+        assert_eq!(resource_database.refund(&dave.to_bytes()), Some(3));
+        assert_eq!(resource_database.has_outstanding_refunds(), Some(true));
+        resource_database.refunded[1] = true;
+        assert_eq!(resource_database.has_outstanding_refunds(), Some(false));
+
+        // contributions too small for a share of their own still hold the round open, the one
+        // refunded last takes the leftovers
+        resource_database.instances[2].quantity = 1;
+        resource_database.instances[3].quantity = 1;
+        let (first, last) = (resource_database.instances[2].from, resource_database.instances[3].from);
+        assert_eq!(resource_database.refund(&first), Some(0));
+        assert_eq!(resource_database.refund(&last), Some(0));
+        assert_eq!(resource_database.has_outstanding_refunds(), Some(true));
+        resource_database.refunded[2] = true;
+        assert_eq!(resource_database.refund(&last), Some(3));
+        assert_eq!(resource_database.has_outstanding_refunds(), Some(true));
+        resource_database.refunded[3] = true;
+        assert_eq!(resource_database.has_outstanding_refunds(), Some(false));
+    }
+
+    fn token_account_data(mint: &Pubkey, owner: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; TokenAccount::LEN];
+        TokenAccount::pack(TokenAccount {
+            mint: *mint,
+            owner: *owner,
+            amount: 10,
+            state: spl_token::state::AccountState::Initialized,
+            ..TokenAccount::default()
+        }, &mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_escrowed_claim_checks_token_accounts() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let token_program = spl_token::id();
+        let mint = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let (authority, _) = escrow_authority(&program_id, &key);
+        let escrow_key = Pubkey::new_unique();
+        let destination_key = Pubkey::new_unique();
+
+        // claims draw on the round's pool, so it has to hold bob's allocation
+        let mut resource_database = ResourceDatabase::try_from_slice(&claimable_database(&alice, &bob, QuorumRule::default())).unwrap();
+        resource_database.escrow_mint = mint.to_bytes();
+        resource_database.instances[0] = ResourceInstance { from: Pubkey::new_unique().to_bytes(), quantity: 10 };
+        let mut data = resource_database.try_to_vec().unwrap();
+        let mut database_lamports = 0;
+        let mut escrow_lamports = 0;
+        let mut destination_lamports = 0;
+        let mut authority_lamports = 0;
+        let mut program_lamports = 0;
+        let mut escrow_data = token_account_data(&mint, &Pubkey::new_unique());
+        let mut destination_data = token_account_data(&mint, &alice);
+        let mut authority_data = vec![];
+        let mut program_data = vec![];
+        let database = AccountInfo::new(&key, false, true, &mut database_lamports, &mut data, &owner, false, Epoch::default());
+        let escrow = AccountInfo::new(&escrow_key, false, true, &mut escrow_lamports, &mut escrow_data, &token_program, false, Epoch::default());
+        let destination = AccountInfo::new(&destination_key, false, true, &mut destination_lamports, &mut destination_data, &token_program, false, Epoch::default());
+        let authority_account = AccountInfo::new(&authority, false, false, &mut authority_lamports, &mut authority_data, &owner, false, Epoch::default());
+        let program = AccountInfo::new(&token_program, false, false, &mut program_lamports, &mut program_data, &owner, true, Epoch::default());
+
+        // the token accounts come first
+        let result = process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob));
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let accounts = vec![database.clone(), escrow.clone(), destination.clone(), authority_account.clone(), database.clone()];
+        let result = process_instruction(&program_id, &accounts, &claim_data(&bob));
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        // an escrow the database's authority does not own
+        let accounts = vec![database.clone(), escrow.clone(), destination.clone(), authority_account.clone(), program.clone()];
+        let result = process_instruction(&program_id, &accounts, &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongEscrow)), result);
+
+        // bob's allocation can't go to alice's token account
+        escrow.data.borrow_mut().copy_from_slice(&token_account_data(&mint, &authority));
+        let result = process_instruction(&program_id, &accounts, &claim_data(&bob));
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongEscrow)), result);
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.claims, [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS]);
+    }
+
+    #[test]
+    fn test_escrowed_refund() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let token_program = spl_token::id();
+        let mint = Pubkey::new_unique();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let carol = Pubkey::new_unique();
+        let (authority, _) = escrow_authority(&program_id, &key);
+        let escrow_key = Pubkey::new_unique();
+        let destination_key = Pubkey::new_unique();
+
+        // carol put 8 tokens in escrow and nobody claimed them
+        let mut resource_database = ResourceDatabase::try_from_slice(&claimable_database(&alice, &bob, QuorumRule::default())).unwrap();
+        resource_database.escrow_mint = mint.to_bytes();
+        resource_database.instances[0] = ResourceInstance { from: carol.to_bytes(), quantity: 8 };
+        resource_database.intents[0].allocation = 4;
+        resource_database.intents[1].allocation = 4;
+        resource_database.final_quantity = 8;
+        resource_database.is_finalized = true;
+        let mut data = resource_database.try_to_vec().unwrap();
+        let mut database_lamports = 0;
+        let mut escrow_lamports = 0;
+        let mut destination_lamports = 0;
+        let mut authority_lamports = 0;
+        let mut program_lamports = 0;
+        let history_key = Pubkey::new_unique();
+        let mut history_lamports = 0;
+        let mut escrow_data = token_account_data(&mint, &authority);
+        let mut destination_data = token_account_data(&mint, &alice);
+        let mut authority_data = vec![];
+        let mut program_data = vec![];
+        let mut history_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut database_lamports, &mut data, &owner, false, Epoch::default());
+        let escrow = AccountInfo::new(&escrow_key, false, true, &mut escrow_lamports, &mut escrow_data, &token_program, false, Epoch::default());
+        let destination = AccountInfo::new(&destination_key, false, true, &mut destination_lamports, &mut destination_data, &token_program, false, Epoch::default());
+        let authority_account = AccountInfo::new(&authority, false, false, &mut authority_lamports, &mut authority_data, &owner, false, Epoch::default());
+        let program = AccountInfo::new(&token_program, false, false, &mut program_lamports, &mut program_data, &owner, true, Epoch::default());
+        let history = AccountInfo::new(&history_key, false, true, &mut history_lamports, &mut history_data, &program_id, false, Epoch::default());
+        let accounts = vec![database.clone(), escrow.clone(), destination.clone(), authority_account.clone(), program.clone()];
+
+        // claims closed with the distribution, the 8 are carol's until she is refunded
+        let result = process_instruction(&program_id, &accounts, &claim_data(&alice));
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionClosed)), result);
+        let result = process_instruction(&program_id, &[database.clone(), history.clone()], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::RefundsOutstanding)), result);
+
+        // the refund goes to whoever owns the token account, alice contributed nothing
+        let result = process_instruction(&program_id, &accounts, &refund_data());
+        assert_eq!(Err(ProgramError::from(ResourceError::NothingToRefund)), result);
+
+        // and only to a token account of the escrow's mint
+        destination.data.borrow_mut().copy_from_slice(&token_account_data(&Pubkey::new_unique(), &carol));
+        let result = process_instruction(&program_id, &accounts, &refund_data());
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongEscrow)), result);
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.refunded, [false; MAX_NUM_RESOURCE_INSTANCES]);
+        assert_eq!(resource_database.refund(&carol.to_bytes()), Some(8));
+    }
+
+    #[test]
+    fn test_set_lamport_vault() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let vault_key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut vault_lamports = 0;
+        let mut vault_data = vec![0u8; LAMPORT_VAULT_SIZE];
+        let vault = AccountInfo::new(&vault_key, false, true, &mut vault_lamports, &mut vault_data, &program_id, false, Epoch::default());
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        // the program has to own the vault to pay out of it
+        let mut foreign_lamports = 0;
+        let mut foreign_data = vec![0u8; LAMPORT_VAULT_SIZE];
+        let foreign = AccountInfo::new(&vault_key, false, true, &mut foreign_lamports, &mut foreign_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), authority(), foreign], &[INSTRUCTION_SET_LAMPORT_VAULT]);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        process_instruction(&program_id, &[database.clone(), authority(), vault.clone()], &[INSTRUCTION_SET_LAMPORT_VAULT]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.vault, vault_key.to_bytes());
+        assert_eq!(LamportVault::try_from_slice(&vault.data.borrow()).unwrap().database, key.to_bytes());
+
+        // a resource is backed by lamports or tokens
+        let mut set_escrow_mint = vec![INSTRUCTION_SET_ESCROW_MINT];
+        set_escrow_mint.extend_from_slice(Pubkey::new_unique().as_ref());
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &set_escrow_mint);
+        assert_eq!(Err(ProgramError::from(ResourceError::EscrowInUse)), result);
+
+        // another database can't share the vault
+        let other_key = Pubkey::new_unique();
+        let mut other_lamports = 0;
+        let mut other_data = data_of(&database);
+        let other = AccountInfo::new(&other_key, false, true, &mut other_lamports, &mut other_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[other, authority(), vault.clone()], &[INSTRUCTION_SET_LAMPORT_VAULT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongEscrow)), result);
+
+        // without a vault account the resource goes back to recording quantities
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_LAMPORT_VAULT]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert!(!resource_database.has_vault());
+    }
+
+    fn data_of(account: &AccountInfo) -> Vec<u8> {
+        account.data.borrow().to_vec()
+    }
+
+    fn refund_data() -> Vec<u8> {
+        vec![INSTRUCTION_REFUND_CONTRIBUTION]
+    }
+
+    #[test]
+    fn test_vault_claim_and_refund() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let vault_key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let carol = Pubkey::new_unique();
+        let dave = Pubkey::new_unique();
+
+        // carol and dave deposited 8 lamports, bob is allocated 5 of them
+        let mut resource_database = ResourceDatabase::try_from_slice(&claimable_database(&alice, &bob, QuorumRule::default())).unwrap();
+        resource_database.vault = vault_key.to_bytes();
+        resource_database.instances[0] = ResourceInstance { from: carol.to_bytes(), quantity: 6 };
+        resource_database.instances[1] = ResourceInstance { from: dave.to_bytes(), quantity: 2 };
+        let mut data = resource_database.try_to_vec().unwrap();
+        let mut database_lamports = 0;
+        let mut vault_lamports = 1000 + 8;
+        let mut bob_lamports = 0;
+        let mut carol_lamports = 0;
+        let mut dave_lamports = 0;
+        let mut vault_data = key.to_bytes().to_vec();
+        let mut bob_data = vec![];
+        let mut carol_data = vec![];
+        let mut dave_data = vec![];
+        let history_key = Pubkey::new_unique();
+        let mut history_lamports = 0;
+        let mut history_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut database_lamports, &mut data, &owner, false, Epoch::default());
+        let vault = AccountInfo::new(&vault_key, false, true, &mut vault_lamports, &mut vault_data, &program_id, false, Epoch::default());
+        let history = AccountInfo::new(&history_key, false, true, &mut history_lamports, &mut history_data, &program_id, false, Epoch::default());
+        let bob_account = AccountInfo::new(&bob, false, true, &mut bob_lamports, &mut bob_data, &owner, false, Epoch::default());
+        let carol_account = AccountInfo::new(&carol, false, true, &mut carol_lamports, &mut carol_data, &owner, false, Epoch::default());
+        let dave_account = AccountInfo::new(&dave, false, true, &mut dave_lamports, &mut dave_data, &owner, false, Epoch::default());
+
+        // the allocation only goes to the claimant
+        let result = process_instruction(&program_id, &[database.clone(), vault.clone(), carol_account.clone()], &claim_data(&bob));
+        assert_eq!(Err(ProgramError::InvalidInstructionData), result);
+        process_instruction(&program_id, &[database.clone(), vault.clone(), bob_account.clone()], &claim_data(&bob)).unwrap();
+        assert_eq!(bob_account.lamports(), 5);
+        assert_eq!(vault.lamports(), 1003);
+
+        let accounts = vec![database.clone(), vault.clone(), carol_account.clone()];
+        let result = process_instruction(&program_id, &accounts, &refund_data());
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionOpen)), result);
+
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        resource_database.is_finalized = true;
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &[database.clone(), vault.clone(), bob_account.clone()], &claim_data(&alice));
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionClosed)), result);
+
+        // the round can't close before carol gets her share back
+        let result = process_instruction(&program_id, &[database.clone(), history.clone()], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::RefundsOutstanding)), result);
+
+        // carol put in three quarters of the 8, so she gets three quarters of the 3 left, rounded down
+        let result = process_instruction(&program_id, &[database.clone(), bob_account.clone(), carol_account.clone()], &refund_data());
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongEscrow)), result);
+        process_instruction(&program_id, &accounts, &refund_data()).unwrap();
+        assert_eq!(carol_account.lamports(), 2);
+        assert_eq!(vault.lamports(), 1001);
+        let result = process_instruction(&program_id, &accounts, &refund_data());
+        assert_eq!(Err(ProgramError::from(ResourceError::NothingToRefund)), result);
+        let result = process_instruction(&program_id, &[database.clone(), vault.clone(), bob_account.clone()], &refund_data());
+        assert_eq!(Err(ProgramError::from(ResourceError::NothingToRefund)), result);
+
+        // dave's quarter of the 3 rounds down to nothing, but refunded last he takes the 1 left
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.refunded[..2], [true, false]);
+        assert_eq!(resource_database.refund(&dave.to_bytes()), Some(1));
+        let result = process_instruction(&program_id, &[database.clone(), history.clone()], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::RefundsOutstanding)), result);
+        process_instruction(&program_id, &[database.clone(), vault.clone(), dave_account.clone()], &refund_data()).unwrap();
+        assert_eq!(dave_account.lamports(), 1);
+        assert_eq!(vault.lamports(), 1000);
+        process_instruction(&program_id, &[database.clone(), history.clone()], &[INSTRUCTION_START_NEW_ROUND]).unwrap();
+    }
+
+    #[test]
+    fn test_start_new_round() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let history_key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let alice = Pubkey::new_unique();
+        let bob = Pubkey::new_unique();
+        let quorum = QuorumRule {
+            kind: QUORUM_MAJORITY,
+            threshold: 0,
+            trust_program: [0u8; PUBLIC_KEY_SIZE],
+        };
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, quorum);
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut history_lamports = 0;
+        let mut history_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let history = AccountInfo::new(&history_key, false, true, &mut history_lamports, &mut history_data, &program_id, false, Epoch::default());
+        let accounts = vec![database.clone(), history.clone()];
+
+        process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob)).unwrap();
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::DistributionOpen)), result);
+
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        resource_database.is_finalized = true;
+        resource_database.unit.decimals = 1;
+        resource_database.draw = Draw { committed_slot: 2, slot: 3, hash: [9u8; PUBLIC_KEY_SIZE] };
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+
+        // the history has to belong to the program
+        let mut other_lamports = 0;
+        let mut other_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let foreign = AccountInfo::new(&history_key, false, true, &mut other_lamports, &mut other_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), foreign], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_START_NEW_ROUND]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.round, 1);
+        assert!(!resource_database.is_distributed);
+        assert!(!resource_database.is_finalized);
+        assert_eq!(registered(&resource_database), [alice.to_bytes(), bob.to_bytes()]);
+        assert_eq!(resource_database.quorum, quorum);
+        assert_eq!(resource_database.unit.decimals, 1);
+        assert_eq!(resource_database.claims, [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS]);
+        assert_eq!(resource_database.challenges, [Challenge::default(); MAX_NUM_CHALLENGES]);
+        assert_eq!(resource_database.draw, Draw::default());
+
+        let round_history = RoundHistory::try_from_slice(&history.data.borrow()).unwrap();
+        assert_eq!(round_history.database, key.to_bytes());
+        let record = round_history.get(0).unwrap();
+        assert_eq!(record.allocation(&bob.to_bytes()), 5);
+        assert_eq!(record.allocation(&alice.to_bytes()), 0);
+        assert_eq!(record.unit.decimals, 1);
+        assert_eq!(record.draw, Draw { committed_slot: 2, slot: 3, hash: [9u8; PUBLIC_KEY_SIZE] });
+        assert_eq!(record.approvals_received(&bob.to_bytes()), 1);
+        assert_eq!(record.approvals_received(&alice.to_bytes()), 0);
+        assert_eq!(round_history.get(1), None);
+
+        // nothing to archive until the next round is distributed and finalized
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceNotInDistribution)), result);
+
+        // later rounds go to the same history, even after a reset
+        assert_eq!(resource_database.history, history_key.to_bytes());
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(resource_database.history, history_key.to_bytes());
+        resource_database.is_distributed = true;
+        resource_database.is_finalized = true;
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let fresh_key = Pubkey::new_unique();
+        let mut fresh_lamports = 0;
+        let mut fresh_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let fresh = AccountInfo::new(&fresh_key, false, true, &mut fresh_lamports, &mut fresh_data, &program_id, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), fresh], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongRoundHistory)), result);
+
+        // another database can't write into this history
+        let other_key = Pubkey::new_unique();
+        let mut other_lamports = 0;
+        let mut other_data = claimable_database(&alice, &bob, quorum);
+        let mut other_database = ResourceDatabase::try_from_slice(&other_data).unwrap();
+        other_database.is_finalized = true;
+        other_data.copy_from_slice(&other_database.try_to_vec().unwrap());
+        let other = AccountInfo::new(&other_key, false, true, &mut other_lamports, &mut other_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[other, history], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongRoundHistory)), result);
+    }
+
+    fn waitlist_page_at(page: &AccountInfo) -> WaitlistPage {
+        WaitlistPage::try_from_slice(&page.data.borrow()).unwrap()
+    }
+
+    fn waiting(page: &AccountInfo) -> Vec<[u8; PUBLIC_KEY_SIZE]> {
+        waitlist_page_at(page).intents.iter()
+            .filter(|intent| intent.is_registered())
+            .map(|intent| intent.recipient)
+            .collect()
+    }
+
+    #[test]
+    fn test_waitlist() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let (alice, bob, carol, dave) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (first_key, second_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut user_lamports = [0u64; 4];
+        let mut user_datas = vec![vec![]; 4];
+        let keys = [alice, bob, carol, dave];
+        let users: Vec<AccountInfo> = keys.iter().zip(user_lamports.iter_mut()).zip(user_datas.iter_mut())
+            .map(|((key, lamports), data)| AccountInfo::new(key, true, false, lamports, data, &owner, false, Epoch::default()))
+            .collect();
+        let (alice_info, bob_info, carol_info, dave_info) = (&users[0], &users[1], &users[2], &users[3]);
+        let mut first_lamports = 0;
+        let mut first_data = vec![0u8; WAITLIST_PAGE_SIZE];
+        let first = AccountInfo::new(&first_key, false, true, &mut first_lamports, &mut first_data, &program_id, false, Epoch::default());
+        let mut second_lamports = 0;
+        let mut second_data = vec![0u8; WAITLIST_PAGE_SIZE];
+        let second = AccountInfo::new(&second_key, false, true, &mut second_lamports, &mut second_data, &program_id, false, Epoch::default());
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+        // a capacity takes at most MAX_NUM_RECIPIENTS intents
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_CAPACITY, MAX_NUM_RECIPIENTS as u8 + 1]);
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidCapacity)), result);
+        process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_CAPACITY, 1]).unwrap();
+
+        // beyond the capacity an intent needs a waitlist page to queue on
+        process_instruction(&program_id, &[database.clone(), alice_info.clone()], &register_intent_data(2, 0)).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone()], &register_intent_data(3, 0));
+        assert_eq!(Err(ProgramError::from(ResourceError::WaitlistFull)), result);
+
+        // pages belong to the program and are chained once
+        let mut foreign_lamports = 0;
+        let mut foreign_data = vec![0u8; WAITLIST_PAGE_SIZE];
+        let foreign = AccountInfo::new(&first_key, false, true, &mut foreign_lamports, &mut foreign_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), foreign], &[INSTRUCTION_ADD_WAITLIST_PAGE]);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+        process_instruction(&program_id, &[database.clone(), first.clone()], &[INSTRUCTION_ADD_WAITLIST_PAGE]).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), first.clone()], &[INSTRUCTION_ADD_WAITLIST_PAGE]);
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongWaitlist)), result);
+
+        // overflow intents wait in the order they registered, registering again keeps their place
+        process_instruction(&program_id, &[database.clone(), bob_info.clone(), first.clone()], &register_intent_data(3, 0)).unwrap();
+        process_instruction(&program_id, &[database.clone(), carol_info.clone(), first.clone()], &register_intent_data(4, 0)).unwrap();
+        process_instruction(&program_id, &[database.clone(), alice_info.clone(), first.clone()], &register_intent_data(5, 0)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [alice.to_bytes()]);
+        assert_eq!(resource_database.intents[0].need.requested, 5);
+        assert_eq!(resource_database.waitlist, Waitlist { head: first_key.to_bytes(), tail: first_key.to_bytes(), waiting: 2 });
+        assert_eq!(waiting(&first), [bob.to_bytes(), carol.to_bytes()]);
+
+        // a second page goes after the tail, new intents queue there once every page was
+        // checked for them
+        process_instruction(&program_id, &[database.clone(), second.clone(), first.clone()], &[INSTRUCTION_ADD_WAITLIST_PAGE]).unwrap();
+        assert_eq!(waitlist_page_at(&first).next, second_key.to_bytes());
+        let result = process_instruction(&program_id, &[database.clone(), dave_info.clone(), second.clone()], &register_intent_data(0, 2));
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        process_instruction(&program_id, &[database.clone(), dave_info.clone(), first.clone(), second.clone()], &register_intent_data(0, 2)).unwrap();
+        assert_eq!(waiting(&second), [dave.to_bytes()]);
+
+        // a waiting intent is queued once
+        let result = process_instruction(&program_id, &[database.clone(), bob_info.clone(), first.clone(), second.clone()], &register_intent_data(1, 0));
+        assert_eq!(Err(ProgramError::from(ResourceError::AlreadyWaiting)), result);
+        let result = process_instruction(&program_id, &[database.clone(), dave_info.clone(), first.clone(), second.clone()], &register_intent_data(1, 0));
+        assert_eq!(Err(ProgramError::from(ResourceError::AlreadyWaiting)), result);
+        assert_eq!(waiting(&first), [bob.to_bytes(), carol.to_bytes()]);
+        assert_eq!(waiting(&second), [dave.to_bytes()]);
+
+        // a larger capacity promotes the longest waiting intent
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_CAPACITY, 2]);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        process_instruction(&program_id, &[database.clone(), authority(), first.clone()], &[INSTRUCTION_SET_CAPACITY, 2]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [alice.to_bytes(), bob.to_bytes()]);
+        assert_eq!(resource_database.intents[1].need.requested, 3);
+        assert_eq!(resource_database.waitlist.waiting, 2);
+        let result = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_CAPACITY, 1]);
+        assert_eq!(Err(ProgramError::from(ResourceError::InvalidCapacity)), result);
+
+        // withdrawing takes the recipient's signature
+        let mut unsigned_lamports = 0;
+        let mut unsigned_data = vec![];
+        let unsigned = AccountInfo::new(&alice, false, false, &mut unsigned_lamports, &mut unsigned_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[database.clone(), unsigned, first.clone()], &[INSTRUCTION_WITHDRAW_INTENT]);
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        let result = process_instruction(&program_id, &[database.clone(), carol_info.clone(), first.clone()], &[INSTRUCTION_WITHDRAW_INTENT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::NotARecipient)), result);
+
+        // the withdrawn slot goes to carol, the drained first page leaves the chain
+        process_instruction(&program_id, &[database.clone(), alice_info.clone(), first.clone()], &[INSTRUCTION_WITHDRAW_INTENT]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [bob.to_bytes(), carol.to_bytes()]);
+        assert_eq!(resource_database.waitlist, Waitlist { head: second_key.to_bytes(), tail: second_key.to_bytes(), waiting: 1 });
+        assert_eq!(waitlist_page_at(&first), WaitlistPage { database: key.to_bytes(), ..WaitlistPage::default() });
+
+        // and can be chained again behind the second
+        process_instruction(&program_id, &[database.clone(), first.clone(), second.clone()], &[INSTRUCTION_ADD_WAITLIST_PAGE]).unwrap();
+        process_instruction(&program_id, &[database.clone(), alice_info.clone(), second.clone(), first.clone()], &register_intent_data(5, 0)).unwrap();
+        process_instruction(&program_id, &[database.clone(), bob_info.clone(), second.clone(), first.clone()], &[INSTRUCTION_WITHDRAW_INTENT]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [carol.to_bytes(), dave.to_bytes()]);
+        assert_eq!(resource_database.intents[1].need.household_size, 2);
+        assert_eq!(resource_database.waitlist, Waitlist { head: first_key.to_bytes(), tail: first_key.to_bytes(), waiting: 1 });
+        assert_eq!(waiting(&first), [alice.to_bytes()]);
+
+        // up to every slot of the database
+        process_instruction(&program_id, &[database.clone(), authority(), first.clone()], &[INSTRUCTION_SET_CAPACITY, MAX_NUM_RECIPIENTS as u8]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [carol.to_bytes(), dave.to_bytes(), alice.to_bytes()]);
+        assert_eq!(resource_database.waitlist.waiting, 0);
+
+        // nobody withdraws while distributing
+        let mut resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        resource_database.is_distributed = true;
+        database.data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &[database.clone(), carol_info.clone(), first.clone()], &[INSTRUCTION_WITHDRAW_INTENT]);
+        assert_eq!(Err(ProgramError::from(ResourceError::ResourceInDistribution)), result);
+
+        // and newcomers wait for the next round, even with a slot open
+        process_instruction(&program_id, &[database.clone(), bob_info.clone(), first.clone()], &register_intent_data(3, 0)).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [carol.to_bytes(), dave.to_bytes(), alice.to_bytes()]);
+        assert_eq!(resource_database.waitlist.waiting, 1);
+        assert_eq!(waiting(&first), [bob.to_bytes()]);
+    }
+
+    #[test]
+    fn test_unclaimed_slots_go_to_waitlist() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let history_key = Pubkey::new_unique();
+        let page_key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let (alice, bob, carol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let quorum = QuorumRule {
+            kind: QUORUM_MAJORITY,
+            threshold: 0,
+            trust_program: [0u8; PUBLIC_KEY_SIZE],
+        };
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, quorum);
+        let mut resource_database = ResourceDatabase::try_from_slice(&data).unwrap();
+        resource_database.is_finalized = true;
+        resource_database.capacity = 2;
+        resource_database.waitlist = Waitlist { head: page_key.to_bytes(), tail: page_key.to_bytes(), waiting: 1 };
+        data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut history_lamports = 0;
+        let mut history_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let history = AccountInfo::new(&history_key, false, true, &mut history_lamports, &mut history_data, &program_id, false, Epoch::default());
+        let mut page = WaitlistPage {
+            database: key.to_bytes(),
+            ..WaitlistPage::default()
+        };
+        page.intents[0] = intent(&carol);
+        let mut page_lamports = 0;
+        let mut page_data = page.try_to_vec().unwrap();
+        let page_info = AccountInfo::new(&page_key, false, true, &mut page_lamports, &mut page_data, &program_id, false, Epoch::default());
+
+        // bob claimed, alice did not and gives her slot to carol
+        process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob)).unwrap();
+        let result = process_instruction(&program_id, &[database.clone(), history.clone()], &[INSTRUCTION_START_NEW_ROUND]);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        process_instruction(&program_id, &[database.clone(), history.clone(), page_info.clone()], &[INSTRUCTION_START_NEW_ROUND]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [bob.to_bytes(), carol.to_bytes()]);
+        assert_eq!(resource_database.waitlist.waiting, 0);
+        assert!(waiting(&page_info).is_empty());
+
+        // the archived round still has alice's intent
+        let round_history = RoundHistory::try_from_slice(&history.data.borrow()).unwrap();
+        assert_eq!(round_history.get(0).unwrap().intents[0].recipient, alice.to_bytes());
+    }
+
+    #[test]
+    fn test_unclaimed_slots_only_go_to_promoted_intents() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let history_key = Pubkey::new_unique();
+        let page_key = Pubkey::new_unique();
+        let owner = Pubkey::default();
+        let (alice, bob, carol) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let quorum = QuorumRule {
+            kind: QUORUM_MAJORITY,
+            threshold: 0,
+            trust_program: [0u8; PUBLIC_KEY_SIZE],
+        };
+        let mut lamports = 0;
+        let mut data = claimable_database(&alice, &bob, quorum);
+        let mut resource_database = ResourceDatabase::try_from_slice(&data).unwrap();
+        resource_database.is_finalized = true;
+        resource_database.capacity = 3;
+        resource_database.waitlist = Waitlist { head: page_key.to_bytes(), tail: page_key.to_bytes(), waiting: 3 };
+        data.copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let database = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+        let mut history_lamports = 0;
+        let mut history_data = vec![0u8; ROUND_HISTORY_SIZE];
+        let history = AccountInfo::new(&history_key, false, true, &mut history_lamports, &mut history_data, &program_id, false, Epoch::default());
+        // carol registered while the slot was open but the round distributing, queued twice
+        // before duplicates were refused, and bob waits while holding a slot
+        let mut page = WaitlistPage {
+            database: key.to_bytes(),
+            ..WaitlistPage::default()
+        };
+        page.intents[0] = intent(&carol);
+        page.intents[1] = intent(&carol);
+        page.intents[2] = intent(&bob);
+        let mut page_lamports = 0;
+        let mut page_data = page.try_to_vec().unwrap();
+        let page_info = AccountInfo::new(&page_key, false, true, &mut page_lamports, &mut page_data, &program_id, false, Epoch::default());
+
+        // carol takes the open slot, alice did not claim but nobody else is promoted to replace her
+        process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&bob)).unwrap();
+        process_instruction(&program_id, &[database.clone(), history.clone(), page_info.clone()], &[INSTRUCTION_START_NEW_ROUND]).unwrap();
+        let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+        assert_eq!(registered(&resource_database), [alice.to_bytes(), bob.to_bytes(), carol.to_bytes()]);
+        assert_eq!(resource_database.waitlist.waiting, 0);
+        assert!(waiting(&page_info).is_empty());
+    }
+
+    #[test]
+    fn test_round_history_keeps_last_rounds() {
+        let mut history = RoundHistory::try_from_slice(&[0u8; ROUND_HISTORY_SIZE]).unwrap();
+        assert!(history.rounds().is_empty());
+        assert_eq!(history.get(0), None);
+
+        let archived = MAX_NUM_ROUNDS as u32 + 2;
+        for round in 0..archived {
+            history.archive(RoundRecord {
+                round,
+                ..RoundRecord::default()
+            });
+        }
+        assert_eq!(history.get(0), None);
+        assert_eq!(history.get(1), None);
+        assert_eq!(history.get(archived), None);
+        let rounds: Vec<u32> = history.rounds().iter().map(|record| record.round).collect();
+        assert_eq!(rounds, (2..archived).collect::<Vec<u32>>());
+
+        // a reset database numbers on from its last round, the records it left stay reachable
+        history.archive(RoundRecord {
+            round: archived,
+            ..RoundRecord::default()
+        });
+        assert_eq!(history.rounds().len(), MAX_NUM_ROUNDS);
+        assert_eq!(history.get(archived - 1).unwrap().round, archived - 1);
+    }
+
+    #[test]
+    fn test_reset_database() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        // freshly created accounts are zeroed
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE];
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let accounts = vec![account, authority()];
+
+        let instruction_data: Vec<u8> = vec![INSTRUCTION_RESET_DATABASE];
+
+        // the signer of the first reset becomes the authority
+        let unsigned = AccountInfo { is_signer: false, ..authority() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), unsigned], &instruction_data);
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        assert_eq!(result.unwrap(), ());
+        let resource_database = ResourceDatabase::try_from_slice(&accounts[0].data.borrow()).unwrap();
+        assert!(!resource_database.is_distributed);
+        assert_eq!(resource_database.intents, [Intent::default(); MAX_NUM_RECIPIENTS]);
+        assert_eq!(resource_database.authority, authority_key().to_bytes());
+
+        // and the only one who can reset it again
+        let other_key = Pubkey::new_unique();
+        let mut other_lamports = 0;
+        let mut other_data = vec![];
+        let other = AccountInfo::new(&other_key, true, false, &mut other_lamports, &mut other_data, &owner, false, Epoch::default());
+        let result = process_instruction(&program_id, &[accounts[0].clone(), other], &instruction_data);
+        assert_eq!(Err(ProgramError::from(ResourceError::WrongAuthority)), result);
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
+
+        // deposits in the vault have to be paid out or refunded first
+        let mut resource_database = ResourceDatabase::try_from_slice(&accounts[0].data.borrow()).unwrap();
+        resource_database.vault = Pubkey::new_unique().to_bytes();
+        resource_database.instances[0] = ResourceInstance { from: Pubkey::new_unique().to_bytes(), quantity: 5 };
+        accounts[0].data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        assert_eq!(Err(ProgramError::from(ResourceError::RefundsOutstanding)), result);
+
+        resource_database.is_distributed = true;
+        resource_database.is_finalized = true;
+        accounts[0].data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        assert_eq!(Err(ProgramError::from(ResourceError::RefundsOutstanding)), result);
+
+        // the bound history and the round number carry over, so archived rounds stay reachable
+        resource_database.refunded[0] = true;
+        resource_database.round = 7;
+        resource_database.history = Pubkey::new_unique().to_bytes();
+        accounts[0].data.borrow_mut().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
+        let reset = ResourceDatabase::try_from_slice(&accounts[0].data.borrow()).unwrap();
+        assert_eq!(reset.vault, [0u8; PUBLIC_KEY_SIZE]);
+        assert_eq!(reset.round, 7);
+        assert_eq!(reset.history, resource_database.history);
+    }
+
+    #[test]
+    fn test_reset_database_wrong_size() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut data = vec![0u8; RESOURCE_DATABASE_SIZE - 1];
+        let account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let accounts = vec![account];
+
+        let instruction_data: Vec<u8> = vec![INSTRUCTION_RESET_DATABASE];
+
+        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        assert_eq!(Err(ProgramError::InvalidAccountData), result);
+    }
+
+    // (key seed, whether it signs, account data) for each account passed to an arbitrary instruction
+    fn arbitrary_accounts() -> impl proptest::strategy::Strategy<Value = Vec<(u8, bool, Vec<u8>)>> {
+        use proptest::prelude::*;
+        let data = prop_oneof![
+            proptest::collection::vec(any::<u8>(), 0..512),
+            Just(vec![0u8; RESOURCE_DATABASE_SIZE]),
+            Just(vec![0u8; ROUND_HISTORY_SIZE]),
+            Just(vec![0u8; LAMPORT_VAULT_SIZE]),
+            Just(vec![0u8; CLAIM_RECEIPT_SIZE]),
+            Just(vec![0u8; WAITLIST_PAGE_SIZE]),
+        ];
+        proptest::collection::vec((0u8..4, any::<bool>(), data), 0..4)
+    }
+
+    #[derive(Clone, Debug)]
+    enum Operation {
+        RegisterIntent { user: u8, requested: u64, household_size: u16 },
+        RecordInstance { user: u8, quantity: u64 },
+        InitiateDistribution { duration: i64 },
+        DrawLottery,
+        RecordChallenge { from: u8, to: u8, value: bool },
+        AdvanceTime { seconds: i64 },
+        Finalize,
+        Claim { user: u8 },
+        SetQuorum { kind: u8, threshold: u16 },
+        SetUnit { decimals: u8 },
+        SetEligibility { kind: u8, user: u8 },
+        SetAllocationStrategy { strategy: u8 },
+        SetCapacity { capacity: u8 },
+        AddWaitlistPage { page: usize },
+        Withdraw { user: u8 },
+        StartNewRound,
+        Reset,
+    }
+
+    fn operations() -> impl proptest::strategy::Strategy<Value = Vec<Operation>> {
+        use proptest::prelude::*;
+        let operation = prop_oneof![
+            (0u8..4, 0u64..16, 0u16..4).prop_map(|(user, requested, household_size)| Operation::RegisterIntent { user, requested, household_size }),
+            (0u8..4, any::<u64>()).prop_map(|(user, quantity)| Operation::RecordInstance { user, quantity }),
+            (-1i64..8).prop_map(|duration| Operation::InitiateDistribution { duration }),
+            Just(Operation::DrawLottery),
+            (0u8..4, 0u8..4, any::<bool>()).prop_map(|(from, to, value)| Operation::RecordChallenge { from, to, value }),
+            (0i64..4).prop_map(|seconds| Operation::AdvanceTime { seconds }),
+            Just(Operation::Finalize),
+            (0u8..4).prop_map(|user| Operation::Claim { user }),
+            (0u8..6, 0u16..3).prop_map(|(kind, threshold)| Operation::SetQuorum { kind, threshold }),
+            (0u8..24).prop_map(|decimals| Operation::SetUnit { decimals }),
+            (0u8..4, 0u8..4).prop_map(|(kind, user)| Operation::SetEligibility { kind, user }),
+            (0u8..6).prop_map(|strategy| Operation::SetAllocationStrategy { strategy }),
+            (0u8..4).prop_map(|capacity| Operation::SetCapacity { capacity }),
+            (0usize..2).prop_map(|page| Operation::AddWaitlistPage { page }),
+            (0u8..4).prop_map(|user| Operation::Withdraw { user }),
+            Just(Operation::StartNewRound),
+            Just(Operation::Reset),
+        ];
+        proptest::collection::vec(operation, 0..24)
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_arbitrary_instruction_never_panics(
+            instruction_data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..256),
+            accounts in arbitrary_accounts(),
+        ) {
+            let program_id = Pubkey::default();
+            let owner = Pubkey::default();
+            let keys: Vec<Pubkey> = accounts.iter().map(|(seed, _, _)| Pubkey::new(&[*seed; PUBLIC_KEY_SIZE])).collect();
+            let signers: Vec<bool> = accounts.iter().map(|(_, is_signer, _)| *is_signer).collect();
+            let mut lamports = vec![0u64; accounts.len()];
+            let mut datas: Vec<Vec<u8>> = accounts.into_iter().map(|(_, _, data)| data).collect();
+            let infos: Vec<AccountInfo> = keys.iter().zip(signers.iter()).zip(lamports.iter_mut()).zip(datas.iter_mut())
+                .map(|(((key, is_signer), lamports), data)| AccountInfo::new(key, *is_signer, true, lamports, data, &owner, false, Epoch::default()))
+                .collect();
+            let _ = process_instruction(&program_id, &infos, &instruction_data);
+        }
+
+        // whatever gets accepted, slots fill front to back without duplicates, the final quantity
+        // is what the recipients were allocated and within the pool and its total fits a u64, only a reset or a new round ends a distribution, only a
+        // distribution past its deadline gets finalized and every new round is archived. Nobody waits
+        // while a slot is free, and the waitlist's pages hold as many intents as it counts
+        #[test]
+        fn test_database_invariants(operations in operations()) {
+            let program_id = Pubkey::default();
+            let owner = Pubkey::default();
+            let database_key = Pubkey::new_unique();
+            // seed 0 is the empty address, which every instruction rejects or treats as a free slot
+            let user_keys: Vec<Pubkey> = (0u8..4).map(|seed| Pubkey::new(&[seed; PUBLIC_KEY_SIZE])).collect();
+            let mut database_lamports = 0;
+            let mut database_data = vec![0u8; RESOURCE_DATABASE_SIZE];
+            let mut user_lamports = vec![0u64; user_keys.len()];
+            let mut user_datas = vec![Vec::new(); user_keys.len()];
+            let users: Vec<AccountInfo> = user_keys.iter().zip(user_lamports.iter_mut()).zip(user_datas.iter_mut())
+                .map(|((key, lamports), data)| AccountInfo::new(key, true, false, lamports, data, &owner, false, Epoch::default()))
+                .collect();
+            let page_keys = [Pubkey::new_unique(), Pubkey::new_unique()];
+            let mut page_lamports = [0u64; 2];
+            let mut page_datas = vec![vec![0u8; WAITLIST_PAGE_SIZE]; 2];
+            let pages: Vec<AccountInfo> = page_keys.iter().zip(page_lamports.iter_mut()).zip(page_datas.iter_mut())
+                .map(|((key, lamports), data)| AccountInfo::new(key, false, true, lamports, data, &program_id, false, Epoch::default()))
+                .collect();
+            let database = AccountInfo::new(&database_key, false, true, &mut database_lamports, &mut database_data, &owner, false, Epoch::default());
+            let clock_key = sysvar::clock::id();
+            let mut clock_lamports = 0;
+            let mut clock_data = clock_data_at(0);
+            let clock = AccountInfo::new(&clock_key, false, false, &mut clock_lamports, &mut clock_data, &owner, false, Epoch::default());
+            let history_key = Pubkey::new_unique();
+            let mut history_lamports = 0;
+            let mut history_data = vec![0u8; ROUND_HISTORY_SIZE];
+            let history = AccountInfo::new(&history_key, false, true, &mut history_lamports, &mut history_data, &program_id, false, Epoch::default());
+            let slot_hashes_key = sysvar::slot_hashes::id();
+            let mut slot_hashes_lamports = 0;
+            // a hash for every slot the operations reach, newest first
+            let mut slot_hashes_data = serialized_slot_hashes(&(0..128u64).rev().map(|slot| (slot, [slot as u8; PUBLIC_KEY_SIZE])).collect::<Vec<_>>());
+            let slot_hashes = AccountInfo::new(&slot_hashes_key, false, false, &mut slot_hashes_lamports, &mut slot_hashes_data, &owner, false, Epoch::default());
+            let mut now = 0;
+            process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).unwrap();
+
+            for operation in operations {
+                let was_distributed = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap().is_distributed;
+                let mut is_reset = false;
+                let mut is_new_round = false;
+                match operation {
+                    Operation::RegisterIntent { user, requested, household_size } => {
+                        let eligibility = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap().eligibility;
+                        let instruction_data = register_intent_data(requested, household_size);
+                        let registered = process_instruction(&program_id, &[database.clone(), users[user as usize].clone(), pages[0].clone(), pages[1].clone()], &instruction_data).is_ok();
+                        proptest::prop_assert!(!registered || eligibility.admits(&user_keys[user as usize].to_bytes(), 0));
+                    }
+                    Operation::RecordInstance { user, quantity } => {
+                        let instance = ResourceInstance {
+                            from: user_keys[user as usize].to_bytes(),
+                            quantity,
+                        };
+                        let mut instruction_data = vec![INSTRUCTION_RECORD_RESOURCE_INSTANCE];
+                        instruction_data.extend(instance.try_to_vec().unwrap());
+                        let _ = process_instruction(&program_id, &[database.clone(), users[user as usize].clone()], &instruction_data);
+                    }
+                    Operation::InitiateDistribution { duration } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), authority(), clock.clone()], &initiate_distribution_data(duration));
+                    }
+                    Operation::DrawLottery => {
+                        let _ = process_instruction(&program_id, &[database.clone(), clock.clone(), slot_hashes.clone()], &[INSTRUCTION_DRAW_LOTTERY]);
+                    }
+                    Operation::RecordChallenge { from, to, value } => {
+                        let challenge = Challenge {
+                            from: user_keys[from as usize].to_bytes(),
+                            to: user_keys[to as usize].to_bytes(),
+                            value,
+                        };
+                        let _ = process_instruction(&program_id, &[database.clone(), clock.clone(), users[from as usize].clone()], &challenge_data(&challenge));
+                    }
+                    Operation::AdvanceTime { seconds } => {
+                        now += seconds;
+                        clock.data.borrow_mut().copy_from_slice(&clock_data_at(now));
+                    }
+                    Operation::Finalize => {
+                        let _ = process_instruction(&program_id, &[database.clone(), clock.clone()], &[INSTRUCTION_FINALIZE_DISTRIBUTION]);
+                    }
+                    Operation::Claim { user } => {
+                        let _ = process_instruction(&program_id, std::slice::from_ref(&database), &claim_data(&user_keys[user as usize]));
+                    }
+                    Operation::SetQuorum { kind, threshold } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), authority()], &set_quorum_data(kind, threshold, &Pubkey::default()));
+                    }
+                    Operation::SetUnit { decimals } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), authority()], &set_unit_data("kg", decimals));
+                    }
+                    Operation::SetEligibility { kind, user } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), authority()], &set_eligibility_data(kind, &user_keys[user as usize..=user as usize]));
+                    }
+                    Operation::SetAllocationStrategy { strategy } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_SET_ALLOCATION_STRATEGY, strategy]);
+                    }
+                    Operation::SetCapacity { capacity } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), authority(), pages[0].clone(), pages[1].clone()], &[INSTRUCTION_SET_CAPACITY, capacity]);
+                    }
+                    Operation::AddWaitlistPage { page } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), pages[page].clone(), pages[1 - page].clone()], &[INSTRUCTION_ADD_WAITLIST_PAGE]);
+                    }
+                    Operation::Withdraw { user } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), users[user as usize].clone(), pages[0].clone(), pages[1].clone()], &[INSTRUCTION_WITHDRAW_INTENT]);
+                    }
+                    Operation::StartNewRound => {
+                        is_new_round = process_instruction(&program_id, &[database.clone(), history.clone(), pages[0].clone(), pages[1].clone()], &[INSTRUCTION_START_NEW_ROUND]).is_ok();
+                    }
+                    Operation::Reset => {
+                        is_reset = process_instruction(&program_id, &[database.clone(), authority()], &[INSTRUCTION_RESET_DATABASE]).is_ok();
+                    }
+                }
+
+                let resource_database = ResourceDatabase::try_from_slice(&database.data.borrow()).unwrap();
+                let empty_address = [0u8; PUBLIC_KEY_SIZE];
+                proptest::prop_assert!(!was_distributed || resource_database.is_distributed || is_reset || is_new_round);
+                if is_new_round {
+                    let round_history = RoundHistory::try_from_slice(&history.data.borrow()).unwrap();
+                    proptest::prop_assert_eq!(round_history.next_round, resource_database.round);
+                    proptest::prop_assert!(round_history.get(resource_database.round - 1).is_some());
+                }
+                proptest::prop_assert!(!resource_database.is_finalized || resource_database.is_distributed);
+                proptest::prop_assert!(!resource_database.is_finalized || resource_database.window.is_closed(now));
+
+                let intents: Vec<_> = resource_database.intents.iter()
+                    .take_while(|intent| intent.is_registered())
+                    .map(|intent| intent.recipient)
+                    .collect();
+                proptest::prop_assert!(resource_database.intents[intents.len()..].iter().all(|intent| *intent == Intent::default()));
+                for (position, intent) in intents.iter().enumerate() {
+                    proptest::prop_assert!(!intents[..position].contains(intent));
+                }
+
+                // only an equal split allocates more than was requested, and the allocations add up to the final quantity
+                for intent in resource_database.intents.iter() {
+                    proptest::prop_assert!(resource_database.strategy == ALLOCATION_EQUAL_SPLIT || intent.need.requested == 0 || intent.allocation <= intent.need.requested);
+                    proptest::prop_assert!(resource_database.is_distributed || intent.allocation == 0);
+                }
+                let allocated: u64 = resource_database.intents.iter().map(|intent| intent.allocation).sum();
+                proptest::prop_assert_eq!(allocated, resource_database.final_quantity);
+                proptest::prop_assert!(resource_database.draw == Draw::default() || (resource_database.strategy == ALLOCATION_LOTTERY && resource_database.is_distributed));
+                // a lottery is drawn from its committed slot on, and nothing is claimed or finalized before
+                proptest::prop_assert!(!resource_database.draw.is_drawn() || resource_database.draw.slot >= resource_database.draw.committed_slot);
+                proptest::prop_assert!(resource_database.is_allocated() || (!resource_database.is_finalized && resource_database.claims.iter().all(|claim| *claim == empty_address)));
+
+                // each contributor holds one slot, and no recipient is allocated more than the pool
+                let contributors: Vec<_> = resource_database.instances.iter()
+                    .take_while(|instance| instance.from != empty_address)
+                    .map(|instance| instance.from)
+                    .collect();
+                proptest::prop_assert!(resource_database.instances[contributors.len()..].iter().all(|instance| instance.from == empty_address));
+                for (position, contributor) in contributors.iter().enumerate() {
+                    proptest::prop_assert!(!contributors[..position].contains(contributor));
+                }
+                proptest::prop_assert!(resource_database.deposited().is_some());
+                proptest::prop_assert!(resource_database.final_quantity <= resource_database.deposited().unwrap());
+                proptest::prop_assert!(resource_database.unit.is_valid());
+
+                let challenges: Vec<_> = resource_database.challenges.iter()
+                    .take_while(|challenge| challenge.from != empty_address)
+                    .map(|challenge| (challenge.from, challenge.to))
+                    .collect();
+                for (position, pair) in challenges.iter().enumerate() {
+                    proptest::prop_assert!(!challenges[..position].contains(pair));
+                }
+
+                // only recipients claim, once each, and only while distributed
+                let claims: Vec<_> = resource_database.claims.iter().take_while(|claim| **claim != empty_address).collect();
+                proptest::prop_assert!(resource_database.claims[claims.len()..].iter().all(|claim| *claim == empty_address));
+                proptest::prop_assert!(claims.is_empty() || resource_database.is_distributed);
+                for (position, claim) in claims.iter().enumerate() {
+                    proptest::prop_assert!(intents.contains(*claim));
+                    proptest::prop_assert!(!claims[..position].contains(claim));
+                }
+                proptest::prop_assert!(resource_database.quorum.is_valid());
+                proptest::prop_assert!(resource_database.eligibility.is_valid());
+                proptest::prop_assert!(allocation::is_valid(resource_database.strategy));
+
+                proptest::prop_assert!(usize::from(resource_database.capacity) <= MAX_NUM_RECIPIENTS);
+                proptest::prop_assert!(intents.len() <= resource_database.capacity());
+                // newcomers wait out a distribution even with a slot open
+                proptest::prop_assert!(resource_database.waitlist.waiting == 0 || resource_database.open_slot().is_none() || resource_database.is_distributed);
+                let mut waiting = 0;
+                let mut page_key = resource_database.waitlist.head;
+                for _ in 0..pages.len() {
+                    if page_key == empty_address {
+                        break
+                    }
+                    let page = pages.iter().find(|page| page.key.to_bytes() == page_key).unwrap();
+                    let page = WaitlistPage::try_from_slice(&page.data.borrow()).unwrap();
+                    waiting += page.intents.iter().filter(|intent| intent.is_registered()).count();
+                    if page_key == resource_database.waitlist.tail {
+                        break
+                    }
+                    page_key = page.next;
+                }
+                proptest::prop_assert_eq!(waiting, resource_database.waitlist.waiting as usize);
+            }
+        }
     }
 }
@@ -1,4 +1,5 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use crate::allocation;
 
 pub const PUBLIC_KEY_SIZE: usize = 32;
 
@@ -9,6 +10,23 @@ pub const INSTRUCTION_INITIATE_DISTRIBUTION: u8 = 3;
 pub const INSTRUCTION_RECORD_CHALLENGE: u8 = 4;
 pub const INSTRUCTION_CLAIM_CHALLENGE: u8 = 5;
 pub const INSTRUCTION_RESET_DATABASE: u8 = 6;
+pub const INSTRUCTION_FINALIZE_DISTRIBUTION: u8 = 7;
+pub const INSTRUCTION_SET_QUORUM: u8 = 8;
+pub const INSTRUCTION_START_NEW_ROUND: u8 = 9;
+pub const INSTRUCTION_SET_ESCROW_MINT: u8 = 10;
+pub const INSTRUCTION_SET_LAMPORT_VAULT: u8 = 11;
+pub const INSTRUCTION_REFUND_CONTRIBUTION: u8 = 12;
+pub const INSTRUCTION_ISSUE_RECEIPT: u8 = 13;
+pub const INSTRUCTION_SET_UNIT: u8 = 14;
+pub const INSTRUCTION_SET_ELIGIBILITY: u8 = 15;
+pub const INSTRUCTION_SET_ALLOCATION_STRATEGY: u8 = 16;
+pub const INSTRUCTION_SET_CAPACITY: u8 = 17;
+pub const INSTRUCTION_ADD_WAITLIST_PAGE: u8 = 18;
+pub const INSTRUCTION_WITHDRAW_INTENT: u8 = 19;
+pub const INSTRUCTION_DRAW_LOTTERY: u8 = 20;
+
+// seed of the address that signs for every escrow token account, together with the database key
+pub const ESCROW_SEED: &[u8] = b"escrow";
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
 pub struct TrustTableEntry {
@@ -18,11 +36,34 @@ pub struct TrustTableEntry {
 
 pub const MAX_TRUST_TABLE_SIZE: usize = 1;
 pub const MAX_FRIENDLY_NAME_SIZE: usize = 32;
+pub const MAX_NUM_INTENTS: usize = 3;
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Reputation {
+    pub approvals_given: u32,
+    pub approvals_received: u32,
+    pub claims: u32,
+    pub disputes: u32,
+    pub next_settle_slots: [u64; MAX_NUM_INTENTS],
+}
 
+impl Reputation {
+    // same score the search engine computes
+    pub fn score(&self) -> u32 {
+        self.approvals_given
+            .saturating_add(self.approvals_received)
+            .saturating_add(self.claims)
+            .saturating_sub(self.disputes)
+    }
+}
+
+// mirrors the search engine's accounts, read for trust and reputation weighted quorums
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub struct SearchEngineAccount {
     pub friendly_name: [u8; MAX_FRIENDLY_NAME_SIZE],
     pub trust_table: [TrustTableEntry; MAX_TRUST_TABLE_SIZE],
+    pub intents: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+    pub reputation: Reputation,
 }
 
 pub const MAX_ZIP_SIZE: usize = 32;
@@ -62,23 +103,535 @@ pub struct Challenge {
     pub value: bool,
 }
 
+// One contributor's part of the round's pool. Recording again from the same contributor adds
+// to their instance, so each contributor holds one slot.
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
 pub struct ResourceInstance {
     pub from: [u8; PUBLIC_KEY_SIZE],
-    pub quantity: u8,
+    // in the database's unit, counting its smallest fraction
+    pub quantity: u64,
+}
+
+pub const MAX_UNIT_NAME_SIZE: usize = 16;
+// 10^19 is the largest power of ten a u64 holds
+pub const MAX_UNIT_DECIMALS: u8 = 19;
+
+// What quantities are counted in. A quantity q stands for q / 10^decimals of the named unit, so
+// 25 is 2.5 kg with one decimal of "kg" and 12 loaves without decimals of "loaves".
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct UnitOfMeasure {
+    pub name: [u8; MAX_UNIT_NAME_SIZE],
+    pub decimals: u8,
+}
+
+impl UnitOfMeasure {
+    pub fn is_valid(&self) -> bool {
+        self.decimals <= MAX_UNIT_DECIMALS
+    }
+}
+
+// When challenges were opened and how long they stay open, taken from the Clock sysvar
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct DistributionWindow {
+    pub start_slot: u64,
+    pub start_timestamp: i64,
+    // seconds
+    pub duration: i64,
+}
+
+impl DistributionWindow {
+    pub fn deadline(&self) -> i64 {
+        self.start_timestamp.saturating_add(self.duration)
+    }
+
+    pub fn is_closed(&self, unix_timestamp: i64) -> bool {
+        unix_timestamp >= self.deadline()
+    }
+}
+
+pub const QUORUM_ALL: u8 = 0;
+pub const QUORUM_MAJORITY: u8 = 1;
+pub const QUORUM_AT_LEAST: u8 = 2;
+pub const QUORUM_TRUST_WEIGHTED: u8 = 3;
+pub const QUORUM_REPUTATION_WEIGHTED: u8 = 4;
+
+// How many of a recipient's peers have to approve them before they can claim. Kept as a
+// fixed size struct rather than an enum so the database size does not depend on the rule.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct QuorumRule {
+    pub kind: u8,
+    // approvals for QUORUM_AT_LEAST, summed trust or reputation for the weighted rules
+    pub threshold: u16,
+    // owner of the approvers' search engine accounts, for the weighted rules
+    pub trust_program: [u8; PUBLIC_KEY_SIZE],
+}
+
+impl QuorumRule {
+    pub fn is_valid(&self) -> bool {
+        match self.kind {
+            QUORUM_ALL | QUORUM_MAJORITY => true,
+            QUORUM_AT_LEAST => self.threshold > 0,
+            QUORUM_TRUST_WEIGHTED | QUORUM_REPUTATION_WEIGHTED => self.threshold > 0 && self.trust_program != [0u8; PUBLIC_KEY_SIZE],
+            _ => false,
+        }
+    }
+
+    pub fn is_weighted(&self) -> bool {
+        self.kind == QUORUM_TRUST_WEIGHTED || self.kind == QUORUM_REPUTATION_WEIGHTED
+    }
+
+    // `weight` is what the approvers trust the recipient with, or their own reputation scores,
+    // summed. A recipient without peers meets QUORUM_ALL and QUORUM_MAJORITY since there is no
+    // one to ask.
+    pub fn is_met(&self, peers: u32, approvals: u32, weight: u32) -> bool {
+        match self.kind {
+            QUORUM_ALL => approvals == peers,
+            QUORUM_MAJORITY => peers == 0 || approvals * 2 > peers,
+            QUORUM_AT_LEAST => approvals >= u32::from(self.threshold),
+            QUORUM_TRUST_WEIGHTED | QUORUM_REPUTATION_WEIGHTED => weight >= u32::from(self.threshold),
+            _ => false,
+        }
+    }
+}
+
+pub const ELIGIBILITY_OPEN: u8 = 0;
+pub const ELIGIBILITY_ALLOW_LIST: u8 = 1;
+pub const ELIGIBILITY_DENY_LIST: u8 = 2;
+pub const ELIGIBILITY_TRUSTED_BY_PROVIDER: u8 = 3;
+
+pub const MAX_ELIGIBILITY_LIST_SIZE: usize = 4;
+
+// Who may register an intent. Only RegisterIntent checks it, so recipients registered before the
+// rule changed keep their slot. Fixed size for the same reason as QuorumRule.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct EligibilityRule {
+    pub kind: u8,
+    // the allow or deny list, unused slots are zero
+    pub list: [[u8; PUBLIC_KEY_SIZE]; MAX_ELIGIBILITY_LIST_SIZE],
+    // for ELIGIBILITY_TRUSTED_BY_PROVIDER, the provider's search engine account and its owner
+    pub provider: [u8; PUBLIC_KEY_SIZE],
+    pub trust_program: [u8; PUBLIC_KEY_SIZE],
+    // trust the provider's table has to give the recipient
+    pub min_trust: u8,
+}
+
+impl EligibilityRule {
+    pub fn is_valid(&self) -> bool {
+        match self.kind {
+            ELIGIBILITY_OPEN | ELIGIBILITY_ALLOW_LIST | ELIGIBILITY_DENY_LIST => true,
+            ELIGIBILITY_TRUSTED_BY_PROVIDER => self.min_trust > 0 && self.provider != [0u8; PUBLIC_KEY_SIZE] && self.trust_program != [0u8; PUBLIC_KEY_SIZE],
+            _ => false,
+        }
+    }
+
+    // `trust` is what the provider's trust table gives the recipient, only read by
+    // ELIGIBILITY_TRUSTED_BY_PROVIDER
+    pub fn admits(&self, recipient: &[u8; PUBLIC_KEY_SIZE], trust: u8) -> bool {
+        let listed = *recipient != [0u8; PUBLIC_KEY_SIZE] && self.list.contains(recipient);
+        match self.kind {
+            ELIGIBILITY_OPEN => true,
+            ELIGIBILITY_ALLOW_LIST => listed,
+            ELIGIBILITY_DENY_LIST => !listed,
+            ELIGIBILITY_TRUSTED_BY_PROVIDER => trust >= self.min_trust,
+            _ => false,
+        }
+    }
+}
+
+// What a recipient asks for with their intent. A zero quantity asks for an even share of the
+// pool, per member of the household, which counts one person when no size is given.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Need {
+    // in the database's unit
+    pub requested: u64,
+    pub household_size: u16,
+}
+
+impl Need {
+    pub fn household(&self) -> u64 {
+        u64::from(self.household_size.max(1))
+    }
+}
+
+// A registered recipient, unused slots are zero
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Intent {
+    pub recipient: [u8; PUBLIC_KEY_SIZE],
+    pub need: Need,
+    // what InitiateDistribution allocated them, paid out when they claim
+    pub allocation: u64,
+}
+
+impl Intent {
+    pub fn is_registered(&self) -> bool {
+        self.recipient != [0u8; PUBLIC_KEY_SIZE]
+    }
+}
+
+// how InitiateDistribution splits the pool between the intents, see allocation.rs
+pub const ALLOCATION_PROPORTIONAL: u8 = 0;
+pub const ALLOCATION_EQUAL_SPLIT: u8 = 1;
+pub const ALLOCATION_MAX_MIN_FAIR: u8 = 2;
+pub const ALLOCATION_FIRST_COME_FIRST_SERVED: u8 = 3;
+pub const ALLOCATION_LOTTERY: u8 = 4;
+
+// slots between the one InitiateDistribution lands in and the one a lottery is committed to, so
+// nobody sending it can know the hash that draws
+pub const DRAW_DELAY: u64 = 4;
+
+// A lottery is committed to a slot past InitiateDistribution, then DrawLottery draws it with the
+// SlotHashes entry of the first slot at or after it. The draw only depends on the hash, and both
+// slots are kept, so anyone can check it and recompute it from the database long after the slot
+// left the sysvar.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Draw {
+    pub committed_slot: u64,
+    // zero until drawn
+    pub slot: u64,
+    pub hash: [u8; PUBLIC_KEY_SIZE],
+}
+
+impl Draw {
+    pub fn is_drawn(&self) -> bool {
+        self.slot != 0
+    }
 }
 
-pub const MAX_NUM_RECIPIENTS: usize = 2;
-pub const MAX_NUM_RESOURCE_INSTANCES: usize = MAX_NUM_RECIPIENTS;
+// recipients taking part in one round, the capacity can lower it and the rest wait. Each ordered
+// pair of them has a challenge, so the database grows with the square of it
+pub const MAX_NUM_RECIPIENTS: usize = 8;
+// contributors to one round's pool
+pub const MAX_NUM_RESOURCE_INSTANCES: usize = 16;
 pub const MAX_NUM_CHALLENGES: usize = MAX_NUM_RECIPIENTS * MAX_NUM_RECIPIENTS;
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub struct ResourceDatabase {
     pub is_distributed: bool,
-    pub final_quantity: u8,
-    pub intents: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+    // what the recipients were allocated in total, at most what was deposited
+    pub final_quantity: u64,
+    pub intents: [Intent; MAX_NUM_RECIPIENTS],
     pub instances: [ResourceInstance; MAX_NUM_RESOURCE_INSTANCES],
     pub challenges: [Challenge; MAX_NUM_CHALLENGES],
-    pub claims: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS]
+    pub claims: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+    pub window: DistributionWindow,
+    // challenges still outstanding when the window closed count as denied
+    pub is_finalized: bool,
+    pub quorum: QuorumRule,
+    // rounds archived by StartNewRound so far, ResetDatabase starts over at 0
+    pub round: u32,
+    // SPL token mint backing the resource, zero for resources that only record a quantity
+    pub escrow_mint: [u8; PUBLIC_KEY_SIZE],
+    // program owned account holding the lamports of SOL denominated resources, zero otherwise
+    pub vault: [u8; PUBLIC_KEY_SIZE],
+    // per contributor, whether they were refunded their share of what was left unclaimed
+    pub refunded: [bool; MAX_NUM_RESOURCE_INSTANCES],
+    // per claim, whether IssueReceipt already issued its receipt
+    pub receipts: [bool; MAX_NUM_RECIPIENTS],
+    // set by SetUnit while nothing is recorded, carries over to the next round
+    pub unit: UnitOfMeasure,
+    // set by SetEligibility while not distributing, carries over to the next round
+    pub eligibility: EligibilityRule,
+    // set by SetAllocationStrategy before anyone registers or contributes, carries over to the
+    // next round
+    pub strategy: u8,
+    // zero unless the round is allocated by lottery
+    pub draw: Draw,
+    // how many intents take part in a round, zero leaves every slot open. Set by SetCapacity
+    // while not distributing, carries over to the next round
+    pub capacity: u8,
+    // intents registered once every slot up to the capacity was taken
+    pub waitlist: Waitlist,
+    // the round history every StartNewRound archives into, bound by the first one and kept by
+    // ResetDatabase
+    pub history: [u8; PUBLIC_KEY_SIZE],
+    // signs every setup instruction, the signer of the ResetDatabase that created the database
+    pub authority: [u8; PUBLIC_KEY_SIZE],
+}
+pub const RESOURCE_DATABASE_SIZE: usize = 6010;
+
+impl ResourceDatabase {
+    pub fn is_escrowed(&self) -> bool {
+        self.escrow_mint != [0u8; PUBLIC_KEY_SIZE]
+    }
+
+    pub fn has_vault(&self) -> bool {
+        self.vault != [0u8; PUBLIC_KEY_SIZE]
+    }
+
+    // None once the recorded quantities add up to more than a u64 holds
+    pub fn deposited(&self) -> Option<u64> {
+        self.instances.iter().try_fold(0u64, |sum, instance| sum.checked_add(instance.quantity))
+    }
+
+    // the contributor's slot, if they contributed this round
+    pub fn contributor(&self, contributor: &[u8; PUBLIC_KEY_SIZE]) -> Option<usize> {
+        if *contributor == [0u8; PUBLIC_KEY_SIZE] {
+            return None
+        }
+        self.instances.iter().position(|instance| instance.from == *contributor)
+    }
+
+    // the recipient's slot, if they registered an intent
+    pub fn intent(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> Option<usize> {
+        if *recipient == [0u8; PUBLIC_KEY_SIZE] {
+            return None
+        }
+        self.intents.iter().position(|intent| intent.recipient == *recipient)
+    }
+
+    pub fn capacity(&self) -> usize {
+        match self.capacity {
+            0 => MAX_NUM_RECIPIENTS,
+            capacity => usize::from(capacity),
+        }
+    }
+
+    // the first free slot below the capacity. Intents stay packed at the front in the order
+    // they got their slot, so it is also how many hold one
+    pub fn open_slot(&self) -> Option<usize> {
+        self.intents.iter()
+            .take(self.capacity())
+            .position(|intent| !intent.is_registered())
+    }
+
+    // frees the slot, the intents after it move up and keep their order
+    pub fn remove_intent(&mut self, index: usize) {
+        for next in index + 1..MAX_NUM_RECIPIENTS {
+            self.intents[next - 1] = self.intents[next];
+        }
+        self.intents[MAX_NUM_RECIPIENTS - 1] = Intent::default();
+    }
+
+    // what the recipient was allocated this round, zero if they have no intent
+    pub fn allocation(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> u64 {
+        self.intent(recipient).map(|index| self.intents[index].allocation).unwrap_or(0)
+    }
+
+    // a lottery only allocates once DrawLottery drew it, nothing is claimed before
+    pub fn is_allocated(&self) -> bool {
+        self.strategy != ALLOCATION_LOTTERY || self.draw.is_drawn()
+    }
+
+    // sets every intent's allocation with the database's strategy
+    pub fn allocate(&mut self, pool: u64) {
+        self.final_quantity = allocation::allocate(self.strategy, pool, &self.draw.hash, &mut self.intents);
+    }
+
+    // every claim was paid its allocation
+    pub fn paid_out(&self) -> Option<u64> {
+        self.claims.iter()
+            .filter(|claim| **claim != [0u8; PUBLIC_KEY_SIZE])
+            .try_fold(0u64, |sum, claim| sum.checked_add(self.allocation(claim)))
+    }
+
+    // what goes back to a contributor once the distribution is finalized: their share of what
+    // was deposited and not allocated or not claimed, in proportion to what they deposited.
+    // Zero once refunded. The last contributor refunded also takes the rounding leftovers, so
+    // nothing stays with the backing.
+    pub fn refund(&self, contributor: &[u8; PUBLIC_KEY_SIZE]) -> Option<u64> {
+        let deposited = self.deposited()?;
+        let index = match self.contributor(contributor) {
+            Some(index) if deposited > 0 && self.instances[index].quantity > 0 && !self.refunded[index] => index,
+            _ => return Some(0),
+        };
+        let remainder = deposited.saturating_sub(self.paid_out()?);
+        // widened so the product can't overflow, the share is at most the remainder
+        let share = |quantity: u64| (u128::from(remainder) * u128::from(quantity) / u128::from(deposited)) as u64;
+        let mut others = self.instances.iter().zip(self.refunded.iter()).enumerate()
+            .filter(|(other, (instance, _))| *other != index && instance.quantity > 0);
+        if others.clone().all(|(_, (_, refunded))| *refunded) {
+            let refunded = others.try_fold(0u64, |sum, (_, (instance, _))| sum.checked_add(share(instance.quantity)))?;
+            return Some(remainder.saturating_sub(refunded))
+        }
+        Some(share(self.instances[index].quantity))
+    }
+
+    // a round backed by tokens or lamports closes once every contributor got their refund back.
+    // While anything is left to refund, a contributor not refunded yet is outstanding even if
+    // their share rounds down to zero: the last one refunded takes the rounding leftovers
+    pub fn has_outstanding_refunds(&self) -> Option<bool> {
+        let remainder = self.deposited()?.saturating_sub(self.paid_out()?);
+        if remainder == 0 {
+            return Some(false)
+        }
+        Some(self.instances.iter().zip(self.refunded.iter())
+            .any(|(instance, refunded)| instance.from != [0u8; PUBLIC_KEY_SIZE] && instance.quantity > 0 && !refunded))
+    }
+
+    // deposits of a backed round are owed until the finalized round paid or refunded all of them
+    pub fn has_outstanding_deposits(&self) -> Option<bool> {
+        if !(self.is_escrowed() || self.has_vault()) || self.deposited()? == 0 {
+            return Some(false)
+        }
+        if !self.is_finalized {
+            return Some(true)
+        }
+        self.has_outstanding_refunds()
+    }
+}
+
+// Where a database's waitlist starts and ends. Intents wait in the order they registered: the
+// head page holds the longest waiting one, new ones are appended to the tail page.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default, Copy)]
+pub struct Waitlist {
+    pub head: [u8; PUBLIC_KEY_SIZE],
+    pub tail: [u8; PUBLIC_KEY_SIZE],
+    // intents waiting over every page
+    pub waiting: u32,
+}
+
+impl Waitlist {
+    pub fn has_pages(&self) -> bool {
+        self.tail != [0u8; PUBLIC_KEY_SIZE]
+    }
+}
+
+pub const WAITLIST_PAGE_CAPACITY: usize = 4;
+
+// One page of a database's waitlist. AddWaitlistPage binds it to the database and chains it
+// after the tail, so the waitlist grows a page at a time with no bound on its length. A page
+// drained by promotions leaves the chain and can be added again.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct WaitlistPage {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+    // the page after this one, zero for the tail
+    pub next: [u8; PUBLIC_KEY_SIZE],
+    // in the order they registered, promoted ones are cleared from the front
+    pub intents: [Intent; WAITLIST_PAGE_CAPACITY],
+}
+pub const WAITLIST_PAGE_SIZE: usize = 264;
+
+impl WaitlistPage {
+    pub fn is_empty(&self) -> bool {
+        !self.intents.iter().any(|intent| intent.is_registered())
+    }
+
+    // queues the intent after the last waiting one, None once the page's last slot is taken
+    pub fn push(&mut self, intent: Intent) -> Option<()> {
+        let index = self.intents.iter()
+            .rposition(|intent| intent.is_registered())
+            .map_or(0, |last| last + 1);
+        *self.intents.get_mut(index)? = intent;
+        Some(())
+    }
+
+    // takes the longest waiting intent off the page
+    pub fn pop(&mut self) -> Option<Intent> {
+        let index = self.intents.iter().position(|intent| intent.is_registered())?;
+        let intent = self.intents[index];
+        self.intents[index] = Intent::default();
+        Some(intent)
+    }
+}
+
+// The account a vault database keeps its lamports in. It is bound to the database by
+// SetLamportVault, so one vault never holds the lamports of two databases.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct LamportVault {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+}
+pub const LAMPORT_VAULT_SIZE: usize = 32;
+
+// Proof that a recipient claimed from a database, issued once per claim by IssueReceipt.
+// Only the resource program can write it and nothing moves it to another recipient, so
+// whoever finds it owned by the program can trust what it records.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Default)]
+pub struct ClaimReceipt {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+    pub recipient: [u8; PUBLIC_KEY_SIZE],
+    pub round: u32,
+    // the recipient's allocation
+    pub quantity: u64,
+    pub unit: UnitOfMeasure,
+}
+pub const CLAIM_RECEIPT_SIZE: usize = 93;
+
+impl ClaimReceipt {
+    pub fn is_issued(&self) -> bool {
+        self.database != [0u8; PUBLIC_KEY_SIZE]
+    }
+}
+
+// A settled round as StartNewRound archived it, with what each recipient was allocated
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Copy)]
+pub struct RoundRecord {
+    pub round: u32,
+    pub window: DistributionWindow,
+    pub final_quantity: u64,
+    pub intents: [Intent; MAX_NUM_RECIPIENTS],
+    pub claims: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+    pub challenges: [Challenge; MAX_NUM_CHALLENGES],
+    pub unit: UnitOfMeasure,
+    // how the round was allocated, so a lottery can be drawn again from the archive
+    pub strategy: u8,
+    pub draw: Draw,
 }
-pub const RESOURCE_DATABASE_SIZE: usize = 456;
\ No newline at end of file
+
+// the challenges are too many for arrays to derive it
+impl Default for RoundRecord {
+    fn default() -> Self {
+        RoundRecord {
+            round: 0,
+            window: DistributionWindow::default(),
+            final_quantity: 0,
+            intents: [Intent::default(); MAX_NUM_RECIPIENTS],
+            claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+            challenges: [Challenge::default(); MAX_NUM_CHALLENGES],
+            unit: UnitOfMeasure::default(),
+            strategy: 0,
+            draw: Draw::default(),
+        }
+    }
+}
+
+impl RoundRecord {
+    pub fn allocation(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> u64 {
+        if *recipient == [0u8; PUBLIC_KEY_SIZE] || !self.claims.contains(recipient) {
+            return 0
+        }
+        self.intents.iter().find(|intent| intent.recipient == *recipient).map(|intent| intent.allocation).unwrap_or(0)
+    }
+
+    pub fn approvals_received(&self, recipient: &[u8; PUBLIC_KEY_SIZE]) -> u32 {
+        self.challenges.iter().filter(|challenge| challenge.to == *recipient && challenge.value).count() as u32
+    }
+}
+
+pub const MAX_NUM_ROUNDS: usize = 4;
+
+// The last MAX_NUM_ROUNDS rounds of one database, round n kept at n % MAX_NUM_ROUNDS. The
+// database is bound on the first archive so rounds of different databases never mix.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
+pub struct RoundHistory {
+    pub database: [u8; PUBLIC_KEY_SIZE],
+    // one past the latest archived round
+    pub next_round: u32,
+    pub rounds: [RoundRecord; MAX_NUM_ROUNDS],
+}
+pub const ROUND_HISTORY_SIZE: usize = 19708;
+
+impl RoundHistory {
+    // None once the round was overwritten, or if it was never archived
+    pub fn get(&self, round: u32) -> Option<&RoundRecord> {
+        if round >= self.next_round || round as usize + MAX_NUM_ROUNDS < self.next_round as usize {
+            return None
+        }
+        let record = &self.rounds[round as usize % MAX_NUM_ROUNDS];
+        if record.round == round {
+            Some(record)
+        } else {
+            None
+        }
+    }
+
+    // oldest first
+    pub fn rounds(&self) -> Vec<&RoundRecord> {
+        let first = self.next_round.saturating_sub(MAX_NUM_ROUNDS as u32);
+        (first..self.next_round).filter_map(|round| self.get(round)).collect()
+    }
+
+    pub fn archive(&mut self, record: RoundRecord) {
+        self.rounds[record.round as usize % MAX_NUM_ROUNDS] = record;
+        self.next_round = record.round.saturating_add(1);
+    }
+}
\ No newline at end of file
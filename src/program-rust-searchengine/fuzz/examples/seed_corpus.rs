@@ -0,0 +1,103 @@
+// Seeds corpus/process_instruction with every step from a fresh index to alice settling a
+// finalized round, each input holding the accounts as the steps before left them, and
+// corpus/unpack with their instruction data. Every step has to succeed, so the fuzzer starts
+// from accounts the program accepts.
+use std::{collections::BTreeMap, fs, path::Path};
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_bpf_searchengine::types::{
+    INSTRUCTION_UPDATE_ACCOUNT, INSTRUCTION_REGISTER_RESOURCE, INSTRUCTION_REGISTER_INTENT,
+    INSTRUCTION_INITIALIZE_DATABASE, INSTRUCTION_SETTLE_INTENT, PUBLIC_KEY_SIZE, MAX_FRIENDLY_NAME_SIZE,
+    MAX_ZIP_SIZE, MAX_NUM_INTENTS, RESOURCE_INDEX_SIZE, SEARCH_ENGINE_ACCOUNT_SIZE,
+    SearchEngineAccount, Resource, Location, TrustTableEntry, Reputation,
+};
+use solana_bpf_resource::types::{ResourceDatabase, Challenge, RESOURCE_DATABASE_SIZE};
+use solana_bpf_searchengine_fuzz::{key, Account, Input, SIGNER, FOREIGN_OWNER, FOREIGN_PROGRAM};
+
+const AUTHORITY: u8 = 7;
+const ALICE: u8 = 1;
+const BOB: u8 = 2;
+const INDEX: u8 = 0x10;
+const RESOURCE_DATABASE: u8 = 0x20;
+
+// the accounts every step reads and writes, by key seed
+struct Ledger {
+    accounts: BTreeMap<u8, Account>,
+}
+
+impl Ledger {
+    fn set(&mut self, seed: u8, data: Vec<u8>) {
+        self.accounts.insert(seed, Account { seed, data, ..Account::default() });
+    }
+
+    // writes the step to the corpus, then runs it and keeps what it wrote
+    fn step(&mut self, corpus: &Path, name: &str, accounts: &[(u8, u8)], instruction_data: Vec<u8>) {
+        let mut input = Input {
+            accounts: accounts.iter().map(|(seed, flags)| Account {
+                flags: *flags,
+                ..self.accounts.get(seed).cloned().unwrap_or(Account { seed: *seed, ..Account::default() })
+            }).collect(),
+            instruction_data,
+        };
+        let bytes = input.to_bytes();
+        // the target reads back the same accounts
+        assert_eq!(Input::from_bytes(&bytes).to_bytes(), bytes);
+        fs::write(corpus.join("process_instruction").join(name), bytes).unwrap();
+        fs::write(corpus.join("unpack").join(name), &input.instruction_data).unwrap();
+        if let Err(error) = input.process() {
+            panic!("{}: {:?}", name, error);
+        }
+        for account in input.accounts.into_iter() {
+            self.accounts.insert(account.seed, Account { flags: 0, ..account });
+        }
+    }
+}
+
+fn with_data<T: BorshSerialize>(instruction: u8, data: &T) -> Vec<u8> {
+    let mut instruction_data = vec![instruction];
+    instruction_data.extend(data.try_to_vec().unwrap());
+    instruction_data
+}
+
+fn padded<T: Default + AsMut<[u8]>>(text: &str) -> T {
+    let mut bytes = T::default();
+    bytes.as_mut()[..text.len()].copy_from_slice(text.as_bytes());
+    bytes
+}
+
+fn main() {
+    let corpus = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus");
+    fs::create_dir_all(corpus.join("process_instruction")).unwrap();
+    fs::create_dir_all(corpus.join("unpack")).unwrap();
+
+    let mut ledger = Ledger { accounts: BTreeMap::new() };
+    ledger.set(INDEX, vec![0u8; RESOURCE_INDEX_SIZE]);
+    ledger.set(ALICE, vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE]);
+    // a round of the resource program's database, finalized with bob approving alice's claim
+    let mut resource_database = ResourceDatabase::try_from_slice(&[0u8; RESOURCE_DATABASE_SIZE]).unwrap();
+    resource_database.is_distributed = true;
+    resource_database.is_finalized = true;
+    resource_database.window.start_slot = 100;
+    resource_database.challenges[0] = Challenge { from: key(BOB).to_bytes(), to: key(ALICE).to_bytes(), value: true };
+    resource_database.claims[0] = key(ALICE).to_bytes();
+    ledger.set(RESOURCE_DATABASE, resource_database.try_to_vec().unwrap());
+    let ledger = &mut ledger;
+    let corpus = corpus.as_path();
+
+    // the resource program is what registers, its databases settle
+    let resource = key(FOREIGN_PROGRAM).to_bytes();
+    ledger.step(corpus, "initialize_database", &[(INDEX, 0), (AUTHORITY, SIGNER)], vec![INSTRUCTION_INITIALIZE_DATABASE]);
+    ledger.step(corpus, "register_resource", &[(INDEX, 0), (AUTHORITY, SIGNER)], with_data(INSTRUCTION_REGISTER_RESOURCE, &Resource {
+        address: resource,
+        name: padded::<[u8; MAX_FRIENDLY_NAME_SIZE]>("bread"),
+        location: Location { zip: padded::<[u8; MAX_ZIP_SIZE]>("12345") },
+        trust_threshold: 0,
+    }));
+    ledger.step(corpus, "update_account", &[(ALICE, SIGNER)], with_data(INSTRUCTION_UPDATE_ACCOUNT, &SearchEngineAccount {
+        friendly_name: padded::<[u8; MAX_FRIENDLY_NAME_SIZE]>("alice"),
+        trust_table: [TrustTableEntry { to: key(BOB).to_bytes(), value: 1 }],
+        intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+        reputation: Reputation::default(),
+    }));
+    ledger.step(corpus, "register_intent", &[(INDEX, 0), (ALICE, SIGNER), (FOREIGN_PROGRAM, 0)], vec![INSTRUCTION_REGISTER_INTENT]);
+    ledger.step(corpus, "settle_intent", &[(INDEX, 0), (ALICE, SIGNER), (RESOURCE_DATABASE, FOREIGN_OWNER)], vec![INSTRUCTION_SETTLE_INTENT]);
+}
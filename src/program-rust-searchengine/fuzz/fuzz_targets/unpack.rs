@@ -0,0 +1,7 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use solana_bpf_searchengine::instruction::SearchEngineInstruction;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = SearchEngineInstruction::unpack(data);
+});
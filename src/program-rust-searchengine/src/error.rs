@@ -20,6 +20,26 @@ pub enum SearchEngineError {
     /// Someone is trying to create an intent for a Resource that does not exist in the Database
     #[error("Resource Not Registered")]
     ResourceNotRegistered,
+
+    /// The account never registered an intent for the resource it is settling
+    #[error("Intent Not Registered")]
+    IntentNotRegistered,
+
+    /// Outcomes are only counted once the resource's distribution is finalized
+    #[error("Distribution Not Finalized")]
+    DistributionNotFinalized,
+
+    /// This distribution, or one that started later, was already settled for the account
+    #[error("Already Settled")]
+    AlreadySettled,
+
+    /// Registering resources takes the signature of the authority the index was initialized with
+    #[error("Wrong Authority")]
+    WrongAuthority,
+
+    /// The round history is not the one the resource database archives its rounds into
+    #[error("Wrong Round History")]
+    WrongRoundHistory,
 }
 impl From<SearchEngineError> for ProgramError {
     fn from(e: SearchEngineError) -> Self {
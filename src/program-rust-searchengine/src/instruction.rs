@@ -3,6 +3,8 @@ use crate::types::{
     INSTRUCTION_UPDATE_ACCOUNT,
     INSTRUCTION_REGISTER_RESOURCE,
     INSTRUCTION_REGISTER_INTENT,
+    INSTRUCTION_INITIALIZE_DATABASE,
+    INSTRUCTION_SETTLE_INTENT,
     SearchEngineAccount,
     Resource,
 };
@@ -18,6 +20,8 @@ pub enum SearchEngineInstruction {
     UpdateAccount(SearchEngineAccount),
     RegisterResource(Resource),
     RegisterIntent(),
+    InitializeDatabase(),
+    SettleIntent(),
 }
 
 impl SearchEngineInstruction {
@@ -46,6 +50,12 @@ impl SearchEngineInstruction {
             INSTRUCTION_REGISTER_INTENT => {
                 Self::RegisterIntent()
             }
+            INSTRUCTION_INITIALIZE_DATABASE => {
+                Self::InitializeDatabase()
+            }
+            INSTRUCTION_SETTLE_INTENT => {
+                Self::SettleIntent()
+            }
             _ => return Err(InvalidInstruction.into()),
         })
     }
@@ -73,6 +83,7 @@ mod test {
         MAX_ZIP_SIZE,
         PUBLIC_KEY_SIZE,
         MAX_NUM_INTENTS,
+        Reputation,
     };
 
     #[test]
@@ -113,6 +124,7 @@ mod test {
                 value: 10,
             }; MAX_TRUST_TABLE_SIZE],
             intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
         };
         data.append(&mut search_engine_account.try_to_vec().unwrap());
 
@@ -138,9 +150,9 @@ mod test {
         let resource = Resource {
             address: Pubkey::new_unique().to_bytes(),
             location: Location {
-                zip: zip,
+                zip,
             },
-            name: name,
+            name,
             trust_threshold: 10,
         };
         data.append(&mut resource.try_to_vec().unwrap());
@@ -152,11 +164,36 @@ mod test {
 
     #[test]
     fn test_unpack_register_intent() {
-        let mut data = Vec::new();
-        data.push(INSTRUCTION_REGISTER_INTENT);
+        let data = vec![INSTRUCTION_REGISTER_INTENT];
 
         let result = SearchEngineInstruction::unpack(&data).unwrap();
         let expected = SearchEngineInstruction::RegisterIntent();
         assert_eq!(expected, result);
     }
+
+    #[test]
+    fn test_unpack_initialize_database() {
+        let data = vec![INSTRUCTION_INITIALIZE_DATABASE];
+
+        let result = SearchEngineInstruction::unpack(&data).unwrap();
+        let expected = SearchEngineInstruction::InitializeDatabase();
+        assert_eq!(expected, result);
+    }
+
+    #[test]
+    fn test_unpack_settle_intent() {
+        let data = vec![INSTRUCTION_SETTLE_INTENT];
+
+        let result = SearchEngineInstruction::unpack(&data).unwrap();
+        let expected = SearchEngineInstruction::SettleIntent();
+        assert_eq!(expected, result);
+    }
+
+    proptest::proptest! {
+        // arbitrary bytes decode to an instruction or an error, never a panic
+        #[test]
+        fn test_unpack_arbitrary(data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..256)) {
+            let _ = SearchEngineInstruction::unpack(&data);
+        }
+    }
 }
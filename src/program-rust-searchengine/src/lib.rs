@@ -2,13 +2,20 @@ pub mod types;
 pub mod error;
 pub mod instruction;
 
-use crate::types::{Resource, ResourceIndex, SearchEngineAccount, MAX_ZIP_SIZE, PUBLIC_KEY_SIZE};
+use crate::types::{
+    Resource, ResourceIndex, ResourceBucket, SearchEngineAccount, Reputation,
+    MAX_ZIP_SIZE, PUBLIC_KEY_SIZE, MAX_INDEX_BUCKETS, RESOURCE_INDEX_SIZE,
+};
 use crate::instruction::{SearchEngineInstruction};
-use crate::error::SearchEngineError::{DatabaseFull, BucketFull, ResourceNotRegistered};
+use crate::error::SearchEngineError::{
+    DatabaseFull, BucketFull, ResourceNotRegistered, IntentNotRegistered, DistributionNotFinalized, AlreadySettled,
+    WrongAuthority, WrongRoundHistory,
+};
 use solana_program::{
-    account_info::AccountInfo, entrypoint, entrypoint::ProgramResult, info, pubkey::Pubkey,
+    account_info::AccountInfo, entrypoint::ProgramResult, info, pubkey::Pubkey,
     program_error::ProgramError,
 };
+use solana_bpf_resource::types::{ResourceDatabase, RoundHistory, Challenge};
 use borsh::{BorshSerialize, BorshDeserialize};
 
 /*
@@ -16,8 +23,8 @@ Search Engine
 fields
 * resources Map<Location, Resource>
 operations
-* register_resource(accounts:[owner, program], data:None)
-  auth: accounts[0].is_signer == true and accounts[1].owner ==  accounts[0]
+* register_resource(accounts:[database, authority], data:Resource)
+  auth: searchengine_id == accounts[0].owner and accounts[1] == database.authority and accounts[1].is_signer == true
 * list_resources(accounts:[], data:Location)
   auth: none
 * update_trust_table(accounts:[owner], data:TrustTable)
@@ -28,10 +35,50 @@ operations
   auth: searchengine_id == accounts[0].owner and accounts[0].is_signer == true
 * list_intents(accounts[search], data:None)
   auth: none
+* initialize_database(accounts:[database, authority], data:None)
+  auth: accounts[1].is_signer == true, the database must be freshly created (all zero) and sized RESOURCE_INDEX_SIZE
+* settle_intent(accounts:[database, owner, resource database, round history], data:None)
+  auth: searchengine_id == accounts[0].owner, the resource database must be owned by a registered resource, its
+  archived rounds and a finalized current one are settled; the round history is only needed once it is bound
 */
 
+// Instructions index into the account list, so a short list is an error rather than a panic
+fn account_at<'a, 'b>(accounts: &'a [AccountInfo<'b>], index: usize) -> Result<&'a AccountInfo<'b>, ProgramError> {
+    accounts.get(index).ok_or(ProgramError::NotEnoughAccountKeys)
+}
+
+// Resources are only trusted from this program's index, anyone can make up an account that
+// looks like one
+fn index_at(program_id: &Pubkey, accounts: &[AccountInfo]) -> Result<ResourceIndex, ProgramError> {
+    let database = account_at(accounts, 0)?;
+    if database.owner != program_id {
+        return Err(ProgramError::IncorrectProgramId)
+    }
+    ResourceIndex::try_from_slice(&database.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)
+}
+
+// Counts a round's outcomes towards the account's reputation, challenges left unanswered when
+// the window closed count as disputes
+fn settle(reputation: &mut Reputation, key: &[u8; PUBLIC_KEY_SIZE], challenges: &[Challenge], claims: &[[u8; PUBLIC_KEY_SIZE]]) {
+    // challenges from an empty slot are placeholders for recipients that never registered
+    for challenge in challenges.iter().filter(|challenge| challenge.from != [0; PUBLIC_KEY_SIZE]) {
+        if challenge.from == *key && challenge.value {
+            reputation.approvals_given = reputation.approvals_given.saturating_add(1);
+        }
+        if challenge.to == *key && challenge.value {
+            reputation.approvals_received = reputation.approvals_received.saturating_add(1);
+        }
+        if challenge.to == *key && !challenge.value {
+            reputation.disputes = reputation.disputes.saturating_add(1);
+        }
+    }
+    if claims.contains(key) {
+        reputation.claims = reputation.claims.saturating_add(1);
+    }
+}
+
 fn _process_instruction(
-    _program_id: &Pubkey,
+    program_id: &Pubkey,
     accounts: &[AccountInfo],
     instruction_data: &[u8],
 ) -> ProgramResult {
@@ -40,13 +87,22 @@ fn _process_instruction(
         SearchEngineInstruction::Default() => {
             info!("OK")
         }
-        SearchEngineInstruction::UpdateAccount(account) => {
+        SearchEngineInstruction::UpdateAccount(mut account) => {
             info!("trying to update account");
-            // TODO check accounts length 1 and first account is signer
-            accounts[0].key.log();
-            let mut account_data = accounts[0].try_borrow_mut_data().unwrap();
+            // TODO check first account is signer
+            let account_info = account_at(accounts, 0)?;
+            account_info.key.log();
+            let mut account_data = account_info.try_borrow_mut_data()?;
+            // reputation is earned through SettleIntent, never written by the owner
+            account.reputation = match SearchEngineAccount::deserialize(&mut &account_data[..]) {
+                Ok(existing) => existing.reputation,
+                Err(_) => Reputation::default(),
+            };
             let account = account.try_to_vec().unwrap();
             info!(&account.len().to_string());
+            if account_data.len() < account.len() {
+                return Err(ProgramError::AccountDataTooSmall)
+            }
             info!("copying data account");
             account_data[..account.len()].copy_from_slice(&account);
         }
@@ -57,9 +113,16 @@ fn _process_instruction(
             trust_threshold: _,
         }) => {
             info!("trying to register resource");
-            // TODO check accounts length and first account is owned by this program id
-            let mut database = accounts[0].try_borrow_mut_data().unwrap();
-            let mut index = ResourceIndex::try_from_slice(&database).unwrap();
+            let mut index = index_at(program_id, accounts)?;
+            // a registered resource settles reputation, so the index's authority vouches for it
+            let authority = account_at(accounts, 1)?;
+            if authority.key.to_bytes() != index.authority {
+                return Err(ProgramError::from(WrongAuthority))
+            }
+            if !authority.is_signer {
+                return Err(ProgramError::MissingRequiredSignature)
+            }
+            let mut database = account_at(accounts, 0)?.try_borrow_mut_data()?;
             let empty_zip = [0u8; MAX_ZIP_SIZE];
             let empty_address = [0u8; PUBLIC_KEY_SIZE];
             for bucket in index.buckets.iter_mut() {
@@ -91,12 +154,12 @@ fn _process_instruction(
         SearchEngineInstruction::RegisterIntent() => {
             info!("registering intent");
             // check if resource is registered in search engine
-            let database = accounts[0].try_borrow_mut_data().unwrap();
-            let index = ResourceIndex::try_from_slice(&database).unwrap();
+            let index = index_at(program_id, accounts)?;
+            let resource_data_key_bytes = account_at(accounts, 2)?.key.to_bytes();
             let mut found = false;
             for bucket in index.buckets.iter() {
                 for bucket_address in bucket.addresses.iter() {
-                    if *bucket_address == accounts[2].key.to_bytes() {
+                    if *bucket_address == resource_data_key_bytes {
                         found = true;
                         break;
                     }
@@ -109,9 +172,8 @@ fn _process_instruction(
             
             // cache the intent in the account for faster reference
             let empty_address = [0u8; PUBLIC_KEY_SIZE];
-            let mut account_data = accounts[1].try_borrow_mut_data().unwrap();
-            let resource_data_key_bytes = accounts[2].key.to_bytes();
-            let mut account = SearchEngineAccount::try_from_slice(&account_data).unwrap();
+            let mut account_data = account_at(accounts, 1)?.try_borrow_mut_data()?;
+            let mut account = SearchEngineAccount::try_from_slice(&account_data).map_err(|_| ProgramError::InvalidAccountData)?;
             for id in account.intents.iter_mut() {
                 if *id == resource_data_key_bytes {
                     info!("intent already exists");
@@ -128,15 +190,99 @@ fn _process_instruction(
             // register intent with Resource via cross program invocation
             // TODO
         }
+        SearchEngineInstruction::InitializeDatabase() => {
+            info!("initializing database");
+            let mut database = account_at(accounts, 0)?.try_borrow_mut_data()?;
+            if database.len() != RESOURCE_INDEX_SIZE {
+                return Err(ProgramError::InvalidAccountData)
+            }
+            // refuse to wipe an index that already has resources registered
+            if database.iter().any(|byte| *byte != 0) {
+                return Err(ProgramError::AccountAlreadyInitialized)
+            }
+            // whoever initializes the index registers its resources
+            let authority = account_at(accounts, 1)?;
+            if !authority.is_signer {
+                return Err(ProgramError::MissingRequiredSignature)
+            }
+            let index = ResourceIndex {
+                buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+                authority: authority.key.to_bytes(),
+            };
+            database.copy_from_slice(&index.try_to_vec().unwrap());
+        }
+        SearchEngineInstruction::SettleIntent() => {
+            info!("settling intent");
+            let index = index_at(program_id, accounts)?;
+            let account_info = account_at(accounts, 1)?;
+            let resource_database_info = account_at(accounts, 2)?;
+
+            // the resource database is only trusted when its program is registered
+            let resource = resource_database_info.owner.to_bytes();
+            if !index.buckets.iter().any(|bucket| bucket.addresses.contains(&resource)) {
+                return Err(ProgramError::from(ResourceNotRegistered))
+            }
+
+            let mut account_data = account_info.try_borrow_mut_data()?;
+            let mut account = SearchEngineAccount::try_from_slice(&account_data).map_err(|_| ProgramError::InvalidAccountData)?;
+            // settlement is tracked per resource, so each program's distributions keep their own order
+            let slot = account.intents.iter().position(|intent| *intent == resource).ok_or(IntentNotRegistered)?;
+
+            // the database and its history are boxed, either is larger than the stack frame
+            let resource_database = Box::<ResourceDatabase>::try_from_slice(&resource_database_info.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)?;
+            let key = account_info.key.to_bytes();
+            let reputation = &mut account.reputation;
+            let mut settled = false;
+
+            // rounds archived since the last settlement count too, so starting a new round does
+            // not wipe outcomes nobody settled yet. Only the history the database binds is read
+            if resource_database.history != [0u8; PUBLIC_KEY_SIZE] {
+                let history_info = account_at(accounts, 3)?;
+                if history_info.key.to_bytes() != resource_database.history || history_info.owner != resource_database_info.owner {
+                    return Err(ProgramError::from(WrongRoundHistory))
+                }
+                let history = Box::<RoundHistory>::try_from_slice(&history_info.try_borrow_data()?).map_err(|_| ProgramError::InvalidAccountData)?;
+                if history.database != resource_database_info.key.to_bytes() {
+                    return Err(ProgramError::from(WrongRoundHistory))
+                }
+                // distributions are counted in the order they started, once each
+                for record in history.rounds() {
+                    let start_slot = record.window.start_slot;
+                    if start_slot < reputation.next_settle_slots[slot] {
+                        continue
+                    }
+                    settle(reputation, &key, &record.challenges, &record.claims);
+                    reputation.next_settle_slots[slot] = start_slot.saturating_add(1);
+                    settled = true;
+                }
+            }
+
+            if resource_database.is_finalized {
+                let start_slot = resource_database.window.start_slot;
+                if start_slot >= reputation.next_settle_slots[slot] {
+                    settle(reputation, &key, &resource_database.challenges, &resource_database.claims);
+                    reputation.next_settle_slots[slot] = start_slot.saturating_add(1);
+                    settled = true;
+                }
+            }
+            if !settled {
+                if resource_database.is_finalized {
+                    return Err(ProgramError::from(AlreadySettled))
+                }
+                return Err(ProgramError::from(DistributionNotFinalized))
+            }
+            account_data.copy_from_slice(&account.try_to_vec().unwrap());
+        }
     }
     Ok(())
 }
 
-// Declare and export the program's entrypoint
-entrypoint!(process_instruction);
+// Declare and export the program's entrypoint, unless another crate links the program in
+#[cfg(not(feature = "no-entrypoint"))]
+solana_program::entrypoint!(process_instruction);
 
 // Program entrypoint's implementation
-fn process_instruction(
+pub fn process_instruction(
     program_id: &Pubkey, // Public key of the account the hello world program was loaded into
     accounts: &[AccountInfo], // The account to say hello to
     instruction_data: &[u8], // Ignored, all helloworld instructions are hellos
@@ -167,11 +313,14 @@ mod test {
             INSTRUCTION_UPDATE_ACCOUNT,
             INSTRUCTION_REGISTER_RESOURCE,
             INSTRUCTION_REGISTER_INTENT,
+            INSTRUCTION_INITIALIZE_DATABASE,
+            INSTRUCTION_SETTLE_INTENT,
+            SEARCH_ENGINE_ACCOUNT_SIZE,
             TrustTableEntry,
             Location,
-            ResourceBucket,
         },
     };
+    use solana_bpf_resource::types::{Intent, RoundRecord, RESOURCE_DATABASE_SIZE, ROUND_HISTORY_SIZE};
     use solana_program::clock::Epoch;
     use solana_program::program_error::ProgramError;
     use borsh::{BorshSerialize, BorshDeserialize};
@@ -182,7 +331,7 @@ mod test {
         let program_id = Pubkey::default();
         let key = Pubkey::default();
         let mut lamports = 0;
-        let mut data = vec![0u8; 161];
+        let mut data = vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE];
         let owner = Pubkey::default();
         let account = AccountInfo::new(
             &key,
@@ -198,7 +347,7 @@ mod test {
 
         let instruction_data: Vec<u8> = Vec::new();
         let result = process_instruction(&program_id, &accounts, &instruction_data);
-        let _result = match result {
+        match result {
             Ok(_ok) => assert_eq!(true, false, "process_instruction should have triggered error"),
             Err(err) => assert_eq!(
                 ProgramError::from(SearchEngineError::InvalidInstruction),
@@ -212,7 +361,7 @@ mod test {
         let program_id = Pubkey::default();
         let key = Pubkey::default();
         let mut lamports = 0;
-        let mut data = vec![0u8; 161];
+        let mut data = vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE];
         let owner = Pubkey::default();
         let account = AccountInfo::new(
             &key,
@@ -243,22 +392,57 @@ mod test {
                 }; MAX_TRUST_TABLE_SIZE
             ],
             intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
         };
         instruction_data.append(&mut search_engine_account.try_to_vec().unwrap());
 
         println!("{:?}", instruction_data);
-        let result = process_instruction(&program_id, &accounts, &instruction_data).unwrap();
-        assert_eq!((), result);
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
         assert_eq!(accounts[0].data.borrow().len(), search_engine_account.try_to_vec().unwrap().len());
     }
 
+    #[test]
+    fn test_update_account_keeps_reputation() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let earned = Reputation {
+            approvals_given: 1,
+            approvals_received: 2,
+            claims: 1,
+            disputes: 0,
+            next_settle_slots: [8, 0, 0],
+        };
+        let mut account = SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: earned,
+        };
+        let mut data = account.try_to_vec().unwrap();
+        let account_info = AccountInfo::new(&key, false, true, &mut lamports, &mut data, &owner, false, Epoch::default());
+
+        // the owner can rename itself but not vouch for itself
+        account.friendly_name[0] = b'j';
+        account.reputation.claims = 100;
+        let mut instruction_data = vec![INSTRUCTION_UPDATE_ACCOUNT];
+        instruction_data.append(&mut account.try_to_vec().unwrap());
+        process_instruction(&program_id, std::slice::from_ref(&account_info), &instruction_data).unwrap();
+
+        let stored = SearchEngineAccount::try_from_slice(&account_info.data.borrow()).unwrap();
+        assert_eq!(stored.friendly_name[0], b'j');
+        assert_eq!(stored.reputation, earned);
+    }
+
     #[test]
     fn test_register_resource() {
         let program_id = Pubkey::default();
         let key = Pubkey::default();
+        let authority_key = Pubkey::new_unique();
         let mut lamports = 0;
         // provision database
-        let mut database_data = vec![0u8; 384];
+        let mut database_data = vec![0u8; RESOURCE_INDEX_SIZE];
         let index = ResourceIndex{
             buckets: [ResourceBucket{
                 addresses: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RESOURCE_IN_BUCKET],
@@ -266,6 +450,7 @@ mod test {
                     zip: [0u8; MAX_ZIP_SIZE],
                 },
             }; MAX_NUM_RESOURCE_IN_BUCKET],
+            authority: authority_key.to_bytes(),
         };
         database_data.copy_from_slice(&index.try_to_vec().unwrap());   
 
@@ -280,7 +465,10 @@ mod test {
             false,
             Epoch::default(),
         );
-        let accounts = vec![database_account];
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+        let authority = AccountInfo::new(&authority_key, true, false, &mut authority_lamports, &mut authority_data, &owner, false, Epoch::default());
+        let accounts = vec![database_account, authority];
 
         let mut instruction_data: Vec<u8> = Vec::new();
         instruction_data.push(INSTRUCTION_REGISTER_RESOURCE);
@@ -296,18 +484,30 @@ mod test {
             *place = *data
         }
         let location = Location{
-            zip: zip,
+            zip,
         };
         let resource = Resource{
             address: to_pubkey.to_bytes(),
-            name: name,
-            location: location,
+            name,
+            location,
             trust_threshold: 10,
         };
         instruction_data.append(&mut resource.try_to_vec().unwrap());
 
-        let result = process_instruction(&program_id, &accounts, &instruction_data).unwrap();
-        assert_eq!((), result);
+        // only the index's authority registers resources, and only in this program's index
+        let result = process_instruction(&program_id, &accounts[..1], &instruction_data);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let unsigned = AccountInfo { is_signer: false, ..accounts[1].clone() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), unsigned], &instruction_data);
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        let stranger = AccountInfo { key: &to_pubkey, ..accounts[1].clone() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), stranger], &instruction_data);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::WrongAuthority)), result);
+        let foreign = AccountInfo { owner: &to_pubkey, ..accounts[0].clone() };
+        let result = process_instruction(&program_id, &[foreign, accounts[1].clone()], &instruction_data);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
 
         let database = ResourceIndex::try_from_slice(&database_data).unwrap();
         println!("{:?}", database);
@@ -343,7 +543,7 @@ mod test {
         );
 
         let mut database_lamports = 0;
-        let mut database_data = vec![0u8; 384];
+        let mut database_data = vec![0u8; RESOURCE_INDEX_SIZE];
         let index = ResourceIndex{
             buckets: [ResourceBucket{
                 // every address will be the resource
@@ -352,6 +552,7 @@ mod test {
                     zip: [0u8; MAX_ZIP_SIZE],
                 },
             }; MAX_NUM_RESOURCE_IN_BUCKET],
+            authority: [0u8; PUBLIC_KEY_SIZE],
         };
         database_data.copy_from_slice(&index.try_to_vec().unwrap());   
         let database_account = AccountInfo::new(
@@ -365,7 +566,7 @@ mod test {
             Epoch::default(),
         );
         let mut search_engine_account_lamports = 0;
-        let mut search_engine_account_data = vec![0u8; 161];
+        let mut search_engine_account_data = vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE];
         let search_engine_account = AccountInfo::new(
             &key,
             false,
@@ -379,12 +580,460 @@ mod test {
         
         let accounts = vec![database_account, search_engine_account, resource_account];
 
-        let mut instruction_data: Vec<u8> = Vec::new();
-        instruction_data.push(INSTRUCTION_REGISTER_INTENT);
+        let instruction_data: Vec<u8> = vec![INSTRUCTION_REGISTER_INTENT];
 
-        let result = process_instruction(&program_id, &accounts, &instruction_data).unwrap();
-        assert_eq!((), result);
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
         let search_engine_account_deserialized = SearchEngineAccount::try_from_slice(&search_engine_account_data).unwrap();
         assert_eq!(search_engine_account_deserialized.intents[0], resource_account_key.to_bytes());
     }
+
+    #[test]
+    fn test_initialize_database() {
+        let program_id = Pubkey::default();
+        let key = Pubkey::default();
+        let owner = Pubkey::default();
+        let mut lamports = 0;
+        let mut database_data = vec![0u8; RESOURCE_INDEX_SIZE];
+        let database_account = AccountInfo::new(
+            &key,
+            false,
+            true,
+            &mut lamports,
+            &mut database_data,
+            &owner,
+            false,
+            Epoch::default(),
+        );
+        let authority_key = Pubkey::new_unique();
+        let mut authority_lamports = 0;
+        let mut authority_data = vec![];
+        let authority = AccountInfo::new(&authority_key, true, false, &mut authority_lamports, &mut authority_data, &owner, false, Epoch::default());
+        let accounts = vec![database_account, authority];
+
+        let instruction_data: Vec<u8> = vec![INSTRUCTION_INITIALIZE_DATABASE];
+
+        // whoever initializes the index signs for it
+        let unsigned = AccountInfo { is_signer: false, ..accounts[1].clone() };
+        let result = process_instruction(&program_id, &[accounts[0].clone(), unsigned], &instruction_data);
+        assert_eq!(Err(ProgramError::MissingRequiredSignature), result);
+        process_instruction(&program_id, &accounts, &instruction_data).unwrap();
+        let index = ResourceIndex::try_from_slice(&accounts[0].data.borrow()).unwrap();
+        assert_eq!(index.authority, authority_key.to_bytes());
+
+        // a second initialization would wipe registered resources
+        accounts[0].try_borrow_mut_data().unwrap()[0] = 1;
+        let result = process_instruction(&program_id, &accounts, &instruction_data);
+        assert_eq!(Err(ProgramError::AccountAlreadyInitialized), result);
+    }
+
+    // a finalized distribution between `user` and one peer, who approved `user`
+    fn finalized_database(user: &Pubkey, peer: &Pubkey, start_slot: u64) -> ResourceDatabase {
+        // the resource program owns the layout, start from an empty database
+        let mut database = ResourceDatabase::try_from_slice(&[0u8; RESOURCE_DATABASE_SIZE]).unwrap();
+        database.is_distributed = true;
+        database.final_quantity = 1;
+        database.intents[0] = Intent { recipient: user.to_bytes(), allocation: 1, ..Intent::default() };
+        database.intents[1] = Intent { recipient: peer.to_bytes(), ..Intent::default() };
+        database.window.start_slot = start_slot;
+        database.is_finalized = true;
+        database.challenges[0] = Challenge {
+            from: user.to_bytes(),
+            to: peer.to_bytes(),
+            value: false,
+        };
+        database.challenges[1] = Challenge {
+            from: peer.to_bytes(),
+            to: user.to_bytes(),
+            value: true,
+        };
+        database.claims[0] = user.to_bytes();
+        database
+    }
+
+    #[test]
+    fn test_settle_intent() {
+        let program_id = Pubkey::default();
+        let owner = Pubkey::default();
+        let resource_program = Pubkey::new_unique();
+        let user_key = Pubkey::new_unique();
+        let peer_key = Pubkey::new_unique();
+        let database_key = Pubkey::new_unique();
+        let resource_database_key = Pubkey::new_unique();
+
+        let mut index = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        };
+        index.buckets[0].addresses[0] = resource_program.to_bytes();
+        let mut database_lamports = 0;
+        let mut database_data = index.try_to_vec().unwrap();
+        let database = AccountInfo::new(&database_key, false, false, &mut database_lamports, &mut database_data, &owner, false, Epoch::default());
+
+        let mut user = SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        let mut user_lamports = 0;
+        let mut user_data = user.try_to_vec().unwrap();
+        let user_info = AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, Epoch::default());
+
+        let mut resource_database = finalized_database(&user_key, &peer_key, 5);
+        let mut resource_lamports = 0;
+        let mut resource_data = resource_database.try_to_vec().unwrap();
+        let resource_info = AccountInfo::new(&resource_database_key, false, false, &mut resource_lamports, &mut resource_data, &resource_program, false, Epoch::default());
+        let accounts = vec![database, user_info.clone(), resource_info.clone()];
+
+        // only resources the account showed interest in, listed in this program's index
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::IntentNotRegistered)), result);
+        let foreign = AccountInfo { owner: &resource_program, ..accounts[0].clone() };
+        let result = process_instruction(&program_id, &[foreign, user_info.clone(), resource_info.clone()], &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::IncorrectProgramId), result);
+
+        user.intents[0] = resource_program.to_bytes();
+        user_info.try_borrow_mut_data().unwrap().copy_from_slice(&user.try_to_vec().unwrap());
+        resource_database.is_finalized = false;
+        resource_info.try_borrow_mut_data().unwrap().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::DistributionNotFinalized)), result);
+
+        resource_database.is_finalized = true;
+        resource_info.try_borrow_mut_data().unwrap().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]).unwrap();
+        let settled = SearchEngineAccount::try_from_slice(&user_info.data.borrow()).unwrap();
+        assert_eq!(settled.reputation, Reputation {
+            approvals_given: 0,
+            approvals_received: 1,
+            claims: 1,
+            disputes: 0,
+            next_settle_slots: [6, 0, 0],
+        });
+        assert_eq!(settled.reputation.score(), 2);
+
+        // the same distribution is counted once
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::AlreadySettled)), result);
+    }
+
+    #[test]
+    fn test_settle_intent_per_resource() {
+        let program_id = Pubkey::default();
+        let owner = Pubkey::default();
+        let first_program = Pubkey::new_unique();
+        let second_program = Pubkey::new_unique();
+        let user_key = Pubkey::new_unique();
+        let peer_key = Pubkey::new_unique();
+        let database_key = Pubkey::new_unique();
+        let first_database_key = Pubkey::new_unique();
+        let second_database_key = Pubkey::new_unique();
+
+        let mut index = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        };
+        index.buckets[0].addresses[0] = first_program.to_bytes();
+        index.buckets[0].addresses[1] = second_program.to_bytes();
+        let mut database_lamports = 0;
+        let mut database_data = index.try_to_vec().unwrap();
+        let database = AccountInfo::new(&database_key, false, false, &mut database_lamports, &mut database_data, &owner, false, Epoch::default());
+
+        let mut user = SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        user.intents[0] = first_program.to_bytes();
+        user.intents[1] = second_program.to_bytes();
+        let mut user_lamports = 0;
+        let mut user_data = user.try_to_vec().unwrap();
+        let user_info = AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, Epoch::default());
+
+        let mut first_lamports = 0;
+        let mut first_data = finalized_database(&user_key, &peer_key, 9).try_to_vec().unwrap();
+        let first_info = AccountInfo::new(&first_database_key, false, false, &mut first_lamports, &mut first_data, &first_program, false, Epoch::default());
+        let mut second_lamports = 0;
+        let mut second_data = finalized_database(&user_key, &peer_key, 4).try_to_vec().unwrap();
+        let second_info = AccountInfo::new(&second_database_key, false, false, &mut second_lamports, &mut second_data, &second_program, false, Epoch::default());
+
+        let accounts = vec![database.clone(), user_info.clone(), first_info];
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]).unwrap();
+
+        // another resource's distribution that started earlier still counts
+        let accounts = vec![database, user_info.clone(), second_info];
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]).unwrap();
+        let settled = SearchEngineAccount::try_from_slice(&user_info.data.borrow()).unwrap();
+        assert_eq!(settled.reputation.next_settle_slots, [10, 5, 0]);
+        assert_eq!(settled.reputation.approvals_received, 2);
+        assert_eq!(settled.reputation.claims, 2);
+    }
+
+    #[test]
+    fn test_settle_intent_from_round_history() {
+        let program_id = Pubkey::default();
+        let owner = Pubkey::default();
+        let resource_program = Pubkey::new_unique();
+        let (user_key, peer_key) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (database_key, resource_database_key, history_key) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let mut index = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        };
+        index.buckets[0].addresses[0] = resource_program.to_bytes();
+        let mut database_lamports = 0;
+        let mut database_data = index.try_to_vec().unwrap();
+        let mut user = SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        user.intents[0] = resource_program.to_bytes();
+        let mut user_lamports = 0;
+        let mut user_data = user.try_to_vec().unwrap();
+
+        // two rounds were archived before anyone settled, the third is finalized
+        let mut history = RoundHistory::try_from_slice(&[0u8; ROUND_HISTORY_SIZE]).unwrap();
+        history.database = resource_database_key.to_bytes();
+        for (round, start_slot) in [(0u32, 5u64), (1, 7)].iter() {
+            let archived = finalized_database(&user_key, &peer_key, *start_slot);
+            history.archive(RoundRecord {
+                round: *round,
+                window: archived.window,
+                challenges: archived.challenges,
+                claims: archived.claims,
+                ..RoundRecord::default()
+            });
+        }
+        let mut resource_database = finalized_database(&user_key, &peer_key, 9);
+        resource_database.history = history_key.to_bytes();
+        let mut resource_lamports = 0;
+        let mut resource_data = resource_database.try_to_vec().unwrap();
+        let mut history_lamports = 0;
+        let mut history_data = history.try_to_vec().unwrap();
+        let mut other_lamports = 0;
+        let mut other_data = RoundHistory::try_from_slice(&[0u8; ROUND_HISTORY_SIZE]).unwrap().try_to_vec().unwrap();
+        let database = AccountInfo::new(&database_key, false, false, &mut database_lamports, &mut database_data, &owner, false, Epoch::default());
+        let user_info = AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, Epoch::default());
+        let resource_info = AccountInfo::new(&resource_database_key, false, false, &mut resource_lamports, &mut resource_data, &resource_program, false, Epoch::default());
+        let history_info = AccountInfo::new(&history_key, false, false, &mut history_lamports, &mut history_data, &resource_program, false, Epoch::default());
+        let other_history = AccountInfo::new(&history_key, false, false, &mut other_lamports, &mut other_data, &resource_program, false, Epoch::default());
+        let accounts = vec![database.clone(), user_info.clone(), resource_info.clone(), history_info.clone()];
+
+        // only the history the database archives into, and that archives the database
+        let result = process_instruction(&program_id, &accounts[..3], &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::NotEnoughAccountKeys), result);
+        let wrong_key = AccountInfo { key: &peer_key, ..history_info.clone() };
+        let result = process_instruction(&program_id, &[database.clone(), user_info.clone(), resource_info.clone(), wrong_key], &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::WrongRoundHistory)), result);
+        let wrong_owner = AccountInfo { owner: &owner, ..history_info.clone() };
+        let result = process_instruction(&program_id, &[database.clone(), user_info.clone(), resource_info.clone(), wrong_owner], &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::WrongRoundHistory)), result);
+        let result = process_instruction(&program_id, &[database.clone(), user_info.clone(), resource_info.clone(), other_history], &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::WrongRoundHistory)), result);
+
+        // the archived rounds count along with the finalized one, each once
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]).unwrap();
+        let settled = SearchEngineAccount::try_from_slice(&user_info.data.borrow()).unwrap();
+        assert_eq!(settled.reputation.approvals_received, 3);
+        assert_eq!(settled.reputation.claims, 3);
+        assert_eq!(settled.reputation.next_settle_slots, [10, 0, 0]);
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::AlreadySettled)), result);
+
+        // once archived too, the next round is not finalized yet and nothing is left to count
+        resource_database.is_finalized = false;
+        resource_database.window.start_slot = 0;
+        resource_info.try_borrow_mut_data().unwrap().copy_from_slice(&resource_database.try_to_vec().unwrap());
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::DistributionNotFinalized)), result);
+    }
+
+    #[test]
+    fn test_settle_intent_alone() {
+        let program_id = Pubkey::default();
+        let owner = Pubkey::default();
+        let resource_program = Pubkey::new_unique();
+        let user_key = Pubkey::new_unique();
+        let database_key = Pubkey::new_unique();
+        let resource_database_key = Pubkey::new_unique();
+
+        let mut index = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        };
+        index.buckets[0].addresses[0] = resource_program.to_bytes();
+        let mut database_lamports = 0;
+        let mut database_data = index.try_to_vec().unwrap();
+
+        let mut user = SearchEngineAccount {
+            friendly_name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        user.intents[0] = resource_program.to_bytes();
+        let mut user_lamports = 0;
+        let mut user_data = user.try_to_vec().unwrap();
+
+        // the only recipient, the distribution left placeholder challenges for the empty slot
+        let mut resource_database = finalized_database(&user_key, &Pubkey::default(), 0);
+        resource_database.challenges[0] = Challenge { from: user_key.to_bytes(), to: [0u8; PUBLIC_KEY_SIZE], value: false };
+        resource_database.challenges[1] = Challenge { from: [0u8; PUBLIC_KEY_SIZE], to: user_key.to_bytes(), value: false };
+        let mut resource_lamports = 0;
+        let mut resource_data = resource_database.try_to_vec().unwrap();
+        let accounts = vec![
+            AccountInfo::new(&database_key, false, false, &mut database_lamports, &mut database_data, &owner, false, Epoch::default()),
+            AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, Epoch::default()),
+            AccountInfo::new(&resource_database_key, false, false, &mut resource_lamports, &mut resource_data, &resource_program, false, Epoch::default()),
+        ];
+
+        process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]).unwrap();
+        let settled = SearchEngineAccount::try_from_slice(&accounts[1].data.borrow()).unwrap();
+        assert_eq!(settled.reputation.disputes, 0);
+        assert_eq!(settled.reputation.claims, 1);
+    }
+
+    #[test]
+    fn test_settle_intent_for_unregistered_resource() {
+        let program_id = Pubkey::default();
+        let owner = Pubkey::default();
+        let user_key = Pubkey::new_unique();
+        let mut database_lamports = 0;
+        let mut database_data = ResourceIndex {
+            buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+            authority: [0u8; PUBLIC_KEY_SIZE],
+        }.try_to_vec().unwrap();
+        let mut user_lamports = 0;
+        let mut user_data = vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE];
+        let mut resource_lamports = 0;
+        let mut resource_data = finalized_database(&user_key, &Pubkey::new_unique(), 0).try_to_vec().unwrap();
+        let resource_program = Pubkey::new_unique();
+        let database_key = Pubkey::new_unique();
+        let resource_database_key = Pubkey::new_unique();
+        let accounts = vec![
+            AccountInfo::new(&database_key, false, false, &mut database_lamports, &mut database_data, &owner, false, Epoch::default()),
+            AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, Epoch::default()),
+            AccountInfo::new(&resource_database_key, false, false, &mut resource_lamports, &mut resource_data, &resource_program, false, Epoch::default()),
+        ];
+
+        let result = process_instruction(&program_id, &accounts, &[INSTRUCTION_SETTLE_INTENT]);
+        assert_eq!(Err(ProgramError::from(SearchEngineError::ResourceNotRegistered)), result);
+    }
+
+    // (key seed, whether it signs, account data) for each account passed to an arbitrary instruction
+    fn arbitrary_accounts() -> impl proptest::strategy::Strategy<Value = Vec<(u8, bool, Vec<u8>)>> {
+        use proptest::prelude::*;
+        let data = prop_oneof![
+            proptest::collection::vec(any::<u8>(), 0..512),
+            Just(vec![0u8; RESOURCE_INDEX_SIZE]),
+            Just(vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE]),
+        ];
+        proptest::collection::vec((0u8..4, any::<bool>(), data), 0..4)
+    }
+
+    #[derive(Clone, Debug)]
+    enum Operation {
+        RegisterResource { address: u8, zip: u8 },
+        RegisterIntent { resource: u8 },
+    }
+
+    fn operations() -> impl proptest::strategy::Strategy<Value = Vec<Operation>> {
+        use proptest::prelude::*;
+        let operation = prop_oneof![
+            (1u8..8, 1u8..5).prop_map(|(address, zip)| Operation::RegisterResource { address, zip }),
+            (1u8..8).prop_map(|resource| Operation::RegisterIntent { resource }),
+        ];
+        proptest::collection::vec(operation, 0..24)
+    }
+
+    proptest::proptest! {
+        #[test]
+        fn test_arbitrary_instruction_never_panics(
+            instruction_data in proptest::collection::vec(proptest::prelude::any::<u8>(), 0..256),
+            accounts in arbitrary_accounts(),
+        ) {
+            let program_id = Pubkey::default();
+            let owner = Pubkey::default();
+            let keys: Vec<Pubkey> = accounts.iter().map(|(seed, _, _)| Pubkey::new(&[*seed; PUBLIC_KEY_SIZE])).collect();
+            let signers: Vec<bool> = accounts.iter().map(|(_, is_signer, _)| *is_signer).collect();
+            let mut lamports = vec![0u64; accounts.len()];
+            let mut datas: Vec<Vec<u8>> = accounts.into_iter().map(|(_, _, data)| data).collect();
+            let infos: Vec<AccountInfo> = keys.iter().zip(signers.iter()).zip(lamports.iter_mut()).zip(datas.iter_mut())
+                .map(|(((key, is_signer), lamports), data)| AccountInfo::new(key, *is_signer, true, lamports, data, &owner, false, Epoch::default()))
+                .collect();
+            let _ = process_instruction(&program_id, &infos, &instruction_data);
+        }
+
+        // whatever gets accepted, zips map to one bucket and a bucket lists an address once
+        #[test]
+        fn test_index_invariants(operations in operations()) {
+            let program_id = Pubkey::default();
+            let owner = Pubkey::default();
+            let database_key = Pubkey::new_unique();
+            let user_key = Pubkey::new_unique();
+            let resource_keys: Vec<Pubkey> = (0u8..8).map(|seed| Pubkey::new(&[seed; PUBLIC_KEY_SIZE])).collect();
+            let mut database_lamports = 0;
+            let mut database_data = vec![0u8; RESOURCE_INDEX_SIZE];
+            let mut user_lamports = 0;
+            let mut user_data = vec![0u8; SEARCH_ENGINE_ACCOUNT_SIZE];
+            let mut resource_lamports = vec![0u64; resource_keys.len()];
+            let mut resource_datas = vec![Vec::new(); resource_keys.len()];
+            let resources: Vec<AccountInfo> = resource_keys.iter().zip(resource_lamports.iter_mut()).zip(resource_datas.iter_mut())
+                .map(|((key, lamports), data)| AccountInfo::new(key, false, false, lamports, data, &owner, false, Epoch::default()))
+                .collect();
+            let database = AccountInfo::new(&database_key, false, true, &mut database_lamports, &mut database_data, &owner, false, Epoch::default());
+            let user = AccountInfo::new(&user_key, false, true, &mut user_lamports, &mut user_data, &owner, false, Epoch::default());
+            let authority_key = Pubkey::new_unique();
+            let mut authority_lamports = 0;
+            let mut authority_data = vec![];
+            let authority = AccountInfo::new(&authority_key, true, false, &mut authority_lamports, &mut authority_data, &owner, false, Epoch::default());
+            process_instruction(&program_id, &[database.clone(), authority.clone()], &[INSTRUCTION_INITIALIZE_DATABASE]).unwrap();
+
+            for operation in operations {
+                match operation {
+                    Operation::RegisterResource { address, zip } => {
+                        let mut zip_bytes = [0u8; MAX_ZIP_SIZE];
+                        zip_bytes[0] = b'0' + zip;
+                        let resource = crate::types::Resource {
+                            address: resource_keys[address as usize].to_bytes(),
+                            name: [0u8; MAX_FRIENDLY_NAME_SIZE],
+                            location: Location { zip: zip_bytes },
+                            trust_threshold: 0,
+                        };
+                        let mut instruction_data = vec![INSTRUCTION_REGISTER_RESOURCE];
+                        instruction_data.extend(resource.try_to_vec().unwrap());
+                        let _ = process_instruction(&program_id, &[database.clone(), authority.clone()], &instruction_data);
+                    }
+                    Operation::RegisterIntent { resource } => {
+                        let _ = process_instruction(&program_id, &[database.clone(), user.clone(), resources[resource as usize].clone()], &[INSTRUCTION_REGISTER_INTENT]);
+                    }
+                }
+
+                let index = ResourceIndex::try_from_slice(&database.data.borrow()).unwrap();
+                let empty_zip = [0u8; MAX_ZIP_SIZE];
+                let empty_address = [0u8; PUBLIC_KEY_SIZE];
+                let zips: Vec<_> = index.buckets.iter().map(|bucket| bucket.location.zip).take_while(|zip| *zip != empty_zip).collect();
+                for (position, zip) in zips.iter().enumerate() {
+                    proptest::prop_assert!(!zips[..position].contains(zip));
+                }
+                for bucket in index.buckets.iter() {
+                    let addresses: Vec<_> = bucket.addresses.iter().take_while(|address| **address != empty_address).collect();
+                    proptest::prop_assert!(bucket.addresses[addresses.len()..].iter().all(|address| *address == empty_address));
+                    for (position, address) in addresses.iter().enumerate() {
+                        proptest::prop_assert!(!addresses[..position].contains(address));
+                    }
+                }
+
+                // intents are only cached for registered resources
+                let account = SearchEngineAccount::try_from_slice(&user.data.borrow()).unwrap();
+                for intent in account.intents.iter().filter(|intent| **intent != empty_address) {
+                    proptest::prop_assert!(index.buckets.iter().any(|bucket| bucket.addresses.contains(intent)));
+                }
+            }
+        }
+    }
 }
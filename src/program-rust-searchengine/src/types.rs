@@ -6,6 +6,8 @@ pub const INSTRUCTION_DEFAULT: u8 = 0;
 pub const INSTRUCTION_UPDATE_ACCOUNT: u8 = 1;
 pub const INSTRUCTION_REGISTER_RESOURCE: u8 = 2;
 pub const INSTRUCTION_REGISTER_INTENT: u8 = 3;
+pub const INSTRUCTION_INITIALIZE_DATABASE: u8 = 4;
+pub const INSTRUCTION_SETTLE_INTENT: u8 = 5;
 
 #[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
 pub struct TrustTableEntry {
@@ -17,12 +19,37 @@ pub const MAX_TRUST_TABLE_SIZE: usize = 1;
 pub const MAX_FRIENDLY_NAME_SIZE: usize = 32;
 pub const MAX_NUM_INTENTS: usize = 3;
 
+// Outcomes of every settled distribution the account took part in, only written by SettleIntent
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy, PartialEq, Debug, Default)]
+pub struct Reputation {
+    pub approvals_given: u32,
+    pub approvals_received: u32,
+    pub claims: u32,
+    // challenges to the account that were denied, or left unanswered, by its peers
+    pub disputes: u32,
+    // per resource in the account's intents, distributions that started before this slot were
+    // already counted (or skipped), so each one is counted at most once
+    pub next_settle_slots: [u64; MAX_NUM_INTENTS],
+}
+
+impl Reputation {
+    // every approval and claim adds one, every dispute takes one away
+    pub fn score(&self) -> u32 {
+        self.approvals_given
+            .saturating_add(self.approvals_received)
+            .saturating_add(self.claims)
+            .saturating_sub(self.disputes)
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub struct SearchEngineAccount {
     pub friendly_name: [u8; MAX_FRIENDLY_NAME_SIZE],
     pub trust_table: [TrustTableEntry; MAX_TRUST_TABLE_SIZE],
     pub intents: [[u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+    pub reputation: Reputation,
 }
+pub const SEARCH_ENGINE_ACCOUNT_SIZE: usize = 201;
 
 pub const MAX_ZIP_SIZE: usize = 32;
 
@@ -52,4 +79,7 @@ pub const MAX_INDEX_BUCKETS: usize = 3;
 #[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq)]
 pub struct ResourceIndex {
     pub buckets: [ResourceBucket; MAX_INDEX_BUCKETS],
-}
\ No newline at end of file
+    // the only one registering resources, whose databases are trusted to settle reputation
+    pub authority: [u8; PUBLIC_KEY_SIZE],
+}
+pub const RESOURCE_INDEX_SIZE: usize = 416;
@@ -0,0 +1,1374 @@
+#![cfg(feature = "test-bpf")]
+// Compute units used by every instruction of both programs at several fill levels. Writes
+// re-serialize the whole ResourceIndex or ResourceDatabase, so cost grows with how full they are.
+//
+// Each case starts from account state written directly into the bank, then runs its one
+// instruction under the case's budget: a case that no longer fits fails the test. The usage
+// itself is found by bisecting the compute limit down to the smallest one that still gives the
+// same outcome, and printed as a table (run with --nocapture). Only meaningful against the BPF
+// builds, see test:program-rust:compute-units.
+use borsh::BorshSerialize;
+use solana_program::{
+    instruction::{AccountMeta, Instruction},
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    system_program,
+    sysvar,
+};
+use solana_program_test::{processor, BanksClientExt, ProgramTest};
+use solana_sdk::{
+    account::Account,
+    signature::{keypair_from_seed, Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use spl_token::state::{Account as TokenAccount, AccountState};
+use tokio::runtime::Builder;
+use solana_bpf_searchengine::types::{
+    INSTRUCTION_INITIALIZE_DATABASE,
+    INSTRUCTION_REGISTER_INTENT,
+    INSTRUCTION_REGISTER_RESOURCE,
+    INSTRUCTION_SETTLE_INTENT,
+    INSTRUCTION_UPDATE_ACCOUNT,
+    MAX_FRIENDLY_NAME_SIZE,
+    MAX_INDEX_BUCKETS,
+    MAX_NUM_INTENTS,
+    MAX_NUM_RESOURCE_IN_BUCKET,
+    MAX_TRUST_TABLE_SIZE,
+    MAX_ZIP_SIZE,
+    PUBLIC_KEY_SIZE,
+    RESOURCE_INDEX_SIZE,
+    Location,
+    Reputation,
+    Resource,
+    ResourceBucket,
+    ResourceIndex,
+    SearchEngineAccount,
+    TrustTableEntry,
+};
+use solana_bpf_resource::types::{
+    INSTRUCTION_CLAIM_CHALLENGE,
+    INSTRUCTION_FINALIZE_DISTRIBUTION,
+    INSTRUCTION_INITIATE_DISTRIBUTION,
+    INSTRUCTION_RECORD_CHALLENGE,
+    INSTRUCTION_RECORD_RESOURCE_INSTANCE,
+    INSTRUCTION_RESET_DATABASE,
+    INSTRUCTION_SET_QUORUM,
+    INSTRUCTION_START_NEW_ROUND,
+    INSTRUCTION_SET_ESCROW_MINT,
+    INSTRUCTION_SET_LAMPORT_VAULT,
+    INSTRUCTION_REFUND_CONTRIBUTION,
+    INSTRUCTION_ISSUE_RECEIPT,
+    INSTRUCTION_SET_UNIT,
+    INSTRUCTION_SET_ELIGIBILITY,
+    INSTRUCTION_SET_ALLOCATION_STRATEGY,
+    INSTRUCTION_SET_CAPACITY,
+    INSTRUCTION_ADD_WAITLIST_PAGE,
+    INSTRUCTION_WITHDRAW_INTENT,
+    INSTRUCTION_DRAW_LOTTERY,
+    ALLOCATION_PROPORTIONAL,
+    ALLOCATION_EQUAL_SPLIT,
+    ALLOCATION_MAX_MIN_FAIR,
+    ALLOCATION_FIRST_COME_FIRST_SERVED,
+    ALLOCATION_LOTTERY,
+    ELIGIBILITY_DENY_LIST,
+    ELIGIBILITY_TRUSTED_BY_PROVIDER,
+    MAX_ELIGIBILITY_LIST_SIZE,
+    MAX_NUM_CHALLENGES,
+    MAX_NUM_RECIPIENTS,
+    MAX_NUM_RESOURCE_INSTANCES,
+    MAX_NUM_ROUNDS,
+    MAX_UNIT_NAME_SIZE,
+    QUORUM_TRUST_WEIGHTED,
+    WAITLIST_PAGE_CAPACITY,
+    Challenge,
+    ClaimReceipt,
+    DistributionWindow,
+    Draw,
+    Waitlist,
+    WaitlistPage,
+    EligibilityRule,
+    Intent,
+    Need,
+    QuorumRule,
+    ResourceDatabase,
+    LamportVault,
+    ResourceInstance,
+    RoundHistory,
+    RoundRecord,
+    UnitOfMeasure,
+};
+
+// the limit used to find out what a case does when compute is not a concern
+const UNLIMITED_UNITS: u64 = 1_000_000;
+// every probe starts a bank, so usage is only bisected down to this many units
+const UNITS_RESOLUTION: u64 = 100;
+
+struct Programs {
+    searchengine_id: Pubkey,
+    resource_id: Pubkey,
+}
+
+struct Case {
+    name: String,
+    budget: u64,
+    // besides the payer
+    signers: Vec<Keypair>,
+    accounts: Vec<(Pubkey, Account)>,
+    instruction: Instruction,
+}
+
+impl Programs {
+    fn new() -> Self {
+        Programs {
+            searchengine_id: Pubkey::new_unique(),
+            resource_id: Pubkey::new_unique(),
+        }
+    }
+
+    // A fresh bank per run, on its own runtime so the bank's background work stops with it
+    fn run(&self, case: &Case, max_units: u64) -> Result<(), TransactionError> {
+        let mut program_test = ProgramTest::new(
+            "solana_bpf_searchengine",
+            self.searchengine_id,
+            processor!(solana_bpf_searchengine::process_instruction),
+        );
+        program_test.add_program(
+            "solana_bpf_resource",
+            self.resource_id,
+            processor!(solana_bpf_resource::process_instruction),
+        );
+        program_test.add_program(
+            "spl_token",
+            spl_token::id(),
+            processor!(spl_token::processor::Processor::process),
+        );
+        program_test.set_bpf_compute_max_units(max_units);
+        for (address, account) in case.accounts.iter() {
+            program_test.add_account(*address, account.clone());
+        }
+
+        let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+        runtime.block_on(async {
+            let (mut banks_client, payer, recent_blockhash) = program_test.start().await;
+            let mut transaction = Transaction::new_with_payer(std::slice::from_ref(&case.instruction), Some(&payer.pubkey()));
+            let mut signers = vec![&payer];
+            signers.extend(case.signers.iter());
+            transaction.sign(&signers, recent_blockhash);
+            banks_client.process_transaction(transaction).await.map_err(|err| err.unwrap())
+        })
+    }
+
+    // Rounded up to UNITS_RESOLUTION, None when the case does not fit its budget any more
+    fn measure(&self, case: &Case) -> Option<u64> {
+        let expected = self.run(case, UNLIMITED_UNITS);
+        if self.run(case, case.budget) != expected {
+            return None
+        }
+        let mut low = 0;
+        let mut high = case.budget;
+        while high - low > UNITS_RESOLUTION {
+            let middle = (low + high) / 2;
+            if self.run(case, middle) == expected {
+                high = middle;
+            } else {
+                low = middle;
+            }
+        }
+        Some(high)
+    }
+
+    fn check(&self, cases: Vec<Case>) {
+        let mut over_budget = Vec::new();
+        println!("{:<48} {:>8} {:>8}", "case", "units", "budget");
+        for case in cases.iter() {
+            match self.measure(case) {
+                Some(units) => println!("{:<48} {:>8} {:>8}", case.name, units, case.budget),
+                None => {
+                    println!("{:<48} {:>8} {:>8}", case.name, "over", case.budget);
+                    over_budget.push(case.name.clone());
+                }
+            }
+        }
+        assert!(over_budget.is_empty(), "over budget: {}", over_budget.join(", "));
+    }
+}
+
+fn program_account<T: BorshSerialize>(owner: &Pubkey, state: &T) -> Account {
+    let data = state.try_to_vec().unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: *owner,
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+// a wallet with enough lamports to deposit or be paid without dropping below rent exemption
+fn system_account() -> Account {
+    Account::new(1_000_000_000, 0, &system_program::id())
+}
+
+fn token_account(mint: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+    let mut data = vec![0u8; TokenAccount::LEN];
+    TokenAccount::pack(TokenAccount {
+        mint: *mint,
+        owner: *owner,
+        amount,
+        state: AccountState::Initialized,
+        ..TokenAccount::default()
+    }, &mut data).unwrap();
+    Account {
+        lamports: Rent::default().minimum_balance(data.len()),
+        data,
+        owner: spl_token::id(),
+        executable: false,
+        rent_epoch: 0,
+    }
+}
+
+fn instruction_data<T: BorshSerialize>(tag: u8, payload: &T) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.append(&mut payload.try_to_vec().unwrap());
+    data
+}
+
+fn address(seed: usize) -> [u8; PUBLIC_KEY_SIZE] {
+    [seed as u8 + 1; PUBLIC_KEY_SIZE]
+}
+
+// signs the setup instructions of every database the cases build
+fn authority() -> Keypair {
+    keypair_from_seed(&[7u8; 32]).unwrap()
+}
+
+// signs the intents the cases register, never one of the database's recipients
+fn recipient() -> Keypair {
+    keypair_from_seed(&[8u8; 32]).unwrap()
+}
+
+// signs the quantity only instances the cases record
+fn contributor() -> Keypair {
+    keypair_from_seed(&[9u8; 32]).unwrap()
+}
+
+fn zip(seed: usize) -> [u8; MAX_ZIP_SIZE] {
+    let mut zip = [0u8; MAX_ZIP_SIZE];
+    zip[..5].copy_from_slice(format!("{:05}", seed).as_bytes());
+    zip
+}
+
+// the first `buckets` buckets used, each holding `addresses` resources
+fn index(buckets: usize, addresses: usize) -> ResourceIndex {
+    let mut index = ResourceIndex {
+        buckets: [ResourceBucket::default(); MAX_INDEX_BUCKETS],
+        authority: authority().pubkey().to_bytes(),
+    };
+    for (bucket_index, bucket) in index.buckets.iter_mut().take(buckets).enumerate() {
+        bucket.location = Location {
+            zip: zip(bucket_index),
+        };
+        for (slot, bucket_address) in bucket.addresses.iter_mut().take(addresses).enumerate() {
+            *bucket_address = address(bucket_index * MAX_NUM_RESOURCE_IN_BUCKET + slot);
+        }
+    }
+    index
+}
+
+fn search_engine_account(intents: usize) -> SearchEngineAccount {
+    let mut account = SearchEngineAccount {
+        friendly_name: [1u8; MAX_FRIENDLY_NAME_SIZE],
+        trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+        intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+        reputation: Reputation::default(),
+    };
+    for (slot, intent) in account.intents.iter_mut().take(intents).enumerate() {
+        *intent = address(100 + slot);
+    }
+    account
+}
+
+// intents, instances and challenges taken by recipient seeds 0, 1, ...; the distribution
+// window never closes
+fn database(intents: usize, instances: usize, challenges: usize, is_distributed: bool) -> ResourceDatabase {
+    let mut database = ResourceDatabase {
+        is_distributed,
+        final_quantity: 0,
+        intents: [Intent::default(); MAX_NUM_RECIPIENTS],
+        instances: [ResourceInstance::default(); MAX_NUM_RESOURCE_INSTANCES],
+        challenges: [Challenge::default(); MAX_NUM_CHALLENGES],
+        claims: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_RECIPIENTS],
+        window: DistributionWindow {
+            start_slot: 0,
+            start_timestamp: 0,
+            duration: i64::MAX,
+        },
+        is_finalized: false,
+        quorum: QuorumRule::default(),
+        round: 0,
+        escrow_mint: [0u8; PUBLIC_KEY_SIZE],
+        vault: [0u8; PUBLIC_KEY_SIZE],
+        refunded: [false; MAX_NUM_RESOURCE_INSTANCES],
+        receipts: [false; MAX_NUM_RECIPIENTS],
+        unit: UnitOfMeasure::default(),
+        eligibility: EligibilityRule::default(),
+        strategy: 0,
+        draw: Draw::default(),
+        capacity: 0,
+        waitlist: Waitlist::default(),
+        history: [0u8; PUBLIC_KEY_SIZE],
+        authority: authority().pubkey().to_bytes(),
+    };
+    for (slot, intent) in database.intents.iter_mut().take(intents).enumerate() {
+        intent.recipient = address(slot);
+    }
+    for (slot, instance) in database.instances.iter_mut().take(instances).enumerate() {
+        *instance = ResourceInstance {
+            from: address(50 + slot),
+            quantity: 10,
+        };
+    }
+    for (slot, challenge) in database.challenges.iter_mut().take(challenges).enumerate() {
+        *challenge = Challenge {
+            from: address(slot),
+            to: address(slot + 1),
+            value: false,
+        };
+    }
+    database
+}
+
+// a page of the database's waitlist whose intents are taken by recipient seeds `first`,
+// `first + 1`, ...
+fn waitlist_page(database: &Pubkey, next: &Pubkey, first: usize, waiting: usize) -> WaitlistPage {
+    let mut page = WaitlistPage {
+        database: database.to_bytes(),
+        next: next.to_bytes(),
+        ..WaitlistPage::default()
+    };
+    for (slot, intent) in page.intents.iter_mut().take(waiting).enumerate() {
+        intent.recipient = address(first + slot);
+    }
+    page
+}
+
+fn searchengine_cases(programs: &Programs) -> Vec<Case> {
+    let program_id = programs.searchengine_id;
+    let database_key = Pubkey::new_unique();
+    let user_key = Pubkey::new_unique();
+    let mut cases = Vec::new();
+
+    cases.push(Case {
+        name: "UpdateAccount".to_string(),
+        budget: 20_000,
+        signers: Vec::new(),
+        accounts: vec![(user_key, program_account(&program_id, &search_engine_account(0)))],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![AccountMeta::new(user_key, false)],
+            data: instruction_data(INSTRUCTION_UPDATE_ACCOUNT, &search_engine_account(MAX_NUM_INTENTS)),
+        },
+    });
+
+    let empty_database = Account {
+        data: vec![0u8; RESOURCE_INDEX_SIZE],
+        ..program_account(&program_id, &index(0, 0))
+    };
+    cases.push(Case {
+        name: "InitializeDatabase".to_string(),
+        budget: 20_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, empty_database)],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(authority().pubkey(), true),
+            ],
+            data: vec![INSTRUCTION_INITIALIZE_DATABASE],
+        },
+    });
+
+    // (description, buckets used, addresses per bucket, zip of the new resource)
+    let fill_levels = [
+        ("empty index", 0, 0, 0),
+        ("new address in a nearly full bucket", 1, MAX_NUM_RESOURCE_IN_BUCKET - 1, 0),
+        ("new zip in the last bucket", MAX_INDEX_BUCKETS - 1, MAX_NUM_RESOURCE_IN_BUCKET, MAX_INDEX_BUCKETS - 1),
+        ("full index", MAX_INDEX_BUCKETS, MAX_NUM_RESOURCE_IN_BUCKET, MAX_INDEX_BUCKETS),
+    ];
+    for (description, buckets, addresses, new_zip) in fill_levels.iter() {
+        let resource = Resource {
+            address: address(200),
+            name: [1u8; MAX_FRIENDLY_NAME_SIZE],
+            location: Location {
+                zip: zip(*new_zip),
+            },
+            trust_threshold: 10,
+        };
+        cases.push(Case {
+            name: format!("RegisterResource, {}", description),
+            budget: 40_000,
+            signers: vec![authority()],
+            accounts: vec![(database_key, program_account(&program_id, &index(*buckets, *addresses)))],
+            instruction: Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new(database_key, false),
+                    AccountMeta::new_readonly(authority().pubkey(), true),
+                ],
+                data: instruction_data(INSTRUCTION_REGISTER_RESOURCE, &resource),
+            },
+        });
+    }
+
+    // (description, resource slot searched for, intents already cached)
+    let fill_levels = [
+        ("first resource, no intents", 0, 0),
+        ("last resource, intents nearly full", MAX_INDEX_BUCKETS * MAX_NUM_RESOURCE_IN_BUCKET - 1, MAX_NUM_INTENTS - 1),
+    ];
+    for (description, slot, intents) in fill_levels.iter() {
+        let resource_key = Pubkey::new(&address(*slot));
+        cases.push(Case {
+            name: format!("RegisterIntent, {}", description),
+            budget: 40_000,
+            signers: Vec::new(),
+            accounts: vec![
+                (database_key, program_account(&program_id, &index(MAX_INDEX_BUCKETS, MAX_NUM_RESOURCE_IN_BUCKET))),
+                (user_key, program_account(&program_id, &search_engine_account(*intents))),
+            ],
+            instruction: Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(database_key, false),
+                    AccountMeta::new(user_key, false),
+                    AccountMeta::new_readonly(resource_key, false),
+                ],
+                data: vec![INSTRUCTION_REGISTER_INTENT],
+            },
+        });
+    }
+
+    // the worst case: the resource is the last one in a full index and the last intent of a
+    // full account, and every challenge is read
+    let resource_slot = MAX_INDEX_BUCKETS * MAX_NUM_RESOURCE_IN_BUCKET - 1;
+    let resource_key = Pubkey::new(&address(resource_slot));
+    let resource_database_key = Pubkey::new_unique();
+    let mut account = search_engine_account(MAX_NUM_INTENTS);
+    account.intents[MAX_NUM_INTENTS - 1] = address(resource_slot);
+    let mut finalized = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, MAX_NUM_CHALLENGES, true);
+    finalized.is_finalized = true;
+    cases.push(Case {
+        name: "SettleIntent, full index and challenges".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &index(MAX_INDEX_BUCKETS, MAX_NUM_RESOURCE_IN_BUCKET))),
+            (user_key, program_account(&program_id, &account)),
+            (resource_database_key, program_account(&resource_key, &finalized)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(database_key, false),
+                AccountMeta::new(user_key, false),
+                AccountMeta::new_readonly(resource_database_key, false),
+            ],
+            data: vec![INSTRUCTION_SETTLE_INTENT],
+        },
+    });
+
+    // every archived round is settled along with the finalized one, each with every challenge
+    let history_key = Pubkey::new_unique();
+    finalized.history = history_key.to_bytes();
+    finalized.window.start_slot = MAX_NUM_ROUNDS as u64;
+    let mut history = RoundHistory {
+        database: resource_database_key.to_bytes(),
+        next_round: 0,
+        rounds: [RoundRecord::default(); MAX_NUM_ROUNDS],
+    };
+    for round in 0..MAX_NUM_ROUNDS as u32 {
+        history.archive(RoundRecord {
+            round,
+            window: DistributionWindow {
+                start_slot: round as u64,
+                ..finalized.window
+            },
+            challenges: finalized.challenges,
+            claims: finalized.claims,
+            ..RoundRecord::default()
+        });
+    }
+    cases.push(Case {
+        name: "SettleIntent, full index, history and challenges".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &index(MAX_INDEX_BUCKETS, MAX_NUM_RESOURCE_IN_BUCKET))),
+            (user_key, program_account(&program_id, &account)),
+            (resource_database_key, program_account(&resource_key, &finalized)),
+            (history_key, program_account(&resource_key, &history)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new_readonly(database_key, false),
+                AccountMeta::new(user_key, false),
+                AccountMeta::new_readonly(resource_database_key, false),
+                AccountMeta::new_readonly(history_key, false),
+            ],
+            data: vec![INSTRUCTION_SETTLE_INTENT],
+        },
+    });
+
+    cases
+}
+
+fn resource_cases(programs: &Programs) -> Vec<Case> {
+    let program_id = programs.resource_id;
+    let database_key = Pubkey::new_unique();
+    let database_instruction = |data: Vec<u8>| Instruction {
+        program_id,
+        accounts: vec![AccountMeta::new(database_key, false)],
+        data,
+    };
+    let record_instance_instruction = |instance: &ResourceInstance| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(database_key, false),
+            AccountMeta::new_readonly(contributor().pubkey(), true),
+        ],
+        data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, instance),
+    };
+    let setup_instruction = |data: Vec<u8>| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(database_key, false),
+            AccountMeta::new_readonly(authority().pubkey(), true),
+        ],
+        data,
+    };
+    let initiate_instruction = || Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(database_key, false),
+            AccountMeta::new_readonly(authority().pubkey(), true),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data: instruction_data(INSTRUCTION_INITIATE_DISTRIBUTION, &3600i64),
+    };
+    let clock_instruction = |data: Vec<u8>| Instruction {
+        program_id,
+        accounts: vec![
+            AccountMeta::new(database_key, false),
+            AccountMeta::new_readonly(sysvar::clock::id(), false),
+        ],
+        data,
+    };
+    let mut cases = Vec::new();
+
+    for intents in [0, MAX_NUM_RECIPIENTS - 1, MAX_NUM_RECIPIENTS].iter() {
+        let recipient_key = recipient().pubkey();
+        cases.push(Case {
+            name: format!("RegisterIntent, {} intents", intents),
+            budget: 40_000,
+            signers: vec![recipient()],
+            accounts: vec![(database_key, program_account(&program_id, &database(*intents, 0, 0, false)))],
+            instruction: Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new(database_key, false),
+                    AccountMeta::new_readonly(recipient_key, true),
+                ],
+                data: vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT],
+            },
+        });
+    }
+
+    // registering again rewrites the need of the last slot
+    let mut need_data = vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT];
+    need_data.extend(Need { requested: 30, household_size: 6 }.try_to_vec().unwrap());
+    let mut registered = database(MAX_NUM_RECIPIENTS, 0, 0, false);
+    registered.intents[MAX_NUM_RECIPIENTS - 1].recipient = recipient().pubkey().to_bytes();
+    cases.push(Case {
+        name: "RegisterIntent, updating need".to_string(),
+        budget: 40_000,
+        signers: vec![recipient()],
+        accounts: vec![(database_key, program_account(&program_id, &registered))],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(recipient().pubkey(), true),
+            ],
+            data: need_data,
+        },
+    });
+
+    // a full deny list is scanned before the intent slots
+    let mut denying = database(MAX_NUM_RECIPIENTS - 1, 0, 0, false);
+    denying.eligibility = EligibilityRule {
+        kind: ELIGIBILITY_DENY_LIST,
+        list: [address(98); MAX_ELIGIBILITY_LIST_SIZE],
+        ..EligibilityRule::default()
+    };
+    let recipient_key = recipient().pubkey();
+    cases.push(Case {
+        name: "RegisterIntent, full deny list".to_string(),
+        budget: 40_000,
+        signers: vec![recipient()],
+        accounts: vec![(database_key, program_account(&program_id, &denying))],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(recipient_key, true),
+            ],
+            data: vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT],
+        },
+    });
+
+    // the provider's search engine account is read for their trust in the recipient
+    let provider_key = Pubkey::new_unique();
+    let mut provider = search_engine_account(MAX_NUM_INTENTS);
+    provider.trust_table = [TrustTableEntry {
+        to: recipient_key.to_bytes(),
+        value: 5,
+    }; MAX_TRUST_TABLE_SIZE];
+    let mut trusting = database(MAX_NUM_RECIPIENTS - 1, 0, 0, false);
+    trusting.eligibility = EligibilityRule {
+        kind: ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        provider: provider_key.to_bytes(),
+        trust_program: programs.searchengine_id.to_bytes(),
+        min_trust: 5,
+        ..EligibilityRule::default()
+    };
+    cases.push(Case {
+        name: "RegisterIntent, trusted by provider".to_string(),
+        budget: 40_000,
+        signers: vec![recipient()],
+        accounts: vec![
+            (database_key, program_account(&program_id, &trusting)),
+            (provider_key, program_account(&programs.searchengine_id, &provider)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(recipient_key, true),
+                AccountMeta::new_readonly(provider_key, false),
+            ],
+            data: vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT],
+        },
+    });
+
+    // every slot is taken, so the intent is looked for on both pages and queues behind the
+    // others on the tail page
+    let head_key = Pubkey::new_unique();
+    let tail_key = Pubkey::new_unique();
+    let mut full = database(MAX_NUM_RECIPIENTS, 0, 0, false);
+    full.waitlist = Waitlist {
+        head: head_key.to_bytes(),
+        tail: tail_key.to_bytes(),
+        waiting: 2 * WAITLIST_PAGE_CAPACITY as u32 - 1,
+    };
+    cases.push(Case {
+        name: format!("RegisterIntent, onto the waitlist, {} waiting", 2 * WAITLIST_PAGE_CAPACITY - 1),
+        budget: 40_000,
+        signers: vec![recipient()],
+        accounts: vec![
+            (database_key, program_account(&program_id, &full)),
+            (head_key, program_account(&program_id, &waitlist_page(&database_key, &tail_key, 20, WAITLIST_PAGE_CAPACITY))),
+            (tail_key, program_account(&program_id, &waitlist_page(&database_key, &Pubkey::default(), 20 + WAITLIST_PAGE_CAPACITY, WAITLIST_PAGE_CAPACITY - 1))),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(recipient_key, true),
+                AccountMeta::new(head_key, false),
+                AccountMeta::new(tail_key, false),
+            ],
+            data: vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT],
+        },
+    });
+
+    // a new page is bound to the database, and chained after the tail once there is one
+    let page_key = Pubkey::new_unique();
+    cases.push(Case {
+        name: "AddWaitlistPage, first page".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, 0, false))),
+            (page_key, program_account(&program_id, &WaitlistPage::default())),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(page_key, false),
+            ],
+            data: vec![INSTRUCTION_ADD_WAITLIST_PAGE],
+        },
+    });
+    full.waitlist.waiting = WAITLIST_PAGE_CAPACITY as u32;
+    cases.push(Case {
+        name: "AddWaitlistPage, after the tail".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &full)),
+            (page_key, program_account(&program_id, &WaitlistPage::default())),
+            (tail_key, program_account(&program_id, &waitlist_page(&database_key, &Pubkey::default(), 20, WAITLIST_PAGE_CAPACITY))),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(page_key, false),
+                AccountMeta::new(tail_key, false),
+            ],
+            data: vec![INSTRUCTION_ADD_WAITLIST_PAGE],
+        },
+    });
+
+    cases.push(Case {
+        name: "SetCapacity, nothing waiting".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, 0, false)))],
+        instruction: setup_instruction(vec![INSTRUCTION_SET_CAPACITY, MAX_NUM_RECIPIENTS as u8]),
+    });
+
+    // raising a capacity of one to every slot promotes an intent into each slot freed up, and
+    // drains the head page on the way
+    let mut narrow = database(1, 0, 0, false);
+    narrow.capacity = 1;
+    narrow.waitlist = Waitlist {
+        head: head_key.to_bytes(),
+        tail: tail_key.to_bytes(),
+        waiting: 2 * WAITLIST_PAGE_CAPACITY as u32,
+    };
+    let mut promoting = setup_instruction(vec![INSTRUCTION_SET_CAPACITY, MAX_NUM_RECIPIENTS as u8]);
+    promoting.accounts.push(AccountMeta::new(head_key, false));
+    promoting.accounts.push(AccountMeta::new(tail_key, false));
+    cases.push(Case {
+        name: format!("SetCapacity, promoting {} intents", MAX_NUM_RECIPIENTS - 1),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![
+            (database_key, program_account(&program_id, &narrow)),
+            (head_key, program_account(&program_id, &waitlist_page(&database_key, &tail_key, 20, WAITLIST_PAGE_CAPACITY))),
+            (tail_key, program_account(&program_id, &waitlist_page(&database_key, &Pubkey::default(), 20 + WAITLIST_PAGE_CAPACITY, WAITLIST_PAGE_CAPACITY))),
+        ],
+        instruction: promoting,
+    });
+
+    // the first slot is withdrawn, so every other intent moves up
+    let mut withdrawing = database(MAX_NUM_RECIPIENTS, 0, 0, false);
+    withdrawing.intents[0].recipient = recipient_key.to_bytes();
+    cases.push(Case {
+        name: "WithdrawIntent, nothing waiting".to_string(),
+        budget: 40_000,
+        signers: vec![recipient()],
+        accounts: vec![(database_key, program_account(&program_id, &withdrawing))],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(recipient_key, true),
+            ],
+            data: vec![INSTRUCTION_WITHDRAW_INTENT],
+        },
+    });
+
+    // the freed slot goes to the last intent on the head page, which leaves the chain
+    withdrawing.waitlist = Waitlist {
+        head: head_key.to_bytes(),
+        tail: tail_key.to_bytes(),
+        waiting: 2,
+    };
+    cases.push(Case {
+        name: "WithdrawIntent, promoting from a drained page".to_string(),
+        budget: 40_000,
+        signers: vec![recipient()],
+        accounts: vec![
+            (database_key, program_account(&program_id, &withdrawing)),
+            (head_key, program_account(&program_id, &waitlist_page(&database_key, &tail_key, 20, 1))),
+            (tail_key, program_account(&program_id, &waitlist_page(&database_key, &Pubkey::default(), 21, 1))),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(recipient_key, true),
+                AccountMeta::new(head_key, false),
+                AccountMeta::new(tail_key, false),
+            ],
+            data: vec![INSTRUCTION_WITHDRAW_INTENT],
+        },
+    });
+
+    cases.push(Case {
+        name: "SetEligibility".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, 0, false)))],
+        instruction: setup_instruction(instruction_data(INSTRUCTION_SET_ELIGIBILITY, &trusting.eligibility)),
+    });
+
+    for instances in [0, MAX_NUM_RESOURCE_INSTANCES - 1, MAX_NUM_RESOURCE_INSTANCES].iter() {
+        let instance = ResourceInstance {
+            from: contributor().pubkey().to_bytes(),
+            quantity: 5,
+        };
+        cases.push(Case {
+            name: format!("RecordResourceInstance, {} instances", instances),
+            budget: 40_000,
+            signers: vec![contributor()],
+            accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, *instances, 0, false)))],
+            instruction: record_instance_instruction(&instance),
+        });
+    }
+
+    // a contributor already in the pool adds to their slot, the last one is found last
+    let mut pooled = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, 0, false);
+    pooled.instances[MAX_NUM_RESOURCE_INSTANCES - 1].from = contributor().pubkey().to_bytes();
+    let instance = ResourceInstance {
+        from: contributor().pubkey().to_bytes(),
+        quantity: 5,
+    };
+    cases.push(Case {
+        name: "RecordResourceInstance, pooled into the last slot".to_string(),
+        budget: 40_000,
+        signers: vec![contributor()],
+        accounts: vec![(database_key, program_account(&program_id, &pooled))],
+        instruction: record_instance_instruction(&instance),
+    });
+
+    // the deposit is a transfer into escrow signed by the provider
+    let mint = Pubkey::new_unique();
+    let (escrow_authority, _) = solana_bpf_resource::escrow_authority(&program_id, &database_key);
+    let escrow_key = Pubkey::new_unique();
+    let provider = Keypair::new();
+    let provider_key = provider.pubkey();
+    let provider_token_key = Pubkey::new_unique();
+    let mut escrowed = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES - 1, 0, false);
+    escrowed.escrow_mint = mint.to_bytes();
+    let instance = ResourceInstance {
+        from: provider_key.to_bytes(),
+        quantity: 5,
+    };
+    cases.push(Case {
+        name: "RecordResourceInstance, escrowed".to_string(),
+        budget: 200_000,
+        signers: vec![provider],
+        accounts: vec![
+            (database_key, program_account(&program_id, &escrowed)),
+            (provider_token_key, token_account(&mint, &provider_key, 10)),
+            (escrow_key, token_account(&mint, &escrow_authority, 0)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(provider_token_key, false),
+                AccountMeta::new(escrow_key, false),
+                AccountMeta::new_readonly(provider_key, true),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &instance),
+        },
+    });
+
+    cases.push(Case {
+        name: "SetEscrowMint".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, 0, false)))],
+        instruction: setup_instruction(instruction_data(INSTRUCTION_SET_ESCROW_MINT, &mint.to_bytes())),
+    });
+
+    let unit = UnitOfMeasure {
+        name: [1u8; MAX_UNIT_NAME_SIZE],
+        decimals: 3,
+    };
+    cases.push(Case {
+        name: "SetUnit".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, 0, false)))],
+        instruction: setup_instruction(instruction_data(INSTRUCTION_SET_UNIT, &unit)),
+    });
+
+    // only picked before anyone joins, so the database is empty
+    cases.push(Case {
+        name: "SetAllocationStrategy".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(0, 0, 0, false)))],
+        instruction: setup_instruction(vec![INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_LOTTERY]),
+    });
+
+    // the lamport deposit is a system transfer signed by the provider
+    let vault_key = Pubkey::new_unique();
+    let vault = LamportVault {
+        database: database_key.to_bytes(),
+    };
+    let provider = Keypair::new();
+    let provider_key = provider.pubkey();
+    let mut vaulted = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES - 1, 0, false);
+    vaulted.vault = vault_key.to_bytes();
+    let instance = ResourceInstance {
+        from: provider_key.to_bytes(),
+        quantity: 5,
+    };
+    cases.push(Case {
+        name: "RecordResourceInstance, vault".to_string(),
+        budget: 40_000,
+        signers: vec![provider],
+        accounts: vec![
+            (database_key, program_account(&program_id, &vaulted)),
+            (vault_key, program_account(&program_id, &vault)),
+            (provider_key, system_account()),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(vault_key, false),
+                AccountMeta::new(provider_key, true),
+                AccountMeta::new_readonly(system_program::id(), false),
+            ],
+            data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &instance),
+        },
+    });
+
+    cases.push(Case {
+        name: "SetLamportVault".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![
+            (database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, 0, false))),
+            (vault_key, program_account(&program_id, &LamportVault::default())),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(authority().pubkey(), true),
+                AccountMeta::new(vault_key, false),
+            ],
+            data: vec![INSTRUCTION_SET_LAMPORT_VAULT],
+        },
+    });
+
+    for (intents, instances) in [(0, 0), (MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES)].iter() {
+        cases.push(Case {
+            name: format!("InitiateDistribution, {} intents, {} instances", intents, instances),
+            budget: 40_000,
+            signers: vec![authority()],
+            accounts: vec![(database_key, program_account(&program_id, &database(*intents, *instances, 0, false)))],
+            instruction: initiate_instruction(),
+        });
+    }
+
+    // every slot and contributor taken under each strategy allocating right away, with needs
+    // that differ so max-min fair fills them over several passes
+    for (strategy, description) in [
+        (ALLOCATION_PROPORTIONAL, "proportional"),
+        (ALLOCATION_EQUAL_SPLIT, "equal split"),
+        (ALLOCATION_MAX_MIN_FAIR, "max-min fair"),
+        (ALLOCATION_FIRST_COME_FIRST_SERVED, "first come, first served"),
+    ]
+    .iter()
+    {
+        let mut allocating = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, 0, false);
+        allocating.strategy = *strategy;
+        for (slot, intent) in allocating.intents.iter_mut().enumerate() {
+            intent.need = Need {
+                requested: 10 * (slot as u64 + 1),
+                household_size: 1 + slot as u16,
+            };
+        }
+        cases.push(Case {
+            name: format!("InitiateDistribution {}, {} intents", description, MAX_NUM_RECIPIENTS),
+            budget: 40_000,
+            signers: vec![authority()],
+            accounts: vec![(database_key, program_account(&program_id, &allocating))],
+            instruction: initiate_instruction(),
+        });
+    }
+
+    // a lottery is only committed to a slot, DrawLottery allocates it
+    let mut lottery = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, 0, false);
+    lottery.strategy = ALLOCATION_LOTTERY;
+    cases.push(Case {
+        name: format!("InitiateDistribution by lottery, {} intents", MAX_NUM_RECIPIENTS),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &lottery))],
+        instruction: initiate_instruction(),
+    });
+
+    // committed to the genesis slot, the one the test bank's SlotHashes holds; the draw hashes
+    // once for every intent after the first
+    let mut committed = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, 0, true);
+    committed.strategy = ALLOCATION_LOTTERY;
+    let mut draw_instruction = clock_instruction(vec![INSTRUCTION_DRAW_LOTTERY]);
+    draw_instruction.accounts.push(AccountMeta::new_readonly(sysvar::slot_hashes::id(), false));
+    cases.push(Case {
+        name: format!("DrawLottery, {} intents", MAX_NUM_RECIPIENTS),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![(database_key, program_account(&program_id, &committed))],
+        instruction: draw_instruction,
+    });
+
+    // a new pair goes after the recorded ones, so the scan grows with them; the last recipient
+    // signs their answer
+    for challenges in [0, MAX_NUM_CHALLENGES - 1].iter() {
+        let approver = Keypair::new();
+        let mut recorded = database(MAX_NUM_RECIPIENTS, 0, *challenges, true);
+        recorded.intents[MAX_NUM_RECIPIENTS - 1].recipient = approver.pubkey().to_bytes();
+        let challenge = Challenge {
+            from: approver.pubkey().to_bytes(),
+            to: address(0),
+            value: true,
+        };
+        let mut instruction = clock_instruction(instruction_data(INSTRUCTION_RECORD_CHALLENGE, &challenge));
+        instruction.accounts.push(AccountMeta::new_readonly(approver.pubkey(), true));
+        cases.push(Case {
+            name: format!("RecordChallenge, {} challenges", challenges),
+            budget: 40_000,
+            signers: vec![approver],
+            accounts: vec![(database_key, program_account(&program_id, &recorded))],
+            instruction,
+        });
+    }
+
+    let challenge = Challenge {
+        from: address(0),
+        to: address(1),
+        value: true,
+    };
+    cases.push(Case {
+        name: "ClaimChallenge".to_string(),
+        budget: 20_000,
+        signers: Vec::new(),
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, 0, MAX_NUM_CHALLENGES, true)))],
+        instruction: database_instruction(instruction_data(INSTRUCTION_CLAIM_CHALLENGE, &challenge)),
+    });
+
+    // recipient 0 approved recipient 1 and its search engine account has to be read for the trust
+    let mut trust_weighted = database(MAX_NUM_RECIPIENTS, 0, MAX_NUM_CHALLENGES, true);
+    for challenge in trust_weighted.challenges.iter_mut() {
+        challenge.value = true;
+    }
+    trust_weighted.quorum = QuorumRule {
+        kind: QUORUM_TRUST_WEIGHTED,
+        threshold: 1,
+        trust_program: programs.searchengine_id.to_bytes(),
+    };
+    let approver_key = Pubkey::new(&address(0));
+    let mut approver = search_engine_account(MAX_NUM_INTENTS);
+    approver.trust_table[0] = TrustTableEntry {
+        to: address(1),
+        value: 10,
+    };
+    let challenge = Challenge {
+        from: address(1),
+        to: [0u8; PUBLIC_KEY_SIZE],
+        value: true,
+    };
+    cases.push(Case {
+        name: "ClaimChallenge, trust weighted".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &trust_weighted)),
+            (approver_key, program_account(&programs.searchengine_id, &approver)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new_readonly(approver_key, false),
+            ],
+            data: instruction_data(INSTRUCTION_CLAIM_CHALLENGE, &challenge),
+        },
+    });
+
+    // the allocation leaves escrow signed by the escrow authority
+    let mut escrowed = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, MAX_NUM_CHALLENGES, true);
+    escrowed.intents[1].allocation = 10;
+    escrowed.final_quantity = 10;
+    escrowed.escrow_mint = mint.to_bytes();
+    for challenge in escrowed.challenges.iter_mut() {
+        challenge.value = true;
+    }
+    let claimant_token_key = Pubkey::new_unique();
+    let challenge = Challenge {
+        from: address(1),
+        to: [0u8; PUBLIC_KEY_SIZE],
+        value: true,
+    };
+    cases.push(Case {
+        name: "ClaimChallenge, escrowed".to_string(),
+        budget: 200_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &escrowed)),
+            (escrow_key, token_account(&mint, &escrow_authority, 20)),
+            (claimant_token_key, token_account(&mint, &Pubkey::new(&address(1)), 0)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(escrow_key, false),
+                AccountMeta::new(claimant_token_key, false),
+                AccountMeta::new_readonly(escrow_authority, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: instruction_data(INSTRUCTION_CLAIM_CHALLENGE, &challenge),
+        },
+    });
+
+    // vault payouts and refunds move lamports directly, the program owns the vault
+    let mut vault_account = program_account(&program_id, &vault);
+    vault_account.lamports += 20;
+    let mut vaulted = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, MAX_NUM_CHALLENGES, true);
+    vaulted.intents[1].allocation = 10;
+    vaulted.final_quantity = 10;
+    vaulted.vault = vault_key.to_bytes();
+    for challenge in vaulted.challenges.iter_mut() {
+        challenge.value = true;
+    }
+    let claimant_key = Pubkey::new(&address(1));
+    cases.push(Case {
+        name: "ClaimChallenge, vault".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &vaulted)),
+            (vault_key, vault_account.clone()),
+            (claimant_key, system_account()),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(vault_key, false),
+                AccountMeta::new(claimant_key, false),
+            ],
+            data: instruction_data(INSTRUCTION_CLAIM_CHALLENGE, &challenge),
+        },
+    });
+
+    vaulted.is_finalized = true;
+    let contributor_key = Pubkey::new(&vaulted.instances[MAX_NUM_RESOURCE_INSTANCES - 1].from);
+    cases.push(Case {
+        name: "RefundContribution".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &vaulted)),
+            (vault_key, vault_account),
+            (contributor_key, system_account()),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(vault_key, false),
+                AccountMeta::new(contributor_key, false),
+            ],
+            data: vec![INSTRUCTION_REFUND_CONTRIBUTION],
+        },
+    });
+
+    // the refund of an escrowed pool leaves escrow like a claim, to the contributor's token account
+    escrowed.is_finalized = true;
+    let contributor_token_key = Pubkey::new_unique();
+    cases.push(Case {
+        name: "RefundContribution, escrowed".to_string(),
+        budget: 200_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &escrowed)),
+            (escrow_key, token_account(&mint, &escrow_authority, 40)),
+            (contributor_token_key, token_account(&mint, &contributor_key, 0)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(escrow_key, false),
+                AccountMeta::new(contributor_token_key, false),
+                AccountMeta::new_readonly(escrow_authority, false),
+                AccountMeta::new_readonly(spl_token::id(), false),
+            ],
+            data: vec![INSTRUCTION_REFUND_CONTRIBUTION],
+        },
+    });
+
+    // the recipient's claim is the last one searched
+    let mut claimed = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, 0, true);
+    for (slot, claim) in claimed.claims.iter_mut().enumerate() {
+        *claim = address(slot);
+    }
+    let recipient_key = Pubkey::new(&address(MAX_NUM_RECIPIENTS - 1));
+    let receipt_key = Pubkey::new_unique();
+    cases.push(Case {
+        name: "IssueReceipt".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &claimed)),
+            (receipt_key, program_account(&program_id, &ClaimReceipt::default())),
+            (recipient_key, system_account()),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(receipt_key, false),
+                AccountMeta::new_readonly(recipient_key, false),
+            ],
+            data: vec![INSTRUCTION_ISSUE_RECEIPT],
+        },
+    });
+
+    let quorum = QuorumRule {
+        kind: QUORUM_TRUST_WEIGHTED,
+        threshold: 10,
+        trust_program: programs.searchengine_id.to_bytes(),
+    };
+    cases.push(Case {
+        name: "SetQuorum, full database".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, 0, false)))],
+        instruction: setup_instruction(instruction_data(INSTRUCTION_SET_QUORUM, &quorum)),
+    });
+
+    let mut closed = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, MAX_NUM_CHALLENGES, true);
+    closed.window.duration = 0;
+    cases.push(Case {
+        name: "FinalizeDistribution, full database".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![(database_key, program_account(&program_id, &closed))],
+        instruction: clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]),
+    });
+
+    // the history is full, so the oldest round is overwritten
+    let history_key = Pubkey::new_unique();
+    let mut finalized = database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, MAX_NUM_CHALLENGES, true);
+    finalized.is_finalized = true;
+    finalized.round = MAX_NUM_ROUNDS as u32;
+    let mut history = RoundHistory {
+        database: database_key.to_bytes(),
+        next_round: 0,
+        rounds: [RoundRecord::default(); MAX_NUM_ROUNDS],
+    };
+    for round in 0..MAX_NUM_ROUNDS as u32 {
+        history.archive(RoundRecord {
+            round,
+            ..RoundRecord::default()
+        });
+    }
+    cases.push(Case {
+        name: "StartNewRound, full database and history".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &finalized)),
+            (history_key, program_account(&program_id, &history)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(history_key, false),
+            ],
+            data: vec![INSTRUCTION_START_NEW_ROUND],
+        },
+    });
+
+    // nobody claimed, so each intent promoted from the waitlist replaces a recipient
+    let mut replacing = finalized.clone();
+    replacing.waitlist = Waitlist {
+        head: head_key.to_bytes(),
+        tail: tail_key.to_bytes(),
+        waiting: 2 * WAITLIST_PAGE_CAPACITY as u32,
+    };
+    cases.push(Case {
+        name: format!("StartNewRound, replacing {} unclaimed recipients", MAX_NUM_RECIPIENTS.min(2 * WAITLIST_PAGE_CAPACITY)),
+        budget: 200_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &replacing)),
+            (history_key, program_account(&program_id, &history)),
+            (head_key, program_account(&program_id, &waitlist_page(&database_key, &tail_key, 40, WAITLIST_PAGE_CAPACITY))),
+            (tail_key, program_account(&program_id, &waitlist_page(&database_key, &Pubkey::default(), 40 + WAITLIST_PAGE_CAPACITY, WAITLIST_PAGE_CAPACITY))),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(history_key, false),
+                AccountMeta::new(head_key, false),
+                AccountMeta::new(tail_key, false),
+            ],
+            data: vec![INSTRUCTION_START_NEW_ROUND],
+        },
+    });
+
+    // a backed round only closes once every contributor was refunded, which scans the pool
+    finalized.vault = vault_key.to_bytes();
+    finalized.refunded = [true; MAX_NUM_RESOURCE_INSTANCES];
+    cases.push(Case {
+        name: "StartNewRound, refunded vault".to_string(),
+        budget: 40_000,
+        signers: Vec::new(),
+        accounts: vec![
+            (database_key, program_account(&program_id, &finalized)),
+            (history_key, program_account(&program_id, &history)),
+        ],
+        instruction: Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(database_key, false),
+                AccountMeta::new(history_key, false),
+            ],
+            data: vec![INSTRUCTION_START_NEW_ROUND],
+        },
+    });
+
+    cases.push(Case {
+        name: "ResetDatabase, full database".to_string(),
+        budget: 40_000,
+        signers: vec![authority()],
+        accounts: vec![(database_key, program_account(&program_id, &database(MAX_NUM_RECIPIENTS, MAX_NUM_RESOURCE_INSTANCES, MAX_NUM_CHALLENGES, true)))],
+        instruction: setup_instruction(vec![INSTRUCTION_RESET_DATABASE]),
+    });
+
+    cases
+}
+
+#[test]
+fn test_searchengine_compute_units() {
+    let programs = Programs::new();
+    let cases = searchengine_cases(&programs);
+    programs.check(cases);
+}
+
+#[test]
+fn test_resource_compute_units() {
+    let programs = Programs::new();
+    let cases = resource_cases(&programs);
+    programs.check(cases);
+}
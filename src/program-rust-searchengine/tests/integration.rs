@@ -0,0 +1,1190 @@
+// End to end: both programs behind a BanksClient, real accounts created through the system
+// program and every step sent as a signed transaction. With BPF_OUT_DIR pointing at the built
+// solana_bpf_searchengine.so and solana_bpf_resource.so those are loaded, otherwise the native
+// processors stand in for them. Deposits move tokens and lamports through another program, which
+// the native processors can't do to accounts they don't own, so those tests need the BPF builds.
+use borsh::{BorshDeserialize, BorshSerialize};
+use solana_program::{
+    instruction::{AccountMeta, Instruction, InstructionError},
+    pubkey::Pubkey,
+    rent::Rent,
+    system_instruction,
+    sysvar,
+};
+use solana_program_test::{processor, BanksClient, BanksClientExt, ProgramTest};
+use solana_sdk::{
+    hash::Hash,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+use solana_bpf_searchengine::{
+    error::SearchEngineError,
+    types::{
+        INSTRUCTION_INITIALIZE_DATABASE,
+        INSTRUCTION_REGISTER_INTENT,
+        INSTRUCTION_REGISTER_RESOURCE,
+        INSTRUCTION_SETTLE_INTENT,
+        INSTRUCTION_UPDATE_ACCOUNT,
+        MAX_FRIENDLY_NAME_SIZE,
+        MAX_NUM_INTENTS,
+        MAX_TRUST_TABLE_SIZE,
+        MAX_ZIP_SIZE,
+        PUBLIC_KEY_SIZE,
+        RESOURCE_INDEX_SIZE,
+        SEARCH_ENGINE_ACCOUNT_SIZE,
+        Location,
+        Reputation,
+        Resource,
+        ResourceIndex,
+        SearchEngineAccount,
+        TrustTableEntry,
+    },
+};
+// only the deposit tests use these, see above
+#[cfg(feature = "test-bpf")]
+use solana_program::{program_pack::Pack, system_program};
+#[cfg(feature = "test-bpf")]
+use spl_token::state::{Account as TokenAccount, Mint};
+#[cfg(feature = "test-bpf")]
+use solana_bpf_resource::types::{
+    INSTRUCTION_REFUND_CONTRIBUTION,
+    INSTRUCTION_SET_ESCROW_MINT,
+    INSTRUCTION_SET_LAMPORT_VAULT,
+    LAMPORT_VAULT_SIZE,
+};
+use solana_bpf_resource::{
+    error::ResourceError,
+    types::{
+        INSTRUCTION_CLAIM_CHALLENGE,
+        INSTRUCTION_FINALIZE_DISTRIBUTION,
+        INSTRUCTION_INITIATE_DISTRIBUTION,
+        INSTRUCTION_RECORD_CHALLENGE,
+        INSTRUCTION_RECORD_RESOURCE_INSTANCE,
+        INSTRUCTION_RESET_DATABASE,
+        INSTRUCTION_SET_QUORUM,
+        INSTRUCTION_START_NEW_ROUND,
+        INSTRUCTION_ISSUE_RECEIPT,
+        INSTRUCTION_SET_UNIT,
+        INSTRUCTION_SET_ELIGIBILITY,
+        INSTRUCTION_SET_ALLOCATION_STRATEGY,
+        INSTRUCTION_SET_CAPACITY,
+        INSTRUCTION_ADD_WAITLIST_PAGE,
+        INSTRUCTION_WITHDRAW_INTENT,
+        INSTRUCTION_DRAW_LOTTERY,
+        ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        ALLOCATION_LOTTERY,
+        DRAW_DELAY,
+        CLAIM_RECEIPT_SIZE,
+        MAX_NUM_RECIPIENTS,
+        QUORUM_TRUST_WEIGHTED,
+        RESOURCE_DATABASE_SIZE,
+        ROUND_HISTORY_SIZE,
+        WAITLIST_PAGE_SIZE,
+        Challenge,
+        ClaimReceipt,
+        EligibilityRule,
+        Intent,
+        Need,
+        QuorumRule,
+        ResourceDatabase,
+        ResourceInstance,
+        RoundHistory,
+        UnitOfMeasure,
+        WaitlistPage,
+    },
+};
+
+fn padded_name(name: &str) -> [u8; MAX_FRIENDLY_NAME_SIZE] {
+    let mut bytes = [0u8; MAX_FRIENDLY_NAME_SIZE];
+    for (place, byte) in bytes.iter_mut().zip(name.as_bytes().iter()) {
+        *place = *byte
+    }
+    bytes
+}
+
+fn padded_zip(zip: &str) -> [u8; MAX_ZIP_SIZE] {
+    let mut bytes = [0u8; MAX_ZIP_SIZE];
+    for (place, byte) in bytes.iter_mut().zip(zip.as_bytes().iter()) {
+        *place = *byte
+    }
+    bytes
+}
+
+fn instruction_data<T: BorshSerialize>(tag: u8, payload: &T) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.append(&mut payload.try_to_vec().unwrap());
+    data
+}
+
+struct Cluster {
+    banks_client: BanksClient,
+    payer: Keypair,
+    recent_blockhash: Hash,
+    searchengine_id: Pubkey,
+    searchengine_database: Pubkey,
+    resource_id: Pubkey,
+    resource_database: Pubkey,
+}
+
+impl Cluster {
+    // Both programs loaded, with the search engine index and the resource database created and
+    // initialized the way the bootstrap does it
+    async fn start() -> Self {
+        let searchengine_id = Pubkey::new_unique();
+        let resource_id = Pubkey::new_unique();
+        let mut program_test = ProgramTest::new(
+            "solana_bpf_searchengine",
+            searchengine_id,
+            processor!(solana_bpf_searchengine::process_instruction),
+        );
+        program_test.add_program(
+            "solana_bpf_resource",
+            resource_id,
+            processor!(solana_bpf_resource::process_instruction),
+        );
+        program_test.add_program(
+            "spl_token",
+            spl_token::id(),
+            processor!(spl_token::processor::Processor::process),
+        );
+        let (banks_client, payer, recent_blockhash) = program_test.start().await;
+
+        let searchengine_database = Keypair::new();
+        let resource_database = Keypair::new();
+        let mut cluster = Cluster {
+            banks_client,
+            payer,
+            recent_blockhash,
+            searchengine_id,
+            searchengine_database: searchengine_database.pubkey(),
+            resource_id,
+            resource_database: resource_database.pubkey(),
+        };
+
+        let create_searchengine_database = cluster.create_account(&searchengine_database.pubkey(), RESOURCE_INDEX_SIZE, &searchengine_id);
+        let initialize_database = cluster.searchengine_instruction(
+            vec![AccountMeta::new(cluster.searchengine_database, false), AccountMeta::new_readonly(cluster.payer.pubkey(), true)],
+            vec![INSTRUCTION_INITIALIZE_DATABASE],
+        );
+        let create_resource_database = cluster.create_account(&resource_database.pubkey(), RESOURCE_DATABASE_SIZE, &resource_id);
+        let reset_database = cluster.setup_instruction(vec![INSTRUCTION_RESET_DATABASE]);
+        cluster.process(
+            &[create_searchengine_database, initialize_database, create_resource_database, reset_database],
+            &[&searchengine_database, &resource_database],
+        ).await.unwrap();
+        cluster
+    }
+
+    fn create_account(&self, account: &Pubkey, space: usize, owner: &Pubkey) -> Instruction {
+        system_instruction::create_account(
+            &self.payer.pubkey(),
+            account,
+            Rent::default().minimum_balance(space),
+            space as u64,
+            owner,
+        )
+    }
+
+    fn searchengine_instruction(&self, accounts: Vec<AccountMeta>, data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: self.searchengine_id,
+            accounts,
+            data,
+        }
+    }
+
+    // every other resource instruction but register intent only touches the database
+    fn resource_instruction(&self, data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: self.resource_id,
+            accounts: vec![AccountMeta::new(self.resource_database, false)],
+            data,
+        }
+    }
+
+    // quantity only instances are signed by their contributor
+    fn record_instance_instruction(&self, instance: &ResourceInstance) -> Instruction {
+        Instruction {
+            program_id: self.resource_id,
+            accounts: vec![
+                AccountMeta::new(self.resource_database, false),
+                AccountMeta::new_readonly(Pubkey::new(&instance.from), true),
+            ],
+            data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, instance),
+        }
+    }
+
+    // resets and the set-* instructions take the payer, the database's authority, as their signer
+    fn setup_instruction(&self, data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: self.resource_id,
+            accounts: vec![
+                AccountMeta::new(self.resource_database, false),
+                AccountMeta::new_readonly(self.payer.pubkey(), true),
+            ],
+            data,
+        }
+    }
+
+    // claims pass the approvers' search engine accounts along for trust weighted quorum rules
+    fn claim_instruction(&self, claimant: &Pubkey, approvers: &[&Pubkey]) -> Instruction {
+        let challenge = Challenge {
+            from: claimant.to_bytes(),
+            to: [0u8; PUBLIC_KEY_SIZE],
+            value: true,
+        };
+        let mut accounts = vec![AccountMeta::new(self.resource_database, false)];
+        accounts.extend(approvers.iter().map(|approver| AccountMeta::new_readonly(**approver, false)));
+        Instruction {
+            program_id: self.resource_id,
+            accounts,
+            data: instruction_data(INSTRUCTION_CLAIM_CHALLENGE, &challenge),
+        }
+    }
+
+    // the distribution window instructions also read the clock sysvar
+    fn resource_clock_instruction(&self, data: Vec<u8>) -> Instruction {
+        Instruction {
+            program_id: self.resource_id,
+            accounts: vec![
+                AccountMeta::new(self.resource_database, false),
+                AccountMeta::new_readonly(sysvar::clock::id(), false),
+            ],
+            data,
+        }
+    }
+
+    // the payer is the database's authority, it opens the round
+    fn initiate_distribution_instruction(&self, duration: i64) -> Instruction {
+        let mut instruction = self.setup_instruction(instruction_data(INSTRUCTION_INITIATE_DISTRIBUTION, &duration));
+        instruction.accounts.push(AccountMeta::new_readonly(sysvar::clock::id(), false));
+        instruction
+    }
+
+    // the challenge's `from` signs their own answer
+    fn challenge_instruction(&self, challenge: &Challenge) -> Instruction {
+        let mut instruction = self.resource_clock_instruction(instruction_data(INSTRUCTION_RECORD_CHALLENGE, challenge));
+        instruction.accounts.push(AccountMeta::new_readonly(Pubkey::new(&challenge.from), true));
+        instruction
+    }
+
+    async fn process(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> Result<(), TransactionError> {
+        let mut transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        let mut all_signers = vec![&self.payer];
+        all_signers.extend_from_slice(signers);
+        transaction.sign(&all_signers, self.recent_blockhash);
+        self.banks_client.process_transaction(transaction).await.map_err(|err| err.unwrap())
+    }
+
+    async fn data<T: BorshDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self.banks_client.get_account(*address).await.unwrap().unwrap();
+        T::try_from_slice(&account.data).unwrap()
+    }
+
+    #[cfg(feature = "test-bpf")]
+    async fn token_balance(&mut self, address: &Pubkey) -> u64 {
+        let account = self.banks_client.get_account(*address).await.unwrap().unwrap();
+        TokenAccount::unpack(&account.data).unwrap().amount
+    }
+
+    // a mint with the payer as mint authority
+    #[cfg(feature = "test-bpf")]
+    async fn create_mint(&mut self) -> Pubkey {
+        let mint = Keypair::new();
+        let create_mint = self.create_account(&mint.pubkey(), Mint::LEN, &spl_token::id());
+        let initialize_mint = spl_token::instruction::initialize_mint(&spl_token::id(), &mint.pubkey(), &self.payer.pubkey(), None, 0).unwrap();
+        self.process(&[create_mint, initialize_mint], &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    // a token account of `mint` owned by `owner`, holding `amount` minted into it
+    #[cfg(feature = "test-bpf")]
+    async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey, amount: u64) -> Pubkey {
+        let account = Keypair::new();
+        let create_account = self.create_account(&account.pubkey(), TokenAccount::LEN, &spl_token::id());
+        let initialize_account = spl_token::instruction::initialize_account(&spl_token::id(), &account.pubkey(), mint, owner).unwrap();
+        let mint_to = spl_token::instruction::mint_to(&spl_token::id(), mint, &account.pubkey(), &self.payer.pubkey(), &[], amount).unwrap();
+        self.process(&[create_account, initialize_account, mint_to], &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    async fn add_user(&mut self, name: &str, trust: Option<(&Pubkey, u8)>) -> Keypair {
+        let user = Keypair::new();
+        let mut trust_table = [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE];
+        if let Some((to, value)) = trust {
+            trust_table[0] = TrustTableEntry {
+                to: to.to_bytes(),
+                value,
+            };
+        }
+        let account = SearchEngineAccount {
+            friendly_name: padded_name(name),
+            trust_table,
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        let searchengine_id = self.searchengine_id;
+        let create_account = self.create_account(&user.pubkey(), SEARCH_ENGINE_ACCOUNT_SIZE, &searchengine_id);
+        let update_account = self.searchengine_instruction(
+            vec![AccountMeta::new(user.pubkey(), false)],
+            instruction_data(INSTRUCTION_UPDATE_ACCOUNT, &account),
+        );
+        self.process(&[create_account, update_account], &[&user]).await.unwrap();
+        user
+    }
+
+    async fn register_resource(&mut self, address: &Pubkey, name: &str, zip: &str) -> Result<(), TransactionError> {
+        let resource = Resource {
+            address: address.to_bytes(),
+            name: padded_name(name),
+            location: Location {
+                zip: padded_zip(zip),
+            },
+            trust_threshold: 10,
+        };
+        // the payer initialized the index, so it vouches for the resources
+        let register_resource = self.searchengine_instruction(
+            vec![AccountMeta::new(self.searchengine_database, false), AccountMeta::new_readonly(self.payer.pubkey(), true)],
+            instruction_data(INSTRUCTION_REGISTER_RESOURCE, &resource),
+        );
+        self.process(&[register_resource], &[]).await
+    }
+
+    async fn register_intent(&mut self, user: &Keypair, resource: &Pubkey) -> Result<(), TransactionError> {
+        self.register_intent_with_data(user, resource, vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT]).await
+    }
+
+    // the recipient also tells the resource what they need
+    async fn register_need(&mut self, user: &Keypair, resource: &Pubkey, need: &Need) -> Result<(), TransactionError> {
+        self.register_intent_with_data(user, resource, instruction_data(solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT, need)).await
+    }
+
+    // the search engine caches the intent on the user's account and the resource records the
+    // recipient, who signs, in one transaction so neither happens without the other
+    async fn register_intent_with_data(&mut self, user: &Keypair, resource: &Pubkey, data: Vec<u8>) -> Result<(), TransactionError> {
+        let searchengine_intent = self.searchengine_instruction(
+            vec![
+                AccountMeta::new_readonly(self.searchengine_database, false),
+                AccountMeta::new(user.pubkey(), false),
+                AccountMeta::new_readonly(*resource, false),
+            ],
+            vec![INSTRUCTION_REGISTER_INTENT],
+        );
+        let resource_intent = Instruction {
+            program_id: self.resource_id,
+            accounts: vec![
+                AccountMeta::new(self.resource_database, false),
+                AccountMeta::new_readonly(user.pubkey(), true),
+            ],
+            data,
+        };
+        self.process(&[searchengine_intent, resource_intent], &[user]).await
+    }
+
+    fn settle_instruction(&self, user: &Pubkey) -> Instruction {
+        self.searchengine_instruction(
+            vec![
+                AccountMeta::new_readonly(self.searchengine_database, false),
+                AccountMeta::new(*user, false),
+                AccountMeta::new_readonly(self.resource_database, false),
+            ],
+            vec![INSTRUCTION_SETTLE_INTENT],
+        )
+    }
+
+    // once the database archives its rounds, settling reads them from its history too
+    fn settle_archived_instruction(&self, user: &Pubkey, history: &Pubkey) -> Instruction {
+        let mut instruction = self.settle_instruction(user);
+        instruction.accounts.push(AccountMeta::new_readonly(*history, false));
+        instruction
+    }
+}
+
+#[tokio::test]
+async fn test_provider_recipient_flow() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let searchengine_database = cluster.searchengine_database;
+    let resource_database = cluster.resource_database;
+
+    // alice and bob are recipients, carol provides
+    let bob = cluster.add_user("bob", None).await;
+    let alice = cluster.add_user("alice", Some((&bob.pubkey(), 10))).await;
+    let carol = cluster.add_user("carol", None).await;
+    let account: SearchEngineAccount = cluster.data(&alice.pubkey()).await;
+    assert_eq!(account.friendly_name, padded_name("alice"));
+    assert_eq!(account.trust_table[0].to, bob.pubkey().to_bytes());
+
+    // two resources in the same zip share a bucket
+    let other_resource = Pubkey::new_unique();
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_resource(&other_resource, "milk", "12345").await.unwrap();
+    let index: ResourceIndex = cluster.data(&searchengine_database).await;
+    assert_eq!(index.buckets[0].location.zip, padded_zip("12345"));
+    assert_eq!(index.buckets[0].addresses[0], resource_id.to_bytes());
+    assert_eq!(index.buckets[0].addresses[1], other_resource.to_bytes());
+    assert_eq!(index.buckets[1].location.zip, [0u8; MAX_ZIP_SIZE]);
+
+    // bob's household of two asks for 4, alice gives no quantity
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    cluster.register_need(&bob, &resource_id, &Need { requested: 4, household_size: 2 }).await.unwrap();
+    let account: SearchEngineAccount = cluster.data(&alice.pubkey()).await;
+    assert_eq!(account.intents[0], resource_id.to_bytes());
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.intents[0].recipient, alice.pubkey().to_bytes());
+    assert_eq!(database.intents[1].recipient, bob.pubkey().to_bytes());
+    assert_eq!(database.intents[1].need.requested, 4);
+
+    // the provider's instances pool into one contribution; alice asks for her household's
+    // third of it, which covers both requests
+    for quantity in [4u64, 8u64].iter() {
+        let instance = ResourceInstance {
+            from: carol.pubkey().to_bytes(),
+            quantity: *quantity,
+        };
+        let record_instance = cluster.record_instance_instruction(&instance);
+        cluster.process(&[record_instance], &[&carol]).await.unwrap();
+    }
+    let initiate_distribution = cluster.initiate_distribution_instruction(3600);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert!(database.is_distributed);
+    assert_eq!(database.window.duration, 3600);
+    assert_eq!(database.instances[0].quantity, 12);
+    assert_eq!(database.instances[1], ResourceInstance::default());
+    assert_eq!(database.allocation(&alice.pubkey().to_bytes()), 4);
+    assert_eq!(database.allocation(&bob.pubkey().to_bytes()), 4);
+    assert_eq!(database.final_quantity, 8);
+    assert_eq!(database.challenges[0].from, alice.pubkey().to_bytes());
+    assert_eq!(database.challenges[0].to, bob.pubkey().to_bytes());
+    assert_eq!(database.challenges[1].from, bob.pubkey().to_bytes());
+    assert_eq!(database.challenges[1].to, alice.pubkey().to_bytes());
+
+    // nothing more can be provided once distribution started
+    let late_instance = ResourceInstance {
+        from: carol.pubkey().to_bytes(),
+        quantity: 1,
+    };
+    let record_instance = cluster.record_instance_instruction(&late_instance);
+    assert_eq!(
+        cluster.process(&[record_instance], &[&carol]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::ResourceInDistribution as u32))),
+    );
+
+    // recipients vouch for each other, then claim
+    let challenge = Challenge {
+        from: alice.pubkey().to_bytes(),
+        to: bob.pubkey().to_bytes(),
+        value: true,
+    };
+    let record_challenge = cluster.challenge_instruction(&challenge);
+    cluster.process(&[record_challenge], &[&alice]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert!(database.challenges[0].value);
+    assert!(!database.challenges[1].value);
+
+    // bob has not vouched for alice yet, and every peer has to under the default rule
+    let claim = cluster.claim_instruction(&alice.pubkey(), &[&bob.pubkey()]);
+    assert_eq!(
+        cluster.process(&[claim], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::QuorumNotMet as u32))),
+    );
+    let claim = cluster.claim_instruction(&bob.pubkey(), &[&alice.pubkey()]);
+    cluster.process(&[claim], &[]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.claims[0], bob.pubkey().to_bytes());
+
+    // the hour is not up yet
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    assert_eq!(
+        cluster.process(&[finalize_distribution], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DistributionOpen as u32))),
+    );
+
+    let reset_database = cluster.setup_instruction(vec![INSTRUCTION_RESET_DATABASE]);
+    cluster.process(&[reset_database], &[]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert!(!database.is_distributed);
+    assert_eq!(database.intents, [Intent::default(); MAX_NUM_RECIPIENTS]);
+}
+
+#[tokio::test]
+async fn test_intent_for_unregistered_resource_is_rolled_back() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+
+    assert_eq!(
+        cluster.register_intent(&alice, &resource_id).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(SearchEngineError::ResourceNotRegistered as u32))),
+    );
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.intents, [Intent::default(); MAX_NUM_RECIPIENTS]);
+}
+
+#[tokio::test]
+async fn test_database_cannot_be_initialized_twice() {
+    let mut cluster = Cluster::start().await;
+    let searchengine_database = cluster.searchengine_database;
+    cluster.register_resource(&Pubkey::new_unique(), "bread", "12345").await.unwrap();
+
+    let initialize_database = cluster.searchengine_instruction(
+        vec![AccountMeta::new(searchengine_database, false), AccountMeta::new_readonly(cluster.payer.pubkey(), true)],
+        vec![INSTRUCTION_INITIALIZE_DATABASE],
+    );
+    assert_eq!(
+        cluster.process(&[initialize_database], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::AccountAlreadyInitialized)),
+    );
+}
+
+#[tokio::test]
+async fn test_closed_window_rejects_challenges_and_finalizes() {
+    let mut cluster = Cluster::start().await;
+    let resource_database = cluster.resource_database;
+    let alice = Keypair::new();
+    let bob = Pubkey::new_unique();
+
+    // a zero second window closes at the cluster time it was opened
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+
+    let challenge = Challenge {
+        from: alice.pubkey().to_bytes(),
+        to: bob.to_bytes(),
+        value: true,
+    };
+    let record_challenge = cluster.challenge_instruction(&challenge);
+    assert_eq!(
+        cluster.process(&[record_challenge], &[&alice]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DistributionClosed as u32))),
+    );
+
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    cluster.process(&[finalize_distribution], &[]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert!(database.is_finalized);
+    assert_eq!(database.challenges[0].from, [0u8; PUBLIC_KEY_SIZE]);
+}
+
+#[tokio::test]
+async fn test_settle_intent_after_finalize() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    cluster.register_intent(&bob, &resource_id).await.unwrap();
+
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+    let settle_bob = cluster.settle_instruction(&bob.pubkey());
+    assert_eq!(
+        cluster.process(&[settle_bob], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(SearchEngineError::DistributionNotFinalized as u32))),
+    );
+
+    // the window closed before bob could answer, so alice's challenge counts as a dispute
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    cluster.process(&[finalize_distribution], &[]).await.unwrap();
+    let settle_alice = cluster.settle_instruction(&alice.pubkey());
+    cluster.process(&[settle_alice], &[]).await.unwrap();
+    let account: SearchEngineAccount = cluster.data(&alice.pubkey()).await;
+    assert_eq!(account.reputation.disputes, 1);
+    assert_eq!(account.reputation.approvals_given + account.reputation.approvals_received + account.reputation.claims, 0);
+    assert!(account.reputation.next_settle_slots[0] > 0);
+
+    // the bank would answer a repeated transaction from its cache, so bob settles in the same one
+    // (and is rolled back with it)
+    let settle_bob = cluster.settle_instruction(&bob.pubkey());
+    let settle_alice = cluster.settle_instruction(&alice.pubkey());
+    assert_eq!(
+        cluster.process(&[settle_bob, settle_alice], &[]).await,
+        Err(TransactionError::InstructionError(1, InstructionError::Custom(SearchEngineError::AlreadySettled as u32))),
+    );
+    let account: SearchEngineAccount = cluster.data(&bob.pubkey()).await;
+    assert_eq!(account.reputation, Reputation::default());
+}
+
+#[tokio::test]
+async fn test_rounds_are_archived() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    cluster.register_intent(&bob, &resource_id).await.unwrap();
+
+    let history = Keypair::new();
+    let create_history = cluster.create_account(&history.pubkey(), ROUND_HISTORY_SIZE, &resource_id);
+    cluster.process(&[create_history], &[&history]).await.unwrap();
+    let start_new_round = Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(history.pubkey(), false),
+        ],
+        data: vec![INSTRUCTION_START_NEW_ROUND],
+    };
+
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+    assert_eq!(
+        cluster.process(std::slice::from_ref(&start_new_round), &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DistributionOpen as u32))),
+    );
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    cluster.process(&[finalize_distribution.clone(), start_new_round.clone()], &[]).await.unwrap();
+
+    // the recipients stay for the next round
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.round, 1);
+    assert!(!database.is_distributed);
+    assert_eq!(database.intents[0].recipient, alice.pubkey().to_bytes());
+    assert_eq!(database.intents[1].recipient, bob.pubkey().to_bytes());
+
+    // nobody settled before the round was archived, alice still can from the history
+    let settle_alice = cluster.settle_instruction(&alice.pubkey());
+    assert_eq!(
+        cluster.process(&[settle_alice], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::NotEnoughAccountKeys)),
+    );
+    let settle_alice = cluster.settle_archived_instruction(&alice.pubkey(), &history.pubkey());
+    cluster.process(&[settle_alice], &[]).await.unwrap();
+    let account: SearchEngineAccount = cluster.data(&alice.pubkey()).await;
+    assert_eq!(account.reputation.disputes, 1);
+
+    // the whole second round in one transaction
+    let instance = ResourceInstance {
+        from: alice.pubkey().to_bytes(),
+        quantity: 3,
+    };
+    let record_instance = cluster.record_instance_instruction(&instance);
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    cluster.process(&[record_instance, initiate_distribution, finalize_distribution, start_new_round], &[&alice]).await.unwrap();
+
+    let round_history: RoundHistory = cluster.data(&history.pubkey()).await;
+    assert_eq!(round_history.database, resource_database.to_bytes());
+    let rounds: Vec<(u32, u64)> = round_history.rounds().iter().map(|record| (record.round, record.final_quantity)).collect();
+    assert_eq!(rounds, vec![(0, 0), (1, 2)]);
+    assert_eq!(round_history.get(1).unwrap().intents[1].recipient, bob.pubkey().to_bytes());
+    assert_eq!(round_history.get(1).unwrap().intents[1].allocation, 1);
+}
+
+// a page created through the system program queues whoever registers past the capacity, and the
+// recipient's signed withdrawal hands their slot on
+#[tokio::test]
+async fn test_waitlist() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    let set_capacity = cluster.setup_instruction(vec![INSTRUCTION_SET_CAPACITY, 1]);
+    cluster.process(&[set_capacity], &[]).await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    assert_eq!(
+        cluster.register_intent(&bob, &resource_id).await,
+        Err(TransactionError::InstructionError(1, InstructionError::Custom(ResourceError::WaitlistFull as u32))),
+    );
+
+    let page = Keypair::new();
+    let create_page = cluster.create_account(&page.pubkey(), WAITLIST_PAGE_SIZE, &resource_id);
+    let mut add_page = cluster.resource_instruction(vec![INSTRUCTION_ADD_WAITLIST_PAGE]);
+    add_page.accounts.push(AccountMeta::new(page.pubkey(), false));
+    let searchengine_intent = cluster.searchengine_instruction(
+        vec![
+            AccountMeta::new_readonly(cluster.searchengine_database, false),
+            AccountMeta::new(bob.pubkey(), false),
+            AccountMeta::new_readonly(resource_id, false),
+        ],
+        vec![INSTRUCTION_REGISTER_INTENT],
+    );
+    let resource_intent = Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new_readonly(bob.pubkey(), true),
+            AccountMeta::new(page.pubkey(), false),
+        ],
+        data: vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT],
+    };
+    cluster.process(&[create_page, add_page, searchengine_intent, resource_intent], &[&page, &bob]).await.unwrap();
+    let waitlist_page: WaitlistPage = cluster.data(&page.pubkey()).await;
+    assert_eq!(waitlist_page.database, resource_database.to_bytes());
+    assert_eq!(waitlist_page.intents[0].recipient, bob.pubkey().to_bytes());
+
+    // only alice can give up her slot
+    let withdraw = Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new_readonly(alice.pubkey(), true),
+            AccountMeta::new(page.pubkey(), false),
+        ],
+        data: vec![INSTRUCTION_WITHDRAW_INTENT],
+    };
+    cluster.process(&[withdraw], &[&alice]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.intents[0].recipient, bob.pubkey().to_bytes());
+    assert_eq!(database.waitlist.waiting, 0);
+}
+
+// the lottery is committed to a slot past the one it was initiated in, and nothing is drawn or
+// claimed until that slot has passed and has a hash
+#[tokio::test]
+async fn test_lottery() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    let set_strategy = cluster.setup_instruction(vec![INSTRUCTION_SET_ALLOCATION_STRATEGY, ALLOCATION_LOTTERY]);
+    cluster.process(&[set_strategy], &[]).await.unwrap();
+    cluster.register_need(&alice, &resource_id, &Need { requested: 8, household_size: 0 }).await.unwrap();
+    cluster.register_need(&bob, &resource_id, &Need { requested: 8, household_size: 0 }).await.unwrap();
+    let instance = ResourceInstance {
+        from: alice.pubkey().to_bytes(),
+        quantity: 10,
+    };
+    let record_instance = cluster.record_instance_instruction(&instance);
+    cluster.process(&[record_instance], &[&alice]).await.unwrap();
+
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert!(database.draw.committed_slot >= DRAW_DELAY);
+    assert!(!database.draw.is_drawn());
+    assert_eq!(database.final_quantity, 0);
+
+    // the committed slot is still to come, without the sysvar there is nothing to draw with
+    let draw_lottery = cluster.resource_clock_instruction(vec![INSTRUCTION_DRAW_LOTTERY]);
+    assert_eq!(
+        cluster.process(std::slice::from_ref(&draw_lottery), &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DrawPending as u32))),
+    );
+    let mut draw_lottery = draw_lottery;
+    draw_lottery.accounts.push(AccountMeta::new_readonly(sysvar::slot_hashes::id(), false));
+    assert_eq!(
+        cluster.process(&[draw_lottery], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DrawPending as u32))),
+    );
+    let claim = cluster.claim_instruction(&alice.pubkey(), &[]);
+    assert_eq!(
+        cluster.process(&[claim], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DrawPending as u32))),
+    );
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    assert_eq!(
+        cluster.process(&[finalize_distribution], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DrawPending as u32))),
+    );
+}
+
+#[tokio::test]
+async fn test_trust_weighted_claim() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let bob = cluster.add_user("bob", None).await;
+    let alice = cluster.add_user("alice", Some((&bob.pubkey(), 10))).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    cluster.register_intent(&bob, &resource_id).await.unwrap();
+
+    let quorum = QuorumRule {
+        kind: QUORUM_TRUST_WEIGHTED,
+        threshold: 10,
+        trust_program: cluster.searchengine_id.to_bytes(),
+    };
+    let set_quorum = cluster.setup_instruction(instruction_data(INSTRUCTION_SET_QUORUM, &quorum));
+    cluster.process(&[set_quorum], &[]).await.unwrap();
+    let initiate_distribution = cluster.initiate_distribution_instruction(3600);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+
+    // the quorum cannot change once distribution started
+    let set_quorum = cluster.setup_instruction(instruction_data(INSTRUCTION_SET_QUORUM, &QuorumRule::default()));
+    assert_eq!(
+        cluster.process(&[set_quorum], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::ResourceInDistribution as u32))),
+    );
+
+    let challenge = Challenge {
+        from: alice.pubkey().to_bytes(),
+        to: bob.pubkey().to_bytes(),
+        value: true,
+    };
+    let record_challenge = cluster.challenge_instruction(&challenge);
+    cluster.process(&[record_challenge], &[&alice]).await.unwrap();
+
+    // alice's approval only counts with her account, which trusts bob with 10
+    let claim = cluster.claim_instruction(&bob.pubkey(), &[]);
+    assert_eq!(
+        cluster.process(&[claim], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::QuorumNotMet as u32))),
+    );
+    let claim = cluster.claim_instruction(&bob.pubkey(), &[&alice.pubkey()]);
+    cluster.process(&[claim], &[]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.claims[0], bob.pubkey().to_bytes());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_token_escrow() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    cluster.register_intent(&bob, &resource_id).await.unwrap();
+
+    let mint = cluster.create_mint().await;
+    let (escrow_authority, _) = solana_bpf_resource::escrow_authority(&resource_id, &resource_database);
+    let escrow = cluster.create_token_account(&mint, &escrow_authority, 0).await;
+    let carol = Keypair::new();
+    let carol_tokens = cluster.create_token_account(&mint, &carol.pubkey(), 10).await;
+    let alice_tokens = cluster.create_token_account(&mint, &alice.pubkey(), 0).await;
+    let set_escrow_mint = cluster.setup_instruction(instruction_data(INSTRUCTION_SET_ESCROW_MINT, &mint.to_bytes()));
+    cluster.process(&[set_escrow_mint], &[]).await.unwrap();
+
+    // carol deposits what she records, and only into the database's escrow
+    let instance = ResourceInstance {
+        from: carol.pubkey().to_bytes(),
+        quantity: 4,
+    };
+    let record_instance = |escrow: &Pubkey| Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(carol_tokens, false),
+            AccountMeta::new(*escrow, false),
+            AccountMeta::new_readonly(carol.pubkey(), true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &instance),
+    };
+    assert_eq!(
+        cluster.process(&[record_instance(&alice_tokens)], &[&carol]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::WrongEscrow as u32))),
+    );
+    cluster.process(&[record_instance(&escrow)], &[&carol]).await.unwrap();
+    assert_eq!(cluster.token_balance(&carol_tokens).await, 6);
+    assert_eq!(cluster.token_balance(&escrow).await, 4);
+
+    let initiate_distribution = cluster.initiate_distribution_instruction(3600);
+    let challenge = Challenge {
+        from: bob.pubkey().to_bytes(),
+        to: alice.pubkey().to_bytes(),
+        value: true,
+    };
+    let record_challenge = cluster.challenge_instruction(&challenge);
+    cluster.process(&[initiate_distribution, record_challenge], &[&bob]).await.unwrap();
+
+    // the escrow authority signs alice's half of the pool over to her
+    let mut claim = cluster.claim_instruction(&alice.pubkey(), &[]);
+    claim.accounts.extend(vec![
+        AccountMeta::new(escrow, false),
+        AccountMeta::new(alice_tokens, false),
+        AccountMeta::new_readonly(escrow_authority, false),
+        AccountMeta::new_readonly(spl_token::id(), false),
+    ]);
+    cluster.process(&[claim], &[]).await.unwrap();
+    assert_eq!(cluster.token_balance(&alice_tokens).await, 2);
+    assert_eq!(cluster.token_balance(&escrow).await, 2);
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.claims[0], alice.pubkey().to_bytes());
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_lamport_vault() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    cluster.register_resource(&resource_id, "soup kitchen", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+
+    let vault = Keypair::new();
+    let create_vault = cluster.create_account(&vault.pubkey(), LAMPORT_VAULT_SIZE, &resource_id);
+    let mut set_lamport_vault = cluster.setup_instruction(vec![INSTRUCTION_SET_LAMPORT_VAULT]);
+    set_lamport_vault.accounts.push(AccountMeta::new(vault.pubkey(), false));
+    cluster.process(&[create_vault, set_lamport_vault], &[&vault]).await.unwrap();
+    let vault_balance = cluster.banks_client.get_balance(vault.pubkey()).await.unwrap();
+
+    let carol = Keypair::new();
+    let dave = Keypair::new();
+    let fund = |to: &Pubkey| system_instruction::transfer(&cluster.payer.pubkey(), to, 1_000_000_000);
+    let fund_providers = [fund(&carol.pubkey()), fund(&dave.pubkey())];
+    cluster.process(&fund_providers, &[]).await.unwrap();
+
+    // providers deposit what they record, and only into the database's vault
+    let record_instance = |provider: &Keypair, vault: &Pubkey, quantity: u64| Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(*vault, false),
+            AccountMeta::new(provider.pubkey(), true),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &ResourceInstance {
+            from: provider.pubkey().to_bytes(),
+            quantity,
+        }),
+    };
+    assert_eq!(
+        cluster.process(&[record_instance(&carol, &alice.pubkey(), 6)], &[&carol]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::WrongEscrow as u32))),
+    );
+    let deposits = [record_instance(&carol, &vault.pubkey(), 6), record_instance(&dave, &vault.pubkey(), 2)];
+    cluster.process(&deposits, &[&carol, &dave]).await.unwrap();
+    assert_eq!(cluster.banks_client.get_balance(vault.pubkey()).await.unwrap(), vault_balance + 8);
+
+    // alice has no peers to approve her, and as the only recipient is paid the whole pool
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    cluster.process(&[initiate_distribution], &[]).await.unwrap();
+    let alice_balance = cluster.banks_client.get_balance(alice.pubkey()).await.unwrap();
+    let mut claim = cluster.claim_instruction(&alice.pubkey(), &[]);
+    claim.accounts.extend(vec![
+        AccountMeta::new(vault.pubkey(), false),
+        AccountMeta::new(alice.pubkey(), false),
+    ]);
+    cluster.process(&[claim], &[]).await.unwrap();
+    assert_eq!(cluster.banks_client.get_balance(alice.pubkey()).await.unwrap(), alice_balance + 8);
+
+    // nothing is left to go back to carol and dave, their refunds only close their part of the pool
+    let refund = |contributor: &Pubkey| Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(vault.pubkey(), false),
+            AccountMeta::new(*contributor, false),
+        ],
+        data: vec![INSTRUCTION_REFUND_CONTRIBUTION],
+    };
+    assert_eq!(
+        cluster.process(&[refund(&carol.pubkey())], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::DistributionOpen as u32))),
+    );
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    let refunds = [finalize_distribution, refund(&carol.pubkey()), refund(&dave.pubkey())];
+    cluster.process(&refunds, &[]).await.unwrap();
+    assert_eq!(cluster.banks_client.get_balance(carol.pubkey()).await.unwrap(), 1_000_000_000 - 6);
+    assert_eq!(cluster.banks_client.get_balance(dave.pubkey()).await.unwrap(), 1_000_000_000 - 2);
+    assert_eq!(cluster.banks_client.get_balance(vault.pubkey()).await.unwrap(), vault_balance);
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.refunded[..3], [true, true, false]);
+}
+
+#[cfg(feature = "test-bpf")]
+#[tokio::test]
+async fn test_unclaimed_pool_is_refunded() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    cluster.register_intent(&bob, &resource_id).await.unwrap();
+
+    let mint = cluster.create_mint().await;
+    let (escrow_authority, _) = solana_bpf_resource::escrow_authority(&resource_id, &resource_database);
+    let escrow = cluster.create_token_account(&mint, &escrow_authority, 0).await;
+    let carol = Keypair::new();
+    let dave = Keypair::new();
+    let carol_tokens = cluster.create_token_account(&mint, &carol.pubkey(), 10).await;
+    let dave_tokens = cluster.create_token_account(&mint, &dave.pubkey(), 10).await;
+    let set_escrow_mint = cluster.setup_instruction(instruction_data(INSTRUCTION_SET_ESCROW_MINT, &mint.to_bytes()));
+    cluster.process(&[set_escrow_mint], &[]).await.unwrap();
+
+    // carol contributes twice, dave once, and their contributions pool by contributor
+    let record_instance = |provider: &Keypair, tokens: &Pubkey, quantity: u64| Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(*tokens, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new_readonly(provider.pubkey(), true),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &ResourceInstance {
+            from: provider.pubkey().to_bytes(),
+            quantity,
+        }),
+    };
+    let deposits = [
+        record_instance(&carol, &carol_tokens, 2),
+        record_instance(&dave, &dave_tokens, 3),
+        record_instance(&carol, &carol_tokens, 4),
+    ];
+    cluster.process(&deposits, &[&carol, &dave]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.instances[0], ResourceInstance { from: carol.pubkey().to_bytes(), quantity: 6 });
+    assert_eq!(database.instances[1], ResourceInstance { from: dave.pubkey().to_bytes(), quantity: 3 });
+
+    // the window closes before anyone approves, so none of the pool is claimed
+    let history = Keypair::new();
+    let create_history = cluster.create_account(&history.pubkey(), ROUND_HISTORY_SIZE, &resource_id);
+    let start_new_round = Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(history.pubkey(), false),
+        ],
+        data: vec![INSTRUCTION_START_NEW_ROUND],
+    };
+    let initiate_distribution = cluster.initiate_distribution_instruction(0);
+    let finalize_distribution = cluster.resource_clock_instruction(vec![INSTRUCTION_FINALIZE_DISTRIBUTION]);
+    cluster.process(&[create_history, initiate_distribution, finalize_distribution], &[&history]).await.unwrap();
+    assert_eq!(
+        cluster.process(std::slice::from_ref(&start_new_round), &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::RefundsOutstanding as u32))),
+    );
+
+    // anyone sends the contributors their share, to token accounts they own
+    let refund = |tokens: &Pubkey| Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(escrow, false),
+            AccountMeta::new(*tokens, false),
+            AccountMeta::new_readonly(escrow_authority, false),
+            AccountMeta::new_readonly(spl_token::id(), false),
+        ],
+        data: vec![INSTRUCTION_REFUND_CONTRIBUTION],
+    };
+    cluster.process(&[refund(&carol_tokens), refund(&dave_tokens), start_new_round], &[]).await.unwrap();
+    assert_eq!(cluster.token_balance(&carol_tokens).await, 10);
+    assert_eq!(cluster.token_balance(&dave_tokens).await, 10);
+    assert_eq!(cluster.token_balance(&escrow).await, 0);
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.round, 1);
+}
+
+#[tokio::test]
+async fn test_claim_receipt() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let alice = cluster.add_user("alice", None).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    cluster.register_intent(&alice, &resource_id).await.unwrap();
+    let instance = ResourceInstance {
+        from: cluster.payer.pubkey().to_bytes(),
+        quantity: 3,
+    };
+    // the receipt says what the quantity counts
+    let mut unit = UnitOfMeasure::default();
+    unit.name[..6].copy_from_slice(b"loaves");
+    let set_unit = cluster.setup_instruction(instruction_data(INSTRUCTION_SET_UNIT, &unit));
+    let record_instance = cluster.record_instance_instruction(&instance);
+    let initiate_distribution = cluster.initiate_distribution_instruction(3600);
+    cluster.process(&[set_unit, record_instance, initiate_distribution], &[]).await.unwrap();
+
+    let receipt = Keypair::new();
+    let create_receipt = cluster.create_account(&receipt.pubkey(), CLAIM_RECEIPT_SIZE, &resource_id);
+    cluster.process(&[create_receipt], &[&receipt]).await.unwrap();
+    let issue_receipt = |receipt: &Pubkey| Instruction {
+        program_id: resource_id,
+        accounts: vec![
+            AccountMeta::new(resource_database, false),
+            AccountMeta::new(*receipt, false),
+            AccountMeta::new_readonly(alice.pubkey(), false),
+        ],
+        data: vec![INSTRUCTION_ISSUE_RECEIPT],
+    };
+
+    // no receipt before the claim
+    assert_eq!(
+        cluster.process(&[issue_receipt(&receipt.pubkey())], &[]).await,
+        Err(TransactionError::InstructionError(0, InstructionError::Custom(ResourceError::NotClaimed as u32))),
+    );
+
+    // alice has no peers, so she claims right away and anyone can issue her receipt
+    let claim = cluster.claim_instruction(&alice.pubkey(), &[]);
+    cluster.process(&[claim, issue_receipt(&receipt.pubkey())], &[]).await.unwrap();
+    let issued: ClaimReceipt = cluster.data(&receipt.pubkey()).await;
+    assert_eq!(issued, ClaimReceipt {
+        database: resource_database.to_bytes(),
+        recipient: alice.pubkey().to_bytes(),
+        round: 0,
+        quantity: 3,
+        unit,
+    });
+
+    // the claim has its receipt, a second one would count it twice
+    let second = Keypair::new();
+    let create_second = cluster.create_account(&second.pubkey(), CLAIM_RECEIPT_SIZE, &resource_id);
+    assert_eq!(
+        cluster.process(&[create_second, issue_receipt(&second.pubkey())], &[&second]).await,
+        Err(TransactionError::InstructionError(1, InstructionError::Custom(ResourceError::ReceiptAlreadyIssued as u32))),
+    );
+}
+
+#[tokio::test]
+async fn test_trusted_by_provider_eligibility() {
+    let mut cluster = Cluster::start().await;
+    let resource_id = cluster.resource_id;
+    let resource_database = cluster.resource_database;
+    let searchengine_id = cluster.searchengine_id;
+
+    // carol provides and only takes recipients she trusts at 5 or more
+    let alice = cluster.add_user("alice", None).await;
+    let bob = cluster.add_user("bob", None).await;
+    let carol = cluster.add_user("carol", Some((&alice.pubkey(), 5))).await;
+    cluster.register_resource(&resource_id, "bread", "12345").await.unwrap();
+    let eligibility = EligibilityRule {
+        kind: ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        provider: carol.pubkey().to_bytes(),
+        trust_program: searchengine_id.to_bytes(),
+        min_trust: 5,
+        ..EligibilityRule::default()
+    };
+    let set_eligibility = cluster.setup_instruction(instruction_data(INSTRUCTION_SET_ELIGIBILITY, &eligibility));
+    cluster.process(&[set_eligibility], &[]).await.unwrap();
+
+    let register_intent = |user: &Pubkey| vec![
+        cluster.searchengine_instruction(
+            vec![
+                AccountMeta::new_readonly(cluster.searchengine_database, false),
+                AccountMeta::new(*user, false),
+                AccountMeta::new_readonly(resource_id, false),
+            ],
+            vec![INSTRUCTION_REGISTER_INTENT],
+        ),
+        Instruction {
+            program_id: resource_id,
+            accounts: vec![
+                AccountMeta::new(resource_database, false),
+                AccountMeta::new_readonly(*user, true),
+                AccountMeta::new_readonly(carol.pubkey(), false),
+            ],
+            data: vec![solana_bpf_resource::types::INSTRUCTION_REGISTER_INTENT],
+        },
+    ];
+    let bob_intent = register_intent(&bob.pubkey());
+    let alice_intent = register_intent(&alice.pubkey());
+
+    // carol does not trust bob, so his intent is rolled back on both sides
+    assert_eq!(
+        cluster.process(&bob_intent, &[&bob]).await,
+        Err(TransactionError::InstructionError(1, InstructionError::Custom(ResourceError::NotEligible as u32))),
+    );
+    let bob_account: SearchEngineAccount = cluster.data(&bob.pubkey()).await;
+    assert_eq!(bob_account.intents[0], [0u8; PUBLIC_KEY_SIZE]);
+
+    cluster.process(&alice_intent, &[&alice]).await.unwrap();
+    let database: ResourceDatabase = cluster.data(&resource_database).await;
+    assert_eq!(database.intents[0].recipient, alice.pubkey().to_bytes());
+    assert_eq!(database.intents[1], Intent::default());
+}
@@ -0,0 +1,359 @@
+// Final state of a scenario with keys replaced by user names, for reading allocation outcomes
+use solana_program::pubkey::Pubkey;
+use std::fmt;
+use solana_bpf_searchengine::types::{PUBLIC_KEY_SIZE, Reputation};
+use solana_bpf_resource::types::{
+    QUORUM_ALL, QUORUM_MAJORITY, QUORUM_AT_LEAST, QUORUM_REPUTATION_WEIGHTED, QuorumRule,
+    ELIGIBILITY_OPEN, ELIGIBILITY_ALLOW_LIST, ELIGIBILITY_DENY_LIST, EligibilityRule, Intent,
+    ALLOCATION_PROPORTIONAL, ALLOCATION_EQUAL_SPLIT, ALLOCATION_MAX_MIN_FAIR, ALLOCATION_LOTTERY, Draw,
+};
+use crate::scenario::{Simulator, Step};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct UserReport {
+    pub name: String,
+    pub trust: Vec<(String, u8)>,
+    pub intents: Vec<String>,
+    pub reputation: Reputation,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DistributionReport {
+    pub is_distributed: bool,
+    pub is_finalized: bool,
+    // unix timestamp after which challenges are rejected, once distributed
+    pub deadline: Option<i64>,
+    pub round: u32,
+    // what the recipients were allocated in total
+    pub final_quantity: u64,
+    pub quorum: String,
+    // who may register an intent
+    pub eligibility: String,
+    // how the pool is split between the recipients
+    pub strategy: String,
+    // (recipient, allocation), allocations are zero until distributed
+    pub recipients: Vec<(String, u64)>,
+    // how many recipients take part in a round
+    pub capacity: usize,
+    // waiting for a slot, the longest waiting first
+    pub waitlist: Vec<String>,
+    pub instances: Vec<(String, u64)>,
+    // (from, to, approved)
+    pub challenges: Vec<(String, String, bool)>,
+    pub claims: Vec<String>,
+}
+
+// an archived round, each claimant was paid their allocation
+#[derive(Clone, Debug, PartialEq)]
+pub struct RoundReport {
+    pub round: u32,
+    pub final_quantity: u64,
+    // (recipient, allocation)
+    pub recipients: Vec<(String, u64)>,
+    pub claims: Vec<String>,
+    // (from, to) for every approval
+    pub approvals: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub users: Vec<UserReport>,
+    // zip and the resources indexed under it
+    pub index: Vec<(String, Vec<String>)>,
+    pub distribution: DistributionReport,
+    // oldest first, as far back as the history account keeps
+    pub history: Vec<RoundReport>,
+    pub steps: Vec<Step>,
+}
+
+const EMPTY_ADDRESS: [u8; PUBLIC_KEY_SIZE] = [0u8; PUBLIC_KEY_SIZE];
+
+fn describe_quorum(quorum: &QuorumRule) -> String {
+    match quorum.kind {
+        QUORUM_ALL => "all peers".to_string(),
+        QUORUM_MAJORITY => "majority of peers".to_string(),
+        QUORUM_AT_LEAST => format!("at least {} approvals", quorum.threshold),
+        QUORUM_REPUTATION_WEIGHTED => format!("reputation of at least {}", quorum.threshold),
+        _ => format!("trust of at least {}", quorum.threshold),
+    }
+}
+
+fn describe_strategy(strategy: u8, draw: &Draw) -> String {
+    match strategy {
+        ALLOCATION_PROPORTIONAL => "proportional to need".to_string(),
+        ALLOCATION_EQUAL_SPLIT => "equal split".to_string(),
+        ALLOCATION_MAX_MIN_FAIR => "max-min fair".to_string(),
+        ALLOCATION_LOTTERY if *draw == Draw::default() => "lottery".to_string(),
+        ALLOCATION_LOTTERY if !draw.is_drawn() => format!("lottery committed to slot {}", draw.committed_slot),
+        ALLOCATION_LOTTERY => format!("lottery drawn with the hash of slot {}", draw.slot),
+        _ => "first come, first served".to_string(),
+    }
+}
+
+fn trimmed(bytes: &[u8]) -> String {
+    let end = bytes.iter().rposition(|byte| *byte != 0).map_or(0, |index| index + 1);
+    String::from_utf8_lossy(&bytes[..end]).to_string()
+}
+
+impl Simulator {
+    fn name_of(&self, bytes: &[u8; PUBLIC_KEY_SIZE]) -> String {
+        let pubkey = Pubkey::new(bytes);
+        if pubkey == self.resource_id {
+            return "resource".to_string();
+        }
+        self.users.iter()
+            .find(|(_, user)| *user == pubkey)
+            .map(|(name, _)| name.clone())
+            .unwrap_or_else(|| pubkey.to_string())
+    }
+
+    fn names_of(&self, addresses: &[[u8; PUBLIC_KEY_SIZE]]) -> Vec<String> {
+        addresses.iter().filter(|address| **address != EMPTY_ADDRESS).map(|address| self.name_of(address)).collect()
+    }
+
+    fn allocations_of(&self, intents: &[Intent]) -> Vec<(String, u64)> {
+        intents.iter().filter(|intent| intent.is_registered()).map(|intent| (self.name_of(&intent.recipient), intent.allocation)).collect()
+    }
+
+    fn describe_eligibility(&self, eligibility: &EligibilityRule) -> String {
+        match eligibility.kind {
+            ELIGIBILITY_OPEN => "anyone".to_string(),
+            ELIGIBILITY_ALLOW_LIST => format!("only [{}]", self.names_of(&eligibility.list).join(", ")),
+            ELIGIBILITY_DENY_LIST => format!("anyone but [{}]", self.names_of(&eligibility.list).join(", ")),
+            _ => format!("trusted by {} at {}", self.name_of(&eligibility.provider), eligibility.min_trust),
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        let users = self.users.iter().map(|(name, _)| {
+            let account = self.search_engine_account(name).unwrap();
+            UserReport {
+                name: name.clone(),
+                trust: account.trust_table.iter()
+                    .filter(|entry| entry.to != EMPTY_ADDRESS)
+                    .map(|entry| (self.name_of(&entry.to), entry.value))
+                    .collect(),
+                intents: self.names_of(&account.intents),
+                reputation: account.reputation,
+            }
+        }).collect();
+
+        let index = self.resource_index().buckets.iter()
+            .filter(|bucket| bucket.addresses[0] != EMPTY_ADDRESS)
+            .map(|bucket| (trimmed(&bucket.location.zip), self.names_of(&bucket.addresses)))
+            .collect();
+
+        let database = self.resource_database();
+        let distribution = DistributionReport {
+            is_distributed: database.is_distributed,
+            is_finalized: database.is_finalized,
+            deadline: if database.is_distributed { Some(database.window.deadline()) } else { None },
+            round: database.round,
+            final_quantity: database.final_quantity,
+            quorum: describe_quorum(&database.quorum),
+            eligibility: self.describe_eligibility(&database.eligibility),
+            strategy: describe_strategy(database.strategy, &database.draw),
+            recipients: self.allocations_of(&database.intents),
+            capacity: database.capacity(),
+            waitlist: self.waitlist_pages().iter()
+                .filter_map(|page| self.waitlist_page(page))
+                .flat_map(|page| page.intents.to_vec())
+                .filter(|intent| intent.is_registered())
+                .map(|intent| self.name_of(&intent.recipient))
+                .collect(),
+            instances: database.instances.iter()
+                .filter(|instance| instance.from != EMPTY_ADDRESS)
+                .map(|instance| (self.name_of(&instance.from), instance.quantity))
+                .collect(),
+            challenges: database.challenges.iter()
+                .filter(|challenge| challenge.from != EMPTY_ADDRESS)
+                .map(|challenge| (self.name_of(&challenge.from), self.name_of(&challenge.to), challenge.value))
+                .collect(),
+            claims: self.names_of(&database.claims),
+        };
+
+        let history = self.round_history().rounds().iter().map(|record| RoundReport {
+            round: record.round,
+            final_quantity: record.final_quantity,
+            recipients: self.allocations_of(&record.intents),
+            claims: self.names_of(&record.claims),
+            approvals: record.challenges.iter()
+                .filter(|challenge| challenge.from != EMPTY_ADDRESS && challenge.value)
+                .map(|challenge| (self.name_of(&challenge.from), self.name_of(&challenge.to)))
+                .collect(),
+        }).collect();
+
+        Report {
+            users,
+            index,
+            distribution,
+            history,
+            steps: self.steps.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "steps")?;
+        for step in self.steps.iter() {
+            match &step.error {
+                Some(error) => writeln!(f, "  {}: {}", step.description, error)?,
+                None => writeln!(f, "  {}: ok", step.description)?,
+            }
+        }
+
+        writeln!(f, "users")?;
+        for user in self.users.iter() {
+            let trust: Vec<String> = user.trust.iter().map(|(to, value)| format!("{} ({})", to, value)).collect();
+            writeln!(f, "  {} trusts [{}], intents [{}], reputation {}", user.name, trust.join(", "), user.intents.join(", "), user.reputation.score())?;
+        }
+
+        writeln!(f, "index")?;
+        for (zip, resources) in self.index.iter() {
+            writeln!(f, "  {}: {}", zip, resources.join(", "))?;
+        }
+
+        let distribution = &self.distribution;
+        writeln!(f, "distribution")?;
+        writeln!(f, "  round: {}", distribution.round)?;
+        writeln!(f, "  distributed: {}", distribution.is_distributed)?;
+        if let Some(deadline) = distribution.deadline {
+            writeln!(f, "  deadline: {}", deadline)?;
+        }
+        writeln!(f, "  finalized: {}", distribution.is_finalized)?;
+        writeln!(f, "  final quantity: {}", distribution.final_quantity)?;
+        writeln!(f, "  quorum: {}", distribution.quorum)?;
+        writeln!(f, "  eligible: {}", distribution.eligibility)?;
+        writeln!(f, "  allocation: {}", distribution.strategy)?;
+        let recipients: Vec<String> = distribution.recipients.iter().map(|(name, allocation)| format!("{} ({})", name, allocation)).collect();
+        writeln!(f, "  recipients: {} of {}", recipients.join(", "), distribution.capacity)?;
+        if !distribution.waitlist.is_empty() {
+            writeln!(f, "  waiting: {}", distribution.waitlist.join(", "))?;
+        }
+        for (from, quantity) in distribution.instances.iter() {
+            writeln!(f, "  {} provided {}", from, quantity)?;
+        }
+        for (from, to, approved) in distribution.challenges.iter() {
+            writeln!(f, "  {} {} {}", from, if *approved { "approved" } else { "has not approved" }, to)?;
+        }
+        writeln!(f, "  claims: {}", distribution.claims.join(", "))?;
+
+        writeln!(f, "history")?;
+        for round in self.history.iter() {
+            let recipients: Vec<String> = round.recipients.iter().map(|(name, allocation)| format!("{} ({})", name, allocation)).collect();
+            let approvals: Vec<String> = round.approvals.iter().map(|(from, to)| format!("{} -> {}", from, to)).collect();
+            writeln!(f, "  round {}: {} to [{}] of [{}], approvals [{}]", round.round, round.final_quantity, round.claims.join(", "), recipients.join(", "), approvals.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_bpf_resource::types::MAX_NUM_RECIPIENTS;
+    use crate::scenario::SimulatorError;
+
+    #[test]
+    fn test_report_uses_names() {
+        let mut simulator = Simulator::new();
+        simulator.add_user("alice").unwrap();
+        simulator.add_user("bob").unwrap();
+        simulator.trust("alice", "bob", 7).unwrap();
+        simulator.register_resource("bread", "12345", 10).unwrap();
+        simulator.register_intent("alice").unwrap();
+        simulator.record_instance("bob", 5).unwrap();
+        simulator.distribute(30).unwrap();
+        let _ = simulator.record_instance("bob", 1);
+
+        let report = simulator.report();
+        assert_eq!(report.users[0].trust, vec![("bob".to_string(), 7)]);
+        assert_eq!(report.users[0].intents, vec!["resource".to_string()]);
+        assert_eq!(report.index, vec![("12345".to_string(), vec!["resource".to_string()])]);
+        assert_eq!(report.distribution.recipients, vec![("alice".to_string(), 5)]);
+        assert_eq!(report.distribution.instances, vec![("bob".to_string(), 5)]);
+        assert_eq!(report.distribution.final_quantity, 5);
+        assert_eq!(report.distribution.deadline, Some(30));
+        assert!(!report.distribution.is_finalized);
+        assert_eq!(report.distribution.quorum, "all peers");
+        assert_eq!(report.distribution.eligibility, "anyone");
+        assert_eq!(report.distribution.strategy, "proportional to need");
+        assert_eq!(report.distribution.capacity, MAX_NUM_RECIPIENTS);
+        assert!(report.distribution.waitlist.is_empty());
+
+        let text = report.to_string();
+        assert!(text.contains("bob provides 1: instruction 0 failed: ResourceInDistribution"));
+        assert!(text.contains("alice trusts [bob (7)], intents [resource], reputation 0"));
+        assert!(text.contains("deadline: 30"));
+        assert!(text.contains("quorum: all peers"));
+    }
+
+    #[test]
+    fn test_report_history() {
+        let mut simulator = Simulator::new();
+        simulator.add_user("alice").unwrap();
+        simulator.add_user("bob").unwrap();
+        simulator.register_resource("bread", "12345", 10).unwrap();
+        simulator.register_intent("alice").unwrap();
+        simulator.register_intent("bob").unwrap();
+        simulator.record_instance("alice", 6).unwrap();
+        simulator.distribute(10).unwrap();
+        simulator.approve("bob", "alice").unwrap();
+        simulator.claim("alice").unwrap();
+        simulator.advance_time(10);
+        simulator.finalize().unwrap();
+        simulator.start_new_round().unwrap();
+
+        let report = simulator.report();
+        assert_eq!(report.distribution.round, 1);
+        assert_eq!(report.history, vec![RoundReport {
+            round: 0,
+            final_quantity: 6,
+            recipients: vec![("alice".to_string(), 3), ("bob".to_string(), 3)],
+            claims: vec!["alice".to_string()],
+            approvals: vec![("bob".to_string(), "alice".to_string())],
+        }]);
+        assert!(report.to_string().contains("round 0: 6 to [alice] of [alice (3), bob (3)], approvals [bob -> alice]"));
+
+        // eligibility can change between rounds
+        simulator.set_trusted_by("alice", 3).unwrap();
+        assert_eq!(simulator.report().distribution.eligibility, "trusted by alice at 3");
+    }
+
+    #[test]
+    fn test_report_waitlist() {
+        let mut simulator = Simulator::new();
+        for name in ["alice", "bob", "carol"].iter() {
+            simulator.add_user(name).unwrap();
+        }
+        simulator.register_resource("bread", "12345", 10).unwrap();
+        simulator.set_capacity(1).unwrap();
+        simulator.add_waitlist_page().unwrap();
+        simulator.add_waitlist_page().unwrap();
+        simulator.register_intent("alice").unwrap();
+        simulator.register_intent("carol").unwrap();
+        simulator.register_intent("bob").unwrap();
+        match simulator.register_intent("carol") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "AlreadyWaiting"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let report = simulator.report();
+        assert_eq!(report.distribution.capacity, 1);
+        assert_eq!(report.distribution.waitlist, vec!["carol".to_string(), "bob".to_string()]);
+        let text = report.to_string();
+        assert!(text.contains("recipients: alice (0) of 1"));
+        assert!(text.contains("waiting: carol, bob"));
+    }
+
+    #[test]
+    fn test_describe_weighted_quorum() {
+        let quorum = QuorumRule {
+            kind: QUORUM_REPUTATION_WEIGHTED,
+            threshold: 3,
+            trust_program: [1u8; PUBLIC_KEY_SIZE],
+        };
+        assert_eq!(describe_quorum(&quorum), "reputation of at least 3");
+    }
+}
@@ -0,0 +1,1032 @@
+// Scenario API over both programs. Every call builds the same instructions a client would send
+// and runs them through the store; outcomes are kept as steps for the final report.
+use solana_program::{clock::Clock, hash::hashv, program_error::ProgramError, pubkey::Pubkey, slot_hashes, sysvar};
+use borsh::{BorshDeserialize, BorshSerialize};
+use num_traits::FromPrimitive;
+use thiserror::Error;
+use solana_bpf_searchengine::{
+    error::SearchEngineError,
+    types::{
+        PUBLIC_KEY_SIZE,
+        INSTRUCTION_UPDATE_ACCOUNT,
+        INSTRUCTION_REGISTER_RESOURCE,
+        INSTRUCTION_REGISTER_INTENT as SE_INSTRUCTION_REGISTER_INTENT,
+        INSTRUCTION_INITIALIZE_DATABASE,
+        INSTRUCTION_SETTLE_INTENT,
+        MAX_FRIENDLY_NAME_SIZE,
+        MAX_TRUST_TABLE_SIZE,
+        MAX_NUM_INTENTS,
+        MAX_ZIP_SIZE,
+        RESOURCE_INDEX_SIZE,
+        Location,
+        Reputation,
+        Resource,
+        ResourceIndex,
+        SearchEngineAccount,
+        TrustTableEntry,
+    },
+};
+use solana_bpf_resource::{
+    error::ResourceError,
+    types::{
+        INSTRUCTION_REGISTER_INTENT as RESOURCE_INSTRUCTION_REGISTER_INTENT,
+        INSTRUCTION_RECORD_RESOURCE_INSTANCE,
+        INSTRUCTION_INITIATE_DISTRIBUTION,
+        INSTRUCTION_DRAW_LOTTERY,
+        INSTRUCTION_RECORD_CHALLENGE,
+        INSTRUCTION_CLAIM_CHALLENGE,
+        INSTRUCTION_RESET_DATABASE,
+        INSTRUCTION_FINALIZE_DISTRIBUTION,
+        INSTRUCTION_SET_QUORUM,
+        INSTRUCTION_START_NEW_ROUND,
+        INSTRUCTION_ISSUE_RECEIPT,
+        INSTRUCTION_SET_UNIT,
+        INSTRUCTION_SET_ELIGIBILITY,
+        INSTRUCTION_SET_ALLOCATION_STRATEGY,
+        INSTRUCTION_SET_CAPACITY,
+        INSTRUCTION_ADD_WAITLIST_PAGE,
+        INSTRUCTION_WITHDRAW_INTENT,
+        ELIGIBILITY_TRUSTED_BY_PROVIDER,
+        QUORUM_TRUST_WEIGHTED,
+        QUORUM_REPUTATION_WEIGHTED,
+        RESOURCE_DATABASE_SIZE,
+        ROUND_HISTORY_SIZE,
+        CLAIM_RECEIPT_SIZE,
+        WAITLIST_PAGE_SIZE,
+        Challenge,
+        ClaimReceipt,
+        EligibilityRule,
+        Need,
+        QuorumRule,
+        ResourceDatabase,
+        ResourceInstance,
+        RoundHistory,
+        UnitOfMeasure,
+        WaitlistPage,
+    },
+};
+use crate::store::{AccountStore, Instruction};
+
+#[derive(Clone, Debug, Error, PartialEq)]
+pub enum SimulatorError {
+    #[error("no user named {0}")]
+    UnknownUser(String),
+
+    #[error("a user named {0} already exists")]
+    DuplicateUser(String),
+
+    #[error("the trust table of {0} is full")]
+    TrustTableFull(String),
+
+    /// An instruction failed; the transaction it belonged to was rolled back
+    #[error("instruction {instruction} failed: {name}")]
+    Program {
+        instruction: usize,
+        error: ProgramError,
+        // SearchEngineError/ResourceError variant when the error is one of ours
+        name: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Step {
+    pub description: String,
+    pub error: Option<SimulatorError>,
+}
+
+pub struct Simulator {
+    pub(crate) store: AccountStore,
+    pub searchengine_id: Pubkey,
+    pub searchengine_database: Pubkey,
+    pub resource_id: Pubkey,
+    pub resource_database: Pubkey,
+    pub round_history: Pubkey,
+    // reset the resource database and signs its setup
+    pub authority: Pubkey,
+    pub(crate) clock: Clock,
+    pub(crate) users: Vec<(String, Pubkey)>,
+    pub(crate) steps: Vec<Step>,
+}
+
+fn instruction_data<T: BorshSerialize>(tag: u8, payload: &T) -> Vec<u8> {
+    let mut data = vec![tag];
+    data.extend(payload.try_to_vec().unwrap());
+    data
+}
+
+// names and zip codes are stored zero padded; longer values are truncated
+fn padded<T: Default + AsMut<[u8]>>(value: &str) -> T {
+    let mut padded = T::default();
+    for (place, byte) in padded.as_mut().iter_mut().zip(value.as_bytes()) {
+        *place = *byte;
+    }
+    padded
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    // both programs deployed, with their databases created and initialized
+    pub fn new() -> Self {
+        let mut simulator = Simulator {
+            store: AccountStore::default(),
+            searchengine_id: Pubkey::new_unique(),
+            searchengine_database: Pubkey::new_unique(),
+            resource_id: Pubkey::new_unique(),
+            resource_database: Pubkey::new_unique(),
+            round_history: Pubkey::new_unique(),
+            authority: Pubkey::new_unique(),
+            clock: Clock::default(),
+            users: Vec::new(),
+            steps: Vec::new(),
+        };
+        simulator.set_sysvars();
+        simulator.store.add_program(simulator.searchengine_id, solana_bpf_searchengine::process_instruction);
+        simulator.store.add_program(simulator.resource_id, solana_bpf_resource::process_instruction);
+        simulator.store.create_account(simulator.searchengine_database, RESOURCE_INDEX_SIZE, simulator.searchengine_id);
+        simulator.store.create_account(simulator.resource_database, RESOURCE_DATABASE_SIZE, simulator.resource_id);
+        simulator.store.create_account(simulator.round_history, ROUND_HISTORY_SIZE, simulator.resource_id);
+        let authority = simulator.authority;
+        simulator.transact_signed("initialize databases", &[
+            Instruction {
+                program_id: simulator.searchengine_id,
+                accounts: vec![simulator.searchengine_database, authority],
+                data: vec![INSTRUCTION_INITIALIZE_DATABASE],
+            },
+            Instruction {
+                program_id: simulator.resource_id,
+                accounts: vec![simulator.resource_database, authority],
+                data: vec![INSTRUCTION_RESET_DATABASE],
+            },
+        ], &[authority]).expect("databases initialize");
+        simulator
+    }
+
+    pub fn user(&self, name: &str) -> Result<Pubkey, SimulatorError> {
+        self.users.iter()
+            .find(|(user, _)| user == name)
+            .map(|(_, pubkey)| *pubkey)
+            .ok_or_else(|| SimulatorError::UnknownUser(name.to_string()))
+    }
+
+    pub fn search_engine_account(&self, name: &str) -> Result<SearchEngineAccount, SimulatorError> {
+        let pubkey = self.user(name)?;
+        Ok(SearchEngineAccount::try_from_slice(&self.store.get(&pubkey).unwrap().data).unwrap())
+    }
+
+    pub fn resource_index(&self) -> ResourceIndex {
+        ResourceIndex::try_from_slice(&self.store.get(&self.searchengine_database).unwrap().data).unwrap()
+    }
+
+    pub fn resource_database(&self) -> ResourceDatabase {
+        ResourceDatabase::try_from_slice(&self.store.get(&self.resource_database).unwrap().data).unwrap()
+    }
+
+    pub fn round_history(&self) -> RoundHistory {
+        RoundHistory::try_from_slice(&self.store.get(&self.round_history).unwrap().data).unwrap()
+    }
+
+    pub fn claim_receipt(&self, receipt: &Pubkey) -> Option<ClaimReceipt> {
+        self.store.get(receipt).map(|account| ClaimReceipt::try_from_slice(&account.data).unwrap())
+    }
+
+    pub fn waitlist_page(&self, page: &Pubkey) -> Option<WaitlistPage> {
+        self.store.get(page).and_then(|account| WaitlistPage::try_from_slice(&account.data).ok())
+    }
+
+    // the database's waitlist pages from the head to the tail
+    pub fn waitlist_pages(&self) -> Vec<Pubkey> {
+        let waitlist = self.resource_database().waitlist;
+        let mut pages = Vec::new();
+        if !waitlist.has_pages() {
+            return pages;
+        }
+        let mut page = Pubkey::new(&waitlist.head);
+        loop {
+            pages.push(page);
+            let next = match self.waitlist_page(&page) {
+                Some(data) if page.to_bytes() != waitlist.tail && data.next != [0u8; PUBLIC_KEY_SIZE] => Pubkey::new(&data.next),
+                _ => return pages,
+            };
+            page = next;
+        }
+    }
+
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    // one slot per second is close enough for deadlines, which only look at the timestamp
+    pub fn advance_time(&mut self, seconds: i64) {
+        self.clock.unix_timestamp += seconds;
+        self.clock.slot += seconds.max(0) as u64;
+        self.set_sysvars();
+    }
+
+    // like the runtime's, SlotHashes holds the slots before the current one, newest first. The hash
+    // of the slot number stands in for a bank hash, so a scenario draws the same lottery every
+    // time it runs
+    fn set_sysvars(&mut self) {
+        self.store.set_clock(&self.clock);
+        let entries: Vec<_> = (0..self.clock.slot).rev()
+            .take(slot_hashes::MAX_ENTRIES)
+            .map(|slot| (slot, hashv(&[&slot.to_le_bytes()])))
+            .collect();
+        self.store.set_slot_hashes(&entries);
+    }
+
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    fn error_name(&self, program_id: &Pubkey, error: &ProgramError) -> String {
+        let name = match error {
+            ProgramError::Custom(code) if *program_id == self.searchengine_id => {
+                SearchEngineError::from_u32(*code).map(|err| format!("{:?}", err))
+            }
+            ProgramError::Custom(code) if *program_id == self.resource_id => {
+                ResourceError::from_u32(*code).map(|err| format!("{:?}", err))
+            }
+            _ => None,
+        };
+        name.unwrap_or_else(|| format!("{:?}", error))
+    }
+
+    fn record(&mut self, description: &str, result: Result<(), SimulatorError>) -> Result<(), SimulatorError> {
+        self.steps.push(Step {
+            description: description.to_string(),
+            error: result.clone().err(),
+        });
+        result
+    }
+
+    fn transact(&mut self, description: &str, instructions: &[Instruction]) -> Result<(), SimulatorError> {
+        self.transact_signed(description, instructions, &[])
+    }
+
+    fn transact_signed(&mut self, description: &str, instructions: &[Instruction], signers: &[Pubkey]) -> Result<(), SimulatorError> {
+        let result = self.store.process_signed_transaction(instructions, signers).map_err(|(instruction, error)| SimulatorError::Program {
+            instruction,
+            name: self.error_name(&instructions[instruction].program_id, &error),
+            error,
+        });
+        self.record(description, result)
+    }
+
+    fn update_account(&mut self, description: &str, pubkey: Pubkey, account: &SearchEngineAccount) -> Result<(), SimulatorError> {
+        self.transact(description, &[Instruction {
+            program_id: self.searchengine_id,
+            accounts: vec![pubkey],
+            data: instruction_data(INSTRUCTION_UPDATE_ACCOUNT, account),
+        }])
+    }
+
+    pub fn add_user(&mut self, name: &str) -> Result<Pubkey, SimulatorError> {
+        let description = format!("add user {}", name);
+        if self.user(name).is_ok() {
+            self.record(&description, Err(SimulatorError::DuplicateUser(name.to_string())))?;
+        }
+        let pubkey = Pubkey::new_unique();
+        let account = SearchEngineAccount {
+            friendly_name: padded::<[u8; MAX_FRIENDLY_NAME_SIZE]>(name),
+            trust_table: [TrustTableEntry::default(); MAX_TRUST_TABLE_SIZE],
+            intents: [[0u8; PUBLIC_KEY_SIZE]; MAX_NUM_INTENTS],
+            reputation: Reputation::default(),
+        };
+        self.store.create_account(pubkey, account.try_to_vec().unwrap().len(), self.searchengine_id);
+        self.update_account(&description, pubkey, &account)?;
+        self.users.push((name.to_string(), pubkey));
+        Ok(pubkey)
+    }
+
+    // replaces an existing entry for `to`, otherwise takes the first empty slot
+    pub fn trust(&mut self, from: &str, to: &str, value: u8) -> Result<(), SimulatorError> {
+        let description = format!("{} trusts {} with {}", from, to, value);
+        let (from_pubkey, to_pubkey) = match (self.user(from), self.user(to)) {
+            (Ok(from_pubkey), Ok(to_pubkey)) => (from_pubkey, to_pubkey),
+            (Err(err), _) | (_, Err(err)) => return self.record(&description, Err(err)),
+        };
+        let mut account = self.search_engine_account(from)?;
+        let to_bytes = to_pubkey.to_bytes();
+        let slot = account.trust_table.iter()
+            .position(|entry| entry.to == to_bytes)
+            .or_else(|| account.trust_table.iter().position(|entry| entry.to == [0u8; PUBLIC_KEY_SIZE]));
+        match slot {
+            Some(slot) => {
+                account.trust_table[slot] = TrustTableEntry { to: to_bytes, value };
+                self.update_account(&description, from_pubkey, &account)
+            }
+            None => self.record(&description, Err(SimulatorError::TrustTableFull(from.to_string()))),
+        }
+    }
+
+    // resources are backed by the resource program, the authority vouches for it
+    pub fn register_resource(&mut self, name: &str, zip: &str, trust_threshold: u8) -> Result<(), SimulatorError> {
+        let resource = Resource {
+            address: self.resource_id.to_bytes(),
+            name: padded::<[u8; MAX_FRIENDLY_NAME_SIZE]>(name),
+            location: Location {
+                zip: padded::<[u8; MAX_ZIP_SIZE]>(zip),
+            },
+            trust_threshold,
+        };
+        self.transact_signed(&format!("register resource {} in {}", name, zip), &[Instruction {
+            program_id: self.searchengine_id,
+            accounts: vec![self.searchengine_database, self.authority],
+            data: instruction_data(INSTRUCTION_REGISTER_RESOURCE, &resource),
+        }], &[self.authority])
+    }
+
+    // asks for an even share of the pool
+    pub fn register_intent(&mut self, name: &str) -> Result<(), SimulatorError> {
+        self.register_need(name, 0, 0)
+    }
+
+    // with both programs in one transaction, like `bootstrap register-intent`, passing the
+    // provider's account when the resource only admits recipients they trust and the
+    // waitlist's pages, checked for the intent and queued on, once every slot is taken
+    pub fn register_need(&mut self, name: &str, requested: u64, household_size: u16) -> Result<(), SimulatorError> {
+        let description = format!("{} registers an intent", name);
+        let pubkey = match self.user(name) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return self.record(&description, Err(err)),
+        };
+        let mut accounts = vec![self.resource_database, pubkey];
+        let database = self.resource_database();
+        if database.eligibility.kind == ELIGIBILITY_TRUSTED_BY_PROVIDER {
+            accounts.push(Pubkey::new(&database.eligibility.provider));
+        }
+        accounts.extend(self.waitlist_pages());
+        self.transact_signed(&description, &[
+            Instruction {
+                program_id: self.searchengine_id,
+                accounts: vec![self.searchengine_database, pubkey, self.resource_id],
+                data: vec![SE_INSTRUCTION_REGISTER_INTENT],
+            },
+            Instruction {
+                program_id: self.resource_id,
+                accounts,
+                data: instruction_data(RESOURCE_INSTRUCTION_REGISTER_INTENT, &Need { requested, household_size }),
+            },
+        ], &[pubkey])
+    }
+
+    // the user signs for what they provide
+    pub fn record_instance(&mut self, name: &str, quantity: u64) -> Result<(), SimulatorError> {
+        let description = format!("{} provides {}", name, quantity);
+        let pubkey = match self.user(name) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return self.record(&description, Err(err)),
+        };
+        let instance = ResourceInstance {
+            from: pubkey.to_bytes(),
+            quantity,
+        };
+        self.transact_signed(&description, &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, pubkey],
+            data: instruction_data(INSTRUCTION_RECORD_RESOURCE_INSTANCE, &instance),
+        }], &[pubkey])
+    }
+
+    // challenges are accepted for `duration` seconds from now, a lottery is committed to a slot
+    // DRAW_DELAY slots from now and waits for draw. The authority opens the round
+    pub fn distribute(&mut self, duration: i64) -> Result<(), SimulatorError> {
+        self.transact_signed(&format!("initiate distribution for {}s", duration), &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, self.authority, sysvar::clock::id()],
+            data: instruction_data(INSTRUCTION_INITIATE_DISTRIBUTION, &duration),
+        }], &[self.authority])
+    }
+
+    // allocates a lottery once the clock is past its committed slot
+    pub fn draw(&mut self) -> Result<(), SimulatorError> {
+        self.transact("draw lottery", &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, sysvar::clock::id(), sysvar::slot_hashes::id()],
+            data: vec![INSTRUCTION_DRAW_LOTTERY],
+        }])
+    }
+
+    pub fn finalize(&mut self) -> Result<(), SimulatorError> {
+        self.transact("finalize distribution", &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, sysvar::clock::id()],
+            data: vec![INSTRUCTION_FINALIZE_DISTRIBUTION],
+        }])
+    }
+
+    // archives the finalized round, recipients and the quorum carry over to the next one and
+    // waiting intents take the slots of those who did not claim
+    pub fn start_new_round(&mut self) -> Result<(), SimulatorError> {
+        let mut accounts = vec![self.resource_database, self.round_history];
+        accounts.extend(self.waitlist_pages());
+        self.transact("start new round", &[Instruction {
+            program_id: self.resource_id,
+            accounts,
+            data: vec![INSTRUCTION_START_NEW_ROUND],
+        }])
+    }
+
+    // at most MAX_NUM_RECIPIENTS take part in a round, 0 for all of them
+    pub fn set_capacity(&mut self, capacity: u8) -> Result<(), SimulatorError> {
+        let mut accounts = vec![self.resource_database, self.authority];
+        accounts.extend(self.waitlist_pages());
+        self.transact_signed(&format!("set capacity {}", capacity), &[Instruction {
+            program_id: self.resource_id,
+            accounts,
+            data: vec![INSTRUCTION_SET_CAPACITY, capacity],
+        }], &[self.authority])
+    }
+
+    // each page goes into a new account chained after the tail
+    pub fn add_waitlist_page(&mut self) -> Result<Pubkey, SimulatorError> {
+        let page = Pubkey::new_unique();
+        self.store.create_account(page, WAITLIST_PAGE_SIZE, self.resource_id);
+        let mut accounts = vec![self.resource_database, page];
+        accounts.extend(self.waitlist_pages().last());
+        self.transact("add waitlist page", &[Instruction {
+            program_id: self.resource_id,
+            accounts,
+            data: vec![INSTRUCTION_ADD_WAITLIST_PAGE],
+        }])?;
+        Ok(page)
+    }
+
+    // signed by the recipient, the longest waiting intent takes their slot
+    pub fn withdraw(&mut self, name: &str) -> Result<(), SimulatorError> {
+        let description = format!("{} withdraws", name);
+        let pubkey = match self.user(name) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return self.record(&description, Err(err)),
+        };
+        let mut accounts = vec![self.resource_database, pubkey];
+        accounts.extend(self.waitlist_pages());
+        self.transact_signed(&description, &[Instruction {
+            program_id: self.resource_id,
+            accounts,
+            data: vec![INSTRUCTION_WITHDRAW_INTENT],
+        }], &[pubkey])
+    }
+
+    // counts the user's part in the archived rounds and the finalized distribution into their
+    // reputation
+    pub fn settle(&mut self, name: &str) -> Result<(), SimulatorError> {
+        let description = format!("{} settles", name);
+        let pubkey = match self.user(name) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return self.record(&description, Err(err)),
+        };
+        self.transact(&description, &[Instruction {
+            program_id: self.searchengine_id,
+            accounts: vec![self.searchengine_database, pubkey, self.resource_database, self.round_history],
+            data: vec![INSTRUCTION_SETTLE_INTENT],
+        }])
+    }
+
+    // weighted rules read the approvers' search engine accounts
+    // quantities recorded afterwards count 1 / 10^decimals of `name`
+    pub fn set_unit(&mut self, name: &str, decimals: u8) -> Result<(), SimulatorError> {
+        let mut unit = UnitOfMeasure {
+            decimals,
+            ..UnitOfMeasure::default()
+        };
+        for (place, data) in unit.name.iter_mut().zip(name.as_bytes().iter()) {
+            *place = *data
+        }
+        self.transact_signed(&format!("set unit {} with {} decimals", name, decimals), &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, self.authority],
+            data: instruction_data(INSTRUCTION_SET_UNIT, &unit),
+        }], &[self.authority])
+    }
+
+    // one of the ALLOCATION_* strategies, before anyone registers or records
+    pub fn set_allocation_strategy(&mut self, strategy: u8) -> Result<(), SimulatorError> {
+        self.transact_signed(&format!("set allocation strategy {}", strategy), &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, self.authority],
+            data: vec![INSTRUCTION_SET_ALLOCATION_STRATEGY, strategy],
+        }], &[self.authority])
+    }
+
+    pub fn set_quorum(&mut self, kind: u8, threshold: u16) -> Result<(), SimulatorError> {
+        let is_weighted = kind == QUORUM_TRUST_WEIGHTED || kind == QUORUM_REPUTATION_WEIGHTED;
+        let quorum = QuorumRule {
+            kind,
+            threshold,
+            trust_program: if is_weighted { self.searchengine_id.to_bytes() } else { [0u8; PUBLIC_KEY_SIZE] },
+        };
+        self.transact_signed(&format!("set quorum {} with threshold {}", kind, threshold), &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, self.authority],
+            data: instruction_data(INSTRUCTION_SET_QUORUM, &quorum),
+        }], &[self.authority])
+    }
+
+    // an allow or deny list of users, or an open resource with no names
+    pub fn set_eligibility(&mut self, kind: u8, names: &[&str]) -> Result<(), SimulatorError> {
+        let description = format!("set eligibility {} for [{}]", kind, names.join(", "));
+        let mut eligibility = EligibilityRule {
+            kind,
+            ..EligibilityRule::default()
+        };
+        for (slot, name) in eligibility.list.iter_mut().zip(names.iter()) {
+            match self.user(name) {
+                Ok(pubkey) => *slot = pubkey.to_bytes(),
+                Err(err) => return self.record(&description, Err(err)),
+            }
+        }
+        self.transact_signed(&description, &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, self.authority],
+            data: instruction_data(INSTRUCTION_SET_ELIGIBILITY, &eligibility),
+        }], &[self.authority])
+    }
+
+    // only users `provider` trusts with at least `min_trust` can register from now on
+    pub fn set_trusted_by(&mut self, provider: &str, min_trust: u8) -> Result<(), SimulatorError> {
+        let description = format!("admit users {} trusts at {}", provider, min_trust);
+        let pubkey = match self.user(provider) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return self.record(&description, Err(err)),
+        };
+        let eligibility = EligibilityRule {
+            kind: ELIGIBILITY_TRUSTED_BY_PROVIDER,
+            provider: pubkey.to_bytes(),
+            trust_program: self.searchengine_id.to_bytes(),
+            min_trust,
+            ..EligibilityRule::default()
+        };
+        self.transact_signed(&description, &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, self.authority],
+            data: instruction_data(INSTRUCTION_SET_ELIGIBILITY, &eligibility),
+        }], &[self.authority])
+    }
+
+    fn challenge(&mut self, description: &str, from: &str, to: &str, value: bool) -> Result<(), SimulatorError> {
+        let (from_pubkey, to_pubkey) = match (self.user(from), self.user(to)) {
+            (Ok(from_pubkey), Ok(to_pubkey)) => (from_pubkey, to_pubkey),
+            (Err(err), _) | (_, Err(err)) => return self.record(description, Err(err)),
+        };
+        let challenge = Challenge {
+            from: from_pubkey.to_bytes(),
+            to: to_pubkey.to_bytes(),
+            value,
+        };
+        self.transact_signed(description, &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, sysvar::clock::id(), from_pubkey],
+            data: instruction_data(INSTRUCTION_RECORD_CHALLENGE, &challenge),
+        }], &[from_pubkey])
+    }
+
+    pub fn approve(&mut self, from: &str, to: &str) -> Result<(), SimulatorError> {
+        self.challenge(&format!("{} approves {}", from, to), from, to, true)
+    }
+
+    pub fn deny(&mut self, from: &str, to: &str) -> Result<(), SimulatorError> {
+        self.challenge(&format!("{} denies {}", from, to), from, to, false)
+    }
+
+    // every other user's account goes along, the program only looks at the approvers'
+    pub fn claim(&mut self, name: &str) -> Result<(), SimulatorError> {
+        let description = format!("{} claims", name);
+        let pubkey = match self.user(name) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return self.record(&description, Err(err)),
+        };
+        let challenge = Challenge {
+            from: pubkey.to_bytes(),
+            to: [0u8; PUBLIC_KEY_SIZE],
+            value: true,
+        };
+        let mut accounts = vec![self.resource_database];
+        accounts.extend(self.users.iter().map(|(_, user)| *user).filter(|user| *user != pubkey));
+        self.transact(&description, &[Instruction {
+            program_id: self.resource_id,
+            accounts,
+            data: instruction_data(INSTRUCTION_CLAIM_CHALLENGE, &challenge),
+        }])
+    }
+    // each receipt goes into a new account, its address is what the recipient hands on
+    pub fn issue_receipt(&mut self, name: &str) -> Result<Pubkey, SimulatorError> {
+        let description = format!("issue {} a receipt", name);
+        let pubkey = match self.user(name) {
+            Ok(pubkey) => pubkey,
+            Err(err) => return Err(self.record(&description, Err(err)).unwrap_err()),
+        };
+        let receipt = Pubkey::new_unique();
+        self.store.create_account(receipt, CLAIM_RECEIPT_SIZE, self.resource_id);
+        self.transact(&description, &[Instruction {
+            program_id: self.resource_id,
+            accounts: vec![self.resource_database, receipt, pubkey],
+            data: vec![INSTRUCTION_ISSUE_RECEIPT],
+        }])?;
+        Ok(receipt)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use solana_bpf_resource::{
+        allocation,
+        types::{QUORUM_ALL, ELIGIBILITY_DENY_LIST, ALLOCATION_PROPORTIONAL, ALLOCATION_EQUAL_SPLIT, ALLOCATION_FIRST_COME_FIRST_SERVED, ALLOCATION_LOTTERY, DRAW_DELAY},
+    };
+
+    fn two_recipients() -> Simulator {
+        let mut simulator = Simulator::new();
+        simulator.add_user("alice").unwrap();
+        simulator.add_user("bob").unwrap();
+        simulator.trust("alice", "bob", 10).unwrap();
+        simulator.trust("bob", "alice", 10).unwrap();
+        simulator.register_resource("bread", "12345", 10).unwrap();
+        simulator.register_intent("alice").unwrap();
+        simulator.register_intent("bob").unwrap();
+        simulator
+    }
+
+    #[test]
+    fn test_lifecycle() {
+        let mut simulator = two_recipients();
+        simulator.record_instance("alice", 4).unwrap();
+        simulator.record_instance("bob", 2).unwrap();
+        simulator.distribute(60).unwrap();
+        simulator.approve("alice", "bob").unwrap();
+        simulator.approve("bob", "alice").unwrap();
+        simulator.claim("alice").unwrap();
+        simulator.claim("bob").unwrap();
+        simulator.advance_time(60);
+        simulator.finalize().unwrap();
+
+        let database = simulator.resource_database();
+        assert!(database.is_distributed);
+        assert!(database.is_finalized);
+        assert_eq!(database.final_quantity, 6);
+        assert!(database.challenges.iter().filter(|challenge| challenge.from != [0u8; PUBLIC_KEY_SIZE]).all(|challenge| challenge.value));
+        assert_eq!(database.claims[..2], [simulator.user("alice").unwrap().to_bytes(), simulator.user("bob").unwrap().to_bytes()]);
+
+        let alice = simulator.search_engine_account("alice").unwrap();
+        assert_eq!(alice.intents[0], simulator.resource_id.to_bytes());
+        assert_eq!(alice.trust_table[0].to, simulator.user("bob").unwrap().to_bytes());
+        assert!(simulator.steps().iter().all(|step| step.error.is_none()));
+    }
+
+    #[test]
+    fn test_receipts() {
+        let mut simulator = two_recipients();
+        simulator.record_instance("alice", 8).unwrap();
+        simulator.distribute(60).unwrap();
+        simulator.approve("alice", "bob").unwrap();
+        simulator.claim("bob").unwrap();
+
+        let receipt = simulator.issue_receipt("bob").unwrap();
+        let issued = simulator.claim_receipt(&receipt).unwrap();
+        assert_eq!(issued.recipient, simulator.user("bob").unwrap().to_bytes());
+        assert_eq!(issued.database, simulator.resource_database.to_bytes());
+        assert_eq!(issued.quantity, 4);
+        match simulator.issue_receipt("bob") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "ReceiptAlreadyIssued"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match simulator.issue_receipt("alice") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "NotClaimed"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_pooled_contributions() {
+        let mut simulator = two_recipients();
+        simulator.record_instance("alice", 4).unwrap();
+        simulator.record_instance("alice", 3).unwrap();
+        simulator.record_instance("bob", 2).unwrap();
+        simulator.distribute(0).unwrap();
+
+        // one slot per contributor, and the pool of 9 splits evenly between the two recipients,
+        // leaving 1 undistributed
+        let database = simulator.resource_database();
+        assert_eq!(database.instances[0], ResourceInstance { from: simulator.user("alice").unwrap().to_bytes(), quantity: 7 });
+        assert_eq!(database.instances[1], ResourceInstance { from: simulator.user("bob").unwrap().to_bytes(), quantity: 2 });
+        assert_eq!(database.instances[2], ResourceInstance::default());
+        assert_eq!(database.final_quantity, 8);
+        assert_eq!(database.allocation(&simulator.user("bob").unwrap().to_bytes()), 4);
+    }
+
+    #[test]
+    fn test_needs() {
+        let mut simulator = Simulator::new();
+        simulator.add_user("alice").unwrap();
+        simulator.add_user("bob").unwrap();
+        simulator.register_resource("bread", "12345", 10).unwrap();
+        simulator.register_need("alice", 2, 1).unwrap();
+        simulator.register_need("bob", 0, 3).unwrap();
+        simulator.record_instance("alice", 10).unwrap();
+        simulator.distribute(0).unwrap();
+
+        // bob asks for his household's share of the pool, 3 of 4 people, and alice for 2
+        let database = simulator.resource_database();
+        assert_eq!(database.allocation(&simulator.user("alice").unwrap().to_bytes()), 2);
+        assert_eq!(database.allocation(&simulator.user("bob").unwrap().to_bytes()), 7);
+        assert_eq!(database.final_quantity, 9);
+    }
+
+    #[test]
+    fn test_allocation_strategies() {
+        // the same pool and needs under every strategy
+        let outcomes: Vec<(u64, u64)> = (ALLOCATION_PROPORTIONAL..=ALLOCATION_FIRST_COME_FIRST_SERVED).map(|strategy| {
+            let mut simulator = Simulator::new();
+            simulator.add_user("alice").unwrap();
+            simulator.add_user("bob").unwrap();
+            simulator.register_resource("bread", "12345", 10).unwrap();
+            simulator.set_allocation_strategy(strategy).unwrap();
+            simulator.register_need("alice", 30, 0).unwrap();
+            simulator.register_need("bob", 2, 0).unwrap();
+            simulator.record_instance("alice", 10).unwrap();
+            simulator.distribute(0).unwrap();
+            let database = simulator.resource_database();
+            (database.allocation(&simulator.user("alice").unwrap().to_bytes()), database.allocation(&simulator.user("bob").unwrap().to_bytes()))
+        }).collect();
+        assert_eq!(outcomes, vec![(9, 0), (5, 5), (8, 2), (10, 0)]);
+
+        let mut simulator = two_recipients();
+        match simulator.set_allocation_strategy(ALLOCATION_EQUAL_SPLIT) {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "StrategyInUse"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_lottery() {
+        let mut simulator = Simulator::new();
+        simulator.add_user("alice").unwrap();
+        simulator.add_user("bob").unwrap();
+        simulator.register_resource("bread", "12345", 10).unwrap();
+        simulator.set_allocation_strategy(ALLOCATION_LOTTERY).unwrap();
+        simulator.register_need("alice", 8, 0).unwrap();
+        simulator.register_need("bob", 8, 0).unwrap();
+        simulator.record_instance("alice", 10).unwrap();
+        simulator.advance_time(5);
+        simulator.distribute(0).unwrap();
+
+        // nothing is drawn before the committed slot passed
+        match simulator.draw() {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "DrawPending"),
+            other => panic!("unexpected {:?}", other),
+        }
+        simulator.advance_time(DRAW_DELAY as i64 + 1);
+        simulator.draw().unwrap();
+
+        // one winner gets their 8, and the draw recorded in the database gives the same winner
+        let database = simulator.resource_database();
+        assert_eq!(database.draw.committed_slot, 5 + DRAW_DELAY);
+        assert_eq!(database.draw.slot, 5 + DRAW_DELAY);
+        assert_eq!(&database.draw.hash[..], hashv(&[&(5 + DRAW_DELAY).to_le_bytes()]).as_ref());
+        let (order, drawn) = allocation::draw(&database.draw.hash, &database.intents);
+        assert_eq!(drawn, 2);
+        assert_eq!(database.intents[order[0]].allocation, 8);
+        assert_eq!(database.intents[order[1]].allocation, 2);
+        let mut intents = database.intents;
+        assert_eq!(allocation::allocate(ALLOCATION_LOTTERY, 10, &database.draw.hash, &mut intents), database.final_quantity);
+        assert_eq!(intents, database.intents);
+        assert_eq!(simulator.report().distribution.strategy, format!("lottery drawn with the hash of slot {}", 5 + DRAW_DELAY));
+    }
+
+    #[test]
+    fn test_eligibility() {
+        let mut simulator = Simulator::new();
+        simulator.add_user("alice").unwrap();
+        simulator.add_user("bob").unwrap();
+        simulator.add_user("carol").unwrap();
+        simulator.trust("carol", "alice", 5).unwrap();
+        simulator.register_resource("bread", "12345", 10).unwrap();
+
+        simulator.set_eligibility(ELIGIBILITY_DENY_LIST, &["bob"]).unwrap();
+        match simulator.register_intent("bob") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "NotEligible"),
+            other => panic!("unexpected {:?}", other),
+        }
+        // the search engine side is rolled back with it
+        assert_eq!(simulator.search_engine_account("bob").unwrap().intents[0], [0u8; PUBLIC_KEY_SIZE]);
+
+        simulator.set_trusted_by("carol", 5).unwrap();
+        match simulator.register_intent("bob") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "NotEligible"),
+            other => panic!("unexpected {:?}", other),
+        }
+        simulator.register_intent("alice").unwrap();
+        assert_eq!(simulator.resource_database().intents[0].recipient, simulator.user("alice").unwrap().to_bytes());
+    }
+
+    #[test]
+    fn test_units() {
+        let mut simulator = two_recipients();
+        match simulator.set_unit("kg", 20) {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "InvalidUnit"),
+            other => panic!("unexpected {:?}", other),
+        }
+        simulator.set_unit("kg", 1).unwrap();
+        simulator.record_instance("alice", 25).unwrap();
+        match simulator.set_unit("g", 0) {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "UnitInUse"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match simulator.record_instance("bob", u64::MAX) {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "QuantityOverflow"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let database = simulator.resource_database();
+        assert_eq!(&database.unit.name[..3], b"kg\0");
+        assert_eq!(database.unit.decimals, 1);
+        assert_eq!(database.instances[1], ResourceInstance::default());
+    }
+
+    #[test]
+    fn test_rounds() {
+        let mut simulator = two_recipients();
+        simulator.record_instance("alice", 8).unwrap();
+        simulator.distribute(60).unwrap();
+        simulator.approve("alice", "bob").unwrap();
+        simulator.claim("bob").unwrap();
+        match simulator.start_new_round() {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "DistributionOpen"),
+            other => panic!("unexpected {:?}", other),
+        }
+        simulator.advance_time(60);
+        simulator.finalize().unwrap();
+        simulator.settle("bob").unwrap();
+        simulator.start_new_round().unwrap();
+
+        // a week later the same recipients share again, without registering
+        simulator.advance_time(7 * 24 * 60 * 60);
+        simulator.record_instance("bob", 2).unwrap();
+        simulator.distribute(0).unwrap();
+        simulator.finalize().unwrap();
+        simulator.start_new_round().unwrap();
+
+        let database = simulator.resource_database();
+        assert_eq!(database.round, 2);
+        assert_eq!(database.intents.iter().filter(|intent| intent.is_registered()).map(|intent| intent.recipient).collect::<Vec<_>>(), vec![simulator.user("alice").unwrap().to_bytes(), simulator.user("bob").unwrap().to_bytes()]);
+
+        let history = simulator.round_history();
+        let bob = simulator.user("bob").unwrap().to_bytes();
+        let first = history.get(0).unwrap();
+        assert_eq!(first.allocation(&bob), 4);
+        assert_eq!(first.approvals_received(&bob), 1);
+        let second = history.get(1).unwrap();
+        assert_eq!(second.final_quantity, 2);
+        assert_eq!(second.allocation(&bob), 0);
+        assert_eq!(history.rounds().len(), 2);
+
+        // both archived rounds still settle, bob's first one only counts once
+        simulator.settle("alice").unwrap();
+        simulator.settle("bob").unwrap();
+        simulator.settle("bob").unwrap_err();
+        let alice = simulator.search_engine_account("alice").unwrap().reputation;
+        assert_eq!((alice.approvals_given, alice.disputes), (1, 2));
+        let bob = simulator.search_engine_account("bob").unwrap().reputation;
+        assert_eq!((bob.approvals_received, bob.claims, bob.disputes), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_waitlist() {
+        let mut simulator = two_recipients();
+        simulator.add_user("carol").unwrap();
+        simulator.add_user("dave").unwrap();
+        simulator.set_capacity(1).unwrap_err();
+        simulator.withdraw("bob").unwrap();
+        simulator.set_capacity(1).unwrap();
+        simulator.add_waitlist_page().unwrap();
+        simulator.register_intent("bob").unwrap();
+        simulator.register_intent("carol").unwrap();
+        assert_eq!(simulator.resource_database().waitlist.waiting, 2);
+
+        // bob waited longest, carol moves up once there is room again
+        simulator.withdraw("alice").unwrap();
+        simulator.set_capacity(2).unwrap();
+        let database = simulator.resource_database();
+        assert_eq!(database.intents[0].recipient, simulator.user("bob").unwrap().to_bytes());
+        assert_eq!(database.intents[1].recipient, simulator.user("carol").unwrap().to_bytes());
+        assert_eq!(database.waitlist.waiting, 0);
+
+        // carol does not claim, dave waiting takes her slot in the next round
+        simulator.register_intent("dave").unwrap();
+        simulator.record_instance("alice", 4).unwrap();
+        simulator.distribute(60).unwrap();
+        simulator.approve("carol", "bob").unwrap();
+        simulator.claim("bob").unwrap();
+        simulator.advance_time(60);
+        simulator.finalize().unwrap();
+        simulator.start_new_round().unwrap();
+        let database = simulator.resource_database();
+        assert_eq!(database.intents[1].recipient, simulator.user("dave").unwrap().to_bytes());
+        assert_eq!(simulator.waitlist_pages().len(), 1);
+    }
+
+    #[test]
+    fn test_errors_are_decoded_and_rolled_back() {
+        let mut simulator = two_recipients();
+        simulator.set_capacity(2).unwrap();
+        simulator.distribute(60).unwrap();
+        let before = simulator.resource_database();
+
+        match simulator.record_instance("alice", 1) {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "ResourceInDistribution"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(simulator.resource_database(), before);
+
+        // the resource has no recipient slot left and no waitlist page, so the search engine side
+        // is rolled back too
+        simulator.add_user("carol").unwrap();
+        assert!(simulator.register_intent("carol").is_err());
+        assert_eq!(simulator.search_engine_account("carol").unwrap().intents[0], [0u8; PUBLIC_KEY_SIZE]);
+
+        assert_eq!(simulator.trust("alice", "carol", 1), Err(SimulatorError::TrustTableFull("alice".to_string())));
+        assert_eq!(simulator.trust("alice", "mallory", 1), Err(SimulatorError::UnknownUser("mallory".to_string())));
+        assert_eq!(simulator.steps().iter().filter(|step| step.error.is_some()).count(), 4);
+    }
+
+    #[test]
+    fn test_quorum() {
+        let mut simulator = two_recipients();
+        simulator.set_quorum(QUORUM_TRUST_WEIGHTED, 10).unwrap();
+        simulator.distribute(60).unwrap();
+        simulator.approve("alice", "bob").unwrap();
+        simulator.deny("bob", "alice").unwrap();
+
+        // alice trusts bob with 10, which is just enough
+        simulator.claim("bob").unwrap();
+        match simulator.claim("alice") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "QuorumNotMet"),
+            other => panic!("unexpected {:?}", other),
+        }
+        match simulator.set_quorum(QUORUM_ALL, 0) {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "ResourceInDistribution"),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(simulator.resource_database().claims[0], simulator.user("bob").unwrap().to_bytes());
+    }
+
+    #[test]
+    fn test_reputation() {
+        let mut simulator = two_recipients();
+        simulator.distribute(60).unwrap();
+        simulator.approve("alice", "bob").unwrap();
+        simulator.claim("bob").unwrap();
+        match simulator.settle("bob") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "DistributionNotFinalized"),
+            other => panic!("unexpected {:?}", other),
+        }
+        simulator.advance_time(60);
+        simulator.finalize().unwrap();
+        simulator.settle("alice").unwrap();
+        simulator.settle("bob").unwrap();
+
+        // bob never answered alice, which counts against him
+        let alice = simulator.search_engine_account("alice").unwrap().reputation;
+        let bob = simulator.search_engine_account("bob").unwrap().reputation;
+        assert_eq!((alice.approvals_given, alice.approvals_received, alice.claims, alice.disputes), (1, 0, 0, 1));
+        assert_eq!((bob.approvals_given, bob.approvals_received, bob.claims, bob.disputes), (0, 1, 1, 0));
+        assert_eq!(bob.score(), 2);
+        match simulator.settle("bob") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "AlreadySettled"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        // renaming does not touch what was earned
+        simulator.trust("bob", "alice", 3).unwrap();
+        assert_eq!(simulator.search_engine_account("bob").unwrap().reputation, bob);
+    }
+
+    #[test]
+    fn test_deadline() {
+        let mut simulator = two_recipients();
+        simulator.distribute(60).unwrap();
+        simulator.advance_time(59);
+        simulator.approve("alice", "bob").unwrap();
+        match simulator.finalize() {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "DistributionOpen"),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        simulator.advance_time(1);
+        match simulator.approve("bob", "alice") {
+            Err(SimulatorError::Program { name, .. }) => assert_eq!(name, "DistributionClosed"),
+            other => panic!("unexpected {:?}", other),
+        }
+        simulator.finalize().unwrap();
+
+        let database = simulator.resource_database();
+        assert!(database.is_finalized);
+        assert_eq!(database.window.deadline(), 60);
+        assert_eq!(simulator.clock().unix_timestamp, 60);
+    }
+}
@@ -0,0 +1,186 @@
+// In-memory accounts and a minimal runtime: instructions are handed to the programs'
+// process_instruction with AccountInfos built from the store, and a transaction's changes are
+// only kept when every instruction in it succeeds.
+use solana_program::{
+    account_info::AccountInfo,
+    clock::{Clock, Epoch},
+    entrypoint::ProgramResult,
+    hash::Hash,
+    program_error::ProgramError,
+    pubkey::Pubkey,
+    sysvar::{self, Sysvar},
+};
+use std::collections::BTreeMap;
+
+pub type Processor = fn(&Pubkey, &[AccountInfo], &[u8]) -> ProgramResult;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Account {
+    pub lamports: u64,
+    pub data: Vec<u8>,
+    pub owner: Pubkey,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Instruction {
+    pub program_id: Pubkey,
+    pub accounts: Vec<Pubkey>,
+    pub data: Vec<u8>,
+}
+
+#[derive(Clone, Default)]
+pub struct AccountStore {
+    accounts: BTreeMap<Pubkey, Account>,
+    programs: BTreeMap<Pubkey, Processor>,
+}
+
+impl AccountStore {
+    pub fn add_program(&mut self, program_id: Pubkey, processor: Processor) {
+        self.programs.insert(program_id, processor);
+    }
+
+    // a zeroed account owned by the program, like system_instruction::create_account
+    pub fn create_account(&mut self, pubkey: Pubkey, space: usize, owner: Pubkey) {
+        self.accounts.insert(pubkey, Account {
+            lamports: 0,
+            data: vec![0u8; space],
+            owner,
+        });
+    }
+
+    // (re)writes the clock sysvar account that programs read the current time from
+    pub fn set_clock(&mut self, clock: &Clock) {
+        let key = sysvar::clock::id();
+        let mut account = Account {
+            lamports: 0,
+            data: vec![0u8; Clock::size_of()],
+            owner: sysvar::id(),
+        };
+        {
+            let mut info = AccountInfo::new(&key, false, false, &mut account.lamports, &mut account.data, &account.owner, false, Epoch::default());
+            clock.to_account_info(&mut info).unwrap();
+        }
+        self.accounts.insert(key, account);
+    }
+
+    // (re)writes the SlotHashes sysvar, `entries` newest first. Laid out like the runtime's: a
+    // u64 count, then (slot, hash) entries.
+    pub fn set_slot_hashes(&mut self, entries: &[(u64, Hash)]) {
+        let mut data = (entries.len() as u64).to_le_bytes().to_vec();
+        for (slot, hash) in entries.iter() {
+            data.extend_from_slice(&slot.to_le_bytes());
+            data.extend_from_slice(hash.as_ref());
+        }
+        self.accounts.insert(sysvar::slot_hashes::id(), Account {
+            lamports: 0,
+            data,
+            owner: sysvar::id(),
+        });
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    // On failure returns the index of the failing instruction, and the store is left untouched
+    pub fn process_transaction(&mut self, instructions: &[Instruction]) -> Result<(), (usize, ProgramError)> {
+        self.process_signed_transaction(instructions, &[])
+    }
+
+    // like process_transaction, with `signers` having signed the transaction
+    pub fn process_signed_transaction(&mut self, instructions: &[Instruction], signers: &[Pubkey]) -> Result<(), (usize, ProgramError)> {
+        let mut accounts = self.accounts.clone();
+        for (index, instruction) in instructions.iter().enumerate() {
+            let processor = match self.programs.get(&instruction.program_id) {
+                Some(processor) => *processor,
+                None => return Err((index, ProgramError::IncorrectProgramId)),
+            };
+            process_instruction(&mut accounts, processor, instruction, signers).map_err(|err| (index, err))?;
+        }
+        self.accounts = accounts;
+        Ok(())
+    }
+}
+
+fn process_instruction(accounts: &mut BTreeMap<Pubkey, Account>, processor: Processor, instruction: &Instruction, signers: &[Pubkey]) -> ProgramResult {
+    // one copy per distinct key, so an account passed twice shares its data like on chain
+    let mut keys: Vec<Pubkey> = Vec::new();
+    for key in instruction.accounts.iter() {
+        if !keys.contains(key) {
+            keys.push(*key);
+        }
+    }
+    let mut copies: Vec<Account> = keys.iter().map(|key| accounts.get(key).cloned().unwrap_or_default()).collect();
+    let originals = copies.clone();
+
+    {
+        let infos: Vec<AccountInfo> = keys.iter().zip(copies.iter_mut()).map(|(key, account)| AccountInfo::new(
+            key,
+            signers.contains(key),
+            true,
+            &mut account.lamports,
+            &mut account.data,
+            &account.owner,
+            false,
+            Epoch::default(),
+        )).collect();
+        let instruction_infos: Vec<AccountInfo> = instruction.accounts.iter().map(|key| {
+            infos[keys.iter().position(|unique| unique == key).unwrap()].clone()
+        }).collect();
+        processor(&instruction.program_id, &instruction_infos, &instruction.data)?;
+    }
+
+    // the runtime only lets the owning program change an account's data
+    for (account, original) in copies.iter().zip(originals.iter()) {
+        if account.data != original.data && account.owner != instruction.program_id {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+    }
+    for (key, account) in keys.into_iter().zip(copies) {
+        accounts.insert(key, account);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_first_byte(_program_id: &Pubkey, accounts: &[AccountInfo], data: &[u8]) -> ProgramResult {
+        if data.is_empty() {
+            return Err(ProgramError::InvalidInstructionData);
+        }
+        accounts[0].try_borrow_mut_data()?[0] = data[0];
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_transaction_is_rolled_back() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut store = AccountStore::default();
+        store.add_program(program_id, write_first_byte);
+        store.create_account(key, 1, program_id);
+
+        let write = Instruction { program_id, accounts: vec![key], data: vec![7] };
+        let fail = Instruction { program_id, accounts: vec![key], data: vec![] };
+        assert_eq!(store.process_transaction(&[write.clone(), fail]), Err((1, ProgramError::InvalidInstructionData)));
+        assert_eq!(store.get(&key).unwrap().data, vec![0]);
+
+        assert_eq!(store.process_transaction(&[write]), Ok(()));
+        assert_eq!(store.get(&key).unwrap().data, vec![7]);
+    }
+
+    #[test]
+    fn test_only_owner_writes() {
+        let program_id = Pubkey::new_unique();
+        let key = Pubkey::new_unique();
+        let mut store = AccountStore::default();
+        store.add_program(program_id, write_first_byte);
+        store.create_account(key, 1, Pubkey::new_unique());
+
+        let write = Instruction { program_id, accounts: vec![key], data: vec![7] };
+        assert_eq!(store.process_transaction(&[write]), Err((0, ProgramError::IncorrectProgramId)));
+        assert_eq!(store.get(&key).unwrap().data, vec![0]);
+    }
+}